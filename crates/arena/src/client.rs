@@ -0,0 +1,263 @@
+//! Talking to a UCI engine running as a separate process.
+
+use std::{
+    ffi::OsStr,
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    time::{Duration, Instant},
+};
+
+use board::{Game, Move, Position};
+use uci::{GoParams, InfoScore};
+
+/// How long an engine gets to answer `uci` and `isready`.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum EngineError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("engine exited")]
+    Exited,
+    #[error("engine didn't respond in time")]
+    Timeout,
+    #[error("engine played an illegal move '{0}'")]
+    IllegalMove(String),
+}
+
+/// The answer to a `go` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reply {
+    pub mv: Move,
+    /// The last score the engine reported, for the player to move.
+    pub score: Option<InfoScore>,
+    /// How long the engine took to answer.
+    pub time: Duration,
+}
+
+/// A UCI engine, quit when dropped.
+pub struct UciEngine {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines of output, read on another thread so reads can time out.
+    lines: Receiver<String>,
+}
+
+impl UciEngine {
+    /// Starts the engine at `path` and waits for it to finish `uci`.
+    pub fn spawn(path: impl AsRef<OsStr>) -> Result<Self, EngineError> {
+        let path = path.as_ref();
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = Self {
+            name: path.to_string_lossy().into_owned(),
+            child,
+            stdin,
+            lines,
+        };
+        engine.send("uci")?;
+        loop {
+            let line = engine.read(Some(STARTUP_TIMEOUT))?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_owned();
+            }
+            if line.trim() == "uciok" {
+                break;
+            }
+        }
+        Ok(engine)
+    }
+
+    /// The name the engine gave itself, or its path if it didn't.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), EngineError> {
+        Ok(self.send(&format!("setoption name {name} value {value}"))?)
+    }
+
+    /// Tells the engine a new game is starting, waiting until it is ready.
+    pub fn new_game(&mut self) -> Result<(), EngineError> {
+        self.send("ucinewgame")?;
+        self.send("isready")?;
+        // skipping anything left over from the last game
+        while self.read(Some(STARTUP_TIMEOUT))?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    /// Asks for a move in the current position of `game`,
+    /// sent as the moves played from the starting position.
+    ///
+    /// Gives up once `timeout` passes without an answer, or waits forever without one.
+    pub fn go(
+        &mut self,
+        game: &Game,
+        params: &GoParams,
+        timeout: Option<Duration>,
+    ) -> Result<Reply, EngineError> {
+        let mut command = String::from("position startpos");
+        if !game.moves().is_empty() {
+            command.push_str(" moves");
+            let mut replay = Position::start();
+            for &mv in game.moves() {
+                command.push(' ');
+                command.push_str(&replay.uci(mv));
+                replay.make_move(mv);
+            }
+        }
+        self.send(&command)?;
+        self.send(&go_command(params))?;
+
+        let start = Instant::now();
+        let mut score = None;
+        loop {
+            let remaining = timeout.map(|timeout| timeout.saturating_sub(start.elapsed()));
+            let line = self.read(remaining)?;
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("info") => score = parse_score(tokens).or(score),
+                Some("bestmove") => {
+                    let text = tokens.next().unwrap_or_default();
+                    let mv = game
+                        .position()
+                        .parse_uci(text)
+                        .map_err(|_| EngineError::IllegalMove(text.to_owned()))?;
+                    return Ok(Reply {
+                        mv,
+                        score,
+                        time: start.elapsed(),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{command}")?;
+        self.stdin.flush()
+    }
+
+    fn read(&self, timeout: Option<Duration>) -> Result<String, EngineError> {
+        match timeout {
+            Some(timeout) => self.lines.recv_timeout(timeout).map_err(|e| match e {
+                RecvTimeoutError::Timeout => EngineError::Timeout,
+                RecvTimeoutError::Disconnected => EngineError::Exited,
+            }),
+            None => self.lines.recv().map_err(|_| EngineError::Exited),
+        }
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Writes the `go` command for `params`.
+fn go_command(params: &GoParams) -> String {
+    let millis = |time: Duration| time.as_millis();
+    let mut command = String::from("go");
+    if let Some(time) = params.wtime {
+        command.push_str(&format!(" wtime {}", millis(time)));
+    }
+    if let Some(time) = params.btime {
+        command.push_str(&format!(" btime {}", millis(time)));
+    }
+    if let Some(time) = params.winc {
+        command.push_str(&format!(" winc {}", millis(time)));
+    }
+    if let Some(time) = params.binc {
+        command.push_str(&format!(" binc {}", millis(time)));
+    }
+    if let Some(moves) = params.moves_to_go {
+        command.push_str(&format!(" movestogo {moves}"));
+    }
+    if let Some(depth) = params.depth {
+        command.push_str(&format!(" depth {depth}"));
+    }
+    if let Some(nodes) = params.nodes {
+        command.push_str(&format!(" nodes {nodes}"));
+    }
+    if let Some(time) = params.move_time {
+        command.push_str(&format!(" movetime {}", millis(time)));
+    }
+    if params.infinite {
+        command.push_str(" infinite");
+    }
+    command
+}
+
+/// Finds the score in the rest of an `info` line, ignoring lines other than the first.
+fn parse_score<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Option<InfoScore> {
+    while let Some(token) = tokens.next() {
+        match token {
+            "multipv" if tokens.next() != Some("1") => return None,
+            "score" => {
+                let kind = tokens.next()?;
+                let value = tokens.next()?.parse().ok()?;
+                return match kind {
+                    "cp" => Some(InfoScore::Centipawns(value)),
+                    "mate" => Some(InfoScore::Mate(value)),
+                    _ => None,
+                };
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands() {
+        let params = GoParams {
+            wtime: Some(Duration::from_secs(10)),
+            btime: Some(Duration::from_millis(9500)),
+            winc: Some(Duration::from_millis(100)),
+            binc: Some(Duration::from_millis(100)),
+            ..GoParams::default()
+        };
+        assert_eq!(
+            go_command(&params),
+            "go wtime 10000 btime 9500 winc 100 binc 100"
+        );
+
+        let score = |line: &str| parse_score(line.split_whitespace());
+        assert_eq!(
+            score("depth 5 score cp -31 nodes 100 pv e2e4"),
+            Some(InfoScore::Centipawns(-31))
+        );
+        assert_eq!(
+            score("depth 9 multipv 1 score mate 3"),
+            Some(InfoScore::Mate(3))
+        );
+        assert_eq!(score("depth 9 multipv 2 score cp 10"), None);
+        assert_eq!(score("currmove e2e4"), None);
+    }
+}
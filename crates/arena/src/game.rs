@@ -0,0 +1,181 @@
+//! Playing a single game between two engines.
+
+use std::{sync::Arc, time::Duration};
+
+use board::{
+    clock::{Clock, TimeControl},
+    openings::Book,
+    pgn::{self, Eval, Outcome},
+    Color, Game, Move, Position,
+};
+use engine::{clock_go_params, Adjudication, Openings, Score, Tablebase};
+use uci::{GoParams, InfoScore};
+
+use crate::client::{EngineError, UciEngine};
+
+/// How long past its remaining time an engine is waited for before it is given up on.
+const GRACE: Duration = Duration::from_secs(1);
+
+/// How the games of a match are played.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// The time control both engines play with, which can be untimed.
+    pub time_control: TimeControl,
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub move_time: Option<Duration>,
+    pub adjudication: Adjudication,
+    /// The tablebase for [`Adjudication::tablebase`].
+    pub tablebase: Option<Arc<dyn Tablebase>>,
+    /// Where the openings are chosen from, at random.
+    pub book: Option<Book>,
+    /// The most moves, in plies, to play from the book.
+    pub book_plies: usize,
+    /// Plies to play at random once out of book.
+    pub random_plies: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            time_control: TimeControl::untimed(),
+            depth: None,
+            nodes: None,
+            move_time: None,
+            adjudication: Adjudication::default(),
+            tablebase: None,
+            book: None,
+            book_plies: 16,
+            random_plies: 8,
+        }
+    }
+}
+
+impl Settings {
+    /// Picks the opening moves for a pair of games, the same for the same `seed`.
+    pub fn opening(&self, seed: u64) -> Vec<Move> {
+        let mut openings =
+            Openings::new(self.book.as_ref(), self.book_plies, self.random_plies, seed);
+        let mut game = Game::new(Position::start());
+        while game.result().is_none() {
+            let Some(mv) = openings.next(&game) else {
+                break;
+            };
+            game.play(mv);
+        }
+        game.moves().to_vec()
+    }
+
+    /// Plays a game between `white` and `black` after the `opening` moves.
+    ///
+    /// A game is lost by an engine that runs out of time or plays an illegal move,
+    /// other errors end the game without a result.
+    pub fn play(
+        &self,
+        white: &mut UciEngine,
+        black: &mut UciEngine,
+        opening: &[Move],
+    ) -> Result<pgn::Game, EngineError> {
+        white.new_game()?;
+        black.new_game()?;
+
+        let timed = !self.time_control.is_untimed();
+        let mut clock = Clock::new(self.time_control.clone());
+        let mut game = Game::new(Position::start());
+        let mut record = pgn::Game::new();
+        record.set_tag("White", white.name());
+        record.set_tag("Black", black.name());
+        // White's score after each move, None for opening moves
+        let mut scores = Vec::new();
+
+        for &mv in opening {
+            game.play(mv);
+            record.moves.push(pgn::Node::new(mv));
+            scores.push(None);
+        }
+
+        let (outcome, termination) = loop {
+            let tablebase = self.tablebase.as_deref();
+            if let Some(end) = self.adjudication.game_over(&game, &scores, tablebase) {
+                break end;
+            }
+
+            let us = game.position().side_to_move();
+            let player = match us {
+                Color::White => &mut *white,
+                Color::Black => &mut *black,
+            };
+            let timeout = timed.then(|| clock.time_left(us, Duration::ZERO) + GRACE);
+            let reply = match player.go(&game, &self.go_params(&clock, us), timeout) {
+                Ok(reply) => reply,
+                Err(EngineError::Timeout) if timed => break (loss(us), "time forfeit"),
+                Err(EngineError::IllegalMove(_)) => break (loss(us), "rules infraction"),
+                Err(e) => return Err(e),
+            };
+            if timed && !clock.record_move(us, reply.time) {
+                break (loss(us), "time forfeit");
+            }
+
+            let mut node = pgn::Node::new(reply.mv);
+            node.eval = reply.score.map(|score| match (score, us) {
+                (InfoScore::Centipawns(cp), Color::White) => Eval::Centipawns(cp),
+                (InfoScore::Centipawns(cp), Color::Black) => Eval::Centipawns(-cp),
+                (InfoScore::Mate(moves), Color::White) => Eval::Mate(moves),
+                (InfoScore::Mate(moves), Color::Black) => Eval::Mate(-moves),
+            });
+            node.clock = timed.then(|| clock.remaining(us));
+            scores.push(node.eval.map(Score::from));
+
+            game.play(reply.mv);
+            record.moves.push(node);
+        };
+
+        record.outcome = outcome;
+        record.set_tag("Result", outcome.as_str());
+        if timed {
+            record.set_tag("TimeControl", self.time_control.to_string());
+        }
+        record.set_tag("Termination", termination);
+        Ok(record)
+    }
+
+    /// The `go` command for `us` to move.
+    fn go_params(&self, clock: &Clock, us: Color) -> GoParams {
+        let clock_params = if self.time_control.is_untimed() {
+            GoParams::default()
+        } else {
+            clock_go_params(clock, us)
+        };
+        GoParams {
+            depth: self.depth,
+            nodes: self.nodes,
+            move_time: self.move_time,
+            ..clock_params
+        }
+    }
+}
+
+/// The outcome when `color` loses.
+fn loss(color: Color) -> Outcome {
+    match color {
+        Color::White => Outcome::BlackWins,
+        Color::Black => Outcome::WhiteWins,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn openings() {
+        let settings = Settings {
+            random_plies: 6,
+            ..Settings::default()
+        };
+        let opening = settings.opening(3);
+        assert_eq!(opening.len(), 6);
+        assert_eq!(settings.opening(3), opening);
+        assert_ne!(settings.opening(4), opening);
+    }
+}
@@ -0,0 +1,12 @@
+//! Engine against engine matches between UCI binaries.
+//!
+//! Each opening is played twice, with the engines swapping colours, and the match
+//! can be stopped early by a [`Sprt`] once it is clear which engine is stronger.
+
+mod client;
+mod game;
+mod sprt;
+
+pub use client::{EngineError, Reply, UciEngine};
+pub use game::Settings;
+pub use sprt::{Hypothesis, Results, Sprt};
@@ -0,0 +1,239 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use arena::{Hypothesis, Results, Settings, Sprt, UciEngine};
+use board::{openings::Book, pgn::Outcome};
+use engine::Adjudication;
+
+const USAGE: &str = "usage: match <engine> <engine> [--games N] [--concurrency N] [--seed N]
+             [--out FILE] [--tc TIMECONTROL] [--nodes N] [--depth N] [--movetime MS]
+             [--book FILE] [--book-plies N] [--random-plies N] [--option NAME=VALUE]
+             [--sprt ELO0 ELO1] [--alpha A] [--beta B] [--resign SCORE MOVES]
+             [--draw SCORE MOVES AFTER] [--max-moves N] [--no-adjudication]";
+
+/// How many games are played when neither `--games` nor `--sprt` is given.
+const DEFAULT_GAMES: usize = 100;
+
+struct Args {
+    engines: Vec<String>,
+    games: Option<usize>,
+    concurrency: usize,
+    seed: u64,
+    out: Option<String>,
+    book: Option<String>,
+    options: Vec<(String, String)>,
+    sprt: Option<Sprt>,
+    settings: Settings,
+}
+
+fn parse_args() -> Option<Args> {
+    let mut args = std::env::args().skip(1);
+    let mut parsed = Args {
+        engines: Vec::new(),
+        games: None,
+        concurrency: 1,
+        seed: 0,
+        out: None,
+        book: None,
+        options: Vec::new(),
+        sprt: None,
+        settings: Settings::default(),
+    };
+    let settings = &mut parsed.settings;
+    let (mut alpha, mut beta) = (None, None);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => parsed.games = Some(args.next()?.parse().ok()?),
+            "--concurrency" => parsed.concurrency = args.next()?.parse().ok().filter(|&n| n > 0)?,
+            "--seed" => parsed.seed = args.next()?.parse().ok()?,
+            "--out" => parsed.out = Some(args.next()?),
+            "--tc" => settings.time_control = args.next()?.parse().ok()?,
+            "--nodes" => settings.nodes = Some(args.next()?.parse().ok()?),
+            "--depth" => settings.depth = Some(args.next()?.parse().ok()?),
+            "--movetime" => {
+                settings.move_time = Some(Duration::from_millis(args.next()?.parse().ok()?))
+            }
+            "--book" => parsed.book = Some(args.next()?),
+            "--book-plies" => settings.book_plies = args.next()?.parse().ok()?,
+            "--random-plies" => settings.random_plies = args.next()?.parse().ok()?,
+            "--option" => {
+                let option = args.next()?;
+                let (name, value) = option.split_once('=')?;
+                parsed.options.push((name.to_owned(), value.to_owned()));
+            }
+            "--sprt" => {
+                let elo0 = args.next()?.parse().ok()?;
+                let elo1 = args.next()?.parse().ok()?;
+                parsed.sprt = Some(Sprt::new(elo0, elo1));
+            }
+            "--alpha" => alpha = Some(args.next()?.parse().ok()?),
+            "--beta" => beta = Some(args.next()?.parse().ok()?),
+            "--resign" => {
+                settings.adjudication.resign_score = Some(args.next()?.parse().ok()?);
+                settings.adjudication.resign_moves = args.next()?.parse().ok()?;
+            }
+            "--draw" => {
+                settings.adjudication.draw_score = Some(args.next()?.parse().ok()?);
+                settings.adjudication.draw_moves = args.next()?.parse().ok()?;
+                settings.adjudication.draw_after = args.next()?.parse().ok()?;
+            }
+            "--max-moves" => settings.adjudication.max_moves = Some(args.next()?.parse().ok()?),
+            "--no-adjudication" => settings.adjudication = Adjudication::none(),
+            _ if arg.starts_with("--") || parsed.engines.len() == 2 => return None,
+            _ => parsed.engines.push(arg),
+        }
+    }
+    if let Some(sprt) = &mut parsed.sprt {
+        sprt.alpha = alpha.unwrap_or(sprt.alpha);
+        sprt.beta = beta.unwrap_or(sprt.beta);
+    }
+    (parsed.engines.len() == 2).then_some(parsed)
+}
+
+/// Starts an engine with the options from the command line.
+fn spawn(path: &str, options: &[(String, String)]) -> Result<UciEngine, String> {
+    let mut engine = UciEngine::spawn(path).map_err(|e| format!("{path}: {e}"))?;
+    for (name, value) in options {
+        engine
+            .set_option(name, value)
+            .map_err(|e| format!("{path}: {e}"))?;
+    }
+    Ok(engine)
+}
+
+fn run(mut args: Args) -> Result<(), String> {
+    if let Some(path) = &args.book {
+        args.settings.book = Some(Book::open(path).map_err(|e| format!("{path}: {e}"))?);
+    }
+    let games = match (args.games, args.sprt) {
+        (Some(games), _) => games,
+        (None, Some(_)) => usize::MAX,
+        (None, None) => DEFAULT_GAMES,
+    };
+
+    let out = match &args.out {
+        Some(path) => Some(Mutex::new(
+            File::create(path).map_err(|e| format!("{path}: {e}"))?,
+        )),
+        None => None,
+    };
+    let results = Mutex::new(Results::default());
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+
+    // each worker plays games one after another with its own pair of engines
+    let worker = || -> Result<(), String> {
+        let mut first = spawn(&args.engines[0], &args.options)?;
+        let mut second = spawn(&args.engines[1], &args.options)?;
+        while !stop.load(Ordering::Relaxed) {
+            let round = next.fetch_add(1, Ordering::Relaxed);
+            if round >= games {
+                break;
+            }
+
+            // each opening is played twice, with the engines swapping colours
+            let opening = args.settings.opening(args.seed + round as u64 / 2);
+            let swapped = round % 2 == 1;
+            let (white, black) = if swapped {
+                (&mut second, &mut first)
+            } else {
+                (&mut first, &mut second)
+            };
+            let mut game = args
+                .settings
+                .play(white, black, &opening)
+                .map_err(|e| e.to_string())?;
+            game.set_tag("Event", "chesster match");
+            game.set_tag("Round", (round + 1).to_string());
+
+            let mut results = results.lock().unwrap();
+            match (game.outcome, swapped) {
+                (Outcome::WhiteWins, false) | (Outcome::BlackWins, true) => results.wins += 1,
+                (Outcome::WhiteWins, true) | (Outcome::BlackWins, false) => results.losses += 1,
+                _ => results.draws += 1,
+            }
+            println!(
+                "game {}: {} vs {}, {} ({})",
+                round + 1,
+                game.tag("White").unwrap_or("?"),
+                game.tag("Black").unwrap_or("?"),
+                game.outcome.as_str(),
+                game.tag("Termination").unwrap_or("?"),
+            );
+            println!("  {} vs {}: {results}", first.name(), second.name());
+            if let Some(sprt) = &args.sprt {
+                let (lower, upper) = sprt.bounds();
+                println!("  LLR {:.2} ({lower:.2}, {upper:.2})", sprt.llr(&results));
+                if sprt.decide(&results).is_some() {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            }
+            drop(results);
+
+            if let Some(out) = &out {
+                writeln!(out.lock().unwrap(), "{game}").map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(())
+    };
+
+    let error = std::thread::scope(|scope| {
+        let workers = (0..args.concurrency)
+            .map(|_| scope.spawn(worker))
+            .collect::<Vec<_>>();
+        // join every worker before reporting the first error
+        let errors = workers
+            .into_iter()
+            .filter_map(|worker| worker.join().unwrap().err())
+            .collect::<Vec<_>>();
+        errors.into_iter().next()
+    });
+    if let Some(e) = error {
+        return Err(e);
+    }
+    if let Some(out) = out {
+        out.into_inner()
+            .unwrap()
+            .flush()
+            .map_err(|e| e.to_string())?;
+    }
+
+    let results = results.into_inner().unwrap();
+    println!("final: {results}");
+    if let Some(sprt) = &args.sprt {
+        let decision = match sprt.decide(&results) {
+            Some(Hypothesis::H0) => "H0 accepted",
+            Some(Hypothesis::H1) => "H1 accepted",
+            None => "inconclusive",
+        };
+        println!(
+            "SPRT [{}, {}]: LLR {:.2}, {decision}",
+            sprt.elo0,
+            sprt.elo1,
+            sprt.llr(&results)
+        );
+    }
+    io::stdout().flush().map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    let Some(args) = parse_args() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! The [sequential probability ratio test](https://www.chessprogramming.org/Sequential_Probability_Ratio_Test)
+//! and Elo estimates for a match.
+
+use std::fmt;
+
+/// How many standard errors either side of the mean a 95% confidence interval spans.
+const Z_95: f64 = 1.959_964;
+
+/// The games played so far, from the first engine's point of view.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Results {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Results {
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    /// The fraction of the points scored, a half if no games have been played.
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games() as f64
+    }
+
+    /// The variance of a single game's score.
+    fn variance(&self) -> f64 {
+        let games = self.games() as f64;
+        let score = self.score();
+        let deviation = |points: f64, count: u32| count as f64 * (points - score).powi(2);
+        (deviation(1.0, self.wins) + deviation(0.0, self.losses) + deviation(0.5, self.draws))
+            / games
+    }
+
+    /// The Elo difference the score suggests, infinite after winning or losing every game.
+    pub fn elo(&self) -> f64 {
+        elo(self.score())
+    }
+
+    /// Half the width of the 95% confidence interval around [`Results::elo`].
+    pub fn elo_margin(&self) -> f64 {
+        if self.games() == 0 {
+            return f64::INFINITY;
+        }
+        let error = (self.variance() / self.games() as f64).sqrt();
+        let score = self.score();
+        let bound = |score: f64| elo(score.clamp(0.0, 1.0));
+        (bound(score + Z_95 * error) - bound(score - Z_95 * error)) / 2.0
+    }
+}
+
+impl fmt::Display for Results {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "+{} -{} ={} ({:.1}%), Elo {:.1} +/- {:.1}",
+            self.wins,
+            self.losses,
+            self.draws,
+            self.score() * 100.0,
+            self.elo(),
+            self.elo_margin(),
+        )
+    }
+}
+
+/// The Elo difference that gives an expected `score`.
+fn elo(score: f64) -> f64 {
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+/// The expected score against an engine `elo` points weaker.
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// Which hypothesis a [`Sprt`] accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hypothesis {
+    /// The first engine is no better than `elo0` stronger.
+    H0,
+    /// The first engine is at least `elo1` stronger.
+    H1,
+}
+
+/// A sequential probability ratio test between two Elo differences,
+/// stopping as soon as the games played are enough to accept either.
+///
+/// The log-likelihood ratio uses the normal approximation of the
+/// generalized SPRT, which treats draws as half a point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    /// The chance of accepting H1 when H0 is true.
+    pub alpha: f64,
+    /// The chance of accepting H0 when H1 is true.
+    pub beta: f64,
+}
+
+impl Sprt {
+    /// Tests `elo0` against `elo1` with 5% error rates.
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        Self {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    /// The log-likelihood ratios that accept H0 and H1.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// The log-likelihood ratio of H1 over H0 given `results`,
+    /// zero until both a win and a loss or draw have been seen.
+    pub fn llr(&self, results: &Results) -> f64 {
+        let variance = results.variance();
+        if results.games() == 0 || variance == 0.0 {
+            return 0.0;
+        }
+        let (score0, score1) = (expected_score(self.elo0), expected_score(self.elo1));
+        results.games() as f64 * (score1 - score0) * (2.0 * results.score() - score0 - score1)
+            / (2.0 * variance)
+    }
+
+    /// The hypothesis to accept, None if more games are needed.
+    pub fn decide(&self, results: &Results) -> Option<Hypothesis> {
+        let llr = self.llr(results);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            Some(Hypothesis::H1)
+        } else if llr <= lower {
+            Some(Hypothesis::H0)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elo() {
+        let even = Results {
+            wins: 10,
+            losses: 10,
+            draws: 20,
+        };
+        assert_eq!(even.score(), 0.5);
+        assert!(even.elo().abs() < 1e-9);
+
+        // a 64% score is about 100 Elo
+        let ahead = Results {
+            wins: 54,
+            losses: 26,
+            draws: 20,
+        };
+        assert!((ahead.elo() - 100.0).abs() < 1.0);
+        assert!(ahead.elo_margin() > 0.0 && ahead.elo_margin() < 100.0);
+        assert_eq!(Results::default().score(), 0.5);
+    }
+
+    #[test]
+    fn sprt() {
+        let sprt = Sprt::new(0.0, 10.0);
+        let (lower, upper) = sprt.bounds();
+        assert!((lower + 2.944).abs() < 1e-3 && (upper - 2.944).abs() < 1e-3);
+
+        let results = |wins, losses| Results {
+            wins,
+            losses,
+            draws: 1000,
+        };
+        assert_eq!(sprt.decide(&results(500, 500)), None);
+        assert_eq!(sprt.decide(&results(700, 400)), Some(Hypothesis::H1));
+        assert_eq!(sprt.decide(&results(400, 700)), Some(Hypothesis::H0));
+        assert!(sprt.llr(&results(520, 500)) > sprt.llr(&results(500, 520)));
+        assert_eq!(sprt.llr(&Results::default()), 0.0);
+    }
+}
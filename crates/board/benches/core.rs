@@ -0,0 +1,106 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use board::{attacks, movegen, BitBoard, MoveList, Position, Square, FEN};
+
+/// Looks up the attacks from a square given the occupied squares.
+type Lookup = fn(Square, BitBoard) -> BitBoard;
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+fn kiwipete() -> Position {
+    FEN::new(KIWIPETE).parse_position().unwrap()
+}
+
+fn fen_parsing(c: &mut Criterion) {
+    c.bench_function("fen/parse", |b| {
+        b.iter(|| FEN::new(black_box(KIWIPETE)).parse_position().unwrap())
+    });
+    let position = kiwipete();
+    c.bench_function("fen/write", |b| b.iter(|| black_box(&position).to_fen()));
+}
+
+fn perft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("perft");
+    group.sample_size(10);
+    for (name, fen, depth) in [
+        (
+            "start",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            4,
+        ),
+        ("kiwipete", KIWIPETE, 3),
+    ] {
+        let mut position = FEN::new(fen).parse_position().unwrap();
+        let nodes = movegen::perft(&mut position, depth);
+        // reported as nodes per second
+        group.throughput(Throughput::Elements(nodes));
+        group.bench_with_input(BenchmarkId::new(name, depth), &depth, |b, &depth| {
+            b.iter(|| movegen::perft(&mut position, depth))
+        });
+    }
+    group.finish();
+}
+
+fn generation(c: &mut Criterion) {
+    let position = kiwipete();
+    let mut moves = MoveList::new();
+    c.bench_function("movegen/legal", |b| {
+        b.iter(|| {
+            moves.clear();
+            movegen::generate_legal(black_box(&position), &mut moves);
+        })
+    });
+    c.bench_function("movegen/captures", |b| {
+        b.iter(|| {
+            moves.clear();
+            movegen::generate_captures(black_box(&position), &mut moves);
+        })
+    });
+}
+
+fn make_unmake(c: &mut Criterion) {
+    let mut position = kiwipete();
+    let mut moves = MoveList::new();
+    movegen::generate_legal(&position, &mut moves);
+    c.bench_function("position/make_unmake", |b| {
+        b.iter(|| {
+            for &mv in moves.iter() {
+                position.make_move(mv);
+                position.unmake_move();
+            }
+        })
+    });
+}
+
+fn attack_lookups(c: &mut Criterion) {
+    let occupied = kiwipete().board().occupied();
+    let squares = (0..64).map(Square::from_raw).collect::<Vec<_>>();
+    let mut group = c.benchmark_group("attacks");
+    group.throughput(Throughput::Elements(squares.len() as u64));
+    let lookups: [(&str, Lookup); 4] = [
+        ("knight", |square, _| attacks::knight_attacks(square)),
+        ("bishop", attacks::bishop_attacks),
+        ("rook", attacks::rook_attacks),
+        ("queen", attacks::queen_attacks),
+    ];
+    for (name, lookup) in lookups {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                squares.iter().fold(BitBoard::EMPTY, |acc, &square| {
+                    acc | lookup(square, black_box(occupied))
+                })
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    fen_parsing,
+    perft,
+    generation,
+    make_unmake,
+    attack_lookups
+);
+criterion_main!(benches);
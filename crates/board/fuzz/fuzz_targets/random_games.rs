@@ -0,0 +1,51 @@
+//! Plays games chosen by the fuzzer's input, checking the position's incremental state
+//! against what is rebuilt from scratch after every move.
+//!
+//! Run with `cargo fuzz run random_games` from `crates/board`.
+
+#![no_main]
+
+use board::{movegen::perft, zobrist, Position, FEN};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // the first two bytes pick the Chess960 start, and each byte after picks a move
+    let [a, b, choices @ ..] = data else {
+        return;
+    };
+    let mut position = Position::chess960(u16::from_le_bytes([*a, *b]) % 960);
+
+    for &choice in choices {
+        let moves = position.legal_moves();
+        if moves.is_empty() {
+            break;
+        }
+        let fen = position.to_fen().as_str().to_owned();
+        let key = position.zobrist_key();
+        let mv = moves[choice as usize % moves.len()];
+
+        position.make_move(mv);
+        assert_eq!(
+            position.zobrist_key(),
+            zobrist::hash(&position),
+            "{mv:?} from {fen}"
+        );
+        assert_eq!(position.pawn_key(), zobrist::pawn_hash(position.board()));
+        assert_eq!(position.validate(), Ok(()), "{mv:?} from {fen}");
+
+        let after = position.to_fen();
+        let reparsed = FEN::new(after.as_str()).parse_position().unwrap();
+        assert_eq!(reparsed.to_fen().as_str(), after.as_str());
+        assert_eq!(reparsed.zobrist_key(), position.zobrist_key());
+
+        position.unmake_move();
+        assert_eq!(position.to_fen().as_str(), fen);
+        assert_eq!(position.zobrist_key(), key);
+        position.make_move(mv);
+    }
+
+    let mut reparsed = FEN::new(position.to_fen().as_str())
+        .parse_position()
+        .unwrap();
+    assert_eq!(perft(&mut position, 2), perft(&mut reparsed, 2));
+});
@@ -0,0 +1,175 @@
+//! Arrows and marked squares drawn over a position, such as the ideas behind a move.
+//!
+//! In PGN they are written in comments with the `[%cal]` and `[%csl]` commands that
+//! ChessBase and Lichess use: `[%cal Ge2e4,Rd8d1]` draws a green arrow and a red arrow,
+//! and `[%csl Yd5]` marks d5 in yellow. They are read into [`Node::annotations`],
+//! and drawn by [`Renderer::annotate`](crate::render::Renderer::annotate).
+//!
+//! [`Node::annotations`]: crate::pgn::Node::annotations
+
+use alloc::{format, string::String, vec::Vec};
+use core::fmt;
+
+use crate::Square;
+
+/// The colours annotations are drawn in, written as their first letter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Brush {
+    Green,
+    Red,
+    Yellow,
+    Blue,
+}
+
+impl Brush {
+    pub const ALL: [Brush; 4] = [Brush::Green, Brush::Red, Brush::Yellow, Brush::Blue];
+
+    fn letter(self) -> char {
+        match self {
+            Brush::Green => 'G',
+            Brush::Red => 'R',
+            Brush::Yellow => 'Y',
+            Brush::Blue => 'B',
+        }
+    }
+
+    fn from_letter(letter: u8) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|brush| brush.letter() as u8 == letter)
+    }
+}
+
+/// Something drawn over a position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Annotation {
+    /// An arrow between the centres of two squares, written in `[%cal]`.
+    Arrow {
+        from: Square,
+        to: Square,
+        brush: Brush,
+    },
+    /// A ring around a square, written in `[%csl]`.
+    Circle { square: Square, brush: Brush },
+    /// A square filled with colour, also written in `[%csl]`.
+    ///
+    /// PGN doesn't tell these apart from circles, so they are read back as circles,
+    /// which is how Lichess draws them.
+    Highlight { square: Square, brush: Brush },
+}
+
+impl Annotation {
+    pub fn brush(&self) -> Brush {
+        match *self {
+            Annotation::Arrow { brush, .. }
+            | Annotation::Circle { brush, .. }
+            | Annotation::Highlight { brush, .. } => brush,
+        }
+    }
+}
+
+impl fmt::Display for Annotation {
+    /// Writes the annotation as it appears in its command, such as `Ge2e4` or `Rd5`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let letter = self.brush().letter();
+        match *self {
+            Annotation::Arrow { from, to, .. } => write!(f, "{letter}{from}{to}"),
+            Annotation::Circle { square, .. } | Annotation::Highlight { square, .. } => {
+                write!(f, "{letter}{square}")
+            }
+        }
+    }
+}
+
+/// Parses the value of a `[%cal]` or `[%csl]` command, given its name.
+///
+/// Returns None for other commands, or if any of the annotations can't be read.
+pub(crate) fn parse_command(name: &str, value: &str) -> Option<Vec<Annotation>> {
+    let arrows = match name {
+        "cal" => true,
+        "csl" => false,
+        _ => return None,
+    };
+    value
+        .split(',')
+        .map(|item| {
+            let item = item.trim();
+            let brush = Brush::from_letter(*item.as_bytes().first()?)?;
+            let squares = item.get(1..)?;
+            if arrows {
+                let (from, to) = (squares.get(..2)?, squares.get(2..)?);
+                Some(Annotation::Arrow {
+                    from: from.parse().ok()?,
+                    to: to.parse().ok()?,
+                    brush,
+                })
+            } else {
+                Some(Annotation::Circle {
+                    square: squares.parse().ok()?,
+                    brush,
+                })
+            }
+        })
+        .collect()
+}
+
+/// The `[%csl]` and `[%cal]` commands for `annotations`, in that order and as one string,
+/// or None if there are no annotations.
+pub(crate) fn commands(annotations: &[Annotation]) -> Option<String> {
+    let list = |arrows: bool| {
+        annotations
+            .iter()
+            .filter(|annotation| matches!(annotation, Annotation::Arrow { .. }) == arrows)
+            .map(|annotation| format!("{annotation}"))
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let commands = [("csl", list(false)), ("cal", list(true))]
+        .into_iter()
+        .filter(|(_, list)| !list.is_empty())
+        .map(|(name, list)| format!("[%{name} {list}]"))
+        .collect::<Vec<_>>();
+    (!commands.is_empty()).then(|| commands.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_round_trip() {
+        let arrows = parse_command("cal", "Ge2e4,Rd8d1").unwrap();
+        assert_eq!(
+            arrows,
+            [
+                Annotation::Arrow {
+                    from: Square::E2,
+                    to: Square::E4,
+                    brush: Brush::Green
+                },
+                Annotation::Arrow {
+                    from: Square::D8,
+                    to: Square::D1,
+                    brush: Brush::Red
+                },
+            ]
+        );
+        let circles = parse_command("csl", "Yd5").unwrap();
+        assert_eq!(
+            circles,
+            [Annotation::Circle {
+                square: Square::D5,
+                brush: Brush::Yellow
+            }]
+        );
+
+        let all = [arrows, circles].concat();
+        assert_eq!(commands(&all).unwrap(), "[%csl Yd5] [%cal Ge2e4,Rd8d1]");
+        assert_eq!(commands(&[]), None);
+
+        assert_eq!(parse_command("cal", "Xe2e4"), None);
+        assert_eq!(parse_command("cal", "Ge2"), None);
+        assert_eq!(parse_command("csl", "Ge9"), None);
+        assert_eq!(parse_command("clk", "0:01:00"), None);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{magic, BitBoard, Color, Square};
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (2, 1),
+    (2, -1),
+    (-2, 1),
+    (-2, -1),
+    (1, 2),
+    (1, -2),
+    (-1, 2),
+    (-1, -2),
+];
+
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+const fn leaper_table(offsets: &[(i8, i8)]) -> [u64; 64] {
+    let mut table = [0; 64];
+    let mut raw = 0;
+    while raw < 64 {
+        let square = Square::from_raw(raw as u8);
+        let mut i = 0;
+        while i < offsets.len() {
+            if let Some(to) = square.offset(offsets[i].0, offsets[i].1) {
+                table[raw] |= to.bit();
+            }
+            i += 1;
+        }
+        raw += 1;
+    }
+    table
+}
+
+const fn pawn_table(color: Color) -> [u64; 64] {
+    let ranks = match color {
+        Color::White => 1,
+        Color::Black => -1,
+    };
+    leaper_table(&[(ranks, -1), (ranks, 1)])
+}
+
+static KNIGHT_ATTACKS: [u64; 64] = leaper_table(&KNIGHT_OFFSETS);
+static KING_ATTACKS: [u64; 64] = leaper_table(&KING_OFFSETS);
+static PAWN_ATTACKS: [[u64; 64]; Color::COUNT] =
+    [pawn_table(Color::White), pawn_table(Color::Black)];
+
+/// Squares attacked by a knight on `square`.
+#[inline]
+pub fn knight_attacks(square: Square) -> BitBoard {
+    BitBoard(KNIGHT_ATTACKS[square.index()])
+}
+
+/// Squares attacked by a king on `square`.
+#[inline]
+pub fn king_attacks(square: Square) -> BitBoard {
+    BitBoard(KING_ATTACKS[square.index()])
+}
+
+/// Squares attacked by a pawn of `color` on `square`.
+#[inline]
+pub fn pawn_attacks(color: Color, square: Square) -> BitBoard {
+    BitBoard(PAWN_ATTACKS[color as usize][square.index()])
+}
+
+/// Squares attacked by a rook on `square`, blocked by `occupied`.
+#[inline]
+pub fn rook_attacks(square: Square, occupied: BitBoard) -> BitBoard {
+    BitBoard(magic::ROOK.attacks(square, occupied.0))
+}
+
+/// Squares attacked by a bishop on `square`, blocked by `occupied`.
+#[inline]
+pub fn bishop_attacks(square: Square, occupied: BitBoard) -> BitBoard {
+    BitBoard(magic::BISHOP.attacks(square, occupied.0))
+}
+
+/// Squares attacked by a queen on `square`, blocked by `occupied`.
+#[inline]
+pub fn queen_attacks(square: Square, occupied: BitBoard) -> BitBoard {
+    rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leapers() {
+        assert_eq!(knight_attacks(Square::A1).count(), 2);
+        assert_eq!(knight_attacks(Square::E4).count(), 8);
+        assert_eq!(king_attacks(Square::H8).count(), 3);
+        assert_eq!(
+            pawn_attacks(Color::White, Square::E4),
+            BitBoard::from_square(Square::D5) | BitBoard::from_square(Square::F5)
+        );
+    }
+
+    #[test]
+    fn sliders() {
+        assert_eq!(rook_attacks(Square::A1, BitBoard::EMPTY).count(), 14);
+        assert_eq!(bishop_attacks(Square::D4, BitBoard::EMPTY).count(), 13);
+
+        let blocker = BitBoard::from_square(Square::A4);
+        assert!(rook_attacks(Square::A1, blocker).is_on(Square::A4));
+        assert!(!rook_attacks(Square::A1, blocker).is_on(Square::A5));
+    }
+}
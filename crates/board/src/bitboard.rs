@@ -1,39 +1,480 @@
-use std::ops::{BitAnd, BitOr};
+use core::ops::{
+    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, ShlAssign, Shr,
+    ShrAssign, Sub, SubAssign,
+};
 
-use crate::square::Square;
+use crate::{
+    attacks,
+    square::{File, Rank, Square},
+    tables::Direction,
+    Color,
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 #[repr(transparent)]
 pub struct BitBoard(pub(crate) u64);
 
 impl BitBoard {
     pub const EMPTY: Self = Self(0);
 
+    pub const FULL: Self = Self(!0);
+
+    pub const RANK_1: Self = Self(0xFF);
+    pub const RANK_2: Self = Self::rank(Rank::Second);
+    pub const RANK_3: Self = Self::rank(Rank::Third);
+    pub const RANK_4: Self = Self::rank(Rank::Fourth);
+    pub const RANK_5: Self = Self::rank(Rank::Fifth);
+    pub const RANK_6: Self = Self::rank(Rank::Sixth);
+    pub const RANK_7: Self = Self::rank(Rank::Seventh);
+    pub const RANK_8: Self = Self::rank(Rank::Eighth);
+
+    pub const FILE_A: Self = Self(0x0101_0101_0101_0101);
+    pub const FILE_B: Self = Self::file(File::B);
+    pub const FILE_C: Self = Self::file(File::C);
+    pub const FILE_D: Self = Self::file(File::D);
+    pub const FILE_E: Self = Self::file(File::E);
+    pub const FILE_F: Self = Self::file(File::F);
+    pub const FILE_G: Self = Self::file(File::G);
+    pub const FILE_H: Self = Self::file(File::H);
+
+    /// The light squares, b1 being the first.
+    pub const LIGHT_SQUARES: Self = Self(0x55AA_55AA_55AA_55AA);
+    /// The dark squares, a1 being the first.
+    pub const DARK_SQUARES: Self = Self(!Self::LIGHT_SQUARES.0);
+
+    /// The four centre squares: d4, e4, d5 and e5.
+    pub const CENTER: Self = Self(0x0000_0018_1800_0000);
+
+    /// Every square on a `rank`.
+    #[inline]
+    pub const fn rank(rank: Rank) -> Self {
+        Self(Self::RANK_1.0 << (8 * rank as u8))
+    }
+
+    /// Every square on a `file`.
+    #[inline]
+    pub const fn file(file: File) -> Self {
+        Self(Self::FILE_A.0 << file as u8)
+    }
+
+    /// The squares around the edge of the board.
+    #[inline]
+    pub const fn edges() -> Self {
+        Self(Self::RANK_1.0 | Self::RANK_8.0 | Self::FILE_A.0 | Self::FILE_H.0)
+    }
+
+    /// Creates a [`BitBoard`] with a single [`Square`] set.
+    #[inline]
+    pub const fn from_square(square: Square) -> Self {
+        Self(square.bit())
+    }
+
     /// Returns `true` if the square is occupied.
     #[inline]
     pub fn is_on(&self, square: Square) -> bool {
         (self.0 & square.bit()) != 0
     }
 
+    /// Returns `true` if no squares are occupied.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// The number of occupied squares.
+    #[inline]
+    pub const fn count(&self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// Returns `true` if more than one square is occupied.
+    #[inline]
+    pub const fn has_many(&self) -> bool {
+        self.0 & self.0.wrapping_sub(1) != 0
+    }
+
+    /// The lowest occupied [`Square`], if there is one.
+    #[inline]
+    pub const fn lsb(&self) -> Option<Square> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(Square::from_raw(self.0.trailing_zeros() as u8))
+        }
+    }
+
+    /// Swaps rank 1 with rank 8, rank 2 with rank 7, and so on.
+    #[inline]
+    pub const fn flip_vertical(self) -> Self {
+        Self(self.0.swap_bytes())
+    }
+
+    /// Swaps the a-file with the h-file, the b-file with the g-file, and so on.
+    #[inline]
+    pub const fn mirror_horizontal(self) -> Self {
+        Self(self.0.reverse_bits().swap_bytes())
+    }
+
+    /// Turns the board half a turn, so a1 and h8 swap places.
+    #[inline]
+    pub const fn rotate_180(self) -> Self {
+        Self(self.0.reverse_bits())
+    }
+
+    /// Moves every square one rank up, towards the eighth rank.
+    #[inline]
+    pub const fn north(self) -> Self {
+        Self(self.0 << 8)
+    }
+
+    /// Moves every square one rank down, towards the first rank.
+    #[inline]
+    pub const fn south(self) -> Self {
+        Self(self.0 >> 8)
+    }
+
+    /// Moves every square one file right, dropping those on the h-file.
+    #[inline]
+    pub const fn east(self) -> Self {
+        Self((self.0 & !Self::FILE_H.0) << 1)
+    }
+
+    /// Moves every square one file left, dropping those on the a-file.
+    #[inline]
+    pub const fn west(self) -> Self {
+        Self((self.0 & !Self::FILE_A.0) >> 1)
+    }
+
+    /// Moves every square one square up and right, dropping those on the h-file.
+    #[inline]
+    pub const fn north_east(self) -> Self {
+        Self((self.0 & !Self::FILE_H.0) << 9)
+    }
+
+    /// Moves every square one square up and left, dropping those on the a-file.
+    #[inline]
+    pub const fn north_west(self) -> Self {
+        Self((self.0 & !Self::FILE_A.0) << 7)
+    }
+
+    /// Moves every square one square down and right, dropping those on the h-file.
+    #[inline]
+    pub const fn south_east(self) -> Self {
+        Self((self.0 & !Self::FILE_H.0) >> 7)
+    }
+
+    /// Moves every square one square down and left, dropping those on the a-file.
+    #[inline]
+    pub const fn south_west(self) -> Self {
+        Self((self.0 & !Self::FILE_A.0) >> 9)
+    }
+
+    /// Moves every square one step in `direction`, dropping any that would leave the board.
+    #[inline]
+    pub const fn shift(self, direction: Direction) -> Self {
+        match direction {
+            Direction::North => self.north(),
+            Direction::NorthEast => self.north_east(),
+            Direction::East => self.east(),
+            Direction::SouthEast => self.south_east(),
+            Direction::South => self.south(),
+            Direction::SouthWest => self.south_west(),
+            Direction::West => self.west(),
+            Direction::NorthWest => self.north_west(),
+        }
+    }
+
+    /// Moves every square one rank forward from `color`'s point of view.
+    #[inline]
+    pub const fn forward(self, color: Color) -> Self {
+        match color {
+            Color::White => self.north(),
+            Color::Black => self.south(),
+        }
+    }
+
+    /// Squares attacked by a king on any square in this set.
+    #[inline]
+    pub const fn king_attacks(self) -> Self {
+        let sideways = Self(self.east().0 | self.west().0);
+        let row = self.0 | sideways.0;
+        Self(sideways.0 | Self(row).north().0 | Self(row).south().0)
+    }
+
+    /// Squares attacked by a rook on `square`, blocked by `occupied`.
+    #[inline]
+    pub fn rook_attacks(square: Square, occupied: BitBoard) -> BitBoard {
+        attacks::rook_attacks(square, occupied)
+    }
+
+    /// Squares attacked by a bishop on `square`, blocked by `occupied`.
+    #[inline]
+    pub fn bishop_attacks(square: Square, occupied: BitBoard) -> BitBoard {
+        attacks::bishop_attacks(square, occupied)
+    }
+
+    /// Squares attacked by a queen on `square`, blocked by `occupied`.
+    #[inline]
+    pub fn queen_attacks(square: Square, occupied: BitBoard) -> BitBoard {
+        attacks::queen_attacks(square, occupied)
+    }
+
+    /// Squares attacked by every pawn of `color` in this set.
+    #[inline]
+    pub const fn pawn_attacks(self, color: Color) -> BitBoard {
+        let forward = self.forward(color);
+        Self(forward.east().0 | forward.west().0)
+    }
+
+    /// Squares reached by pushing every pawn of `color` in this set one square
+    /// forward onto an `empty` square.
+    #[inline]
+    pub const fn pawn_pushes(self, color: Color, empty: BitBoard) -> BitBoard {
+        Self(self.forward(color).0 & empty.0)
+    }
+
+    /// Squares reached by pushing pawns of `color` two squares from their
+    /// starting rank, with both squares in front `empty`.
+    #[inline]
+    pub const fn pawn_double_pushes(self, color: Color, empty: BitBoard) -> BitBoard {
+        let start = match color {
+            Color::White => Self::RANK_2,
+            Color::Black => Self::RANK_7,
+        };
+        Self(self.0 & start.0)
+            .pawn_pushes(color, empty)
+            .pawn_pushes(color, empty)
+    }
+
     /// Toggles a specific square (XOR).
     #[inline]
-    pub fn toggle(&mut self, square: Square) {
+    pub const fn toggle(&mut self, square: Square) {
         self.0 ^= square.bit()
     }
 }
 
-impl BitAnd for BitBoard {
+macro_rules! impl_bit_op {
+    ($op:ident, $fn:ident, $assign:ident, $assign_fn:ident) => {
+        impl $op for BitBoard {
+            type Output = BitBoard;
+
+            fn $fn(self, rhs: Self) -> Self::Output {
+                BitBoard(self.0.$fn(rhs.0))
+            }
+        }
+
+        impl $assign for BitBoard {
+            fn $assign_fn(&mut self, rhs: Self) {
+                self.0.$assign_fn(rhs.0)
+            }
+        }
+    };
+}
+
+impl_bit_op!(BitAnd, bitand, BitAndAssign, bitand_assign);
+impl_bit_op!(BitOr, bitor, BitOrAssign, bitor_assign);
+impl_bit_op!(BitXor, bitxor, BitXorAssign, bitxor_assign);
+
+impl Not for BitBoard {
     type Output = BitBoard;
 
-    fn bitand(self, rhs: Self) -> Self::Output {
-        BitBoard(self.0 & rhs.0)
+    fn not(self) -> Self::Output {
+        BitBoard(!self.0)
     }
 }
 
-impl BitOr for BitBoard {
+/// Set difference, the squares in `self` that aren't in `rhs`.
+impl Sub for BitBoard {
     type Output = BitBoard;
 
-    fn bitor(self, rhs: Self) -> Self::Output {
-        BitBoard(self.0 | rhs.0)
+    fn sub(self, rhs: Self) -> Self::Output {
+        BitBoard(self.0 & !rhs.0)
+    }
+}
+
+impl SubAssign for BitBoard {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.0 &= !rhs.0
+    }
+}
+
+/// Shifts towards H8, bits shifted off the board are lost.
+impl Shl<u32> for BitBoard {
+    type Output = BitBoard;
+
+    fn shl(self, rhs: u32) -> Self::Output {
+        BitBoard(self.0 << rhs)
+    }
+}
+
+impl ShlAssign<u32> for BitBoard {
+    fn shl_assign(&mut self, rhs: u32) {
+        self.0 <<= rhs
+    }
+}
+
+/// Shifts towards A1, bits shifted off the board are lost.
+impl Shr<u32> for BitBoard {
+    type Output = BitBoard;
+
+    fn shr(self, rhs: u32) -> Self::Output {
+        BitBoard(self.0 >> rhs)
+    }
+}
+
+impl ShrAssign<u32> for BitBoard {
+    fn shr_assign(&mut self, rhs: u32) {
+        self.0 >>= rhs
+    }
+}
+
+impl FromIterator<Square> for BitBoard {
+    fn from_iter<T: IntoIterator<Item = Square>>(iter: T) -> Self {
+        iter.into_iter()
+            .fold(Self::EMPTY, |bb, square| bb | Self::from_square(square))
+    }
+}
+
+/// Iterates over the occupied [`Squares`](Square) of a [`BitBoard`],
+/// from A1 to H8.
+pub struct Squares(u64);
+
+impl Iterator for Squares {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let square = Square::from_raw(self.0.trailing_zeros() as u8);
+        // clear the lowest bit
+        self.0 &= self.0 - 1;
+        Some(square)
     }
-}
\ No newline at end of file
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remainder = self.0.count_ones() as usize;
+        (remainder, Some(remainder))
+    }
+}
+
+impl ExactSizeIterator for Squares {}
+
+impl IntoIterator for BitBoard {
+    type Item = Square;
+    type IntoIter = Squares;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Squares(self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn square_iteration() {
+        let bb = BitBoard::from_square(Square::A1) | BitBoard::from_square(Square::E4);
+        let squares = bb.into_iter().collect::<Vec<_>>();
+
+        assert_eq!(squares, [Square::A1, Square::E4]);
+        assert_eq!(bb.count(), 2);
+        assert!(bb.has_many());
+        assert_eq!(bb.lsb(), Some(Square::A1));
+    }
+
+    #[test]
+    fn operators() {
+        let a = [Square::A1, Square::B1].into_iter().collect::<BitBoard>();
+        let b = [Square::B1, Square::C1].into_iter().collect::<BitBoard>();
+
+        assert_eq!(a ^ b, [Square::A1, Square::C1].into_iter().collect());
+        assert_eq!(a - b, BitBoard::from_square(Square::A1));
+        assert_eq!((!a).count(), 62);
+        assert_eq!(a << 8, [Square::A2, Square::B2].into_iter().collect());
+        assert_eq!((a << 8) >> 8, a);
+
+        let mut c = a;
+        c |= b;
+        c -= BitBoard::from_square(Square::B1);
+        assert_eq!(c, a ^ b);
+    }
+
+    #[test]
+    fn masks() {
+        assert_eq!(BitBoard::RANK_2, BitBoard(0xFF << 8));
+        assert_eq!(BitBoard::FILE_H, BitBoard::FILE_A << 7);
+        assert_eq!(BitBoard::rank(Rank::Eighth).lsb(), Some(Square::A8));
+        assert_eq!(BitBoard::file(File::E), BitBoard::FILE_E);
+        assert!(BitBoard::LIGHT_SQUARES.is_on(Square::H1));
+        assert!(BitBoard::DARK_SQUARES.is_on(Square::A1));
+        assert_eq!(
+            BitBoard::CENTER,
+            [Square::D4, Square::E4, Square::D5, Square::E5]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(BitBoard::edges().count(), 28);
+        assert!((BitBoard::edges() & BitBoard::CENTER).is_empty());
+    }
+
+    #[test]
+    fn pawns() {
+        let squares = |squares: &[Square]| squares.iter().copied().collect::<BitBoard>();
+        let white = squares(&[Square::A2, Square::E2]);
+        let black = squares(&[Square::H7]);
+
+        assert_eq!(
+            white.pawn_attacks(Color::White),
+            squares(&[Square::B3, Square::D3, Square::F3])
+        );
+        assert_eq!(black.pawn_attacks(Color::Black), squares(&[Square::G6]));
+
+        let empty = !BitBoard::from_square(Square::E4);
+        assert_eq!(
+            white.pawn_pushes(Color::White, empty),
+            squares(&[Square::A3, Square::E3])
+        );
+        assert_eq!(
+            white.pawn_double_pushes(Color::White, empty),
+            squares(&[Square::A4])
+        );
+        assert_eq!(
+            black.pawn_double_pushes(Color::Black, empty),
+            squares(&[Square::H5])
+        );
+    }
+
+    #[test]
+    fn shifts() {
+        let corners = BitBoard::from_square(Square::A1) | BitBoard::from_square(Square::H8);
+        assert_eq!(corners.north(), BitBoard::from_square(Square::A2));
+        assert_eq!(corners.south(), BitBoard::from_square(Square::H7));
+        assert_eq!(corners.east(), BitBoard::from_square(Square::B1));
+        assert_eq!(corners.west(), BitBoard::from_square(Square::G8));
+        assert_eq!(corners.north_east(), BitBoard::from_square(Square::B2));
+        assert_eq!(corners.south_west(), BitBoard::from_square(Square::G7));
+        assert!(corners.north_west().is_empty());
+        assert!(corners.south_east().is_empty());
+        assert_eq!(BitBoard::FILE_H.east(), BitBoard::EMPTY);
+
+        for direction in Direction::ALL {
+            let (ranks, files) = direction.offset();
+            let moved = BitBoard::from_square(Square::D4).shift(direction);
+            assert_eq!(moved.lsb(), Square::D4.offset(ranks, files));
+            assert_eq!(
+                moved.shift(direction.opposite()),
+                BitBoard::from_square(Square::D4)
+            );
+        }
+
+        for square in BitBoard::FULL {
+            assert_eq!(
+                BitBoard::from_square(square).king_attacks(),
+                attacks::king_attacks(square)
+            );
+        }
+    }
+}
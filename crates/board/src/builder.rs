@@ -0,0 +1,362 @@
+//! Fluent builders for setting up a [`Board`] or [`Position`] piece by piece,
+//! checked once at the end rather than after every change.
+
+use alloc::vec::Vec;
+
+use crate::{
+    position::STANDARD_CASTLING_ROOKS,
+    variant::{Standard, Variant},
+    BitBoard, Board, CastleSide, CastlingRights, Color, Piece, PieceKind, Position, Square,
+    ValidationError,
+};
+
+/// Builds a [`Board`], validating it in [`BoardBuilder::build`].
+///
+/// ```
+/// # use board::{builder::BoardBuilder, Color, Piece, PieceKind, Square};
+/// let board = BoardBuilder::new()
+///     .piece(Piece::new_with(Color::White, PieceKind::King), Square::E1)
+///     .piece(Piece::new_with(Color::Black, PieceKind::King), Square::E8)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct BoardBuilder {
+    board: Board,
+}
+
+impl BoardBuilder {
+    /// Starts from an empty board.
+    pub fn new() -> Self {
+        Self {
+            board: Board::empty(),
+        }
+    }
+
+    /// Places `piece` on `square`, replacing whatever was there.
+    pub fn piece(mut self, piece: Piece, square: Square) -> Self {
+        self.board.remove_piece(square);
+        self.board.toggle_square(piece, square);
+        self
+    }
+
+    /// Places each piece on its square, replacing whatever was there.
+    pub fn pieces(self, pieces: impl IntoIterator<Item = (Piece, Square)>) -> Self {
+        pieces.into_iter().fold(self, |builder, (piece, square)| {
+            builder.piece(piece, square)
+        })
+    }
+
+    /// Clears `square`.
+    pub fn remove(mut self, square: Square) -> Self {
+        self.board.remove_piece(square);
+        self
+    }
+
+    /// Clears every square.
+    pub fn clear(self) -> Self {
+        Self::new()
+    }
+
+    /// The board as it stands, which may not be valid yet.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Finishes the board, checking it with [`Board::validate`].
+    pub fn build(self) -> Result<Board, ValidationError> {
+        self.board.validate()?;
+        Ok(self.board)
+    }
+}
+
+impl Default for BoardBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Board> for BoardBuilder {
+    fn from(board: Board) -> Self {
+        Self { board }
+    }
+}
+
+/// Builds a [`Position`], validating it in [`PositionBuilder::build`].
+///
+/// Unless changed, the game is standard chess, White is to move, nobody can castle,
+/// there is no en passant square and the game is on its first move.
+#[derive(Clone, Debug)]
+pub struct PositionBuilder {
+    board: BoardBuilder,
+    variant: &'static dyn Variant,
+    hands: [[u8; PieceKind::COUNT]; Color::COUNT],
+    promoted: BitBoard,
+    checks: [u8; Color::COUNT],
+    side_to_move: Color,
+    castling: CastlingRights,
+    castling_rooks: [[Square; 2]; Color::COUNT],
+    chess960: bool,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+}
+
+impl PositionBuilder {
+    /// Starts from an empty board.
+    pub fn new() -> Self {
+        Self {
+            board: BoardBuilder::new(),
+            variant: &Standard,
+            hands: Default::default(),
+            promoted: BitBoard::EMPTY,
+            checks: [0; Color::COUNT],
+            side_to_move: Color::White,
+            castling: CastlingRights::NONE,
+            castling_rooks: STANDARD_CASTLING_ROOKS,
+            chess960: false,
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+        }
+    }
+
+    /// Places `piece` on `square`, replacing whatever was there.
+    pub fn piece(self, piece: Piece, square: Square) -> Self {
+        self.map_board(|board| board.piece(piece, square))
+    }
+
+    /// Places each piece on its square, replacing whatever was there.
+    pub fn pieces(self, pieces: impl IntoIterator<Item = (Piece, Square)>) -> Self {
+        self.map_board(|board| board.pieces(pieces))
+    }
+
+    /// Clears `square`.
+    pub fn remove(self, square: Square) -> Self {
+        self.map_board(|board| board.remove(square))
+    }
+
+    /// Replaces the whole board.
+    pub fn board(self, board: Board) -> Self {
+        self.map_board(|_| board.into())
+    }
+
+    fn map_board(mut self, f: impl FnOnce(BoardBuilder) -> BoardBuilder) -> Self {
+        self.board = f(self.board);
+        self
+    }
+
+    /// Sets the rules the game is played by.
+    pub fn variant(mut self, variant: &'static dyn Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Sets how many of `piece` are in hand, for a variant with drops.
+    pub fn in_hand(mut self, piece: Piece, count: u8) -> Self {
+        self.hands[piece.color() as usize][piece.kind() as usize] = count;
+        self
+    }
+
+    /// Sets how many checks `color` has given, for a variant that counts them.
+    pub fn checks_given(mut self, color: Color, count: u8) -> Self {
+        self.checks[color as usize] = count;
+        self
+    }
+
+    /// Sets the player to move.
+    pub fn side_to_move(mut self, color: Color) -> Self {
+        self.side_to_move = color;
+        self
+    }
+
+    /// Replaces every castling right at once.
+    pub fn castling(mut self, castling: CastlingRights) -> Self {
+        self.castling = castling;
+        self
+    }
+
+    /// Sets whether `color` may castle towards `side`.
+    pub fn castle(mut self, color: Color, side: CastleSide, allowed: bool) -> Self {
+        self.castling.set(color, side, allowed);
+        self
+    }
+
+    /// Sets the square the rook `color` castles with towards `side` starts on.
+    ///
+    /// Rooks start in the corners unless told otherwise,
+    /// and moving one makes the position follow Chess960 castling rules.
+    pub fn castling_rook(mut self, color: Color, side: CastleSide, square: Square) -> Self {
+        self.castling_rooks[color as usize][side as usize] = square;
+        self.chess960 |= square != STANDARD_CASTLING_ROOKS[color as usize][side as usize];
+        self
+    }
+
+    /// Sets whether castling follows Chess960 rules, see [`Position::is_chess960`].
+    pub fn chess960(mut self, chess960: bool) -> Self {
+        self.chess960 = chess960;
+        self
+    }
+
+    /// Sets the square a pawn can capture en passant onto.
+    pub fn en_passant(mut self, square: Option<Square>) -> Self {
+        self.en_passant = square;
+        self
+    }
+
+    /// Sets how many half moves have passed since the last capture or pawn move.
+    pub fn halfmove_clock(mut self, halfmove_clock: u32) -> Self {
+        self.halfmove_clock = halfmove_clock;
+        self
+    }
+
+    /// Sets the move number, starting from 1.
+    pub fn fullmove_number(mut self, fullmove_number: u32) -> Self {
+        self.fullmove_number = fullmove_number;
+        self
+    }
+
+    /// Finishes the position, checking it with [`Position::validate`].
+    pub fn build(self) -> Result<Position, ValidationError> {
+        let mut position = Position {
+            board: self.board.board,
+            side_to_move: self.side_to_move,
+            castling: self.castling,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            key: 0,
+            pawn_key: 0,
+            history: Vec::new(),
+            castling_rooks: self.castling_rooks,
+            chess960: self.chess960,
+            counts: Default::default(),
+            material: Default::default(),
+            kings: Default::default(),
+            variant: self.variant,
+            hands: self.hands,
+            promoted: self.promoted & self.board.board.occupied(),
+            exploded: Vec::new(),
+            checks: self.checks,
+        };
+        position.refresh();
+        position.validate()?;
+        Ok(position)
+    }
+}
+
+impl Default for PositionBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Starts from an existing position, to edit it.
+impl From<&Position> for PositionBuilder {
+    fn from(position: &Position) -> Self {
+        Self {
+            board: (*position.board()).into(),
+            variant: position.variant,
+            hands: position.hands,
+            promoted: position.promoted,
+            checks: position.checks,
+            side_to_move: position.side_to_move,
+            castling: position.castling,
+            castling_rooks: position.castling_rooks,
+            chess960: position.chess960,
+            en_passant: position.en_passant,
+            halfmove_clock: position.halfmove_clock,
+            fullmove_number: position.fullmove_number,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PieceKind, FEN};
+
+    fn piece(color: Color, kind: PieceKind) -> Piece {
+        Piece::new_with(color, kind)
+    }
+
+    #[test]
+    fn build_positions() {
+        let position = PositionBuilder::new()
+            .piece(piece(Color::White, PieceKind::King), Square::E1)
+            .piece(piece(Color::White, PieceKind::Rook), Square::H1)
+            .piece(piece(Color::Black, PieceKind::Queen), Square::E8)
+            // replaces the queen
+            .piece(piece(Color::Black, PieceKind::King), Square::E8)
+            .piece(piece(Color::Black, PieceKind::Pawn), Square::D4)
+            .piece(piece(Color::White, PieceKind::Pawn), Square::E4)
+            .side_to_move(Color::Black)
+            .castle(Color::White, CastleSide::King, true)
+            .en_passant(Some(Square::E3))
+            .fullmove_number(20)
+            .build()
+            .unwrap();
+        assert_eq!(
+            position.to_fen(),
+            FEN::new("4k3/8/8/8/3pP3/8/8/4K2R b K e3 0 20")
+        );
+        assert_eq!(
+            position.zobrist_key(),
+            FEN::new("4k3/8/8/8/3pP3/8/8/4K2R b K e3 0 20")
+                .parse_position()
+                .unwrap()
+                .zobrist_key()
+        );
+
+        // editing an existing position
+        let edited = PositionBuilder::from(&Position::start())
+            .remove(Square::D1)
+            .build()
+            .unwrap();
+        assert_eq!(
+            edited.material(Color::White),
+            Position::start().material(Color::White) - 900
+        );
+
+        let missing_rook = PositionBuilder::from(&position).remove(Square::H1).build();
+        assert_eq!(
+            missing_rook.unwrap_err(),
+            ValidationError::InvalidCastling {
+                color: Color::White,
+                side: CastleSide::King
+            }
+        );
+    }
+
+    #[test]
+    fn build_boards() {
+        let board = BoardBuilder::from(Board::start()).build().unwrap();
+        assert_eq!(board.to_fen(), Board::start().to_fen());
+
+        let no_black_king = BoardBuilder::new()
+            .piece(piece(Color::White, PieceKind::King), Square::E1)
+            .build();
+        assert_eq!(
+            no_black_king.unwrap_err(),
+            ValidationError::KingCount {
+                color: Color::Black,
+                count: 0
+            }
+        );
+    }
+
+    #[test]
+    fn chess960_rooks() {
+        let position = PositionBuilder::new()
+            .board(Board::chess960(0))
+            .castling(CastlingRights::ALL)
+            .castling_rook(Color::White, CastleSide::King, Square::H1)
+            .castling_rook(Color::White, CastleSide::Queen, Square::F1)
+            .castling_rook(Color::Black, CastleSide::King, Square::H8)
+            .castling_rook(Color::Black, CastleSide::Queen, Square::F8)
+            .build()
+            .unwrap();
+        assert!(position.is_chess960());
+        assert_eq!(position.to_fen(), Position::chess960(0).to_fen());
+    }
+}
@@ -0,0 +1,167 @@
+use core::{fmt, str::FromStr};
+
+use bitfield_struct::bitfield;
+
+use crate::{
+    fen::{ParseError, ParseErrorKind},
+    Color, File,
+};
+
+/// Which side of the board a king castles towards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastleSide {
+    /// Castling towards the h-file (O-O).
+    King,
+    /// Castling towards the a-file (O-O-O).
+    Queen,
+}
+
+impl CastleSide {
+    /// The files the king and rook end up on, in any variant.
+    pub(crate) const fn destination_files(self) -> (File, File) {
+        match self {
+            Self::King => (File::G, File::F),
+            Self::Queen => (File::C, File::D),
+        }
+    }
+}
+
+/// The castling moves each player is still allowed to make.
+#[bitfield(u8)]
+#[derive(PartialEq, Eq)]
+pub struct CastlingRights {
+    pub white_king: bool,
+    pub white_queen: bool,
+    pub black_king: bool,
+    pub black_queen: bool,
+    #[bits(4)]
+    __: u8,
+}
+
+impl CastlingRights {
+    /// No player can castle.
+    pub const NONE: Self = Self::new();
+
+    /// Both players can castle on both sides.
+    pub const ALL: Self = Self::from_bits(0b1111);
+
+    /// Both of `color`'s rights.
+    pub const fn of(color: Color) -> Self {
+        match color {
+            Color::White => Self::from_bits(0b0011),
+            Color::Black => Self::from_bits(0b1100),
+        }
+    }
+
+    /// Returns `true` if nobody can castle.
+    pub const fn is_empty(&self) -> bool {
+        self.into_bits() == 0
+    }
+
+    /// Returns `true` if `color` may castle towards either side.
+    pub const fn has_any(&self, color: Color) -> bool {
+        self.into_bits() & Self::of(color).into_bits() != 0
+    }
+
+    /// Takes away both of `color`'s rights, as when their king moves.
+    pub fn remove(&mut self, color: Color) {
+        *self = Self::from_bits(self.into_bits() & !Self::of(color).into_bits());
+    }
+
+    /// Returns `true` if `color` may castle towards `side`.
+    pub const fn has(&self, color: Color, side: CastleSide) -> bool {
+        match (color, side) {
+            (Color::White, CastleSide::King) => self.white_king(),
+            (Color::White, CastleSide::Queen) => self.white_queen(),
+            (Color::Black, CastleSide::King) => self.black_king(),
+            (Color::Black, CastleSide::Queen) => self.black_queen(),
+        }
+    }
+
+    /// Sets whether `color` may castle towards `side`.
+    pub fn set(&mut self, color: Color, side: CastleSide, value: bool) {
+        match (color, side) {
+            (Color::White, CastleSide::King) => self.set_white_king(value),
+            (Color::White, CastleSide::Queen) => self.set_white_queen(value),
+            (Color::Black, CastleSide::King) => self.set_black_king(value),
+            (Color::Black, CastleSide::Queen) => self.set_black_queen(value),
+        }
+    }
+}
+
+/// Writes the rights as in a standard FEN, such as `KQkq`, or `-` when there are none.
+///
+/// Chess960 positions need the rook files too, see [`Position::to_fen`](crate::Position::to_fen).
+impl fmt::Display for CastlingRights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("-");
+        }
+        for (right, c) in [
+            (self.white_king(), 'K'),
+            (self.white_queen(), 'Q'),
+            (self.black_king(), 'k'),
+            (self.black_queen(), 'q'),
+        ] {
+            if right {
+                write!(f, "{c}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads standard FEN castling rights, such as `KQkq` or `-`.
+impl FromStr for CastlingRights {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rights = Self::NONE;
+        if s == "-" {
+            return Ok(rights);
+        }
+        if s.is_empty() {
+            return Err(ParseError::new(ParseErrorKind::InvalidCastling, 0));
+        }
+
+        for (i, c) in s.char_indices() {
+            let (color, side) = match c {
+                'K' => (Color::White, CastleSide::King),
+                'Q' => (Color::White, CastleSide::Queen),
+                'k' => (Color::Black, CastleSide::King),
+                'q' => (Color::Black, CastleSide::Queen),
+                _ => return Err(ParseError::new(ParseErrorKind::InvalidCastling, i)),
+            };
+            rights.set(color, side, true);
+        }
+        Ok(rights)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rights() {
+        let mut rights = "Kq".parse::<CastlingRights>().unwrap();
+        assert!(rights.has(Color::White, CastleSide::King));
+        assert!(!rights.has(Color::White, CastleSide::Queen));
+        assert!(rights.has_any(Color::Black));
+        assert_eq!(rights.to_string(), "Kq");
+
+        rights.remove(Color::White);
+        assert!(!rights.has_any(Color::White));
+        assert_eq!(rights.to_string(), "q");
+        rights.remove(Color::Black);
+        assert!(rights.is_empty());
+        assert_eq!(rights.to_string(), "-");
+
+        assert_eq!(
+            "KQkq".parse::<CastlingRights>().unwrap(),
+            CastlingRights::ALL
+        );
+        assert!("KQx".parse::<CastlingRights>().is_err());
+        assert!("".parse::<CastlingRights>().is_err());
+    }
+}
@@ -0,0 +1,461 @@
+//! Time controls and the chess clock that keeps them.
+//!
+//! Nothing here reads the system time: the clock is told how long each move took,
+//! so it works the same in a GUI, an engine match or a replayed PGN.
+
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+use core::{fmt, str::FromStr, time::Duration};
+
+use crate::Color;
+
+/// One period of a [`TimeControl`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Stage {
+    /// Moves to be made in this stage, or None for the rest of the game.
+    pub moves: Option<u32>,
+    /// Time added to the clock when the stage starts.
+    pub time: Duration,
+    /// Time added after every move.
+    pub increment: Duration,
+    /// Time at the start of every move that doesn't count against the clock.
+    pub delay: Duration,
+}
+
+impl Stage {
+    /// A stage of `time` for the rest of the game, with no increment or delay.
+    pub const fn new(time: Duration) -> Self {
+        Self {
+            moves: None,
+            time,
+            increment: Duration::ZERO,
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Ends the stage after `moves` moves.
+    pub const fn moves(mut self, moves: u32) -> Self {
+        self.moves = Some(moves);
+        self
+    }
+
+    /// Adds `increment` after every move.
+    pub const fn increment(mut self, increment: Duration) -> Self {
+        self.increment = increment;
+        self
+    }
+
+    /// Gives `delay` at the start of every move before the clock runs.
+    pub const fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+/// How much time each player gets, as a list of [`Stage`]s.
+///
+/// A game without a time control has no stages.
+/// When the last stage has a move limit it repeats, so `40/7200` gives
+/// another two hours every 40 moves.
+///
+/// Written the way the PGN `TimeControl` tag is, with stages separated by `:`,
+/// for example `40/5400+30:1800+30` for 90 minutes for 40 moves then 30 for the rest,
+/// with 30 seconds added every move. Times are in seconds and may have fractions.
+/// Delays aren't part of the PGN standard, so they're written after a `d`,
+/// as in `300d5`, and other programs may not read them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct TimeControl {
+    pub stages: Vec<Stage>,
+}
+
+impl TimeControl {
+    /// A time control from its stages, in the order they are played.
+    pub fn new(stages: impl IntoIterator<Item = Stage>) -> Self {
+        Self {
+            stages: stages.into_iter().collect(),
+        }
+    }
+
+    /// No time limit.
+    pub fn untimed() -> Self {
+        Self::default()
+    }
+
+    /// `time` for the whole game.
+    pub fn sudden_death(time: Duration) -> Self {
+        Self::new([Stage::new(time)])
+    }
+
+    /// `time` for the whole game, with `increment` added after every move.
+    pub fn increment(time: Duration, increment: Duration) -> Self {
+        Self::new([Stage::new(time).increment(increment)])
+    }
+
+    /// `time` for the whole game, with `delay` at the start of every move.
+    pub fn delay(time: Duration, delay: Duration) -> Self {
+        Self::new([Stage::new(time).delay(delay)])
+    }
+
+    /// Returns `true` if there is no time limit.
+    pub fn is_untimed(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// The stage played after `stage`, repeating the last one.
+    fn next_stage(&self, stage: usize) -> usize {
+        (stage + 1).min(self.stages.len().saturating_sub(1))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("'{0}' is not a valid time control")]
+pub struct ParseTimeControlError(String);
+
+impl FromStr for TimeControl {
+    type Err = ParseTimeControlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "-" {
+            return Ok(Self::untimed());
+        }
+        let error = || ParseTimeControlError(s.to_owned());
+
+        let stages = s
+            .split(':')
+            .map(|stage| {
+                let (moves, rest) = match stage.split_once('/') {
+                    Some((moves, rest)) => (Some(moves.parse().map_err(|_| error())?), rest),
+                    None => (None, stage),
+                };
+                let (rest, delay) = match rest.split_once('d') {
+                    Some((rest, delay)) => (rest, parse_seconds(delay).ok_or_else(error)?),
+                    None => (rest, Duration::ZERO),
+                };
+                let (time, increment) = match rest.split_once('+') {
+                    Some((time, increment)) => (time, parse_seconds(increment).ok_or_else(error)?),
+                    None => (rest, Duration::ZERO),
+                };
+                Ok(Stage {
+                    moves,
+                    time: parse_seconds(time).ok_or_else(error)?,
+                    increment,
+                    delay,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if stages.iter().any(|stage| stage.moves == Some(0)) {
+            return Err(error());
+        }
+        Ok(Self { stages })
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_untimed() {
+            return f.write_str("-");
+        }
+        for (i, stage) in self.stages.iter().enumerate() {
+            if i > 0 {
+                f.write_str(":")?;
+            }
+            if let Some(moves) = stage.moves {
+                write!(f, "{moves}/")?;
+            }
+            write!(f, "{}", Seconds(stage.time))?;
+            if !stage.increment.is_zero() {
+                write!(f, "+{}", Seconds(stage.increment))?;
+            }
+            if !stage.delay.is_zero() {
+                write!(f, "d{}", Seconds(stage.delay))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses a number of seconds, which may have a fraction.
+fn parse_seconds(s: &str) -> Option<Duration> {
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() || !digits(whole) || !digits(fraction) {
+        return None;
+    }
+    let mut nanos = 0;
+    let mut scale = 100_000_000;
+    for digit in fraction.bytes().take(9) {
+        nanos += u32::from(digit - b'0') * scale;
+        scale /= 10;
+    }
+    Some(Duration::new(whole.parse().ok()?, nanos))
+}
+
+/// A [`Duration`] written in seconds, with a fraction only when needed.
+struct Seconds(Duration);
+
+impl fmt::Display for Seconds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.as_secs())?;
+        let millis = self.0.subsec_millis();
+        if millis > 0 {
+            let fraction = format!("{millis:03}");
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a time the way the PGN `%clk` command does, as `H:MM:SS`,
+/// with tenths of a second when there are any.
+pub fn format_clock(time: Duration) -> String {
+    let seconds = time.as_secs();
+    let mut text = format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    let tenths = time.subsec_millis() / 100;
+    if tenths > 0 {
+        text.push_str(&format!(".{tenths}"));
+    }
+    text
+}
+
+/// Reads a time written as `H:MM:SS`, `MM:SS` or `SS`,
+/// where the seconds may have a fraction, as in the PGN `%clk` command.
+pub fn parse_clock(text: &str) -> Option<Duration> {
+    let mut parts = text.trim().rsplit(':');
+    let seconds = parse_seconds(parts.next()?)?;
+    let mut total = seconds;
+    for scale in [60, 3600] {
+        let Some(part) = parts.next() else {
+            return Some(total);
+        };
+        if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        total += Duration::from_secs(part.parse::<u64>().ok()? * scale);
+    }
+    parts.next().is_none().then_some(total)
+}
+
+/// The clocks of both players, following a [`TimeControl`].
+///
+/// ```
+/// # use core::time::Duration;
+/// # use board::{clock::{Clock, TimeControl}, Color};
+/// let mut clock = Clock::new(TimeControl::increment(Duration::from_secs(180), Duration::from_secs(2)));
+/// clock.record_move(Color::White, Duration::from_secs(5));
+/// assert_eq!(clock.remaining(Color::White), Duration::from_secs(177));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clock {
+    control: TimeControl,
+    remaining: [Duration; Color::COUNT],
+    /// The stage each player is on.
+    stages: [usize; Color::COUNT],
+    /// Moves each player has made in their current stage.
+    stage_moves: [u32; Color::COUNT],
+    flagged: Option<Color>,
+}
+
+impl Clock {
+    /// Starts both clocks with the time for the first stage.
+    pub fn new(control: TimeControl) -> Self {
+        let time = control
+            .stages
+            .first()
+            .map_or(Duration::ZERO, |stage| stage.time);
+        Self {
+            control,
+            remaining: [time; Color::COUNT],
+            stages: [0; Color::COUNT],
+            stage_moves: [0; Color::COUNT],
+            flagged: None,
+        }
+    }
+
+    /// The time control being kept.
+    pub fn time_control(&self) -> &TimeControl {
+        &self.control
+    }
+
+    /// The time `color` has left, not counting the move they may be thinking about.
+    pub fn remaining(&self, color: Color) -> Duration {
+        self.remaining[color as usize]
+    }
+
+    /// Sets the time `color` has left, such as from a `%clk` comment.
+    pub fn set_remaining(&mut self, color: Color, time: Duration) {
+        self.remaining[color as usize] = time;
+    }
+
+    /// The stage `color` is playing in, None without a time control.
+    pub fn stage(&self, color: Color) -> Option<&Stage> {
+        self.control.stages.get(self.stages[color as usize])
+    }
+
+    /// How many moves `color` must make before their next time control,
+    /// None if the stage lasts for the rest of the game.
+    pub fn moves_to_go(&self, color: Color) -> Option<u32> {
+        let moves = self.stage(color)?.moves?;
+        Some(moves - self.stage_moves[color as usize])
+    }
+
+    /// The time `color` would have left after thinking for `elapsed` on their move,
+    /// with any delay used up first.
+    pub fn time_left(&self, color: Color, elapsed: Duration) -> Duration {
+        let delay = self
+            .stage(color)
+            .map_or(Duration::ZERO, |stage| stage.delay);
+        self.remaining(color)
+            .saturating_sub(elapsed.saturating_sub(delay))
+    }
+
+    /// Returns `true` if `color` runs out of time after thinking for `elapsed`.
+    ///
+    /// A game without a time control never flags.
+    pub fn is_flagged(&self, color: Color, elapsed: Duration) -> bool {
+        !self.control.is_untimed() && self.time_left(color, elapsed).is_zero()
+    }
+
+    /// The player who ran out of time, if either has.
+    pub fn flagged(&self) -> Option<Color> {
+        self.flagged
+    }
+
+    /// Stops `color`'s clock after a move that took `elapsed`,
+    /// adding any increment and starting the next stage when its moves are made.
+    ///
+    /// Returns `false` if `color` ran out of time, leaving their clock at zero.
+    pub fn record_move(&mut self, color: Color, elapsed: Duration) -> bool {
+        let Some(&stage) = self.stage(color) else {
+            return true;
+        };
+        let side = color as usize;
+
+        if self.is_flagged(color, elapsed) {
+            self.remaining[side] = Duration::ZERO;
+            self.flagged.get_or_insert(color);
+            return false;
+        }
+        self.remaining[side] = self.time_left(color, elapsed) + stage.increment;
+
+        self.stage_moves[side] += 1;
+        if stage.moves == Some(self.stage_moves[side]) {
+            self.stages[side] = self.control.next_stage(self.stages[side]);
+            self.stage_moves[side] = 0;
+            self.remaining[side] += self.control.stages[self.stages[side]].time;
+        }
+        true
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new(TimeControl::untimed())
+    }
+}
+
+/// Writes the time each player has left, as `White 0:05:00, Black 0:04:58`.
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [white, black] = Color::ALL.map(|color| format_clock(self.remaining(color)));
+        write!(f, "White {white}, Black {black}")
+    }
+}
+
+impl From<TimeControl> for Clock {
+    fn from(control: TimeControl) -> Self {
+        Self::new(control)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    fn secs(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn time_control_text() {
+        let classical = TimeControl::new([
+            Stage::new(secs(5400)).moves(40).increment(secs(30)),
+            Stage::new(secs(1800)).increment(secs(30)),
+        ]);
+        assert_eq!(classical.to_string(), "40/5400+30:1800+30");
+        assert_eq!("40/5400+30:1800+30".parse(), Ok(classical));
+
+        for text in ["-", "300", "180+2", "40/7200", "300d5", "60+0.5"] {
+            assert_eq!(text.parse::<TimeControl>().unwrap().to_string(), text);
+        }
+        assert_eq!(
+            "60+0.5".parse::<TimeControl>().unwrap(),
+            TimeControl::increment(secs(60), Duration::from_millis(500))
+        );
+        for text in ["?", "", "0/60", "40/", "+5", "1:2:x", "5m", "1.2.3"] {
+            assert!(text.parse::<TimeControl>().is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn clock_text() {
+        assert_eq!(format_clock(secs(3923)), "1:05:23");
+        assert_eq!(format_clock(Duration::from_millis(5_300)), "0:00:05.3");
+        assert_eq!(format_clock(Duration::from_millis(65_340)), "0:01:05.3");
+        assert_eq!(parse_clock("1:05:23"), Some(secs(3923)));
+        assert_eq!(parse_clock("0:00:05.3"), Some(Duration::from_millis(5_300)));
+        assert_eq!(parse_clock("2:30"), Some(secs(150)));
+        assert_eq!(parse_clock("1:2:3:4"), None);
+        assert_eq!(parse_clock("1:x:03"), None);
+    }
+
+    #[test]
+    fn increments_and_delays() {
+        let mut clock = Clock::new(TimeControl::increment(secs(60), secs(2)));
+        assert!(clock.record_move(Color::White, secs(10)));
+        assert_eq!(clock.remaining(Color::White), secs(52));
+        assert_eq!(clock.remaining(Color::Black), secs(60));
+        assert_eq!(clock.moves_to_go(Color::White), None);
+
+        let mut clock = Clock::new(TimeControl::delay(secs(60), secs(5)));
+        assert!(clock.record_move(Color::Black, secs(3)));
+        assert_eq!(clock.remaining(Color::Black), secs(60));
+        assert!(clock.record_move(Color::Black, secs(8)));
+        assert_eq!(clock.remaining(Color::Black), secs(57));
+        assert_eq!(clock.time_left(Color::Black, secs(10)), secs(52));
+    }
+
+    #[test]
+    fn stages_and_flags() {
+        let mut clock = Clock::new("2/100:50".parse().unwrap());
+        assert_eq!(clock.moves_to_go(Color::White), Some(2));
+        clock.record_move(Color::White, secs(10));
+        assert_eq!(clock.moves_to_go(Color::White), Some(1));
+        clock.record_move(Color::White, secs(10));
+        // the second stage starts with its time added
+        assert_eq!(clock.remaining(Color::White), secs(130));
+        assert_eq!(clock.moves_to_go(Color::White), None);
+
+        assert!(clock.is_flagged(Color::Black, secs(100)));
+        assert!(!clock.record_move(Color::Black, secs(120)));
+        assert_eq!(clock.flagged(), Some(Color::Black));
+        assert_eq!(clock.remaining(Color::Black), Duration::ZERO);
+        assert_eq!(clock.to_string(), "White 0:02:10, Black 0:00:00");
+
+        // a repeating stage gives more time every time its moves are made
+        let mut clock = Clock::new("1/60".parse().unwrap());
+        clock.record_move(Color::White, secs(30));
+        clock.record_move(Color::White, secs(30));
+        assert_eq!(clock.remaining(Color::White), secs(120));
+
+        let mut untimed = Clock::default();
+        assert!(untimed.record_move(Color::White, secs(1_000_000)));
+        assert!(!untimed.is_flagged(Color::White, secs(1_000_000)));
+    }
+}
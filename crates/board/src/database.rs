@@ -0,0 +1,437 @@
+//! An index of PGN games, for finding every game that reached a position.
+//!
+//! Each game is stored as its tags of interest, and every position along its main line
+//! is indexed by [`Position::zobrist_key`]. The moves themselves aren't kept, each game
+//! remembers where it was in its PGN instead, see [`GameInfo::number`].
+//!
+//! An index can be written to disk with [`Database::to_bytes`] and read back with
+//! [`Database::from_bytes`], so large collections only need to be parsed once.
+
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+#[cfg(feature = "std")]
+use std::{io, path::Path};
+
+use crate::{
+    pgn::{self, Outcome},
+    Position,
+};
+
+/// The details of one game in a [`Database`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameInfo {
+    /// Which game this was in the PGN it was read from, counting from 0,
+    /// so it can be found again with `pgn::games(text).nth(number)`.
+    pub number: u32,
+    pub white: String,
+    pub black: String,
+    pub event: String,
+    pub date: String,
+    /// The opening code, such as `B90`, or empty if there isn't one.
+    pub eco: String,
+    pub outcome: Outcome,
+    /// How many half moves are in the main line.
+    pub plies: u32,
+}
+
+impl GameInfo {
+    fn new(game: &pgn::Game, number: u32) -> Self {
+        let tag = |name| game.tag(name).unwrap_or_default().to_owned();
+        Self {
+            number,
+            white: tag("White"),
+            black: tag("Black"),
+            event: tag("Event"),
+            date: tag("Date"),
+            eco: tag("ECO"),
+            outcome: game.outcome,
+            plies: game.moves.len() as u32,
+        }
+    }
+}
+
+/// Which games to include in a search. Fields left as `None` match every game.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    /// Part of the name of either player, ignoring case.
+    pub player: Option<String>,
+    /// Part of the name of the white player, ignoring case.
+    pub white: Option<String>,
+    /// Part of the name of the black player, ignoring case.
+    pub black: Option<String>,
+    /// The start of the opening code, so `B` matches every semi-open game.
+    pub eco: Option<String>,
+    pub outcome: Option<Outcome>,
+}
+
+impl Filter {
+    /// Returns `true` if `game` passes every part of the filter.
+    pub fn matches(&self, game: &GameInfo) -> bool {
+        let named = |name: &str, part: &Option<String>| {
+            part.as_ref()
+                .is_none_or(|part| name.to_lowercase().contains(part.to_lowercase().as_str()))
+        };
+
+        (named(&game.white, &self.player) || named(&game.black, &self.player))
+            && named(&game.white, &self.white)
+            && named(&game.black, &self.black)
+            && self
+                .eco
+                .as_ref()
+                .is_none_or(|eco| game.eco.starts_with(eco.as_str()))
+            && self.outcome.is_none_or(|outcome| game.outcome == outcome)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DatabaseError {
+    #[error("not a game database")]
+    NotADatabase,
+    #[error("database ends part way through")]
+    Truncated,
+    #[error("database holds a name that isn't UTF-8")]
+    InvalidText,
+    #[error(transparent)]
+    Pgn(#[from] pgn::PgnError),
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Games indexed by the positions they reached.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Database {
+    games: Vec<GameInfo>,
+    /// Position keys and the games reaching them, sorted.
+    positions: Vec<(u64, u32)>,
+}
+
+impl Database {
+    /// The first bytes of a database, followed by its version.
+    const MAGIC: &'static [u8; 4] = b"CHDB";
+    const VERSION: u8 = 1;
+
+    /// Creates an empty database.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Indexes every game in `text`, returning how many couldn't be read and were skipped.
+    ///
+    /// Each game's [`GameInfo::number`] is its place in `text`, including skipped games.
+    pub fn add_pgn(&mut self, text: &str) -> usize {
+        let mut skipped = 0;
+        let start = self.positions.len();
+        for (number, game) in pgn::games(text).enumerate() {
+            let added = game
+                .map_err(DatabaseError::from)
+                .and_then(|game| self.push(&game, number as u32));
+            if added.is_err() {
+                skipped += 1;
+            }
+        }
+        self.sort_from(start);
+        skipped
+    }
+
+    /// Indexes `games`, numbering them in order from 0.
+    pub fn add_games<'a>(
+        &mut self,
+        games: impl IntoIterator<Item = &'a pgn::Game>,
+    ) -> Result<(), DatabaseError> {
+        let start = self.positions.len();
+        let result = games
+            .into_iter()
+            .enumerate()
+            .try_for_each(|(number, game)| self.push(game, number as u32));
+        self.sort_from(start);
+        result
+    }
+
+    /// Adds one game, leaving its positions unsorted at the end of the index.
+    fn push(&mut self, game: &pgn::Game, number: u32) -> Result<(), DatabaseError> {
+        let id = self.games.len() as u32;
+        let mut position = game.start_position()?;
+        let mut keys = Vec::with_capacity(game.moves.len() + 1);
+        keys.push(position.zobrist_key());
+        for node in &game.moves {
+            position.make_move(node.mv);
+            keys.push(position.zobrist_key());
+        }
+        // a game reaching a position more than once is only listed once
+        keys.sort_unstable();
+        keys.dedup();
+
+        self.positions.extend(keys.into_iter().map(|key| (key, id)));
+        self.games.push(GameInfo::new(game, number));
+        Ok(())
+    }
+
+    /// Sorts the positions added since `start` into the rest.
+    fn sort_from(&mut self, start: usize) {
+        if start < self.positions.len() {
+            self.positions[start..].sort_unstable();
+            // both halves are sorted, which the stable sort merges quickly
+            self.positions.sort();
+        }
+    }
+
+    /// Every game, indexed by id.
+    #[inline]
+    pub fn games(&self) -> &[GameInfo] {
+        &self.games
+    }
+
+    /// How many games there are.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    /// Returns `true` if there are no games.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// The ids of every game that reached `position` and passes `filter`, in the order
+    /// they were added.
+    pub fn find(&self, position: &Position, filter: &Filter) -> Vec<u32> {
+        let key = position.zobrist_key();
+        let start = self.positions.partition_point(|&(k, _)| k < key);
+        self.positions[start..]
+            .iter()
+            .take_while(|&&(k, _)| k == key)
+            .map(|&(_, id)| id)
+            .filter(|&id| filter.matches(&self.games[id as usize]))
+            .collect()
+    }
+
+    /// The ids of every game passing `filter`.
+    pub fn search(&self, filter: &Filter) -> Vec<u32> {
+        (0..self.games.len() as u32)
+            .filter(|&id| filter.matches(&self.games[id as usize]))
+            .collect()
+    }
+
+    /// Reads a database written by [`Database::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DatabaseError> {
+        let mut reader = Reader { bytes };
+        if reader.take(4)? != Self::MAGIC || reader.u8()? != Self::VERSION {
+            return Err(DatabaseError::NotADatabase);
+        }
+
+        let count = reader.u32()?;
+        let mut games = Vec::new();
+        for _ in 0..count {
+            games.push(GameInfo {
+                number: reader.u32()?,
+                white: reader.string()?,
+                black: reader.string()?,
+                event: reader.string()?,
+                date: reader.string()?,
+                eco: reader.string()?,
+                outcome: match reader.u8()? {
+                    0 => Outcome::WhiteWins,
+                    1 => Outcome::BlackWins,
+                    2 => Outcome::Draw,
+                    _ => Outcome::Unknown,
+                },
+                plies: reader.u32()?,
+            });
+        }
+
+        let count = reader.u64()?;
+        let mut positions = Vec::new();
+        for _ in 0..count {
+            let entry = (reader.u64()?, reader.u32()?);
+            if entry.1 as usize >= games.len() {
+                return Err(DatabaseError::NotADatabase);
+            }
+            positions.push(entry);
+        }
+        if !positions.is_sorted() {
+            return Err(DatabaseError::NotADatabase);
+        }
+
+        Ok(Self { games, positions })
+    }
+
+    /// Writes the database in a compact binary format, with numbers big endian.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(9 + self.games.len() * 32 + self.positions.len() * 12);
+        bytes.extend_from_slice(Self::MAGIC);
+        bytes.push(Self::VERSION);
+
+        bytes.extend_from_slice(&(self.games.len() as u32).to_be_bytes());
+        for game in &self.games {
+            bytes.extend_from_slice(&game.number.to_be_bytes());
+            for text in [&game.white, &game.black, &game.event, &game.date, &game.eco] {
+                // names longer than this are cut short, on a character boundary
+                let mut len = text.len().min(u16::MAX as usize);
+                while !text.is_char_boundary(len) {
+                    len -= 1;
+                }
+                bytes.extend_from_slice(&(len as u16).to_be_bytes());
+                bytes.extend_from_slice(&text.as_bytes()[..len]);
+            }
+            bytes.push(match game.outcome {
+                Outcome::WhiteWins => 0,
+                Outcome::BlackWins => 1,
+                Outcome::Draw => 2,
+                Outcome::Unknown => 3,
+            });
+            bytes.extend_from_slice(&game.plies.to_be_bytes());
+        }
+
+        bytes.extend_from_slice(&(self.positions.len() as u64).to_be_bytes());
+        for (key, id) in &self.positions {
+            bytes.extend_from_slice(&key.to_be_bytes());
+            bytes.extend_from_slice(&id.to_be_bytes());
+        }
+        bytes
+    }
+
+    /// Reads a database from a file.
+    #[cfg(feature = "std")]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DatabaseError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Writes the database to a file, replacing it if it exists.
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DatabaseError> {
+        Ok(std::fs::write(path, self.to_bytes())?)
+    }
+}
+
+/// Reads big endian numbers from the front of a slice.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DatabaseError> {
+        if self.bytes.len() < len {
+            return Err(DatabaseError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn int<const N: usize>(&mut self) -> Result<[u8; N], DatabaseError> {
+        Ok(self.take(N)?.try_into().expect("took N bytes"))
+    }
+
+    fn u8(&mut self) -> Result<u8, DatabaseError> {
+        Ok(self.int::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, DatabaseError> {
+        Ok(u32::from_be_bytes(self.int()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, DatabaseError> {
+        Ok(u64::from_be_bytes(self.int()?))
+    }
+
+    fn string(&mut self) -> Result<String, DatabaseError> {
+        let len = u16::from_be_bytes(self.int()?);
+        let bytes = self.take(len as usize)?;
+        core::str::from_utf8(bytes)
+            .map(ToOwned::to_owned)
+            .map_err(|_| DatabaseError::InvalidText)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAMES: &str = r#"[White "Carlsen, Magnus"]
+[Black "Nakamura, Hikaru"]
+[ECO "C50"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bc4 1-0
+
+[White "Nakamura, Hikaru"]
+[Black "Caruana, Fabiano"]
+[ECO "C44"]
+[Result "1/2-1/2"]
+
+1. Nf3 Nc6 2. e4 e5 3. Ng1 Nb8 4. Nf3 1/2-1/2
+
+1. e4 e5 2. Qxf7 *
+
+[White "Carlsen, Magnus"]
+[ECO "A40"]
+
+1. d4 0-1
+"#;
+
+    fn database() -> Database {
+        let mut database = Database::new();
+        // the third game has an illegal move
+        assert_eq!(database.add_pgn(GAMES), 1);
+        database
+    }
+
+    #[test]
+    fn find_positions() {
+        let database = database();
+        assert_eq!(database.len(), 3);
+        assert_eq!(database.games()[2].number, 3);
+        assert_eq!(database.games()[1].plies, 7);
+
+        let all = Filter::default();
+        let mut position = Position::start();
+        assert_eq!(database.find(&position, &all), [0, 1, 2]);
+
+        // both of the first games reach the same position by different move orders,
+        // and the second reaches it twice but is only listed once
+        for mv in ["e2e4", "e7e5", "g1f3", "b8c6"] {
+            position.make_move(position.parse_uci(mv).unwrap());
+        }
+        assert_eq!(database.find(&position, &all), [0, 1]);
+
+        let filter = Filter {
+            player: Some("caruana".to_owned()),
+            ..Filter::default()
+        };
+        assert_eq!(database.find(&position, &filter), [1]);
+        let filter = Filter {
+            white: Some("Carlsen".to_owned()),
+            eco: Some("C".to_owned()),
+            outcome: Some(Outcome::WhiteWins),
+            ..Filter::default()
+        };
+        assert_eq!(database.find(&position, &filter), [0]);
+        assert_eq!(
+            database.search(&Filter {
+                player: Some("carlsen".to_owned()),
+                ..Filter::default()
+            }),
+            [0, 2]
+        );
+
+        position.make_move(position.parse_uci("a2a3").unwrap());
+        assert!(database.find(&position, &all).is_empty());
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let database = database();
+        let bytes = database.to_bytes();
+        assert_eq!(Database::from_bytes(&bytes).unwrap(), database);
+
+        assert!(matches!(
+            Database::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DatabaseError::Truncated)
+        ));
+        assert!(matches!(
+            Database::from_bytes(b"PGN!"),
+            Err(DatabaseError::NotADatabase)
+        ));
+    }
+}
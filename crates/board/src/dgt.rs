@@ -0,0 +1,425 @@
+//! Playing over the board on [DGT](https://www.digitalgametechnology.com) electronic boards.
+//!
+//! A [`DgtBoard`] talks to a board over a serial port, or any other byte stream.
+//! It keeps a copy of the pieces standing on the board, updated as each one is lifted or
+//! put down, and matches them against the game's [`Position`] with [`Position::infer_move`].
+//! Once the pieces show a legal move, the move is made and handed out as a [`DgtEvent::Move`].
+//!
+//! The board only sees pieces, so some moves have to be played in a certain order:
+//! castle by moving the king first, as moving the rook first may already be a legal rook move,
+//! and finish a capture by putting the capturing piece down last.
+//! Variants with pieces in hand can't be followed.
+
+use alloc::vec::Vec;
+use std::{
+    io::{self, Read, Write},
+    time::Duration,
+};
+
+use crate::{Board, Color, Move, Piece, PieceKind, Position, Square};
+
+/// Asks the board to forget any mode it was in.
+const SEND_RESET: u8 = 0x40;
+/// Asks the board for all its pieces, answered with [`BOARD_DUMP`].
+const SEND_BOARD: u8 = 0x42;
+/// Asks the board to send a [`FIELD_UPDATE`] whenever a piece is lifted or put down.
+const SEND_UPDATE_BOARD: u8 = 0x44;
+
+/// The pieces on all 64 squares, from a8 to h1.
+const BOARD_DUMP: u8 = 0x86;
+/// One square changed, given as its field and the piece now on it.
+const FIELD_UPDATE: u8 = 0x8e;
+
+/// Every message starts with its id and its length, which counts these 3 bytes.
+const HEADER: usize = 3;
+
+/// What the board sends, see [`read_message`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// Every piece on the board.
+    BoardDump(Board),
+    /// The piece now on `square`, None once it is lifted.
+    FieldUpdate {
+        square: Square,
+        piece: Option<Piece>,
+    },
+    /// Any other message, such as a clock's times or the board's version.
+    Other { id: u8, data: Vec<u8> },
+}
+
+/// Something that happened on the board, see [`DgtBoard::next_event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DgtEvent {
+    /// A legal move was played on the board, and has been made in the position.
+    Move(Move),
+    /// The pieces were set up at the start, so the position went back to the start too.
+    NewGame,
+    /// The pieces match the position again, after it was changed with [`DgtBoard::set_position`].
+    Synced,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DgtError {
+    #[error("the board sent a message too short for its header")]
+    TooShort,
+    #[error("the board sent a malformed message with id {0:#04x}")]
+    Malformed(u8),
+    #[error(transparent)]
+    Serial(#[from] serialport::Error),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// A DGT board following a game.
+#[derive(Debug)]
+pub struct DgtBoard<S> {
+    stream: S,
+    pieces: Board,
+    position: Position,
+    /// Whether the position was changed without the pieces following yet.
+    awaiting: bool,
+}
+
+impl DgtBoard<Box<dyn serialport::SerialPort>> {
+    /// Connects to the board on the serial port at `path`, such as `/dev/ttyUSB0` or `COM3`,
+    /// following a game from the start.
+    pub fn open(path: &str) -> Result<Self, DgtError> {
+        let port = serialport::new(path, 9600)
+            .data_bits(serialport::DataBits::Eight)
+            .parity(serialport::Parity::None)
+            .stop_bits(serialport::StopBits::One)
+            .timeout(Duration::from_secs(1))
+            .open()?;
+        Self::new(port, Position::start())
+    }
+}
+
+impl<S: Read + Write> DgtBoard<S> {
+    /// Connects to the board on `stream`, following the game from `position`.
+    ///
+    /// Asks for the board's pieces and for updates to them from then on,
+    /// waiting until the pieces arrive.
+    pub fn new(mut stream: S, position: Position) -> Result<Self, DgtError> {
+        stream.write_all(&[SEND_RESET, SEND_BOARD])?;
+        stream.flush()?;
+        let pieces = loop {
+            if let Message::BoardDump(board) = read_message(&mut stream)? {
+                break board;
+            }
+        };
+        stream.write_all(&[SEND_UPDATE_BOARD])?;
+        stream.flush()?;
+
+        let awaiting = pieces != *position.board();
+        Ok(Self {
+            stream,
+            pieces,
+            position,
+            awaiting,
+        })
+    }
+
+    /// The position of the game, with every move played on the board so far.
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// The pieces standing on the board, which may be part way through a move.
+    pub fn pieces(&self) -> &Board {
+        &self.pieces
+    }
+
+    /// Whether the pieces on the board match the position.
+    pub fn is_synced(&self) -> bool {
+        self.pieces == *self.position.board()
+    }
+
+    /// Follows the game from `position` instead, such as after an engine's move.
+    ///
+    /// Until the pieces are moved to match, moves are still looked for from `position`,
+    /// and [`DgtEvent::Synced`] is sent once they match.
+    pub fn set_position(&mut self, position: Position) {
+        self.position = position;
+        self.awaiting = !self.is_synced();
+    }
+
+    /// Waits for the next thing to happen on the board.
+    pub fn next_event(&mut self) -> Result<DgtEvent, DgtError> {
+        loop {
+            match read_message(&mut self.stream)? {
+                Message::BoardDump(board) => self.pieces = board,
+                Message::FieldUpdate { square, piece } => {
+                    self.pieces.remove_piece(square);
+                    if let Some(piece) = piece {
+                        self.pieces.toggle_square(piece, square);
+                    }
+                }
+                Message::Other { .. } => continue,
+            }
+            if let Some(event) = self.reconcile() {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Gives back the stream the board was read from.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    /// Compares the pieces with the position, after they changed.
+    fn reconcile(&mut self) -> Option<DgtEvent> {
+        if self.is_synced() {
+            return core::mem::take(&mut self.awaiting).then_some(DgtEvent::Synced);
+        }
+
+        let mut after = self.position.scratch();
+        after.board = self.pieces;
+        if let Some(mv) = Position::infer_move(&self.position, &after) {
+            self.position.make_move(mv);
+            self.awaiting = false;
+            return Some(DgtEvent::Move(mv));
+        }
+
+        let start = Position::start();
+        if self.pieces == *start.board() {
+            self.position = start;
+            self.awaiting = false;
+            return Some(DgtEvent::NewGame);
+        }
+        None
+    }
+}
+
+/// Reads the next message the board sends.
+///
+/// Bytes that can't start a message are skipped, and reads that time out are tried again.
+pub fn read_message(reader: &mut impl Read) -> Result<Message, DgtError> {
+    let mut header = [0; HEADER];
+    read_exact(reader, &mut header[..1])?;
+    // message ids have their top bit set, and no other byte does
+    while header[0] & 0x80 == 0 {
+        read_exact(reader, &mut header[..1])?;
+    }
+    read_exact(reader, &mut header[1..])?;
+
+    let id = header[0];
+    let length = (usize::from(header[1] & 0x7f) << 7) | usize::from(header[2] & 0x7f);
+    let Some(length) = length.checked_sub(HEADER) else {
+        return Err(DgtError::TooShort);
+    };
+    let mut data = alloc::vec![0; length];
+    read_exact(reader, &mut data)?;
+
+    match id {
+        BOARD_DUMP => {
+            let fields: &[u8; 64] = data
+                .as_slice()
+                .try_into()
+                .map_err(|_| DgtError::Malformed(id))?;
+            let mut board = Board::empty();
+            for (field, &code) in fields.iter().enumerate() {
+                if let Some(piece) = piece(code) {
+                    board.toggle_square(piece, square(field as u8));
+                }
+            }
+            Ok(Message::BoardDump(board))
+        }
+        FIELD_UPDATE => match *data {
+            [field, code] if field < 64 => Ok(Message::FieldUpdate {
+                square: square(field),
+                piece: piece(code),
+            }),
+            _ => Err(DgtError::Malformed(id)),
+        },
+        _ => Ok(Message::Other { id, data }),
+    }
+}
+
+/// Fills `buf`, trying again when the stream times out.
+fn read_exact(reader: &mut impl Read, mut buf: &mut [u8]) -> io::Result<()> {
+    while !buf.is_empty() {
+        match reader.read(buf) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => buf = &mut buf[n..],
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::TimedOut | io::ErrorKind::Interrupted
+                ) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// The square of a board's field, which count from a8 to h1.
+fn square(field: u8) -> Square {
+    Square::from_raw(field ^ 56)
+}
+
+/// The piece a board's code stands for, None for an empty square or any of the special markers.
+fn piece(code: u8) -> Option<Piece> {
+    use PieceKind::*;
+
+    let (color, kind) = match code {
+        1 => (Color::White, Pawn),
+        2 => (Color::White, Rook),
+        3 => (Color::White, Knight),
+        4 => (Color::White, Bishop),
+        5 => (Color::White, King),
+        6 => (Color::White, Queen),
+        7 => (Color::Black, Pawn),
+        8 => (Color::Black, Rook),
+        9 => (Color::Black, Knight),
+        10 => (Color::Black, Bishop),
+        11 => (Color::Black, King),
+        12 => (Color::Black, Queen),
+        _ => return None,
+    };
+    Some(Piece::new_with(color, kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The code a board sends for `piece`.
+    fn code(piece: Option<Piece>) -> u8 {
+        (1..=12)
+            .find(|&code| super::piece(code) == piece)
+            .unwrap_or(0)
+    }
+
+    fn board_dump(board: &Board) -> Vec<u8> {
+        let mut bytes = alloc::vec![BOARD_DUMP, 0, 67];
+        bytes.extend((0..64).map(|field| code(board.piece_on(square(field)))));
+        bytes
+    }
+
+    fn field_update(square: Square, piece: Option<Piece>) -> Vec<u8> {
+        alloc::vec![FIELD_UPDATE, 0, 5, square as u8 ^ 56, code(piece)]
+    }
+
+    /// A stream that sends `input` and keeps what is written to it.
+    struct Stream {
+        input: io::Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for Stream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Stream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn messages() {
+        let start = Position::start();
+        let mut bytes = alloc::vec![0x12, 0x34];
+        bytes.extend(board_dump(start.board()));
+        bytes.extend(field_update(Square::E2, None));
+        bytes.extend([0x93, 0, 5, 1, 2]);
+        let mut reader = bytes.as_slice();
+
+        assert_eq!(
+            read_message(&mut reader).unwrap(),
+            Message::BoardDump(*start.board())
+        );
+        assert_eq!(
+            read_message(&mut reader).unwrap(),
+            Message::FieldUpdate {
+                square: Square::E2,
+                piece: None
+            }
+        );
+        assert_eq!(
+            read_message(&mut reader).unwrap(),
+            Message::Other {
+                id: 0x93,
+                data: alloc::vec![1, 2]
+            }
+        );
+        assert!(matches!(
+            read_message(&mut [FIELD_UPDATE, 0, 5, 64, 0].as_slice()),
+            Err(DgtError::Malformed(FIELD_UPDATE))
+        ));
+    }
+
+    #[test]
+    fn moves_on_the_board() {
+        let start = Position::start();
+        let pawn = start.board().piece_on(Square::E2);
+        let knight = start.board().piece_on(Square::G8);
+
+        let mut input = board_dump(start.board());
+        // lifting a piece and putting it back does nothing
+        input.extend(field_update(Square::E2, None));
+        input.extend(field_update(Square::E2, pawn));
+        input.extend(field_update(Square::E2, None));
+        input.extend(field_update(Square::E4, pawn));
+        input.extend(field_update(Square::G8, None));
+        input.extend(field_update(Square::F6, knight));
+        // setting the pieces up again starts a new game
+        input.extend(board_dump(start.board()));
+        let stream = Stream {
+            input: io::Cursor::new(input),
+            output: Vec::new(),
+        };
+
+        let mut dgt = DgtBoard::new(stream, Position::start()).unwrap();
+        assert!(dgt.is_synced());
+        let mut position = Position::start();
+        for uci in ["e2e4", "g8f6"] {
+            let mv = position.parse_uci(uci).unwrap();
+            assert_eq!(dgt.next_event().unwrap(), DgtEvent::Move(mv));
+            position.make_move(mv);
+            assert_eq!(dgt.position().zobrist_key(), position.zobrist_key());
+        }
+        assert_eq!(dgt.next_event().unwrap(), DgtEvent::NewGame);
+        assert_eq!(dgt.position().zobrist_key(), start.zobrist_key());
+        // the stream ran out
+        assert!(dgt.next_event().is_err());
+
+        let stream = dgt.into_inner();
+        assert_eq!(stream.output, [SEND_RESET, SEND_BOARD, SEND_UPDATE_BOARD]);
+    }
+
+    #[test]
+    fn following_a_set_position() {
+        let start = Position::start();
+        let white = start.board().piece_on(Square::D2);
+        let black = start.board().piece_on(Square::D7);
+
+        let mut input = board_dump(start.board());
+        input.extend(field_update(Square::D2, None));
+        input.extend(field_update(Square::D4, white));
+        input.extend(field_update(Square::D7, None));
+        input.extend(field_update(Square::D5, black));
+        let stream = Stream {
+            input: io::Cursor::new(input),
+            output: Vec::new(),
+        };
+
+        // an engine's move, which still has to be made on the board
+        let mut dgt = DgtBoard::new(stream, Position::start()).unwrap();
+        let mut position = Position::start();
+        position.make_move(position.parse_uci("d2d4").unwrap());
+        dgt.set_position(position.clone());
+        assert!(!dgt.is_synced());
+
+        assert_eq!(dgt.next_event().unwrap(), DgtEvent::Synced);
+        let mv = position.parse_uci("d7d5").unwrap();
+        assert_eq!(dgt.next_event().unwrap(), DgtEvent::Move(mv));
+    }
+}
@@ -0,0 +1,150 @@
+use alloc::format;
+use core::fmt;
+
+use crate::{BitBoard, Board, Color, File, Piece, Rank, Square};
+
+/// A [`Board`] drawn as text with rank and file labels, see [`Board::display`].
+///
+/// ```text
+/// 8  r  n  b  q  k  b  n  r
+/// 7  p  p  p  p  .  p  p  p
+/// 6  .  .  .  .  .  .  .  .
+/// 5  .  .  .  . [p] .  .  .
+/// 4  .  .  .  .  P  .  .  .
+/// 3  .  .  .  .  .  .  .  .
+/// 2  P  P  P  P  .  P  P  P
+/// 1  R  N  B  Q  K  B  N  R
+///    a  b  c  d  e  f  g  h
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct BoardDisplay<'a> {
+    board: &'a Board,
+    unicode: bool,
+    perspective: Color,
+    highlights: BitBoard,
+}
+
+impl<'a> BoardDisplay<'a> {
+    pub(crate) fn new(board: &'a Board) -> Self {
+        Self {
+            board,
+            unicode: false,
+            perspective: Color::White,
+            highlights: BitBoard::EMPTY,
+        }
+    }
+
+    /// Draws pieces with chess glyphs such as ♞ rather than FEN letters.
+    pub fn unicode(self, unicode: bool) -> Self {
+        Self { unicode, ..self }
+    }
+
+    /// Which player's pieces are drawn at the bottom.
+    pub fn perspective(self, perspective: Color) -> Self {
+        Self {
+            perspective,
+            ..self
+        }
+    }
+
+    /// Marks `squares` with brackets, such as the last move played.
+    pub fn highlight(self, squares: BitBoard) -> Self {
+        Self {
+            highlights: squares,
+            ..self
+        }
+    }
+
+    fn glyph(&self, piece: Option<Piece>) -> char {
+        const WHITE: [char; 6] = ['♙', '♘', '♗', '♖', '♕', '♔'];
+        const BLACK: [char; 6] = ['♟', '♞', '♝', '♜', '♛', '♚'];
+
+        match piece {
+            None if self.unicode => '·',
+            None => '.',
+            Some(piece) if self.unicode => {
+                let glyphs = match piece.color() {
+                    Color::White => WHITE,
+                    Color::Black => BLACK,
+                };
+                glyphs[piece.kind() as usize]
+            }
+            Some(piece) => piece.as_char(),
+        }
+    }
+}
+
+impl fmt::Display for BoardDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (mut ranks, mut files) = (Rank::ALL, File::ALL);
+        match self.perspective {
+            Color::White => ranks.reverse(),
+            Color::Black => files.reverse(),
+        }
+
+        for rank in ranks {
+            let mut line = format!("{rank} ");
+            for file in files {
+                let square = Square::new(rank, file);
+                let glyph = self.glyph(self.board.piece_on(square));
+                if self.highlights.is_on(square) {
+                    line.push_str(&format!("[{glyph}]"));
+                } else {
+                    line.push_str(&format!(" {glyph} "));
+                }
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        write!(f, " ")?;
+        for file in files {
+            write!(f, "  {file}")?;
+        }
+        writeln!(f)
+    }
+}
+
+impl Board {
+    /// Draws the board as text, with options for how it looks.
+    ///
+    /// ```
+    /// # use board::{Board, Color};
+    /// let text = Board::start().display().perspective(Color::Black).to_string();
+    /// assert!(text.starts_with("1  R  N  B  K  Q  B  N  R"));
+    /// ```
+    pub fn display(&self) -> BoardDisplay<'_> {
+        BoardDisplay::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FEN;
+
+    #[test]
+    fn orientation_and_highlights() {
+        let board = FEN::new("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR")
+            .parse_board()
+            .unwrap();
+        let last_move = [Square::E7, Square::E5].into_iter().collect();
+
+        let text = board.display().highlight(last_move).to_string();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 9);
+        assert_eq!(lines[0], "8  r  n  b  q  k  b  n  r");
+        assert_eq!(lines[1], "7  p  p  p  p [.] p  p  p");
+        assert_eq!(lines[3], "5  .  .  .  . [p] .  .  .");
+        assert_eq!(lines[8], "   a  b  c  d  e  f  g  h");
+
+        let text = board
+            .display()
+            .perspective(Color::Black)
+            .unicode(true)
+            .to_string();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "1  ♖  ♘  ♗  ♔  ♕  ♗  ♘  ♖");
+        assert_eq!(lines[4], "5  ·  ·  ·  ♟  ·  ·  ·  ·");
+        assert_eq!(lines[8], "   h  g  f  e  d  c  b  a");
+    }
+}
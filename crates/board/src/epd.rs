@@ -0,0 +1,281 @@
+//! [Extended Position Description](https://www.chessprogramming.org/Extended_Position_Description),
+//! the format used by test suites such as WAC and STS.
+//!
+//! An EPD record is the first four fields of a FEN followed by operations,
+//! each an opcode and its operands ended by a semicolon:
+//!
+//! ```text
+//! 2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";
+//! ```
+
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+use core::fmt;
+
+use crate::{fen, Move, Position, SanError, FEN};
+
+/// A single opcode and its operands, such as `bm Qg6`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation {
+    pub opcode: String,
+    pub operands: Vec<String>,
+}
+
+/// A [`Position`] along with the operations describing it.
+#[derive(Debug, Clone)]
+pub struct Epd {
+    pub position: Position,
+    pub operations: Vec<Operation>,
+}
+
+#[derive(Clone, Debug, thiserror::Error)]
+pub enum EpdError {
+    #[error("missing a position field")]
+    MissingField,
+    #[error(transparent)]
+    Fen(#[from] fen::ParseError),
+    #[error("string operand for '{0}' is never closed")]
+    UnterminatedString(String),
+    #[error("invalid operand for '{0}'")]
+    InvalidOperand(String),
+}
+
+/// Opcodes whose operands are always written as quoted strings.
+const STRING_OPCODES: [&str; 3] = ["id", "eco", "nic"];
+
+impl Epd {
+    /// Parses a single EPD record.
+    ///
+    /// The `hmvc` and `fmvn` operations set the position's move clocks.
+    pub fn parse(text: &str) -> Result<Self, EpdError> {
+        let text = text.trim();
+        let mut rest = text;
+        let mut fields = Vec::with_capacity(4);
+        for _ in 0..4 {
+            rest = rest.trim_start();
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            if end == 0 {
+                return Err(EpdError::MissingField);
+            }
+            fields.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+
+        let operations = parse_operations(rest)?;
+
+        let clock = |opcode: &str, default: u32| -> Result<u32, EpdError> {
+            match operations.iter().find(|op| op.opcode == opcode) {
+                Some(op) => op
+                    .operands
+                    .first()
+                    .and_then(|operand| operand.parse().ok())
+                    .ok_or_else(|| EpdError::InvalidOperand(opcode.to_owned())),
+                None => Ok(default),
+            }
+        };
+        let fen = format!(
+            "{} {} {}",
+            fields.join(" "),
+            clock("hmvc", 0)?,
+            clock("fmvn", 1)?
+        );
+        let position = FEN::try_from(fen)?.parse_position()?;
+
+        Ok(Self {
+            position,
+            operations,
+        })
+    }
+
+    /// The operands of the first operation with `opcode`.
+    pub fn get(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|op| op.opcode == opcode)
+            .map(|op| op.operands.as_slice())
+    }
+
+    /// Replaces the operands of `opcode`, adding it if it isn't there.
+    pub fn set(&mut self, opcode: &str, operands: Vec<String>) {
+        match self.operations.iter_mut().find(|op| op.opcode == opcode) {
+            Some(op) => op.operands = operands,
+            None => self.operations.push(Operation {
+                opcode: opcode.to_owned(),
+                operands,
+            }),
+        }
+    }
+
+    /// The `id` naming this record.
+    pub fn id(&self) -> Option<&str> {
+        self.get("id")?.first().map(String::as_str)
+    }
+
+    /// The best moves given by `bm`, empty if there are none.
+    pub fn best_moves(&self) -> Result<Vec<Move>, SanError> {
+        self.moves("bm")
+    }
+
+    /// The moves to avoid given by `am`, empty if there are none.
+    pub fn avoid_moves(&self) -> Result<Vec<Move>, SanError> {
+        self.moves("am")
+    }
+
+    /// The centipawn evaluation given by `ce`.
+    pub fn centipawns(&self) -> Option<i32> {
+        self.get("ce")?.first()?.parse().ok()
+    }
+
+    fn moves(&self, opcode: &str) -> Result<Vec<Move>, SanError> {
+        self.get(opcode)
+            .unwrap_or_default()
+            .iter()
+            .map(|san| self.position.parse_san(san))
+            .collect()
+    }
+}
+
+fn parse_operations(mut rest: &str) -> Result<Vec<Operation>, EpdError> {
+    let mut operations = Vec::new();
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ';');
+        if rest.is_empty() {
+            return Ok(operations);
+        }
+
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == ';')
+            .unwrap_or(rest.len());
+        let opcode = rest[..end].to_owned();
+        rest = &rest[end..];
+
+        let mut operands = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            match rest.chars().next() {
+                None => break,
+                Some(';') => {
+                    rest = &rest[1..];
+                    break;
+                }
+                Some('"') => {
+                    let end = rest[1..]
+                        .find('"')
+                        .ok_or_else(|| EpdError::UnterminatedString(opcode.clone()))?;
+                    operands.push(rest[1..end + 1].to_owned());
+                    rest = &rest[end + 2..];
+                }
+                Some(_) => {
+                    let end = rest
+                        .find(|c: char| c.is_whitespace() || c == ';')
+                        .unwrap_or(rest.len());
+                    operands.push(rest[..end].to_owned());
+                    rest = &rest[end..];
+                }
+            }
+        }
+
+        operations.push(Operation { opcode, operands });
+    }
+}
+
+/// Parses every record in `text`, one per line, skipping blank lines.
+pub fn parse_lines(text: &str) -> impl Iterator<Item = Result<Epd, EpdError>> + '_ {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(Epd::parse)
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.opcode)?;
+        for operand in &self.operands {
+            let quote = STRING_OPCODES.contains(&self.opcode.as_str())
+                || operand.is_empty()
+                || operand.contains(|c: char| c.is_whitespace() || c == ';');
+            if quote {
+                write!(f, " \"{operand}\"")?;
+            } else {
+                write!(f, " {operand}")?;
+            }
+        }
+        write!(f, ";")
+    }
+}
+
+impl fmt::Display for Epd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fen = self.position.fen_string();
+        let fields = fen.split(' ').take(4).collect::<Vec<_>>();
+        write!(f, "{}", fields.join(" "))?;
+
+        for op in &self.operations {
+            write!(f, " {op}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WAC: &str = r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id "WAC.001";
+8/7p/5k2/5p2/p1p2P2/Pr1pPK2/1P1R3P/8 b - - bm Rxb2; id "WAC.002";
+
+5rk1/1ppb3p/p1pb4/6q1/3P1p1r/2P1R2P/PP1BQ1P1/5RKR b - - am Qxg2; c0 "some comment; with a semicolon"; ce -35;"#;
+
+    #[test]
+    fn parse_suite() {
+        let records = parse_lines(WAC).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records.len(), 3);
+
+        let first = &records[0];
+        assert_eq!(first.id(), Some("WAC.001"));
+        let best = first.best_moves().unwrap();
+        assert_eq!(best.len(), 1);
+        assert_eq!(first.position.san(best[0]), "Qg6");
+
+        let third = &records[2];
+        assert_eq!(third.id(), None);
+        assert_eq!(third.avoid_moves().unwrap().len(), 1);
+        assert_eq!(third.centipawns(), Some(-35));
+        assert_eq!(
+            third.get("c0"),
+            Some(&["some comment; with a semicolon".to_owned()][..])
+        );
+    }
+
+    #[test]
+    fn write_round_trip() {
+        for line in WAC.lines().filter(|line| !line.is_empty()) {
+            assert_eq!(Epd::parse(line).unwrap().to_string(), line);
+        }
+
+        let mut epd = Epd::parse("4k3/8/8/8/8/8/8/4K3 b - - hmvc 12; fmvn 40;").unwrap();
+        assert_eq!(epd.position.halfmove_clock(), 12);
+        assert_eq!(epd.position.fullmove_number(), 40);
+
+        epd.set("id", vec!["kings".to_owned()]);
+        assert_eq!(
+            epd.to_string(),
+            r#"4k3/8/8/8/8/8/8/4K3 b - - hmvc 12; fmvn 40; id "kings";"#
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            Epd::parse("4k3/8/8/8/8/8/8/4K3 w -"),
+            Err(EpdError::MissingField)
+        ));
+        assert!(matches!(
+            Epd::parse(r#"4k3/8/8/8/8/8/8/4K3 w - - id "open"#),
+            Err(EpdError::UnterminatedString(_))
+        ));
+        assert!(matches!(
+            Epd::parse("4k3/8/8/8/8/8/8/4K3 w - - hmvc x;"),
+            Err(EpdError::InvalidOperand(_))
+        ));
+    }
+}
@@ -0,0 +1,154 @@
+//! Encoding positions as inputs for neural networks.
+//!
+//! Two layouts are given, both from the point of view of a player:
+//!
+//! - [`Features::planes`], [AlphaZero](https://arxiv.org/abs/1712.01815) style
+//!   dense planes of 64 squares each.
+//! - [`Features::half_kp`], the sparse `HalfKP` inputs of the first
+//!   [NNUE](https://www.chessprogramming.org/NNUE) networks.
+
+use alloc::vec::Vec;
+
+use crate::{CastleSide, Color, PieceKind, Position, Square};
+
+/// How many planes [`Features::planes`] gives.
+///
+/// Six piece kinds for each player, then one plane each for
+/// the side to move, the four castling rights and the en passant square.
+pub const PLANES: usize = 2 * PieceKind::COUNT + 1 + 4 + 1;
+
+/// How many inputs each perspective of [`Features::half_kp`] has:
+/// the ten non-king pieces on each square, plus one unused input,
+/// for each square the king can be on.
+pub const HALF_KP_INPUTS: usize = 64 * (10 * 64 + 1);
+
+/// A view of a [`Position`] as network inputs, see [`Position::features`].
+#[derive(Debug, Clone, Copy)]
+pub struct Features<'a> {
+    position: &'a Position,
+}
+
+impl<'a> Features<'a> {
+    pub(crate) fn new(position: &'a Position) -> Self {
+        Self { position }
+    }
+
+    /// The position as [`PLANES`] planes of 1s and 0s, indexed by square,
+    /// from the side to move's point of view.
+    ///
+    /// The first six planes are the side to move's pawns, knights, bishops,
+    /// rooks, queens and king, and the next six the opponent's.
+    /// When black is to move the board is flipped, so their pieces move up the planes.
+    /// Then come a plane of 1s when black is to move, then planes of 1s for
+    /// our king side, our queen side, their king side and their queen side castling rights,
+    /// and lastly the en passant square.
+    pub fn planes(&self) -> [[f32; 64]; PLANES] {
+        let position = self.position;
+        let us = position.side_to_move();
+        let orient = |square: Square| match us {
+            Color::White => square,
+            Color::Black => square.flip_vertical(),
+        };
+
+        let mut planes = [[0.0; 64]; PLANES];
+        for (piece, square) in position.board().iter().zip((0..64).map(Square::from_raw)) {
+            let Some(piece) = piece else { continue };
+            let side = (piece.color() != us) as usize;
+            planes[side * PieceKind::COUNT + piece.kind() as usize][orient(square) as usize] = 1.0;
+        }
+
+        let mut plane = 2 * PieceKind::COUNT;
+        if us == Color::Black {
+            planes[plane] = [1.0; 64];
+        }
+        for color in [us, us.opposite()] {
+            for side in [CastleSide::King, CastleSide::Queen] {
+                plane += 1;
+                if position.castling().has(color, side) {
+                    planes[plane] = [1.0; 64];
+                }
+            }
+        }
+        if let Some(square) = position.en_passant() {
+            planes[PLANES - 1][orient(square) as usize] = 1.0;
+        }
+        planes
+    }
+
+    /// The `HalfKP` inputs that are set for `perspective`, each below [`HALF_KP_INPUTS`].
+    ///
+    /// Every piece besides the kings sets one input, picked by the square of
+    /// `perspective`'s king, the piece's kind, whether it is theirs, and its square.
+    /// Squares are rotated for black, so both perspectives see their king at the bottom.
+    /// Positions without a king for `perspective` have no inputs.
+    pub fn half_kp(&self, perspective: Color) -> Vec<usize> {
+        let position = self.position;
+        let orient = |square: Square| match perspective {
+            Color::White => square as usize,
+            Color::Black => square as usize ^ 63,
+        };
+        let Some(king) = position.find_king(perspective) else {
+            return Vec::new();
+        };
+        let king = orient(king);
+
+        position
+            .board()
+            .iter()
+            .zip((0..64).map(Square::from_raw))
+            .filter_map(|(piece, square)| {
+                let piece = piece.filter(|piece| piece.kind() != PieceKind::King)?;
+                let kind = 2 * piece.kind() as usize + (piece.color() != perspective) as usize;
+                Some(king * (10 * 64 + 1) + 1 + kind * 64 + orient(square))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FEN;
+
+    #[test]
+    fn planes() {
+        let start = Position::start().features().planes();
+        let count = |plane: &[f32; 64]| plane.iter().filter(|&&x| x == 1.0).count();
+        assert_eq!(count(&start[0]), 8);
+        assert_eq!(start[0][Square::E2 as usize], 1.0);
+        assert_eq!(start[PieceKind::COUNT + 5][Square::E8 as usize], 1.0);
+        assert_eq!(count(&start[12]), 0);
+        assert!(start[13..17].iter().all(|plane| count(plane) == 64));
+
+        // black to move sees the same, besides the side to move and en passant
+        let mut position = Position::start();
+        position.make_move(position.parse_uci("e2e4").unwrap());
+        let black = position.features().planes();
+        assert_eq!(
+            black[PieceKind::COUNT][Square::E4.flip_vertical() as usize],
+            1.0
+        );
+        assert_eq!(black[5][Square::E1 as usize], 1.0);
+        assert_eq!(count(&black[12]), 64);
+        assert_eq!(black[PLANES - 1][Square::E6 as usize], 1.0);
+    }
+
+    #[test]
+    fn half_kp() {
+        let position = FEN::try_from("4k3/8/8/8/8/8/3P4/4K3 w - - 0 1")
+            .unwrap()
+            .parse_position()
+            .unwrap();
+        let features = position.features();
+
+        let white = features.half_kp(Color::White);
+        let e1 = Square::E1 as usize;
+        assert_eq!(white, [e1 * 641 + 1 + Square::D2 as usize]);
+
+        // black sees the pawn as an opponent's, with the board rotated
+        let black = features.half_kp(Color::Black);
+        let e8 = Square::E8 as usize ^ 63;
+        assert_eq!(black, [e8 * 641 + 1 + 64 + (Square::D2 as usize ^ 63)]);
+        assert!(white[0] < HALF_KP_INPUTS && black[0] < HALF_KP_INPUTS);
+    }
+}
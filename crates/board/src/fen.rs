@@ -1,160 +1,811 @@
-use crate::{piece::piece, square::Square, Board};
-use std::borrow::Cow;
+use crate::{
+    piece::piece,
+    position::STANDARD_CASTLING_ROOKS,
+    square::{File, Rank, Square},
+    variant::{Crazyhouse, Standard, ThreeCheck, Variant},
+    BitBoard, Board, CastleSide, CastlingRights, Color, Piece, PieceKind, Position,
+    ValidationError,
+};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::str::FromStr;
 
 #[derive(PartialEq, Eq, Clone)]
-pub struct FEN<'a>(Cow<'a, [u8]>);
+pub struct FEN<'a>(pub(crate) Cow<'a, [u8]>);
 
-#[derive(Clone, Copy, Debug, thiserror::Error)]
-pub enum ParseError {
+/// A FEN that couldn't be parsed, and where in it things went wrong.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{kind} at byte {offset}")]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// The byte the problem was found at, or the start of the field
+    /// when the field as a whole is wrong.
+    pub offset: usize,
+}
+
+impl ParseError {
+    pub(crate) const fn new(kind: ParseErrorKind, offset: usize) -> Self {
+        Self { kind, offset }
+    }
+
+    /// Moves the offset on by `by`, for errors found in part of the FEN.
+    const fn after(self, by: usize) -> Self {
+        Self::new(self.kind, self.offset + by)
+    }
+}
+
+/// Why a FEN couldn't be parsed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ParseErrorKind {
     #[error("encountered unknown characer")]
     UnknownChar,
     #[error("too little information in rank")]
     TooLittleRankInfo,
     #[error("too much information in rank")]
     TooMuchRankInfo,
+    #[error("more than 8 ranks")]
+    TooManyRanks,
+    #[error("missing a field after the piece placement")]
+    MissingField,
+    #[error("side to move must be 'w' or 'b'")]
+    InvalidSideToMove,
+    #[error("invalid castling rights")]
+    InvalidCastling,
+    #[error("invalid en passant square")]
+    InvalidEnPassant,
+    #[error("invalid move clock")]
+    InvalidClock,
+    #[error("invalid pieces in hand")]
+    InvalidPocket,
+    #[error("invalid check counts")]
+    InvalidChecks,
+    #[error("unexpected field after the move counters")]
+    TrailingField,
+    /// The fields could be read, but don't describe a position that can be reached,
+    /// see [`Position::validate`].
+    #[error(transparent)]
+    Invalid(ValidationError),
 }
 
 impl<'a> FEN<'a> {
-    pub const fn from_str(raw: &'a str) -> Self {
+    /// Wraps a FEN string, usually a literal.
+    ///
+    /// Use [`str::parse`] or [`FEN::try_from`] for text that might not be ASCII.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `raw` isn't ASCII, which fails to compile in a const context.
+    pub const fn new(raw: &'a str) -> Self {
         assert!(raw.is_ascii(), "FEN strings must be ASCII");
         Self(Cow::Borrowed(raw.as_bytes()))
     }
 
-    pub fn from_string(raw: String) -> Self {
-        assert!(raw.is_ascii(), "FEN strings must be ASCII");
-        Self(Cow::Owned(raw.into_bytes()))
+    /// The FEN as text.
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.0).expect("FEN should only contain ASCII")
     }
 
-    pub fn parse_board(self) -> Result<Board, ParseError> {
-        let mut it = self.0.into_iter();
+    /// Parses the piece placement field into a [`Board`], ignoring the other fields.
+    ///
+    /// This is a `const fn`, see [`Board::from_fen`] for making boards at compile time.
+    pub const fn parse_board(&self) -> Result<Board, ParseError> {
+        let raw: &[u8] = match &self.0 {
+            Cow::Borrowed(raw) => raw,
+            Cow::Owned(raw) => raw.as_slice(),
+        };
+        parse_board(raw)
+    }
 
-        let mut board = Board::empty();
+    /// Parses all six fields of the FEN into a [`Position`].
+    ///
+    /// Pieces in hand after the board, such as `[Qn]`, make it a [`Crazyhouse`] position,
+    /// and check counts make it a [`ThreeCheck`] position, otherwise it is standard chess.
+    ///
+    /// Check counts can be the checks each side still needs before the clocks, as in `3+2`,
+    /// or the checks each side has given after them, as in `+0+1`.
+    ///
+    /// The position must pass [`Position::validate`], so every side has its king,
+    /// no pawns stand on the back ranks and the castling rights and en passant square
+    /// could have come from a game.
+    pub fn parse_position(self) -> Result<Position, ParseError> {
+        self.parse_fields(None, true)
+    }
 
-        let mut rank = 0_u8;
-        let mut file = 0_u8;
+    /// Parses the FEN like [`FEN::parse_position`], but without [`Position::validate`],
+    /// for positions that can't come from a game, such as one being set up by hand.
+    ///
+    /// Move generation assumes a valid position, so the moves from one that isn't
+    /// may not make sense.
+    pub fn parse_position_unchecked(self) -> Result<Position, ParseError> {
+        self.parse_fields(None, false)
+    }
 
-        // parse board position
-        'parsing: while let Some(&b) = it.next() {
-            let raw = rank * 8 + file;
-            if raw >= 64 {
-                break 'parsing;
+    /// Parses the FEN like [`FEN::parse_position`], after fixing the mistakes
+    /// often found in FENs copied from elsewhere, see [`FEN::normalize`].
+    ///
+    /// Offsets in errors are into the normalized FEN.
+    pub fn parse_lenient(self) -> Result<Position, ParseError> {
+        self.normalize().parse_position()
+    }
+
+    /// The FEN with its fields separated by single spaces, and these mistakes fixed:
+    ///
+    /// - missing move clocks start at `0 1`, and when they are missing
+    ///   the castling rights and en passant square can be too
+    /// - the side to move and en passant square are written in lower case
+    /// - castling rights padded with dashes, such as `KQ-` or `--`, have them removed
+    ///
+    /// Check counts are moved before the clocks.
+    pub fn normalize(&self) -> FEN<'static> {
+        let mut fields: Vec<String> = fields(&self.0)
+            .map(|(_, field)| String::from_utf8_lossy(field).into_owned())
+            .collect();
+        // the placement never has a `+`, so a field that does holds the check counts
+        let checks = (1..fields.len())
+            .find(|&i| fields[i].contains('+'))
+            .map(|i| fields.remove(i));
+
+        if fields.len() > 1 {
+            for (i, default) in ["-", "-", "0", "1"].into_iter().enumerate() {
+                if fields.len() == i + 2 {
+                    fields.push(default.to_owned());
+                }
             }
-            let square = Square::from_raw(raw);
-
-            match b {
-                b'p' => board.toggle_square(piece!(p), square),
-                b'n' => board.toggle_square(piece!(n), square),
-                b'b' => board.toggle_square(piece!(b), square),
-                b'r' => board.toggle_square(piece!(r), square),
-                b'q' => board.toggle_square(piece!(q), square),
-                b'k' => board.toggle_square(piece!(k), square),
-                b'P' => board.toggle_square(piece!(P), square),
-                b'N' => board.toggle_square(piece!(N), square),
-                b'B' => board.toggle_square(piece!(B), square),
-                b'R' => board.toggle_square(piece!(R), square),
-                b'Q' => board.toggle_square(piece!(Q), square),
-                b'K' => board.toggle_square(piece!(K), square),
-                b'/' => {
-                    match file.cmp(&8) {
-                        // next rank
-                        std::cmp::Ordering::Equal => {
-                            rank += 1;
-                            file = 0;
-                            continue 'parsing;
-                        }
-                        // too little info for rank
-                        std::cmp::Ordering::Less => return Err(ParseError::TooLittleRankInfo),
-                        // too much info for rank
-                        std::cmp::Ordering::Greater => return Err(ParseError::TooMuchRankInfo),
-                    }
+            fields[1].make_ascii_lowercase();
+            fields[2].retain(|c| c != '-');
+            if fields[2].is_empty() {
+                fields[2].push('-');
+            }
+            fields[3].make_ascii_lowercase();
+        }
+        if let Some(checks) = checks {
+            fields.insert(fields.len().min(4), checks);
+        }
+        FEN(Cow::Owned(fields.join(" ").into_bytes()))
+    }
+
+    /// Parses all six fields of the FEN into a [`Position`] of `variant`.
+    ///
+    /// Pieces in hand can only be given when the variant has drops,
+    /// and check counts when it counts checks.
+    pub fn parse_position_as(self, variant: &'static dyn Variant) -> Result<Position, ParseError> {
+        self.parse_fields(Some(variant), true)
+    }
+
+    /// Parses the FEN, in the variant given or the one its pieces in hand suggest,
+    /// and checks the position is valid if asked to.
+    fn parse_fields(
+        self,
+        variant: Option<&'static dyn Variant>,
+        validate: bool,
+    ) -> Result<Position, ParseError> {
+        let end = self.0.len();
+        let mut fields = fields(&self.0);
+        let mut next = || {
+            fields
+                .next()
+                .ok_or(ParseError::new(ParseErrorKind::MissingField, end))
+        };
+        let error = ParseError::new;
+
+        let (placement_at, placement) = next()?;
+        let Placement {
+            board,
+            promoted,
+            hands,
+        } = parse_placement(placement).map_err(|e| e.after(placement_at))?;
+
+        let (side_at, side) = next()?;
+        let side_to_move = match side {
+            b"w" => Color::White,
+            b"b" => Color::Black,
+            _ => return Err(error(ParseErrorKind::InvalidSideToMove, side_at)),
+        };
+
+        let (castling_at, castling) = next()?;
+        let (castling, castling_rooks, chess960) =
+            parse_castling(castling, &board).map_err(|e| e.after(castling_at))?;
+
+        let (en_passant_at, en_passant) = next()?;
+        let en_passant = match en_passant {
+            b"-" => None,
+            raw => {
+                let square = core::str::from_utf8(raw)
+                    .ok()
+                    .and_then(|s| s.parse::<Square>().ok())
+                    .filter(|square| matches!(square.rank(), Rank::Third | Rank::Sixth))
+                    .ok_or(error(ParseErrorKind::InvalidEnPassant, en_passant_at))?;
+                Some(square)
+            }
+        };
+
+        let (mut clock_at, mut clock) = next()?;
+        let mut checks = None;
+        if clock.contains(&b'+') {
+            checks = Some((
+                clock_at,
+                parse_checks(clock).map_err(|e| e.after(clock_at))?,
+            ));
+            (clock_at, clock) = next()?;
+        }
+        let halfmove_clock = parse_number(clock).map_err(|e| e.after(clock_at))?;
+        let (fullmove_at, fullmove) = next()?;
+        let fullmove_number = parse_number(fullmove).map_err(|e| e.after(fullmove_at))?;
+        let mut extra = next().ok();
+        if let (None, Some((last_at, last @ [b'+', ..]))) = (checks, extra) {
+            checks = Some((last_at, parse_checks(last).map_err(|e| e.after(last_at))?));
+            extra = next().ok();
+        }
+        if let Some((extra_at, _)) = extra {
+            return Err(error(ParseErrorKind::TrailingField, extra_at));
+        }
+
+        let variant: &'static dyn Variant = match (variant, hands, checks) {
+            (Some(variant), _, _) => variant,
+            (None, Some(_), _) => &Crazyhouse,
+            (None, None, Some(_)) => &ThreeCheck,
+            (None, None, None) => &Standard,
+        };
+        if hands.is_some() && !variant.has_drops() {
+            return Err(error(ParseErrorKind::InvalidPocket, placement_at));
+        }
+        let checks_at = checks.map_or(placement_at, |(at, _)| at);
+        let checks = match (checks, variant.check_limit()) {
+            (None, _) => [0; Color::COUNT],
+            (Some(_), None) => return Err(error(ParseErrorKind::InvalidChecks, checks_at)),
+            (Some((_, Checks::Given(given))), Some(_)) => given,
+            (Some((_, Checks::Remaining(remaining))), Some(limit)) => {
+                let given = remaining.map(|remaining| limit.checked_sub(remaining));
+                match given {
+                    [Some(white), Some(black)] => [white, black],
+                    _ => return Err(error(ParseErrorKind::InvalidChecks, checks_at)),
                 }
-                n @ b'1'..=b'8' => {
-                    let inc = n - b'0';
-                    file += inc;
-                    continue 'parsing;
+            }
+        };
+
+        let mut position = Position {
+            board,
+            side_to_move,
+            castling,
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+            key: 0,
+            pawn_key: 0,
+            history: Vec::new(),
+            castling_rooks,
+            chess960,
+            counts: Default::default(),
+            material: Default::default(),
+            kings: Default::default(),
+            variant,
+            hands: hands.unwrap_or_default(),
+            promoted,
+            exploded: Vec::new(),
+            checks,
+        };
+        position.refresh();
+
+        if !validate {
+            return Ok(position);
+        }
+        position.validate().map_err(|e| {
+            let at = match e {
+                ValidationError::OpponentInCheck => side_at,
+                ValidationError::InvalidCastling { .. } => castling_at,
+                ValidationError::InvalidEnPassant(_) => en_passant_at,
+                ValidationError::InvalidChecks(_) => checks_at,
+                _ => placement_at,
+            };
+            error(ParseErrorKind::Invalid(e), at)
+        })?;
+        Ok(position)
+    }
+}
+
+/// The fields of a FEN, each with the offset it starts at.
+fn fields(raw: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut offset = 0;
+    raw.split(|b| b.is_ascii_whitespace())
+        .filter_map(move |field| {
+            let at = offset;
+            offset += field.len() + 1;
+            (!field.is_empty()).then_some((at, field))
+        })
+}
+
+/// Parses castling rights, along with where the castling rooks start
+/// and whether they can only be from a Chess960 game.
+///
+/// As well as `KQkq`, the rook files can be given for Chess960 as in Shredder-FEN (`HAha`),
+/// and `KQkq` picks the outermost rook as in X-FEN.
+/// Shredder-FEN is only written for Chess960, so using it always means a Chess960 game.
+fn parse_castling(
+    raw: &[u8],
+    board: &Board,
+) -> Result<(CastlingRights, [[Square; 2]; Color::COUNT], bool), ParseError> {
+    let mut castling = CastlingRights::NONE;
+    let mut rooks = STANDARD_CASTLING_ROOKS;
+    let mut king_off_e_file = false;
+    let mut shredder = false;
+    if raw == b"-" {
+        return Ok((castling, rooks, false));
+    }
+
+    for (i, &b) in raw.iter().enumerate() {
+        let invalid = ParseError::new(ParseErrorKind::InvalidCastling, i);
+        let (color, rank) = if b.is_ascii_uppercase() {
+            (Color::White, Rank::First)
+        } else {
+            (Color::Black, Rank::Eighth)
+        };
+        let ours = board.colors(color);
+        let king = (board.kings() & ours)
+            .into_iter()
+            .find(|king| king.rank() == rank);
+        let mut back_rooks = (board.rooks() & ours)
+            .into_iter()
+            .filter(|rook| rook.rank() == rank);
+
+        let (side, rook) = match (b.to_ascii_lowercase(), king) {
+            (b'k', Some(king)) => (
+                CastleSide::King,
+                back_rooks.filter(|rook| rook.file() > king.file()).last(),
+            ),
+            (b'q', Some(king)) => (
+                CastleSide::Queen,
+                back_rooks.find(|rook| rook.file() < king.file()),
+            ),
+            // without a king or rook in place the rights can't be used,
+            // but are kept to match the FEN
+            (b'k', None) => (CastleSide::King, None),
+            (b'q', None) => (CastleSide::Queen, None),
+            (file @ b'a'..=b'h', Some(king)) => {
+                shredder = true;
+                let rook = Square::new(rank, File::from_char(file as char).unwrap());
+                match rook.file().cmp(&king.file()) {
+                    core::cmp::Ordering::Greater => (CastleSide::King, Some(rook)),
+                    core::cmp::Ordering::Less => (CastleSide::Queen, Some(rook)),
+                    core::cmp::Ordering::Equal => return Err(invalid),
                 }
-                _ => return Err(ParseError::UnknownChar),
             }
+            _ => return Err(invalid),
+        };
 
-            file += 1;
+        // each right can only be given once
+        if castling.has(color, side) {
+            return Err(invalid);
+        }
+        castling.set(color, side, true);
+        if let Some(rook) = rook {
+            rooks[color as usize][side as usize] = rook;
         }
+        king_off_e_file |= king.is_some_and(|king| king.file() != File::E);
+    }
 
-        // TODO: parse rest of game state
+    let chess960 = shredder || king_off_e_file || rooks != STANDARD_CASTLING_ROOKS;
+    Ok((castling, rooks, chess960))
+}
+
+/// Check counts for Three-check, in either of the ways they can be written.
+#[derive(Clone, Copy)]
+enum Checks {
+    /// How many each side has given, as in `+0+1`.
+    Given([u8; Color::COUNT]),
+    /// How many each side still needs to give, as in `3+2`.
+    Remaining([u8; Color::COUNT]),
+}
 
-        Ok(board)
+fn parse_checks(raw: &[u8]) -> Result<Checks, ParseError> {
+    let invalid = ParseError::new(ParseErrorKind::InvalidChecks, 0);
+    let digit = |b: &u8| match b {
+        b'0'..=b'9' => Ok(b - b'0'),
+        _ => Err(invalid),
+    };
+    match raw {
+        [b'+', white, b'+', black] => Ok(Checks::Given([digit(white)?, digit(black)?])),
+        [white, b'+', black] => Ok(Checks::Remaining([digit(white)?, digit(black)?])),
+        _ => Err(invalid),
     }
 }
 
-impl Board {
-    pub fn to_fen(&self) -> FEN {
-        struct EmptyCounter {
-            count: u32,
-        }
+fn parse_number(raw: &[u8]) -> Result<u32, ParseError> {
+    core::str::from_utf8(raw)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or(ParseError::new(ParseErrorKind::InvalidClock, 0))
+}
+
+/// The piece for a FEN letter.
+const fn piece_from_byte(b: u8) -> Option<Piece> {
+    Some(match b {
+        b'p' => piece!(p),
+        b'n' => piece!(n),
+        b'b' => piece!(b),
+        b'r' => piece!(r),
+        b'q' => piece!(q),
+        b'k' => piece!(k),
+        b'P' => piece!(P),
+        b'N' => piece!(N),
+        b'B' => piece!(B),
+        b'R' => piece!(R),
+        b'Q' => piece!(Q),
+        b'K' => piece!(K),
+        _ => return None,
+    })
+}
+
+/// The piece placement field, along with what Crazyhouse adds to it.
+struct Placement {
+    board: Board,
+    /// Pieces marked as promoted with a `~` after them.
+    promoted: BitBoard,
+    /// The pieces in hand, if any were given.
+    hands: Option<[[u8; PieceKind::COUNT]; Color::COUNT]>,
+}
 
-        impl EmptyCounter {
-            const NEW: Self = EmptyCounter { count: 0 };
+/// Parses the piece placement field at the start of a FEN into a [`Board`].
+const fn parse_board(raw: &[u8]) -> Result<Board, ParseError> {
+    let mut end = 0;
+    while end < raw.len() && !raw[end].is_ascii_whitespace() {
+        end += 1;
+    }
+    match parse_placement(raw.split_at(end).0) {
+        Ok(placement) => Ok(placement.board),
+        Err(e) => Err(e),
+    }
+}
 
-            fn inc(&mut self) {
-                self.count += 1;
+/// Splits the pieces in hand off the end of the piece placement field.
+///
+/// Pieces in hand can follow the board in brackets, as in `RNBQKBNR[Qn]`,
+/// or as a ninth rank, as in `RNBQKBNR/Qn`.
+/// The pieces in hand always start one byte after the board ends.
+const fn split_pocket(raw: &[u8]) -> Result<(&[u8], Option<&[u8]>), ParseError> {
+    let mut slashes = 0;
+    let mut i = 0;
+    while i < raw.len() {
+        match raw[i] {
+            b'[' => {
+                let (board, rest) = raw.split_at(i);
+                return match rest.split_last() {
+                    Some((b']', brackets)) => Ok((board, Some(brackets.split_at(1).1))),
+                    _ => Err(ParseError::new(ParseErrorKind::InvalidPocket, i)),
+                };
             }
+            b'/' => slashes += 1,
+            _ => {}
+        }
+        if slashes == 8 {
+            let (board, rest) = raw.split_at(i);
+            // a ninth rank with empty squares or another rank after it is a board rank,
+            // not pieces in hand
+            let mut j = 1;
+            while j < rest.len() {
+                if matches!(rest[j], b'/' | b'0'..=b'9') {
+                    return Err(ParseError::new(ParseErrorKind::TooManyRanks, i));
+                }
+                j += 1;
+            }
+            return Ok((board, Some(rest.split_at(1).1)));
+        }
+        i += 1;
+    }
+    Ok((raw, None))
+}
+
+/// Parses the piece placement field, starting from the 8th rank.
+///
+/// Written without iterators or `?` so it can run at compile time.
+const fn parse_placement(raw: &[u8]) -> Result<Placement, ParseError> {
+    let (raw, pocket) = match split_pocket(raw) {
+        Ok(split) => split,
+        Err(e) => return Err(e),
+    };
 
-            fn push_if_needed(&mut self, fen: &mut String) {
-                if self.count > 0 {
-                    fen.push(char::from_digit(self.count, 10).unwrap());
-                    self.count = 0;
+    let mut board = Board::empty();
+    let mut promoted = BitBoard::EMPTY;
+    // the square of the piece just placed, which a `~` marks as promoted
+    let mut last: Option<Square> = None;
+
+    let mut rank = 8_u8;
+    let mut file = 1_u8;
+
+    let mut i = 0;
+    while i < raw.len() {
+        let b = raw[i];
+        i += 1;
+        let piece = match b {
+            b'~' => match last {
+                Some(square) => {
+                    promoted = BitBoard(promoted.0 | square.bit());
+                    last = None;
+                    continue;
+                }
+                None => return Err(ParseError::new(ParseErrorKind::UnknownChar, i - 1)),
+            },
+            b'/' => {
+                // next rank
+                if file == 9 && rank > 1 {
+                    rank -= 1;
+                    file = 1;
+                    continue;
+                }
+                return if file < 9 {
+                    Err(ParseError::new(ParseErrorKind::TooLittleRankInfo, i - 1))
+                } else if rank == 1 {
+                    Err(ParseError::new(ParseErrorKind::TooManyRanks, i - 1))
+                } else {
+                    Err(ParseError::new(ParseErrorKind::TooMuchRankInfo, i - 1))
+                };
+            }
+            n @ b'1'..=b'8' => {
+                file += n - b'0';
+                if file > 9 {
+                    return Err(ParseError::new(ParseErrorKind::TooMuchRankInfo, i - 1));
                 }
+                continue;
             }
+            b => match piece_from_byte(b) {
+                Some(piece) => piece,
+                None => return Err(ParseError::new(ParseErrorKind::UnknownChar, i - 1)),
+            },
+        };
+
+        if file > 8 {
+            return Err(ParseError::new(ParseErrorKind::TooMuchRankInfo, i - 1));
         }
+        let square = Square::new(Rank::ALL[rank as usize - 1], File::ALL[file as usize - 1]);
+        board.toggle_square(piece, square);
+        last = Some(square);
+        file += 1;
+    }
 
-        let mut fen = String::new();
-        let mut file = 0_u32;
-        let mut rank = 0_u32;
-        let mut empties = EmptyCounter::NEW;
+    if rank != 1 || file != 9 {
+        return Err(ParseError::new(
+            ParseErrorKind::TooLittleRankInfo,
+            raw.len(),
+        ));
+    }
+
+    let hands = match pocket {
+        Some(pocket) => match parse_pocket(pocket) {
+            Ok(hands) => Some(hands),
+            Err(e) => return Err(e.after(raw.len() + 1)),
+        },
+        None => None,
+    };
+    Ok(Placement {
+        board,
+        promoted,
+        hands,
+    })
+}
 
-        for piece in self.iter() {
-            file += 1;
+/// Counts the pieces in hand, where `-` stands for none.
+const fn parse_pocket(raw: &[u8]) -> Result<[[u8; PieceKind::COUNT]; Color::COUNT], ParseError> {
+    let mut hands = [[0_u8; PieceKind::COUNT]; Color::COUNT];
+    if let [b'-'] = raw {
+        return Ok(hands);
+    }
+    let mut i = 0;
+    while i < raw.len() {
+        let invalid = ParseError::new(ParseErrorKind::InvalidPocket, i);
+        let piece = match piece_from_byte(raw[i]) {
+            Some(piece) if !matches!(piece.kind(), PieceKind::King) => piece,
+            _ => return Err(invalid),
+        };
+        let count = &mut hands[piece.color() as usize][piece.kind() as usize];
+        *count = match count.checked_add(1) {
+            Some(count) => count,
+            None => return Err(invalid),
+        };
+        i += 1;
+    }
+    Ok(hands)
+}
 
-            if let Some(piece) = piece {
+impl Board {
+    /// Parses the piece placement field at the start of `fen`, ignoring any other fields.
+    ///
+    /// Unlike going through a [`FEN`], this can run at compile time:
+    ///
+    /// ```
+    /// # use board::Board;
+    /// const ENDGAME: Board = match Board::from_fen("8/8/4k3/8/8/4K3/4P3/8 w - - 0 1") {
+    ///     Ok(board) => board,
+    ///     Err(_) => panic!("invalid FEN"),
+    /// };
+    /// assert_eq!(ENDGAME.occupied().count(), 3);
+    /// ```
+    pub const fn from_fen(fen: &str) -> Result<Board, ParseError> {
+        parse_board(fen.as_bytes())
+    }
+
+    pub fn to_fen(&self) -> FEN<'_> {
+        FEN(Cow::Owned(placement(self, BitBoard::EMPTY).into_bytes()))
+    }
+}
+
+/// Writes the piece placement field, with a `~` after each promoted piece.
+fn placement(board: &Board, promoted: BitBoard) -> String {
+    struct EmptyCounter {
+        count: u32,
+    }
+
+    impl EmptyCounter {
+        const NEW: Self = EmptyCounter { count: 0 };
+
+        fn inc(&mut self) {
+            self.count += 1;
+        }
+
+        fn push_if_needed(&mut self, fen: &mut String) {
+            if self.count > 0 {
+                fen.push(char::from_digit(self.count, 10).unwrap());
+                self.count = 0;
+            }
+        }
+    }
+
+    let mut fen = String::new();
+    let mut empties = EmptyCounter::NEW;
+
+    for rank in Rank::ALL.into_iter().rev() {
+        for file in File::ALL {
+            let square = Square::new(rank, file);
+            if let Some(piece) = board.piece_on(square) {
                 // push any empty squares before new piece
                 empties.push_if_needed(&mut fen);
 
                 fen.push(piece.as_char());
+                if promoted.is_on(square) {
+                    fen.push('~');
+                }
             } else {
                 // increment empties when there is no piece
                 empties.inc();
             }
+        }
 
-            if file == 8 {
-                // at a new rank, push empty count before
-                empties.push_if_needed(&mut fen);
+        // at a new rank, push empty count before
+        empties.push_if_needed(&mut fen);
 
-                file = 0;
-                rank += 1;
+        // only push a '/' when there is another rank to come
+        if rank > Rank::First {
+            fen.push('/');
+        }
+    }
+
+    fen
+}
 
-                // only push a '/' when there is another rank to come
-                if rank < 8 {
-                    fen.push('/');
+impl Position {
+    /// Writes all six fields of the FEN, using Shredder-FEN castling in Chess960,
+    /// adding the pieces in hand in brackets for variants with drops
+    /// and the checks each side still needs before the clocks for variants that count them.
+    ///
+    /// Parsing the FEN gives back the same position, apart from the moves made to reach it.
+    pub fn to_fen(&self) -> FEN<'static> {
+        FEN(Cow::Owned(self.fen_string().into_bytes()))
+    }
+
+    /// [`Position::to_fen`] as a [`String`].
+    pub(crate) fn fen_string(&self) -> String {
+        let drops = self.variant.has_drops();
+        let promoted = if drops {
+            self.promoted
+        } else {
+            BitBoard::EMPTY
+        };
+        let mut fen = placement(&self.board, promoted);
+
+        if drops {
+            fen.push('[');
+            for color in Color::ALL {
+                for kind in PieceKind::ALL.into_iter().rev() {
+                    let piece = Piece::new_with(color, kind);
+                    for _ in 0..self.in_hand(piece) {
+                        fen.push(piece.as_char());
+                    }
+                }
+            }
+            fen.push(']');
+        }
+
+        fen.push_str(match self.side_to_move {
+            Color::White => " w ",
+            Color::Black => " b ",
+        });
+
+        if self.chess960 && !self.castling.is_empty() {
+            for color in Color::ALL {
+                for side in [CastleSide::King, CastleSide::Queen] {
+                    if self.castling.has(color, side) {
+                        let file = self.castling_rook(color, side).file().as_char();
+                        fen.push(match color {
+                            Color::White => file.to_ascii_uppercase(),
+                            Color::Black => file,
+                        });
+                    }
                 }
             }
+        } else {
+            fen.push_str(&self.castling.to_string());
         }
+        fen.push(' ');
+
+        match self.en_passant {
+            Some(square) => fen.push_str(&square.to_string()),
+            None => fen.push('-'),
+        }
+
+        if let Some(limit) = self.variant.check_limit() {
+            let [white, black] = self.checks.map(|given| limit.saturating_sub(given));
+            fen.push_str(&format!(" {white}+{black}"));
+        }
+
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
+        fen
+    }
+}
+
+impl<'a> TryFrom<&'a str> for FEN<'a> {
+    type Error = ParseError;
+
+    fn try_from(raw: &'a str) -> Result<Self, Self::Error> {
+        check_ascii(raw)?;
+        Ok(Self(Cow::Borrowed(raw.as_bytes())))
+    }
+}
+
+impl TryFrom<String> for FEN<'static> {
+    type Error = ParseError;
+
+    fn try_from(raw: String) -> Result<Self, Self::Error> {
+        check_ascii(&raw)?;
+        Ok(Self(Cow::Owned(raw.into_bytes())))
+    }
+}
 
-        FEN::from_string(fen)
+/// Fails at the first byte that isn't ASCII, which a FEN never has.
+fn check_ascii(raw: &str) -> Result<(), ParseError> {
+    match raw.bytes().position(|b| !b.is_ascii()) {
+        Some(at) => Err(ParseError::new(ParseErrorKind::UnknownChar, at)),
+        None => Ok(()),
     }
 }
 
-impl<'a> std::fmt::Debug for FEN<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("FEN")
-            .field(&std::str::from_utf8(&self.0).unwrap())
-            .finish()
+impl FromStr for FEN<'static> {
+    type Err = ParseError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        Self::try_from(raw.to_owned())
+    }
+}
+
+impl<'a> core::fmt::Display for FEN<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<'a> core::fmt::Debug for FEN<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("FEN").field(&self.as_str()).finish()
     }
 }
 
 #[test]
 fn parse_round_trip() {
-    // TODO: change this to include game state
-    // let fen = FEN::from_str("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
-    let fen = FEN::from_str("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR");
+    let fen = FEN::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR");
 
     let board = fen
         .clone()
@@ -162,4 +813,180 @@ fn parse_round_trip() {
         .expect("FEN string was not parsed to board correctly");
 
     assert_eq!(fen, board.to_fen(), "FEN conversion should be lossless");
+
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w Kq e6 0 2",
+        "r3k2r/8/8/8/8/8/8/R3K2R b - - 37 90",
+        "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1",
+    ] {
+        let position = FEN::new(fen).parse_position().expect("FEN should be valid");
+        assert_eq!(position.to_fen(), FEN::new(fen));
+    }
+
+    // every Chess960 position writes the same FEN it is parsed from
+    for index in (0..960).step_by(37).chain([518]) {
+        let position = Position::chess960(index);
+        let parsed = position.to_fen().parse_position().unwrap();
+        assert!(parsed.is_chess960());
+        assert_eq!(parsed.to_fen(), position.to_fen());
+        assert_eq!(parsed.zobrist_key(), position.zobrist_key());
+    }
+}
+
+#[test]
+fn fen_from_text() {
+    let text = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
+    let fen = text.parse::<FEN>().unwrap();
+    assert_eq!(fen, FEN::new(text));
+    assert_eq!(fen.to_string(), text);
+    assert_eq!(FEN::try_from(text.to_owned()).unwrap(), fen);
+
+    assert_eq!(
+        "4k3/8/8/8/8/8/8/4K3 w - - 0 1 ♔".parse::<FEN>(),
+        Err(ParseError::new(ParseErrorKind::UnknownChar, 30))
+    );
+    assert!(FEN::try_from("♚").is_err());
+}
+
+#[test]
+fn parse_game_state() {
+    let fen = FEN::new("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w Kq e6 0 2");
+    let position = fen.parse_position().expect("FEN should be valid");
+
+    assert_eq!(position.side_to_move(), Color::White);
+    assert!(position.castling().white_king());
+    assert!(!position.castling().white_queen());
+    assert_eq!(position.en_passant(), Some(Square::E6));
+    assert_eq!(position.fullmove_number(), 2);
+    assert_eq!(position.board().piece_on(Square::E1), Some(piece!(K)));
+}
+
+#[test]
+fn const_boards() {
+    const KIWIPETE: Board =
+        match Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq -") {
+            Ok(board) => board,
+            Err(_) => panic!("FEN should be valid"),
+        };
+    let parsed = FEN::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R")
+        .parse_board()
+        .unwrap();
+    assert_eq!(KIWIPETE, parsed);
+
+    // pieces in hand are checked but left off the board
+    assert_eq!(
+        Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[Qn] w KQkq - 0 1").unwrap(),
+        Board::start()
+    );
+    assert_eq!(
+        Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[K]"),
+        Err(ParseError::new(ParseErrorKind::InvalidPocket, 44))
+    );
+    assert_eq!(
+        Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP"),
+        Err(ParseError::new(ParseErrorKind::TooLittleRankInfo, 34))
+    );
+}
+
+#[test]
+fn parse_errors() {
+    let error = |fen| FEN::new(fen).parse_position().unwrap_err();
+    let at = |kind, offset| ParseError::new(kind, offset);
+
+    assert_eq!(
+        error("rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        at(ParseErrorKind::TooLittleRankInfo, 16)
+    );
+    assert_eq!(
+        error("rnbqkbnr/pppppppp/54/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        at(ParseErrorKind::TooMuchRankInfo, 19)
+    );
+    assert_eq!(
+        error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR/8 w KQkq - 0 1"),
+        at(ParseErrorKind::TooManyRanks, 43)
+    );
+    assert_eq!(
+        error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkx - 0 1"),
+        at(ParseErrorKind::InvalidCastling, 49)
+    );
+    assert_eq!(
+        error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KKqk - 0 1"),
+        at(ParseErrorKind::InvalidCastling, 47)
+    );
+    assert_eq!(
+        error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0"),
+        at(ParseErrorKind::MissingField, 54)
+    );
+    assert_eq!(
+        error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 garbage trailing"),
+        at(ParseErrorKind::TrailingField, 57)
+    );
+    assert_eq!(
+        error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 +0+0 x"),
+        at(ParseErrorKind::TrailingField, 62)
+    );
+    assert_eq!(
+        error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1 +0+0"),
+        at(ParseErrorKind::TrailingField, 61)
+    );
+
+    // fields that read fine but can't be true
+    assert_eq!(
+        error("rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1"),
+        at(
+            ParseErrorKind::Invalid(ValidationError::KingCount {
+                color: Color::Black,
+                count: 0
+            }),
+            0
+        )
+    );
+    assert_eq!(
+        error("Pnbqkbnr/8/8/8/8/8/8/4K3 w - - 0 1"),
+        at(
+            ParseErrorKind::Invalid(ValidationError::PawnOnBackRank(Square::A8)),
+            0
+        )
+    );
+    assert_eq!(
+        error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1"),
+        at(
+            ParseErrorKind::Invalid(ValidationError::InvalidEnPassant(Square::E3)),
+            51
+        )
+    );
+}
+
+#[test]
+fn lenient_parsing() {
+    let start = Position::start();
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR W KQkq",
+        "  rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR\tw  KQkq-  - 0 ",
+    ] {
+        assert_eq!(FEN::new(fen).parse_lenient().unwrap(), start, "{fen}");
+        assert!(FEN::new(fen).parse_position().is_err());
+    }
+
+    let fen = FEN::new("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR W -- E6");
+    assert_eq!(
+        fen.normalize(),
+        FEN::new("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w - e6 0 1")
+    );
+    let position = fen.parse_lenient().unwrap();
+    assert_eq!(position.en_passant(), Some(Square::E6));
+
+    // check counts stay with the other fields
+    let fen = FEN::new("4k3/8/8/8/8/8/8/4K3 b - - 0 1 +1+2");
+    assert_eq!(
+        fen.normalize(),
+        FEN::new("4k3/8/8/8/8/8/8/4K3 b - - +1+2 0 1")
+    );
+    assert_eq!(fen.parse_lenient().unwrap().checks_given(Color::Black), 2);
+
+    // leniency doesn't stretch to a missing side to move
+    assert!(FEN::new("4k3/8/8/8/8/8/8/4K3").parse_lenient().is_err());
 }
@@ -0,0 +1,295 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{pgn::Outcome, Color, Move, Position};
+
+/// Why a game is drawn, see [`Game::result`].
+///
+/// Under the FIDE rules some draws end the game straight away,
+/// while the rest only end it if a player claims them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Draw {
+    /// The player to move has no legal moves but isn't in check.
+    Stalemate,
+    /// Neither player can checkmate, see [`Position::is_insufficient_material`].
+    InsufficientMaterial,
+    /// The same position has occurred three times, which can be claimed.
+    ThreefoldRepetition,
+    /// The same position has occurred five times.
+    FivefoldRepetition,
+    /// Fifty moves by each player without a capture or pawn move, which can be claimed.
+    FiftyMoveRule,
+    /// Seventy-five moves by each player without a capture or pawn move.
+    SeventyFiveMoveRule,
+}
+
+impl Draw {
+    /// Returns `true` if the draw only ends the game when a player claims it.
+    pub fn is_claimable(self) -> bool {
+        matches!(self, Self::ThreefoldRepetition | Self::FiftyMoveRule)
+    }
+}
+
+/// How a game has ended, or could be ended by a claim.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    Checkmate {
+        winner: Color,
+    },
+    /// Won by a rule of the [`Variant`](crate::Variant) being played.
+    VariantWin {
+        winner: Color,
+    },
+    Draw(Draw),
+}
+
+impl GameResult {
+    /// The winning player, None for a draw.
+    pub fn winner(self) -> Option<Color> {
+        match self {
+            Self::Checkmate { winner } | Self::VariantWin { winner } => Some(winner),
+            Self::Draw(_) => None,
+        }
+    }
+
+    /// Returns `true` if the game is over without anyone having to claim it.
+    pub fn is_automatic(self) -> bool {
+        match self {
+            Self::Checkmate { .. } | Self::VariantWin { .. } => true,
+            Self::Draw(draw) => !draw.is_claimable(),
+        }
+    }
+
+    /// The result as written in a PGN.
+    pub fn outcome(self) -> Outcome {
+        match self.winner() {
+            Some(Color::White) => Outcome::WhiteWins,
+            Some(Color::Black) => Outcome::BlackWins,
+            None => Outcome::Draw,
+        }
+    }
+}
+
+/// A [`Position`] along with the moves played to reach it.
+#[derive(Clone, Debug)]
+pub struct Game {
+    position: Position,
+    moves: Vec<Move>,
+    /// The Zobrist key of every position reached, including the first.
+    keys: Vec<u64>,
+}
+
+impl Game {
+    /// Starts a game from `position`.
+    pub fn new(position: Position) -> Self {
+        let keys = vec![position.zobrist_key()];
+        Self {
+            position,
+            moves: Vec::new(),
+            keys,
+        }
+    }
+
+    /// Starts a game from the standard starting position.
+    pub fn start() -> Self {
+        Self::new(Position::start())
+    }
+
+    /// The current [`Position`].
+    #[inline]
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// The moves played so far.
+    #[inline]
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    /// Plays a legal [`Move`].
+    pub fn play(&mut self, mv: Move) {
+        self.position.make_move(mv);
+        self.moves.push(mv);
+        self.keys.push(self.position.zobrist_key());
+    }
+
+    /// Takes back the last move, returning it.
+    pub fn undo(&mut self) -> Option<Move> {
+        let mv = self.moves.pop()?;
+        self.position.unmake_move();
+        self.keys.pop();
+        Some(mv)
+    }
+
+    /// How many times the current position has occurred, including now.
+    pub fn repetitions(&self) -> usize {
+        let current = self.position.zobrist_key();
+
+        // a capture or pawn move means no earlier position can repeat,
+        // and positions with the other side to move can't be equal
+        self.keys
+            .iter()
+            .rev()
+            .take(self.position.halfmove_clock() as usize + 1)
+            .step_by(2)
+            .filter(|&&key| key == current)
+            .count()
+    }
+
+    /// Returns `true` if the current position has occurred at least three times.
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetitions() >= 3
+    }
+
+    /// Returns `true` if fifty moves have been made by each player
+    /// without a capture or pawn move, unless the last move gave checkmate.
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.position.halfmove_clock() >= 100 && !self.position.is_checkmate()
+    }
+
+    /// Returns `true` if seventy-five moves have been made by each player
+    /// without a capture or pawn move, unless the last move gave checkmate.
+    pub fn is_seventy_five_move_draw(&self) -> bool {
+        self.position.halfmove_clock() >= 150 && !self.position.is_checkmate()
+    }
+
+    /// Returns `true` if the current position has occurred at least five times.
+    pub fn is_fivefold_repetition(&self) -> bool {
+        self.repetitions() >= 5
+    }
+
+    /// How the game has ended, or None if it is still going.
+    ///
+    /// Draws that must be claimed are included, since either player could end the game with them;
+    /// [`GameResult::is_automatic`] tells them apart from the ones that end it regardless.
+    /// When several apply, the automatic ones are returned first.
+    pub fn result(&self) -> Option<GameResult> {
+        let position = &self.position;
+        if let Some(winner) = position.variant().winner(position) {
+            return Some(GameResult::VariantWin { winner });
+        }
+        if position.legal_moves().is_empty() {
+            return Some(if position.in_check(position.side_to_move()) {
+                GameResult::Checkmate {
+                    winner: position.side_to_move().opposite(),
+                }
+            } else if position.variant().stalemate_is_win() {
+                GameResult::VariantWin {
+                    winner: position.side_to_move(),
+                }
+            } else {
+                GameResult::Draw(Draw::Stalemate)
+            });
+        }
+
+        let draw = if position.is_insufficient_material() {
+            Draw::InsufficientMaterial
+        } else if self.is_fivefold_repetition() {
+            Draw::FivefoldRepetition
+        } else if position.halfmove_clock() >= 150 {
+            Draw::SeventyFiveMoveRule
+        } else {
+            self.draw_status()?
+        };
+        Some(GameResult::Draw(draw))
+    }
+
+    /// Whether either player could claim a draw now.
+    pub fn draw_status(&self) -> Option<Draw> {
+        if self.is_threefold_repetition() {
+            Some(Draw::ThreefoldRepetition)
+        } else if self.is_fifty_move_draw() {
+            Some(Draw::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FEN;
+
+    #[test]
+    fn threefold_repetition() {
+        let mut game = Game::start();
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+        for _ in 0..2 {
+            assert!(!game.is_threefold_repetition());
+            for mv in shuffle {
+                game.play(game.position().parse_uci(mv).unwrap());
+            }
+        }
+
+        assert_eq!(game.repetitions(), 3);
+        assert_eq!(game.draw_status(), Some(Draw::ThreefoldRepetition));
+
+        game.undo();
+        assert_eq!(game.draw_status(), None);
+    }
+
+    #[test]
+    fn fifty_move_rule() {
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 99 80";
+        let mut game = Game::new(FEN::new(fen).parse_position().unwrap());
+        assert_eq!(game.draw_status(), None);
+
+        game.play(game.position().parse_uci("a1a2").unwrap());
+        assert_eq!(game.draw_status(), Some(Draw::FiftyMoveRule));
+        assert!(!game.result().unwrap().is_automatic());
+
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 149 120";
+        let mut game = Game::new(FEN::new(fen).parse_position().unwrap());
+        game.play(game.position().parse_uci("a1a2").unwrap());
+        assert!(game.is_seventy_five_move_draw());
+        assert_eq!(
+            game.result(),
+            Some(GameResult::Draw(Draw::SeventyFiveMoveRule))
+        );
+    }
+
+    #[test]
+    fn results() {
+        let result = |fen: &str| Game::new(FEN::new(fen).parse_position().unwrap()).result();
+
+        assert_eq!(result("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"), None);
+        let mate = result("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+        assert_eq!(mate.winner(), Some(Color::Black));
+        assert_eq!(mate.outcome(), Outcome::BlackWins);
+        assert_eq!(
+            result("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+            Some(GameResult::Draw(Draw::Stalemate))
+        );
+
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "2b1k3/8/8/8/8/8/8/4KB2 w - - 0 1",
+        ] {
+            assert_eq!(
+                result(fen),
+                Some(GameResult::Draw(Draw::InsufficientMaterial))
+            );
+        }
+        // bishops on different colors can still mate
+        assert_eq!(result("3bk3/8/8/8/8/8/8/4KB2 w - - 0 1"), None);
+        assert_eq!(result("4k3/8/8/8/8/8/8/3NKN2 w - - 0 1"), None);
+    }
+
+    #[test]
+    fn fivefold_repetition() {
+        let mut game = Game::start();
+        for _ in 0..4 {
+            for mv in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                game.play(game.position().parse_uci(mv).unwrap());
+            }
+        }
+
+        assert_eq!(game.repetitions(), 5);
+        let result = game.result().unwrap();
+        assert_eq!(result, GameResult::Draw(Draw::FivefoldRepetition));
+        assert!(result.is_automatic());
+    }
+}
@@ -92,7 +92,7 @@ fn api() {
         iter.next(),
         Some(Some(
             Piece::new()
-                .with_color(crate::Color::Black)
+                .with_color(crate::Color::White)
                 .with_kind(crate::PieceKind::Rook)
         ))
     );
@@ -0,0 +1,370 @@
+//! Explaining why a move can't be played, for showing to a user.
+
+use crate::{
+    attacks::{bishop_attacks, king_attacks, knight_attacks, pawn_attacks, rook_attacks},
+    tables::between,
+    BitBoard, CastleSide, Color, Move, MoveFlag, Piece, PieceKind, Position, Rank, Square,
+};
+
+/// Why [`Position::why_illegal`] rejected a move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum IllegalMoveReason {
+    #[error("there is no piece on {0}")]
+    NoPiece(Square),
+    #[error("the piece on {0} belongs to the side not to move")]
+    WrongSide(Square),
+    #[error("{0} holds a piece of the same color")]
+    OwnPiece(Square),
+    #[error("a {0:?} can't move that way")]
+    CannotMoveThatWay(PieceKind),
+    #[error("the way is blocked by the piece on {0}")]
+    Blocked(Square),
+    #[error("a pawn can only move diagonally to capture")]
+    NothingToCapture,
+    #[error("a pawn reaching the last rank must promote")]
+    MustPromote,
+    #[error("only a pawn reaching the last rank can promote")]
+    CannotPromote,
+    #[error("the king has lost the right to castle {0:?} side")]
+    NoCastlingRights(CastleSide),
+    #[error("the king can't castle out of check")]
+    CastleOutOfCheck,
+    #[error("the king can't castle through {0}, which is attacked")]
+    CastleThroughCheck(Square),
+    #[error("the king would be in check on {0}")]
+    KingAttacked(Square),
+    #[error("the move doesn't get the king out of check")]
+    StillInCheck,
+    #[error("the move would expose the king to check")]
+    ExposesKing,
+    #[error("there is no king to move")]
+    NoKing,
+    #[error("the move's flag doesn't match how the piece moves")]
+    WrongFlag,
+    #[error("pieces can't be dropped in this variant")]
+    NoDrops,
+    #[error("there is no {0:?} in hand to drop")]
+    NotInHand(PieceKind),
+    #[error("pieces can only be dropped on empty squares, not {0}")]
+    DropOnPiece(Square),
+    #[error("a pawn can't be dropped on the first or last rank")]
+    PawnDropOnBackRank,
+    #[error("a capture has to be made when one is possible")]
+    MustCapture,
+}
+
+impl Position {
+    /// Explains why `mv` isn't legal, or returns None if it is.
+    ///
+    /// The reasons are checked roughly in the order a player would think of them,
+    /// so a move with several problems reports the most basic one.
+    pub fn why_illegal(&self, mv: Move) -> Option<IllegalMoveReason> {
+        use IllegalMoveReason::*;
+
+        if self.legal_moves().contains(&mv) {
+            return None;
+        }
+
+        if let Some(kind) = mv.dropped() {
+            return Some(self.why_drop_illegal(kind, mv.to()));
+        }
+
+        let board = self.board();
+        let us = self.side_to_move();
+        let (from, to) = (mv.from(), mv.to());
+
+        let Some(piece) = board.piece_on(from) else {
+            return Some(NoPiece(from));
+        };
+        if piece.color() != us {
+            return Some(WrongSide(from));
+        }
+
+        let kind = piece.kind();
+        let is_castle = mv.flag() == MoveFlag::Castle
+            || kind == PieceKind::King
+                && from.rank() == to.rank()
+                && from.file().distance(to.file()) == 2;
+        if is_castle {
+            return Some(self.why_castling_illegal(mv).unwrap_or(WrongFlag));
+        }
+
+        if board.colors(us).is_on(to) {
+            return Some(OwnPiece(to));
+        }
+
+        let last_rank = Rank::Eighth.relative_to(us);
+        if mv.promotion().is_some() && (kind != PieceKind::Pawn || to.rank() != last_rank) {
+            return Some(CannotPromote);
+        }
+
+        let occupied = board.occupied();
+        let reach = match kind {
+            PieceKind::Pawn => return Some(self.why_pawn_illegal(mv).unwrap_or(WrongFlag)),
+            PieceKind::Knight => knight_attacks(from),
+            PieceKind::Bishop => bishop_attacks(from, BitBoard::EMPTY),
+            PieceKind::Rook => rook_attacks(from, BitBoard::EMPTY),
+            PieceKind::Queen => {
+                bishop_attacks(from, BitBoard::EMPTY) | rook_attacks(from, BitBoard::EMPTY)
+            }
+            PieceKind::King => king_attacks(from),
+        };
+        if !reach.is_on(to) {
+            return Some(CannotMoveThatWay(kind));
+        }
+        if let Some(blocker) = nearest(from, between(from, to) & occupied) {
+            return Some(Blocked(blocker));
+        }
+        if mv.flag() != MoveFlag::Normal {
+            return Some(WrongFlag);
+        }
+
+        Some(self.why_unsafe(kind, to))
+    }
+
+    /// Why a move the piece could otherwise make isn't legal,
+    /// which is usually that it leaves the king in check.
+    fn why_unsafe(&self, kind: PieceKind, to: Square) -> IllegalMoveReason {
+        use IllegalMoveReason::*;
+
+        if self.variant().captures_are_compulsory() {
+            return MustCapture;
+        }
+        match self.find_king(self.side_to_move()) {
+            None => NoKing,
+            Some(_) if kind == PieceKind::King => KingAttacked(to),
+            Some(king) => self.why_king_unsafe(king),
+        }
+    }
+
+    fn why_drop_illegal(&self, kind: PieceKind, to: Square) -> IllegalMoveReason {
+        use IllegalMoveReason::*;
+
+        let us = self.side_to_move();
+        if !self.variant().has_drops() {
+            return NoDrops;
+        }
+        if self.in_hand(Piece::new_with(us, kind)) == 0 {
+            return NotInHand(kind);
+        }
+        if self.board().occupied().is_on(to) {
+            return DropOnPiece(to);
+        }
+        if kind == PieceKind::Pawn && matches!(to.rank(), Rank::First | Rank::Eighth) {
+            return PawnDropOnBackRank;
+        }
+        self.why_unsafe(kind, to)
+    }
+
+    fn why_pawn_illegal(&self, mv: Move) -> Option<IllegalMoveReason> {
+        use IllegalMoveReason::*;
+
+        let board = self.board();
+        let us = self.side_to_move();
+        let (from, to) = (mv.from(), mv.to());
+        let forward = match us {
+            Color::White => 1,
+            Color::Black => -1,
+        };
+        let start_rank = Rank::Second.relative_to(us);
+        let last_rank = Rank::Eighth.relative_to(us);
+
+        if pawn_attacks(us, from).is_on(to) {
+            let en_passant = self.en_passant() == Some(to);
+            if !en_passant && !board.colors(us.opposite()).is_on(to) {
+                return Some(NothingToCapture);
+            }
+            if en_passant != (mv.flag() == MoveFlag::EnPassant) {
+                return Some(WrongFlag);
+            }
+        } else if from.file() == to.file() {
+            let steps = (to.rank() as i8 - from.rank() as i8) * forward;
+            let double = steps == 2 && from.rank() == start_rank;
+            if steps != 1 && !double {
+                return Some(CannotMoveThatWay(PieceKind::Pawn));
+            }
+            let path = between(from, to) | BitBoard::from_square(to);
+            if let Some(blocker) = nearest(from, path & board.occupied()) {
+                return Some(Blocked(blocker));
+            }
+            if double != (mv.flag() == MoveFlag::DoublePush) {
+                return Some(WrongFlag);
+            }
+        } else {
+            return Some(CannotMoveThatWay(PieceKind::Pawn));
+        }
+
+        if to.rank() == last_rank && mv.promotion().is_none() {
+            return Some(MustPromote);
+        }
+        if mv
+            .promotion()
+            .is_some_and(|kind| !self.variant().promotions().contains(&kind))
+        {
+            return Some(CannotPromote);
+        }
+        Some(self.why_unsafe(PieceKind::Pawn, to))
+    }
+
+    fn why_castling_illegal(&self, mv: Move) -> Option<IllegalMoveReason> {
+        use IllegalMoveReason::*;
+
+        let board = self.board();
+        let us = self.side_to_move();
+        let (king, to) = (mv.from(), mv.to());
+        let rank = Rank::First.relative_to(us);
+        if board.piece_on(king).map(|piece| piece.kind()) != Some(PieceKind::King)
+            || king.rank() != rank
+            || to.rank() != rank
+        {
+            return Some(CannotMoveThatWay(PieceKind::King));
+        }
+
+        let side = if to.file() > king.file() {
+            CastleSide::King
+        } else {
+            CastleSide::Queen
+        };
+        let rook = self.castling_rook(us, side);
+        let rook_there = (board.rooks() & board.colors(us)).is_on(rook);
+        if !self.castling().has(us, side) || !rook_there {
+            return Some(NoCastlingRights(side));
+        }
+
+        let (king_file, rook_file) = side.destination_files();
+        if to.file() != king_file {
+            return Some(CannotMoveThatWay(PieceKind::King));
+        }
+        if self.in_check(us) {
+            return Some(CastleOutOfCheck);
+        }
+
+        let rook_to = Square::new(rank, rook_file);
+        let movers = BitBoard::from_square(king) | BitBoard::from_square(rook);
+        let occupied = board.occupied() - movers;
+        let king_path = between(king, to) | BitBoard::from_square(to);
+        let rook_path = between(rook, rook_to) | BitBoard::from_square(rook_to);
+        let blockers = (king_path | rook_path) & occupied;
+        if let Some(blocker) = nearest(king, blockers) {
+            return Some(Blocked(blocker));
+        }
+
+        let them = board.colors(us.opposite());
+        let attacked = nearest(
+            king,
+            king_path
+                .into_iter()
+                .filter(|&sq| !(board.attackers_to(sq, occupied) & them).is_empty())
+                .collect(),
+        );
+        if let Some(square) = attacked {
+            return Some(if square == to {
+                KingAttacked(to)
+            } else {
+                CastleThroughCheck(square)
+            });
+        }
+        // the move is castling done right, so only its flag can be wrong
+        (mv.flag() != MoveFlag::Castle).then_some(WrongFlag)
+    }
+
+    /// Why a move that the piece could otherwise make is illegal.
+    fn why_king_unsafe(&self, king: Square) -> IllegalMoveReason {
+        let them = self.board().colors(self.side_to_move().opposite());
+        let checkers = self.board().attackers_to(king, self.board().occupied()) & them;
+        if checkers.is_empty() {
+            IllegalMoveReason::ExposesKing
+        } else {
+            IllegalMoveReason::StillInCheck
+        }
+    }
+}
+
+/// The square in `squares` closest to `from`.
+fn nearest(from: Square, squares: BitBoard) -> Option<Square> {
+    squares.into_iter().min_by_key(|&sq| from.distance(sq))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FEN;
+
+    fn why(fen: &str, from: Square, to: Square, flag: MoveFlag) -> Option<IllegalMoveReason> {
+        let position = FEN::new(fen).parse_position().unwrap();
+        position.why_illegal(Move::new(from, to, flag))
+    }
+
+    #[test]
+    fn reasons() {
+        use IllegalMoveReason::*;
+        use MoveFlag::*;
+        use Square::*;
+
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        assert_eq!(why(start, E2, E4, DoublePush), None);
+        assert_eq!(why(start, E3, E4, Normal), Some(NoPiece(E3)));
+        assert_eq!(why(start, E7, E5, DoublePush), Some(WrongSide(E7)));
+        assert_eq!(why(start, D1, D2, Normal), Some(OwnPiece(D2)));
+        assert_eq!(
+            why(start, G1, G3, Normal),
+            Some(CannotMoveThatWay(PieceKind::Knight))
+        );
+        assert_eq!(why(start, C1, F4, Normal), Some(Blocked(D2)));
+        assert_eq!(why(start, E2, D3, Normal), Some(NothingToCapture));
+        assert_eq!(why(start, E2, E4, Normal), Some(WrongFlag));
+
+        let check = "4k3/8/8/8/1b6/8/7P/4K2R w K - 0 1";
+        assert_eq!(why(check, H2, H3, Normal), Some(StillInCheck));
+        assert_eq!(why(check, E1, G1, Castle), Some(CastleOutOfCheck));
+        assert_eq!(why(check, E1, D2, Normal), Some(KingAttacked(D2)));
+        assert_eq!(why(check, E1, E2, Normal), None);
+
+        let pinned = "4k3/8/8/8/1b6/8/3N4/4K3 w - - 0 1";
+        assert_eq!(why(pinned, D2, F3, Normal), Some(ExposesKing));
+        assert_eq!(why(pinned, E1, D1, Normal), None);
+        assert_eq!(why(pinned, E1, F2, Normal), None);
+        assert_eq!(why(pinned, E1, E2, Normal), None);
+
+        let castling = "4k3/8/8/8/8/8/8/R3K1rR w Q - 0 1";
+        assert_eq!(
+            why(castling, E1, G1, Castle),
+            Some(NoCastlingRights(CastleSide::King))
+        );
+        let castling = "4k3/8/8/8/8/5r2/8/RN2K2R w KQ - 0 1";
+        assert_eq!(why(castling, E1, C1, Castle), Some(Blocked(B1)));
+        assert_eq!(why(castling, E1, G1, Castle), Some(CastleThroughCheck(F1)));
+        assert_eq!(why(castling, E1, F1, Normal), Some(KingAttacked(F1)));
+
+        let promotion = "4k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(why(promotion, A7, A8, Normal), Some(MustPromote));
+        let position = FEN::new(promotion).parse_position().unwrap();
+        let knight = Move::new_promotion(E1, E2, PieceKind::Queen);
+        assert_eq!(position.why_illegal(knight), Some(CannotPromote));
+    }
+
+    #[test]
+    fn drops() {
+        use IllegalMoveReason::*;
+        use PieceKind::*;
+        use Square::*;
+
+        let why = |fen: &str, kind, to| {
+            let position = FEN::new(fen).parse_position().unwrap();
+            position.why_illegal(Move::new_drop(kind, to))
+        };
+
+        let standard = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(why(standard, Knight, F3), Some(NoDrops));
+
+        let crazyhouse = "4k3/8/8/8/8/8/8/4K3[Pn] w - - 0 1";
+        assert_eq!(why(crazyhouse, Pawn, E4), None);
+        assert_eq!(why(crazyhouse, Knight, F3), Some(NotInHand(Knight)));
+        assert_eq!(why(crazyhouse, Pawn, E1), Some(DropOnPiece(E1)));
+        assert_eq!(why(crazyhouse, Pawn, A8), Some(PawnDropOnBackRank));
+
+        let check = "4k3/8/8/8/8/8/8/r3K3[N] w - - 0 1";
+        assert_eq!(why(check, Knight, B1), None);
+        assert_eq!(why(check, Knight, F3), Some(StillInCheck));
+    }
+}
@@ -1,68 +1,218 @@
 #![feature(variant_count)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+pub mod annotation;
+pub mod attacks;
 mod bitboard;
-mod fen;
+pub mod builder;
+mod castling;
+pub mod clock;
+pub mod database;
+#[cfg(feature = "dgt")]
+pub mod dgt;
+mod display;
+pub mod epd;
+pub mod features;
+pub mod fen;
+mod game;
 mod iter;
+mod legality;
+mod magic;
+pub mod movegen;
+mod moves;
+pub mod openings;
+pub mod pgn;
 mod piece;
+mod position;
+#[cfg(feature = "proptest")]
+mod proptest_impls;
+#[cfg(feature = "std")]
+pub mod render;
+mod san;
+mod see;
+#[cfg(feature = "serde")]
+mod serde_impls;
 mod square;
+pub mod tables;
+mod validate;
+pub mod variant;
+pub mod zobrist;
 
 pub use bitboard::BitBoard;
+pub use castling::{CastleSide, CastlingRights};
+pub use display::BoardDisplay;
 pub use fen::FEN;
+pub use game::{Draw, Game, GameResult};
+pub use legality::IllegalMoveReason;
+pub use moves::{Move, MoveFlag, MoveList, UciMoveError};
 pub use piece::{Color, Piece, PieceKind};
-pub use square::Square;
+pub use position::Position;
+pub use san::SanError;
+pub use square::{File, ParseSquareError, Rank, Square};
+pub use validate::ValidationError;
+pub use variant::Variant;
+
+/// Returned when placing a [`Piece`] on a [`Square`] that is already taken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{square} is already occupied by '{}'", .occupant.as_char())]
+pub struct OccupiedError {
+    pub square: Square,
+    pub occupant: Piece,
+}
 
+/// Boards are equal when every piece is on the same square.
+///
+/// Pieces are kept both in bitboards, for generating moves,
+/// and in a mailbox of squares, for finding what is on a square at once.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Board {
     pieces: [BitBoard; PieceKind::COUNT],
     colors: [BitBoard; Color::COUNT],
+    mailbox: [Option<Piece>; 64],
 }
 
 impl Board {
+    /// The starting board for a game of chess, parsed at compile time.
+    ///
+    /// See [`Position::START`] for the whole starting position.
+    pub const START: Self =
+        match Self::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1") {
+            Ok(board) => board,
+            Err(_) => panic!("Failed to parse FEN for starting position"),
+        };
+
     /// Creates an empty [`Board`].
-    pub fn empty() -> Self {
+    pub const fn empty() -> Self {
         Self {
             pieces: [BitBoard::EMPTY; PieceKind::COUNT],
             colors: [BitBoard::EMPTY; Color::COUNT],
+            mailbox: [None; 64],
         }
     }
 
-    /// The starting board state for a game of chess.
-    pub fn start() -> Self {
-        const START_FEN: FEN =
-            FEN::from_str("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    /// Creates a [`Board`] from its bitboards, filling in the mailbox to match.
+    pub(crate) fn from_bitboards(
+        pieces: [BitBoard; PieceKind::COUNT],
+        colors: [BitBoard; Color::COUNT],
+    ) -> Self {
+        let mut board = Self {
+            pieces,
+            colors,
+            mailbox: [None; 64],
+        };
+        for square in board.occupied() {
+            let kind = PieceKind::ALL
+                .into_iter()
+                .find(|&kind| board.pieces(kind).is_on(square));
+            let color = Color::ALL
+                .into_iter()
+                .find(|&color| board.colors(color).is_on(square));
+            if let (Some(kind), Some(color)) = (kind, color) {
+                board.mailbox[square.index()] = Some(Piece::new_with(color, kind));
+            }
+        }
+        board
+    }
 
-        START_FEN
-            .parse_board()
-            .expect("Failed to parse FEN for starting position")
+    /// The starting board state for a game of chess, see [`Board::START`].
+    #[inline]
+    pub const fn start() -> Self {
+        Self::START
+    }
+
+    /// One of the 960 starting boards for Chess960, numbered as in the
+    /// [standard scheme](https://en.wikipedia.org/wiki/Fischer_random_chess_numbering_scheme)
+    /// where 518 is the usual starting board.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not below 960.
+    pub fn chess960(index: u16) -> Self {
+        assert!(index < 960, "there are only 960 starting positions");
+
+        // where the two knights go among the five squares left after the bishops and queen
+        const KNIGHTS: [(usize, usize); 10] = [
+            (0, 1),
+            (0, 2),
+            (0, 3),
+            (0, 4),
+            (1, 2),
+            (1, 3),
+            (1, 4),
+            (2, 3),
+            (2, 4),
+            (3, 4),
+        ];
+
+        let mut index = index as usize;
+        let mut back_rank = [None; 8];
+
+        back_rank[index % 4 * 2 + 1] = Some(PieceKind::Bishop);
+        index /= 4;
+        back_rank[index % 4 * 2] = Some(PieceKind::Bishop);
+        index /= 4;
+
+        let mut place = |n: usize, kind: PieceKind| {
+            let file = (0..8).filter(|&f| back_rank[f].is_none()).nth(n).unwrap();
+            back_rank[file] = Some(kind);
+        };
+        place(index % 6, PieceKind::Queen);
+        let (first, second) = KNIGHTS[index / 6];
+        // placing the second knight first keeps the first's index valid
+        place(second, PieceKind::Knight);
+        place(first, PieceKind::Knight);
+        for kind in [PieceKind::Rook, PieceKind::King, PieceKind::Rook] {
+            place(0, kind);
+        }
+
+        let mut board = Self::empty();
+        for (file, kind) in File::ALL.into_iter().zip(back_rank) {
+            let kind = kind.expect("every file should be filled");
+            board.toggle_square(
+                Piece::new_with(Color::White, kind),
+                Square::new(Rank::First, file),
+            );
+            board.toggle_square(
+                Piece::new_with(Color::Black, kind),
+                Square::new(Rank::Eighth, file),
+            );
+            board.toggle_square(
+                Piece::new_with(Color::White, PieceKind::Pawn),
+                Square::new(Rank::Second, file),
+            );
+            board.toggle_square(
+                Piece::new_with(Color::Black, PieceKind::Pawn),
+                Square::new(Rank::Seventh, file),
+            );
+        }
+        board
     }
 
     /// Returns an iterator over the boards [`Pieces`](Piece).
-    pub fn iter(&self) -> iter::Iter {
+    pub fn iter(&self) -> iter::Iter<'_> {
         iter::Iter::new(self)
     }
 
     /// Finds the [`Piece`] on a [`Square`].
     ///
     /// If there is nothing on the [`Square`], None is returned.
+    #[inline]
     pub fn piece_on(&self, square: Square) -> Option<Piece> {
-        let kind = self.kind_on(square)?;
-        let is_white = self.whites().is_on(square);
-        let color = if is_white { Color::White } else { Color::Black };
-
-        Some(Piece::new().with_kind(kind).with_color(color))
+        self.mailbox[square.index()]
     }
 
     /// Finds the kind of [`Piece`] on a square, without it's color.
+    #[inline]
     pub fn kind_on(&self, square: Square) -> Option<PieceKind> {
-        PieceKind::ALL
-            .into_iter()
-            .find(|&p| self.pieces(p).is_on(square))
+        self.piece_on(square).map(|piece| piece.kind())
     }
 
     /// Finds the color of a [`Piece`] on a square.
+    #[inline]
     pub fn color_of(&self, square: Square) -> Option<Color> {
-        Color::ALL
-            .into_iter()
-            .find(|&c| self.colors(c).is_on(square))
+        self.piece_on(square).map(|piece| piece.color())
     }
 
     /// The [`BitBoard`] for a specific [`PieceKind`].
@@ -72,6 +222,11 @@ impl Board {
     }
 
     /// A mutable [`BitBoard`] for a specific [`PieceKind`].
+    ///
+    /// Only the bitboard changes, so [`Board::piece_on`] and the rest of the
+    /// lookups by square don't see the change. Use [`Board::set_piece`],
+    /// [`Board::remove_piece`] or [`Board::toggle_square`], which keep them in step.
+    #[deprecated(note = "lookups by square miss the change, use `set_piece` or `remove_piece`")]
     #[inline]
     pub fn pieces_mut(&mut self, kind: PieceKind) -> &mut BitBoard {
         &mut self.pieces[kind as usize]
@@ -84,16 +239,63 @@ impl Board {
     }
 
     /// A mutable [`BitBoard`] for all the pieces of a specific [`Color`].
+    ///
+    /// Only the bitboard changes, see [`Board::pieces_mut`].
+    #[deprecated(note = "lookups by square miss the change, use `set_piece` or `remove_piece`")]
     #[inline]
     pub fn colors_mut(&mut self, color: Color) -> &mut BitBoard {
         &mut self.colors[color as usize]
     }
 
     /// Toggles a [`Piece`] on a [`Square`].
+    ///
+    /// Nothing is checked, so toggling onto a square holding another piece
+    /// leaves the board inconsistent. [`Board::set_piece`] catches this.
     #[inline]
-    pub fn toggle_square(&mut self, piece: Piece, square: Square) {
-        self.pieces_mut(piece.kind()).toggle(square);
-        self.colors_mut(piece.color()).toggle(square);
+    pub const fn toggle_square(&mut self, piece: Piece, square: Square) {
+        self.pieces[piece.kind() as usize].toggle(square);
+        self.colors[piece.color() as usize].toggle(square);
+
+        let entry = &mut self.mailbox[square.index()];
+        *entry = match *entry {
+            Some(on) if on.into_bits() == piece.into_bits() => None,
+            _ => Some(piece),
+        };
+    }
+
+    /// Places a [`Piece`] on an empty [`Square`].
+    pub fn set_piece(&mut self, piece: Piece, square: Square) -> Result<(), OccupiedError> {
+        if let Some(occupant) = self.piece_on(square) {
+            return Err(OccupiedError { square, occupant });
+        }
+        self.toggle_square(piece, square);
+        Ok(())
+    }
+
+    /// Takes the [`Piece`] off a [`Square`], returning it.
+    ///
+    /// If there is nothing on the [`Square`], None is returned.
+    pub fn remove_piece(&mut self, square: Square) -> Option<Piece> {
+        let piece = self.piece_on(square)?;
+        self.toggle_square(piece, square);
+        Some(piece)
+    }
+
+    /// Moves the [`Piece`] on `from` to the empty square `to`, returning it.
+    ///
+    /// If there is nothing on `from`, None is returned and the board is unchanged.
+    pub fn move_piece(&mut self, from: Square, to: Square) -> Result<Option<Piece>, OccupiedError> {
+        if let Some(occupant) = self.piece_on(to) {
+            return Err(OccupiedError {
+                square: to,
+                occupant,
+            });
+        }
+        let Some(piece) = self.remove_piece(from) else {
+            return Ok(None);
+        };
+        self.toggle_square(piece, to);
+        Ok(Some(piece))
     }
 
     /// All occupied spaces are represented by this [`BitBoard`].
@@ -151,21 +353,218 @@ impl Board {
     pub fn kings(&self) -> BitBoard {
         self.pieces(PieceKind::King)
     }
+
+    /// Where the king of `color` stands.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `color` has no king.
+    #[inline]
+    pub fn king_square(&self, color: Color) -> Square {
+        self.find_king(color).expect("there should be a king")
+    }
+
+    /// Where the king of `color` stands, or None if it has no king.
+    #[inline]
+    pub(crate) fn find_king(&self, color: Color) -> Option<Square> {
+        (self.kings() & self.colors(color)).lsb()
+    }
+
+    /// All pieces of either color that attack `square`.
+    ///
+    /// Sliding attacks are blocked by `occupied` rather than the pieces on the board,
+    /// so squares can be cleared or filled to see what attacks through them.
+    pub fn attackers_to(&self, square: Square, occupied: BitBoard) -> BitBoard {
+        use attacks::*;
+
+        let rooks = self.rooks() | self.queens();
+        let bishops = self.bishops() | self.queens();
+
+        (pawn_attacks(Color::White, square) & self.pawns() & self.blacks())
+            | (pawn_attacks(Color::Black, square) & self.pawns() & self.whites())
+            | (knight_attacks(square) & self.knights())
+            | (king_attacks(square) & self.kings())
+            | (rook_attacks(square, occupied) & rooks)
+            | (bishop_attacks(square, occupied) & bishops)
+    }
+
+    /// The pieces standing alone between `square` and a slider in `snipers` aiming at it,
+    /// of either color.
+    pub(crate) fn slider_blockers(&self, square: Square, snipers: BitBoard) -> BitBoard {
+        use attacks::{bishop_attacks, rook_attacks};
+
+        let snipers = (rook_attacks(square, BitBoard::EMPTY) & (self.rooks() | self.queens())
+            | bishop_attacks(square, BitBoard::EMPTY) & (self.bishops() | self.queens()))
+            & snipers;
+
+        let mut blockers = BitBoard::EMPTY;
+        for sniper in snipers {
+            let between = tables::between(square, sniper) & self.occupied();
+            if !between.is_empty() && !between.has_many() {
+                blockers |= between;
+            }
+        }
+        blockers
+    }
+
+    /// Returns `true` if any piece of `color` attacks `square`.
+    pub fn is_attacked_by(&self, square: Square, color: Color) -> bool {
+        !(self.attackers_to(square, self.occupied()) & self.colors(color)).is_empty()
+    }
+
+    /// The board upside down, with rank 8 where rank 1 was.
+    ///
+    /// Pieces keep their colors, see [`Position::swap_colors`] for the same position
+    /// from the other side's point of view.
+    pub fn flip_vertical(&self) -> Self {
+        self.map(BitBoard::flip_vertical)
+    }
+
+    /// The board mirrored left to right, with the h-file where the a-file was.
+    pub fn mirror_horizontal(&self) -> Self {
+        self.map(BitBoard::mirror_horizontal)
+    }
+
+    /// The board turned half a turn, so a1 and h8 swap places.
+    pub fn rotate_180(&self) -> Self {
+        self.map(BitBoard::rotate_180)
+    }
+
+    /// The board with every white piece turned black, and every black piece white.
+    pub fn swap_colors(&self) -> Self {
+        let [white, black] = self.colors;
+        Self::from_bitboards(self.pieces, [black, white])
+    }
+
+    /// Applies the same transform to every [`BitBoard`].
+    fn map(&self, transform: fn(BitBoard) -> BitBoard) -> Self {
+        Self::from_bitboards(self.pieces.map(transform), self.colors.map(transform))
+    }
 }
 
-impl std::fmt::Debug for Board {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (i, place) in self.iter().enumerate() {
-            if let Some(piece) = place {
-                write!(f, "{}", piece.as_char())?;
-            } else {
-                write!(f, " ")?;
+/// Draws the board the standard way up, with rank 8 at the top.
+///
+/// Use [`Board::display`] to draw it from Black's side instead.
+impl core::fmt::Debug for Board {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.display())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chess960_boards() {
+        assert_eq!(Board::chess960(518).to_fen(), Board::start().to_fen());
+        assert_eq!(
+            Board::chess960(0).to_fen(),
+            FEN::new("bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR")
+        );
+        assert_eq!(
+            Board::chess960(959).to_fen(),
+            FEN::new("rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB")
+        );
+    }
+
+    #[test]
+    fn start_constants() {
+        let parsed = FEN::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR")
+            .parse_board()
+            .unwrap();
+        assert_eq!(Board::START, parsed);
+        assert_eq!(Board::start(), parsed);
+
+        let mut position = Position::start();
+        position.make_move(Move::new(Square::E2, Square::E4, MoveFlag::DoublePush));
+        assert_eq!(Position::start().board(), &Board::START);
+        assert_eq!(Position::start().unmake_move(), None);
+    }
+
+    #[test]
+    fn debug_has_rank_8_on_top() {
+        let text = format!("{:?}", Board::start());
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "8  r  n  b  q  k  b  n  r");
+        assert_eq!(lines[7], "1  R  N  B  Q  K  B  N  R");
+    }
+
+    #[test]
+    fn placing_pieces() {
+        let mut board = Board::empty();
+        let king = Piece::new_with(Color::White, PieceKind::King);
+        let rook = Piece::new_with(Color::Black, PieceKind::Rook);
+
+        board.set_piece(king, Square::E1).unwrap();
+        board.set_piece(rook, Square::A8).unwrap();
+        assert_eq!(
+            board.set_piece(rook, Square::E1),
+            Err(OccupiedError {
+                square: Square::E1,
+                occupant: king
+            })
+        );
+
+        assert_eq!(board.move_piece(Square::A8, Square::A1), Ok(Some(rook)));
+        assert!(board.move_piece(Square::E1, Square::A1).is_err());
+        assert_eq!(board.move_piece(Square::H8, Square::H1), Ok(None));
+        assert_eq!(board.blacks(), BitBoard::from_square(Square::A1));
+
+        assert_eq!(board.remove_piece(Square::E1), Some(king));
+        assert_eq!(board.remove_piece(Square::E1), None);
+        assert_eq!(board.occupied(), BitBoard::from_square(Square::A1));
+        assert_eq!(board.rooks(), BitBoard::from_square(Square::A1));
+    }
+
+    #[test]
+    fn mailbox_follows_moves() {
+        fn walk(position: &mut Position, depth: u32) {
+            let board = position.board();
+            assert_eq!(*board, Board::from_bitboards(board.pieces, board.colors));
+            if depth == 0 {
+                return;
             }
-            if (i + 1) % 8 == 0 {
-                write!(f, "\n")?;
+            for &mv in position.legal_moves().iter() {
+                position.make_move(mv);
+                walk(position, depth - 1);
+                position.unmake_move();
             }
         }
 
-        Ok(())
+        let kiwipete = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let promotions = "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1";
+        for fen in [kiwipete, promotions] {
+            walk(&mut FEN::new(fen).parse_position().unwrap(), 2);
+        }
+        walk(&mut Position::chess960(0), 3);
+        walk(
+            &mut FEN::new(kiwipete)
+                .parse_position_as(&variant::Atomic)
+                .unwrap(),
+            2,
+        );
+    }
+
+    #[test]
+    fn attackers() {
+        let board = FEN::new("4k3/8/8/3p4/8/1N3B2/8/R3K3")
+            .parse_board()
+            .unwrap();
+
+        let attackers = board.attackers_to(Square::E4, board.occupied());
+        assert_eq!(
+            attackers,
+            [Square::D5, Square::F3].into_iter().collect::<BitBoard>()
+        );
+        assert!(board.is_attacked_by(Square::C4, Color::Black));
+        assert!(!board.is_attacked_by(Square::D4, Color::Black));
+
+        // the bishop sees through d5 once it is removed
+        let occupied = board.occupied() - BitBoard::from_square(Square::D5);
+        assert!(board.attackers_to(Square::C6, occupied).is_on(Square::F3));
+        assert!(!board
+            .attackers_to(Square::C6, board.occupied())
+            .is_on(Square::F3));
     }
 }
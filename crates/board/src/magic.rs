@@ -0,0 +1,190 @@
+//! [Magic bitboards](https://www.chessprogramming.org/Magic_Bitboards) for slider attacks.
+//!
+//! The magic numbers are searched for with a fixed seed the first time a table is used,
+//! so the tables are identical on every run.
+
+use alloc::{vec, vec::Vec};
+
+use spin::Lazy;
+
+use crate::Square;
+
+const RANK_1: u64 = 0xFF;
+const RANK_8: u64 = RANK_1 << 56;
+const FILE_A: u64 = 0x0101_0101_0101_0101;
+const FILE_H: u64 = FILE_A << 7;
+
+/// Everything needed to index the attack table for one square.
+#[derive(Clone, Copy, Default)]
+struct Magic {
+    /// Squares whose occupancy affects the attacks, excluding the edges.
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    /// Where this square's attacks start in the shared table.
+    offset: usize,
+}
+
+impl Magic {
+    #[inline]
+    fn index(&self, occupied: u64) -> usize {
+        let hash = (occupied & self.mask).wrapping_mul(self.magic) >> self.shift;
+        self.offset + hash as usize
+    }
+}
+
+pub(crate) struct Table {
+    magics: [Magic; 64],
+    attacks: Vec<u64>,
+}
+
+impl Table {
+    #[inline]
+    pub(crate) fn attacks(&self, square: Square, occupied: u64) -> u64 {
+        let magic = &self.magics[square.index()];
+        // SAFETY:
+        // Every index produced by a magic was filled in when searching for it
+        unsafe { *self.attacks.get_unchecked(magic.index(occupied)) }
+    }
+
+    fn new(slow: fn(Square, u64) -> u64) -> Self {
+        let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+        let mut magics = [Magic::default(); 64];
+        let mut attacks = Vec::new();
+
+        for (raw, entry) in magics.iter_mut().enumerate() {
+            let square = Square::from_raw(raw as u8);
+
+            let edges =
+                ((RANK_1 | RANK_8) & !rank_mask(square)) | ((FILE_A | FILE_H) & !file_mask(square));
+            let mask = slow(square, 0) & !edges;
+            let bits = mask.count_ones();
+
+            // every subset of the mask, along with the attacks it produces
+            let mut occupancies = Vec::with_capacity(1 << bits);
+            let mut subset = 0_u64;
+            loop {
+                occupancies.push((subset, slow(square, subset)));
+                subset = subset.wrapping_sub(mask) & mask;
+                if subset == 0 {
+                    break;
+                }
+            }
+
+            let mut magic = Magic {
+                mask,
+                magic: 0,
+                shift: 64 - bits,
+                offset: attacks.len(),
+            };
+            let mut table = vec![0; 1 << bits];
+            // tracks which attempt filled each slot, to avoid clearing the table
+            let mut filled_by = vec![0_u32; 1 << bits];
+
+            for attempt in 1.. {
+                magic.magic = rng.sparse();
+                // magics that don't spread the high bits are rarely any good
+                if (mask.wrapping_mul(magic.magic) >> 56).count_ones() < 6 {
+                    continue;
+                }
+
+                let collision = occupancies.iter().any(|&(occupied, attacked)| {
+                    let index = magic.index(occupied) - magic.offset;
+                    if filled_by[index] != attempt {
+                        filled_by[index] = attempt;
+                        table[index] = attacked;
+                        false
+                    } else {
+                        table[index] != attacked
+                    }
+                });
+
+                if !collision {
+                    break;
+                }
+            }
+
+            attacks.extend_from_slice(&table);
+            *entry = magic;
+        }
+
+        Self { magics, attacks }
+    }
+}
+
+pub(crate) static ROOK: Lazy<Table> = Lazy::new(|| Table::new(slow_rook_attacks));
+pub(crate) static BISHOP: Lazy<Table> = Lazy::new(|| Table::new(slow_bishop_attacks));
+
+fn rank_mask(square: Square) -> u64 {
+    RANK_1 << (8 * square.rank().index())
+}
+
+fn file_mask(square: Square) -> u64 {
+    FILE_A << square.file().index()
+}
+
+fn slow_attacks(square: Square, occupied: u64, directions: &[(i8, i8)]) -> u64 {
+    let mut attacks = 0;
+    for &(ranks, files) in directions {
+        let mut current = square;
+        while let Some(next) = current.offset(ranks, files) {
+            attacks |= next.bit();
+            if occupied & next.bit() != 0 {
+                break;
+            }
+            current = next;
+        }
+    }
+    attacks
+}
+
+/// Rook attacks found by walking each direction, used to fill the magic tables.
+pub(crate) fn slow_rook_attacks(square: Square, occupied: u64) -> u64 {
+    slow_attacks(square, occupied, &[(1, 0), (0, 1), (-1, 0), (0, -1)])
+}
+
+/// Bishop attacks found by walking each direction, used to fill the magic tables.
+pub(crate) fn slow_bishop_attacks(square: Square, occupied: u64) -> u64 {
+    slow_attacks(square, occupied, &[(1, 1), (-1, 1), (-1, -1), (1, -1)])
+}
+
+/// A small deterministic PRNG for searching magics.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Random numbers with few bits set make better magic candidates.
+    fn sparse(&mut self) -> u64 {
+        self.next() & self.next() & self.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magics_match_slow_attacks() {
+        let mut rng = XorShift(42);
+        for raw in 0..64 {
+            let square = Square::from_raw(raw);
+            for _ in 0..64 {
+                let occupied = rng.next() & rng.next();
+                assert_eq!(
+                    ROOK.attacks(square, occupied),
+                    slow_rook_attacks(square, occupied)
+                );
+                assert_eq!(
+                    BISHOP.attacks(square, occupied),
+                    slow_bishop_attacks(square, occupied)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,758 @@
+use crate::{
+    attacks::{
+        bishop_attacks, king_attacks, knight_attacks, pawn_attacks, queen_attacks, rook_attacks,
+    },
+    tables::{between, line},
+    BitBoard, Board, CastleSide, Color, Move, MoveFlag, MoveList, Piece, PieceKind, Position, Rank,
+    Square,
+};
+
+/// The first and last ranks, where pawns can't be dropped.
+const BACK_RANKS: BitBoard = BitBoard(BitBoard::RANK_1.0 | BitBoard::RANK_8.0);
+
+/// Which moves to generate.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    All,
+    /// Captures, including en passant, and promotions.
+    Captures,
+    /// Every move, but only when in check.
+    Evasions,
+    /// Quiet moves giving check, see [`generate_checks`].
+    Checks,
+}
+
+/// State shared while generating moves for a single position.
+struct Generator<'a> {
+    position: &'a Position,
+    us: Color,
+    ours: BitBoard,
+    theirs: BitBoard,
+    occupied: BitBoard,
+    /// None for a side without a king, as in Horde.
+    king: Option<Square>,
+    checkers: BitBoard,
+    pinned: BitBoard,
+    /// Whether to skip the checks that keep the king safe, see [`generate_pseudo_legal`].
+    pseudo_legal: bool,
+    mode: Mode,
+    /// For [`Mode::Checks`], the squares each kind of piece gives check from.
+    check_squares: [BitBoard; 6],
+    /// For [`Mode::Checks`], our pieces that give a discovered check by moving off the line.
+    discoverers: BitBoard,
+}
+
+impl<'a> Generator<'a> {
+    fn new(position: &'a Position, pseudo_legal: bool, mode: Mode) -> Self {
+        let board = position.board();
+        let us = position.side_to_move();
+        let ours = board.colors(us);
+        let theirs = board.colors(us.opposite());
+        let occupied = board.occupied();
+
+        let king = position.find_king(us);
+        let checkers = match king {
+            Some(king) => board.attackers_to(king, occupied) & theirs,
+            None => BitBoard::EMPTY,
+        };
+
+        // pins only matter when the moves have to be legal
+        let pinned = if pseudo_legal {
+            BitBoard::EMPTY
+        } else {
+            position.pinned(us)
+        };
+
+        let mut check_squares = [BitBoard::EMPTY; 6];
+        let mut discoverers = BitBoard::EMPTY;
+        let their_king = position.find_king(us.opposite());
+        if let (Mode::Checks, Some(their_king)) = (mode, their_king) {
+            let bishop = bishop_attacks(their_king, occupied);
+            let rook = rook_attacks(their_king, occupied);
+            check_squares = [
+                pawn_attacks(us.opposite(), their_king),
+                knight_attacks(their_king),
+                bishop,
+                rook,
+                bishop | rook,
+                BitBoard::EMPTY,
+            ];
+            discoverers = position.discovered_check_candidates();
+        }
+
+        Self {
+            position,
+            us,
+            ours,
+            theirs,
+            occupied,
+            king,
+            checkers,
+            pinned,
+            pseudo_legal,
+            mode,
+            check_squares,
+            discoverers,
+        }
+    }
+
+    fn board(&self) -> &Board {
+        self.position.board()
+    }
+
+    /// Returns `true` if a square is attacked by the opponent,
+    /// ignoring our king so it can't hide behind itself.
+    fn is_attacked(&self, square: Square) -> bool {
+        let occupied = self.occupied - self.king.map_or(BitBoard::EMPTY, BitBoard::from_square);
+        !(self.board().attackers_to(square, occupied) & self.theirs).is_empty()
+    }
+
+    /// Squares a pinned piece on `from` may still move to.
+    fn pin_mask(&self, from: Square) -> BitBoard {
+        match self.king {
+            Some(king) if self.pinned.is_on(from) => line(king, from),
+            _ => BitBoard::FULL,
+        }
+    }
+
+    /// Squares that get the king out of check, or every square when not in check.
+    ///
+    /// Only the king can move out of double check, so then there are none.
+    fn check_mask(&self) -> BitBoard {
+        match (self.checkers.lsb(), self.king) {
+            _ if self.checkers.has_many() => BitBoard::EMPTY,
+            (Some(checker), Some(king)) => between(king, checker) | self.checkers,
+            _ => BitBoard::FULL,
+        }
+    }
+
+    /// Squares a `kind` of piece on `from` may move to for the [`Mode`],
+    /// before pins and checks are considered.
+    fn mode_mask(&self, kind: PieceKind, from: Square) -> BitBoard {
+        match self.mode {
+            Mode::All | Mode::Evasions => !self.ours,
+            Mode::Captures => self.theirs,
+            Mode::Checks => {
+                let discovered = if self.discoverers.is_on(from) {
+                    match self.board().kings() & self.theirs {
+                        kings if kings.is_empty() => BitBoard::EMPTY,
+                        kings => !line(kings.lsb().unwrap(), from),
+                    }
+                } else {
+                    BitBoard::EMPTY
+                };
+                (self.check_squares[kind as usize] | discovered) & !self.occupied
+            }
+        }
+    }
+
+    fn generate(&self, moves: &mut MoveList) {
+        if self.mode == Mode::Evasions && self.checkers.is_empty() {
+            return;
+        }
+
+        self.king_moves(moves);
+
+        // castling has to be safe even when generating pseudo-legal moves,
+        // since it can't be checked afterwards by looking at the king alone
+        if self.mode == Mode::All && self.checkers.is_empty() {
+            self.castling_moves(moves);
+        }
+
+        // when in check, moves must capture the checker or block it
+        let target = if self.pseudo_legal {
+            BitBoard::FULL
+        } else {
+            self.check_mask()
+        };
+        if target.is_empty() {
+            return;
+        }
+
+        self.pawn_moves(moves, target);
+
+        let board = self.board();
+        let pieces = [
+            (PieceKind::Knight, board.knights()),
+            (PieceKind::Bishop, board.bishops()),
+            (PieceKind::Rook, board.rooks()),
+            (PieceKind::Queen, board.queens()),
+        ];
+        for (kind, bb) in pieces {
+            for from in bb & self.ours {
+                let attacks = match kind {
+                    PieceKind::Knight => knight_attacks(from),
+                    PieceKind::Bishop => bishop_attacks(from, self.occupied),
+                    PieceKind::Rook => rook_attacks(from, self.occupied),
+                    _ => queen_attacks(from, self.occupied),
+                };
+                let targets = attacks & target & self.pin_mask(from) & self.mode_mask(kind, from);
+                for to in targets {
+                    moves.push(Move::new(from, to, MoveFlag::Normal));
+                }
+            }
+        }
+
+        if self.position.variant().has_drops() && self.mode != Mode::Captures {
+            self.drops(moves, target);
+        }
+    }
+
+    /// Drops of each kind of piece in hand onto the empty squares,
+    /// which block a check but can never expose the king.
+    fn drops(&self, moves: &mut MoveList, target: BitBoard) {
+        let empty = !self.occupied & target;
+        for kind in [
+            PieceKind::Pawn,
+            PieceKind::Knight,
+            PieceKind::Bishop,
+            PieceKind::Rook,
+            PieceKind::Queen,
+        ] {
+            if self.position.in_hand(Piece::new_with(self.us, kind)) == 0 {
+                continue;
+            }
+            let mut squares = match kind {
+                PieceKind::Pawn => empty - BACK_RANKS,
+                _ => empty,
+            };
+            if self.mode == Mode::Checks {
+                squares &= self.check_squares[kind as usize];
+            }
+            for to in squares {
+                moves.push(Move::new_drop(kind, to));
+            }
+        }
+    }
+
+    fn king_moves(&self, moves: &mut MoveList) {
+        // only in Antichess, where the king is an ordinary piece, can there be more than one
+        for king in self.board().kings() & self.ours {
+            let targets = king_attacks(king) & self.mode_mask(PieceKind::King, king);
+            for to in targets {
+                if self.pseudo_legal || !self.is_attacked(to) {
+                    moves.push(Move::new(king, to, MoveFlag::Normal));
+                }
+            }
+        }
+    }
+
+    /// Castling in standard chess or Chess960, where the king and rook
+    /// always end up on the same squares but can start anywhere on the back rank.
+    fn castling_moves(&self, moves: &mut MoveList) {
+        let rank = Rank::First.relative_to(self.us);
+        let Some(king) = self.king.filter(|king| king.rank() == rank) else {
+            return;
+        };
+
+        let rooks = self.board().rooks() & self.ours;
+        let castling = self.position.castling();
+
+        for side in [CastleSide::King, CastleSide::Queen] {
+            let rook_from = self.position.castling_rook(self.us, side);
+            if !castling.has(self.us, side) || !rooks.is_on(rook_from) {
+                continue;
+            }
+
+            let (king_file, rook_file) = side.destination_files();
+            let king_to = Square::new(rank, king_file);
+            let rook_to = Square::new(rank, rook_file);
+
+            // every square either piece crosses must be empty, apart from the two of them
+            let movers = BitBoard::from_square(king) | BitBoard::from_square(rook_from);
+            let king_path = between(king, king_to) | BitBoard::from_square(king_to);
+            let rook_path = between(rook_from, rook_to) | BitBoard::from_square(rook_to);
+            if !((king_path | rook_path) & (self.occupied - movers)).is_empty() {
+                continue;
+            }
+
+            // the castling rook could be shielding the king from an attack along the rank
+            let occupied = self.occupied - movers;
+            let path_safe = king_path
+                .into_iter()
+                .all(|sq| (self.board().attackers_to(sq, occupied) & self.theirs).is_empty());
+
+            if path_safe {
+                moves.push(Move::new(king, king_to, MoveFlag::Castle));
+            }
+        }
+    }
+
+    fn pawn_moves(&self, moves: &mut MoveList, target: BitBoard) {
+        let back = match self.us {
+            Color::White => -1,
+            Color::Black => 1,
+        };
+        let promotion_rank = Rank::Eighth.relative_to(self.us);
+
+        let push = |moves: &mut MoveList, from: Square, to: Square| {
+            if to.rank() == promotion_rank {
+                for &kind in self.position.variant().promotions() {
+                    moves.push(Move::new_promotion(from, to, kind));
+                }
+            } else {
+                moves.push(Move::new(from, to, MoveFlag::Normal));
+            }
+        };
+
+        let pawns = self.board().pawns() & self.ours;
+        let empty = !self.occupied;
+
+        let promotions = BitBoard::rank(promotion_rank);
+        // captures only push to promote, and checks leave promotions to them
+        let (pushes, captures) = match self.mode {
+            Mode::All | Mode::Evasions => (BitBoard::FULL, true),
+            Mode::Captures => (promotions, true),
+            Mode::Checks => (!promotions, false),
+        };
+        let allowed = |from: Square, to: Square| {
+            let mode = match self.mode {
+                Mode::Checks => self.mode_mask(PieceKind::Pawn, from),
+                _ => BitBoard::FULL,
+            };
+            (self.pin_mask(from) & mode).is_on(to)
+        };
+
+        // single and double pushes, found for every pawn at once
+        for to in pawns.pawn_pushes(self.us, empty) & target & pushes {
+            let from = to.offset(back, 0).unwrap();
+            if allowed(from, to) {
+                push(moves, from, to);
+            }
+        }
+        if self.mode != Mode::Captures {
+            let mut double_pushes = pawns.pawn_double_pushes(self.us, empty);
+            if self.position.variant().double_pushes_from_first_rank() {
+                let first_rank = match self.us {
+                    Color::White => BitBoard::RANK_1,
+                    Color::Black => BitBoard::RANK_8,
+                };
+                double_pushes |= (pawns & first_rank)
+                    .pawn_pushes(self.us, empty)
+                    .pawn_pushes(self.us, empty);
+            }
+            for to in double_pushes & target {
+                let from = to.offset(2 * back, 0).unwrap();
+                if allowed(from, to) {
+                    moves.push(Move::new(from, to, MoveFlag::DoublePush));
+                }
+            }
+        }
+        if !captures {
+            return;
+        }
+
+        let ep = self.position.en_passant();
+        for from in pawns {
+            let attacks = pawn_attacks(self.us, from);
+            for to in attacks & self.theirs & target & self.pin_mask(from) {
+                push(moves, from, to);
+            }
+
+            if let Some(ep) = ep {
+                if attacks.is_on(ep) && (self.pseudo_legal || self.en_passant_is_legal(from, ep)) {
+                    moves.push(Move::new(from, ep, MoveFlag::EnPassant));
+                }
+            }
+        }
+    }
+
+    /// En passant removes two pieces from the same rank at once,
+    /// so it is checked by looking at the resulting position directly.
+    fn en_passant_is_legal(&self, from: Square, ep: Square) -> bool {
+        let Some(king) = self.king else {
+            return true;
+        };
+        let captured = Square::new(from.rank(), ep.file());
+        let moved = [from, captured, ep].into_iter().collect::<BitBoard>();
+        let occupied = self.occupied ^ moved;
+        let theirs = self.theirs ^ BitBoard::from_square(captured);
+
+        (self.board().attackers_to(king, occupied) & theirs).is_empty()
+    }
+
+    /// Whether a pseudo-legal move keeps the king safe.
+    fn is_legal(&self, mv: Move) -> bool {
+        let (from, to) = (mv.from(), mv.to());
+        if mv.dropped().is_some() {
+            return self.check_mask().is_on(to);
+        }
+        if Some(from) == self.king {
+            return mv.flag() == MoveFlag::Castle || !self.is_attacked(to);
+        }
+        if mv.flag() == MoveFlag::EnPassant {
+            return self.en_passant_is_legal(from, to);
+        }
+        (self.check_mask() & self.pin_mask(from)).is_on(to)
+    }
+}
+
+/// Generates all legal moves for the side to move in `position`.
+///
+/// A side without a king, as in Horde, only has to follow the rules for its other pieces,
+/// and nobody can move once the game is won by the rules of the [`Variant`].
+pub fn generate_legal(position: &Position, moves: &mut MoveList) {
+    generate_mode(position, moves, Mode::All);
+}
+
+/// Generates legal moves of one [`Mode`].
+fn generate_mode(position: &Position, moves: &mut MoveList, mode: Mode) {
+    let variant = position.variant();
+    if variant.winner(position).is_some() {
+        return;
+    }
+    if !variant.has_own_legality() {
+        Generator::new(position, false, mode).generate(moves);
+        return;
+    }
+
+    // the variant decides which moves are legal, so they are found one at a time
+    let mut pseudo = MoveList::new();
+    Generator::new(position, true, Mode::All).generate(&mut pseudo);
+    let us = position.side_to_move();
+    let theirs = position.board().colors(us.opposite());
+    let in_check = position.in_check(us);
+
+    for &mv in &pseudo {
+        let capture = theirs.is_on(mv.to()) || mv.flag() == MoveFlag::EnPassant;
+        let wanted = match mode {
+            Mode::All => true,
+            Mode::Captures => capture || mv.promotion().is_some(),
+            Mode::Evasions => in_check,
+            Mode::Checks => {
+                !capture
+                    && mv.promotion().is_none()
+                    && mv.flag() != MoveFlag::Castle
+                    && gives_check(position, mv)
+            }
+        };
+        if wanted && variant.is_legal(position, mv) {
+            moves.push(mv);
+        }
+    }
+}
+
+/// Whether `mv` leaves the opponent in check, found by playing it.
+fn gives_check(position: &Position, mv: Move) -> bool {
+    let them = position.side_to_move().opposite();
+    let mut after = position.scratch();
+    after.make_move(mv);
+    after.in_check(them)
+}
+
+/// Generates the legal captures, including en passant, and promotions,
+/// the moves a quiescence search looks at.
+pub fn generate_captures(position: &Position, moves: &mut MoveList) {
+    generate_mode(position, moves, Mode::Captures);
+}
+
+/// Generates the legal moves out of check, or nothing when not in check.
+pub fn generate_evasions(position: &Position, moves: &mut MoveList) {
+    generate_mode(position, moves, Mode::Evasions);
+}
+
+/// Generates the legal quiet moves that give check, directly or by discovery.
+///
+/// Captures and promotions are left to [`generate_captures`] so no move is generated by both,
+/// and castling is never included.
+pub fn generate_checks(position: &Position, moves: &mut MoveList) {
+    generate_mode(position, moves, Mode::Checks);
+}
+
+/// Generates moves for the side to move in `position` without checking
+/// that they keep the king out of check, apart from castling.
+///
+/// This is quicker than [`generate_legal`] when most moves won't be played,
+/// such as in a search that often cuts off after the first few.
+/// Check each move with [`is_legal`] before playing it.
+pub fn generate_pseudo_legal(position: &Position, moves: &mut MoveList) {
+    if position.variant().winner(position).is_none() {
+        Generator::new(position, true, Mode::All).generate(moves);
+    }
+}
+
+/// Returns `true` if a move from [`generate_pseudo_legal`] doesn't leave the king in check,
+/// or is legal by the [`Variant`]'s own rules.
+///
+/// Moves that aren't pseudo-legal in `position` give a meaningless answer.
+pub fn is_legal(position: &Position, mv: Move) -> bool {
+    let variant = position.variant();
+    if variant.has_own_legality() {
+        variant.is_legal(position, mv)
+    } else {
+        Generator::new(position, false, Mode::All).is_legal(mv)
+    }
+}
+
+/// Counts the leaf nodes of the legal move tree to `depth`.
+///
+/// Used to check move generation against known results.
+pub fn perft(position: &mut Position, depth: u32) -> u64 {
+    let moves = position.legal_moves();
+    if depth <= 1 {
+        return if depth == 1 { moves.len() as u64 } else { 1 };
+    }
+
+    let mut nodes = 0;
+    for &mv in &moves {
+        position.make_move(mv);
+        nodes += perft(position, depth - 1);
+        position.unmake_move();
+    }
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{zobrist, FEN};
+
+    fn count(fen: &str) -> usize {
+        FEN::new(fen)
+            .parse_position()
+            .expect("test FEN should be valid")
+            .legal_moves()
+            .len()
+    }
+
+    #[test]
+    fn start_position() {
+        assert_eq!(
+            count("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            20
+        );
+    }
+
+    #[test]
+    fn known_positions() {
+        // positions and counts from https://www.chessprogramming.org/Perft_Results
+        let positions = [
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                48,
+            ),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 14),
+            (
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                6,
+            ),
+            (
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                44,
+            ),
+            (
+                "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+                46,
+            ),
+        ];
+
+        for (fen, expected) in positions {
+            assert_eq!(count(fen), expected, "wrong move count for {fen}");
+        }
+    }
+
+    #[test]
+    fn pinned_en_passant() {
+        // capturing en passant would expose the king along the rank
+        assert_eq!(count("8/8/8/K1pP3r/8/8/8/7k w - c6 0 1"), 5);
+    }
+
+    #[test]
+    fn pseudo_legal_filtered_by_is_legal() {
+        fn walk(position: &mut Position, depth: u32) {
+            let mut pseudo = MoveList::new();
+            generate_pseudo_legal(position, &mut pseudo);
+            let mut filtered = pseudo
+                .iter()
+                .copied()
+                .filter(|&mv| is_legal(position, mv))
+                .collect::<Vec<_>>();
+            let mut legal = position.legal_moves().iter().copied().collect::<Vec<_>>();
+            filtered.sort_by_key(|mv| mv.to_bits());
+            legal.sort_by_key(|mv| mv.to_bits());
+            assert_eq!(filtered, legal, "{}", position.to_fen());
+
+            if depth > 1 {
+                for mv in legal {
+                    position.make_move(mv);
+                    walk(position, depth - 1);
+                    position.unmake_move();
+                }
+            }
+        }
+
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/8/8/K1pP3r/8/8/8/7k w - c6 0 1",
+        ] {
+            walk(&mut FEN::new(fen).parse_position().unwrap(), 3);
+        }
+    }
+
+    #[test]
+    fn captures_evasions_and_checks() {
+        fn sorted(moves: impl IntoIterator<Item = Move>) -> Vec<Move> {
+            let mut moves = moves.into_iter().collect::<Vec<_>>();
+            moves.sort_by_key(|mv| mv.to_bits());
+            moves
+        }
+
+        fn walk(position: &mut Position, depth: u32) {
+            let legal = position.legal_moves();
+            let board = *position.board();
+            let them = position.side_to_move().opposite();
+            let capture =
+                |mv: &Move| board.colors(them).is_on(mv.to()) || mv.flag() == MoveFlag::EnPassant;
+
+            let mut captures = MoveList::new();
+            generate_captures(position, &mut captures);
+            let expected = legal
+                .iter()
+                .copied()
+                .filter(|mv| capture(mv) || mv.promotion().is_some());
+            assert_eq!(
+                sorted(captures.iter().copied()),
+                sorted(expected),
+                "{}",
+                position.to_fen()
+            );
+
+            let mut evasions = MoveList::new();
+            generate_evasions(position, &mut evasions);
+            if position.in_check(position.side_to_move()) {
+                assert_eq!(
+                    sorted(evasions.iter().copied()),
+                    sorted(legal.iter().copied())
+                );
+            } else {
+                assert!(evasions.is_empty());
+            }
+
+            let mut checks = MoveList::new();
+            generate_checks(position, &mut checks);
+            let expected = legal.iter().copied().filter(|mv| {
+                let mut after = position.clone();
+                after.make_move(*mv);
+                !capture(mv)
+                    && mv.promotion().is_none()
+                    && mv.flag() != MoveFlag::Castle
+                    && after.in_check(them)
+            });
+            assert_eq!(
+                sorted(checks.iter().copied()),
+                sorted(expected),
+                "{}",
+                position.to_fen()
+            );
+
+            if depth > 1 {
+                for &mv in &legal {
+                    position.make_move(mv);
+                    walk(position, depth - 1);
+                    position.unmake_move();
+                }
+            }
+        }
+
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ] {
+            walk(&mut FEN::new(fen).parse_position().unwrap(), 3);
+        }
+    }
+
+    #[test]
+    fn perft_results() {
+        // positions and counts from https://www.chessprogramming.org/Perft_Results
+        let positions = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                4,
+                197281,
+            ),
+            (
+                "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+                3,
+                97862,
+            ),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 4, 43238),
+            (
+                "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+                3,
+                9467,
+            ),
+            (
+                "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+                3,
+                62379,
+            ),
+            (
+                "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+                3,
+                89890,
+            ),
+        ];
+
+        for (fen, depth, expected) in positions {
+            let mut position = FEN::new(fen).parse_position().unwrap();
+            assert_eq!(
+                perft(&mut position, depth),
+                expected,
+                "wrong perft for {fen}"
+            );
+        }
+    }
+
+    #[test]
+    fn chess960_perft() {
+        // from the Chess960 perft results by Reinhard Scharnagl
+        let fen = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+        let mut position = FEN::new(fen).parse_position().unwrap();
+        assert!(position.is_chess960());
+        assert_eq!(perft(&mut position, 3), 12189);
+    }
+
+    proptest::proptest! {
+        /// Plays random games from random Chess960 starts, checking the incremental state
+        /// against what is rebuilt from scratch after every move.
+        #[test]
+        fn random_games(
+            start in 0..960u16,
+            choices in proptest::collection::vec(0..256usize, 0..80),
+        ) {
+            let mut position = Position::chess960(start);
+            for choice in choices {
+                let moves = position.legal_moves();
+                if moves.is_empty() {
+                    break;
+                }
+                let (fen, key) = (position.fen_string(), position.zobrist_key());
+                let mv = moves[choice % moves.len()];
+
+                position.make_move(mv);
+                assert_eq!(position.zobrist_key(), zobrist::hash(&position), "{mv:?} from {fen}");
+                assert_eq!(position.pawn_key(), zobrist::pawn_hash(position.board()));
+                assert_eq!(position.validate(), Ok(()), "{mv:?} from {fen}");
+
+                let reparsed = FEN::new(&position.fen_string()).parse_position().unwrap();
+                assert_eq!(reparsed.fen_string(), position.fen_string());
+                assert_eq!(reparsed.zobrist_key(), position.zobrist_key());
+
+                position.unmake_move();
+                assert_eq!(position.fen_string(), fen);
+                assert_eq!(position.zobrist_key(), key);
+                position.make_move(mv);
+            }
+
+            // nothing hidden in the position, such as the moves made to reach it,
+            // changes the moves that can be made from it
+            let mut reparsed = FEN::new(&position.fen_string()).parse_position().unwrap();
+            assert_eq!(perft(&mut position, 2), perft(&mut reparsed, 2));
+        }
+    }
+}
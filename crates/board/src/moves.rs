@@ -0,0 +1,404 @@
+use alloc::{borrow::ToOwned, format, string::String};
+
+use crate::{CastleSide, File, PieceKind, Position, Square};
+
+/// Extra information about how a [`Move`] is played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MoveFlag {
+    /// A regular move or capture.
+    Normal,
+    /// A pawn moving two squares forward.
+    DoublePush,
+    /// The king castling, `to` is the king's destination.
+    Castle,
+    /// A pawn capturing en passant.
+    EnPassant,
+}
+
+/// A move from one [`Square`] to another, packed into 16 bits.
+///
+/// `from` and `to` take the low 6 bits each, then the top 4 bits hold the [`MoveFlag`],
+/// 4 to 7 for promotions to a knight, bishop, rook or queen,
+/// 8 to 12 for dropping a pawn to a queen in variants like [`Crazyhouse`](crate::variant::Crazyhouse),
+/// or 13 for promoting to a king in [`Antichess`](crate::variant::Antichess).
+/// See [`Move::to_bits`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move(u16);
+
+impl Move {
+    /// Creates a [`Move`] with a [`MoveFlag`].
+    pub const fn new(from: Square, to: Square, flag: MoveFlag) -> Self {
+        Self::pack(from, to, flag as u16)
+    }
+
+    /// Creates a pawn [`Move`] that promotes to `kind`.
+    pub const fn new_promotion(from: Square, to: Square, kind: PieceKind) -> Self {
+        match kind {
+            PieceKind::King => Self::pack(from, to, 13),
+            _ => Self::pack(from, to, 3 + kind as u16),
+        }
+    }
+
+    /// Creates a [`Move`] dropping a `kind` of piece from the hand onto `to`,
+    /// which is also its `from` square.
+    pub const fn new_drop(kind: PieceKind, to: Square) -> Self {
+        Self::pack(to, to, 8 + kind as u16)
+    }
+
+    const fn pack(from: Square, to: Square, extra: u16) -> Self {
+        Self(from.as_u8() as u16 | (to.as_u8() as u16) << 6 | extra << 12)
+    }
+
+    /// The move's packed representation.
+    #[inline]
+    pub const fn to_bits(self) -> u16 {
+        self.0
+    }
+
+    /// Unpacks a move written by [`Move::to_bits`],
+    /// None if the top 4 bits aren't a flag, promotion or drop.
+    #[inline]
+    pub const fn from_bits(bits: u16) -> Option<Self> {
+        if bits >> 12 <= 13 {
+            Some(Self(bits))
+        } else {
+            None
+        }
+    }
+
+    /// The [`Square`] the piece moves from.
+    #[inline]
+    pub const fn from(&self) -> Square {
+        Square::from_raw((self.0 & 0x3F) as u8)
+    }
+
+    /// The [`Square`] the piece moves to.
+    #[inline]
+    pub const fn to(&self) -> Square {
+        Square::from_raw((self.0 >> 6 & 0x3F) as u8)
+    }
+
+    /// The kind of piece a pawn promotes to, if any.
+    #[inline]
+    pub const fn promotion(&self) -> Option<PieceKind> {
+        match self.0 >> 12 {
+            4 => Some(PieceKind::Knight),
+            5 => Some(PieceKind::Bishop),
+            6 => Some(PieceKind::Rook),
+            7 => Some(PieceKind::Queen),
+            13 => Some(PieceKind::King),
+            _ => None,
+        }
+    }
+
+    /// The kind of piece dropped from the hand, if any.
+    #[inline]
+    pub const fn dropped(&self) -> Option<PieceKind> {
+        match self.0 >> 12 {
+            8 => Some(PieceKind::Pawn),
+            9 => Some(PieceKind::Knight),
+            10 => Some(PieceKind::Bishop),
+            11 => Some(PieceKind::Rook),
+            12 => Some(PieceKind::Queen),
+            _ => None,
+        }
+    }
+
+    /// The [`MoveFlag`] of the move, [`MoveFlag::Normal`] for promotions and drops.
+    #[inline]
+    pub const fn flag(&self) -> MoveFlag {
+        match self.0 >> 12 {
+            1 => MoveFlag::DoublePush,
+            2 => MoveFlag::Castle,
+            3 => MoveFlag::EnPassant,
+            _ => MoveFlag::Normal,
+        }
+    }
+
+    /// The same move going to a different square.
+    const fn with_to(self, to: Square) -> Self {
+        Self(self.0 & !(0x3F << 6) | (to.as_u8() as u16) << 6)
+    }
+
+    /// Which way the king castles, if this is a castling move.
+    pub const fn castle_side(&self) -> Option<CastleSide> {
+        match self.flag() {
+            MoveFlag::Castle if matches!(self.to().file(), File::G) => Some(CastleSide::King),
+            MoveFlag::Castle => Some(CastleSide::Queen),
+            _ => None,
+        }
+    }
+
+    /// Writes the move in UCI long algebraic notation, such as `e2e4` or `e7e8q`,
+    /// or `N@f3` for a drop.
+    ///
+    /// Castling is written as the king moving two squares,
+    /// use [`Position::uci`] to write Chess960 castling correctly.
+    pub fn to_uci(&self) -> String {
+        if let Some(kind) = self.dropped() {
+            return format!("{}@{}", kind_letter(kind), self.to());
+        }
+        let mut text = format!("{}{}", self.from(), self.to());
+        match self.promotion() {
+            Some(PieceKind::Knight) => text.push('n'),
+            Some(PieceKind::Bishop) => text.push('b'),
+            Some(PieceKind::Rook) => text.push('r'),
+            Some(PieceKind::Queen) => text.push('q'),
+            Some(PieceKind::King) => text.push('k'),
+            _ => {}
+        }
+        text
+    }
+}
+
+impl core::fmt::Debug for Move {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Move")
+            .field("from", &self.from())
+            .field("to", &self.to())
+            .field("promotion", &self.promotion())
+            .field("dropped", &self.dropped())
+            .field("flag", &self.flag())
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum UciMoveError {
+    #[error("'{0}' is not a UCI move")]
+    Invalid(String),
+    #[error("'{0}' is not a legal move")]
+    Illegal(String),
+}
+
+impl Position {
+    /// Writes a [`Move`] in UCI long algebraic notation.
+    ///
+    /// In Chess960 castling is written as the king capturing its own rook.
+    pub fn uci(&self, mv: Move) -> String {
+        mv.with_to(self.uci_target(mv)).to_uci()
+    }
+
+    /// Finds the legal [`Move`] written in UCI long algebraic notation.
+    ///
+    /// The position is needed to tell castling, en passant and double pushes apart.
+    /// In Chess960 castling must be written as the king capturing its own rook.
+    pub fn parse_uci(&self, text: &str) -> Result<Move, UciMoveError> {
+        let invalid = || UciMoveError::Invalid(text.to_owned());
+        let square = |range| -> Result<Square, UciMoveError> {
+            text.get(range)
+                .and_then(|s: &str| s.parse().ok())
+                .ok_or_else(invalid)
+        };
+
+        if text.get(1..2) == Some("@") {
+            let kind = match text.as_bytes()[0] {
+                b'P' => PieceKind::Pawn,
+                b'N' => PieceKind::Knight,
+                b'B' => PieceKind::Bishop,
+                b'R' => PieceKind::Rook,
+                b'Q' => PieceKind::Queen,
+                _ => return Err(invalid()),
+            };
+            let to = square(2..4)?;
+            if text.len() != 4 {
+                return Err(invalid());
+            }
+            let mv = Move::new_drop(kind, to);
+            return self
+                .legal_moves()
+                .contains(&mv)
+                .then_some(mv)
+                .ok_or_else(|| UciMoveError::Illegal(text.to_owned()));
+        }
+
+        let from = square(0..2)?;
+        let to = square(2..4)?;
+        let promotion = match text.get(4..) {
+            Some("") => None,
+            Some("n") => Some(PieceKind::Knight),
+            Some("b") => Some(PieceKind::Bishop),
+            Some("r") => Some(PieceKind::Rook),
+            Some("q") => Some(PieceKind::Queen),
+            Some("k") => Some(PieceKind::King),
+            _ => return Err(invalid()),
+        };
+
+        self.legal_moves()
+            .iter()
+            .copied()
+            .find(|&mv| {
+                mv.from() == from && self.uci_target(mv) == to && mv.promotion() == promotion
+            })
+            .ok_or_else(|| UciMoveError::Illegal(text.to_owned()))
+    }
+
+    /// The square a [`Move`] goes to when written in UCI.
+    fn uci_target(&self, mv: Move) -> Square {
+        match mv.castle_side() {
+            Some(side) if self.chess960 => self.castling_rook(self.side_to_move, side),
+            _ => mv.to(),
+        }
+    }
+}
+
+/// The uppercase letter for a kind of piece.
+fn kind_letter(kind: PieceKind) -> char {
+    match kind {
+        PieceKind::Pawn => 'P',
+        PieceKind::Knight => 'N',
+        PieceKind::Bishop => 'B',
+        PieceKind::Rook => 'R',
+        PieceKind::Queen => 'Q',
+        PieceKind::King => 'K',
+    }
+}
+
+/// The most moves possible in any legal chess position is 218,
+/// but with pieces to drop in Crazyhouse there can be a few hundred more.
+pub(crate) const MAX_MOVES: usize = 512;
+
+/// A fixed capacity list of [`Moves`](Move).
+///
+/// Generating moves into a list on the stack avoids allocating
+/// for every position visited.
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    /// The most moves a list can hold, enough for every legal move in any position.
+    pub const CAPACITY: usize = MAX_MOVES;
+
+    /// Creates an empty [`MoveList`].
+    pub const fn new() -> Self {
+        const NULL: Move = Move::new(Square::A1, Square::A1, MoveFlag::Normal);
+
+        Self {
+            moves: [NULL; MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    /// Adds a [`Move`] to the end of the list.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the list is full.
+    #[inline]
+    pub fn push(&mut self, mv: Move) {
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+
+    /// Removes all moves from the list.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl core::ops::Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &Self::Target {
+        &self.moves[..self.len]
+    }
+}
+
+impl core::ops::DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.moves[..self.len]
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = core::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl core::fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FEN;
+
+    #[test]
+    fn packing() {
+        assert_eq!(core::mem::size_of::<Move>(), 2);
+
+        let push = Move::new(Square::E2, Square::E4, MoveFlag::DoublePush);
+        assert_eq!(push.to_bits(), 12 | 28 << 6 | 1 << 12);
+        assert_eq!((push.from(), push.to()), (Square::E2, Square::E4));
+        assert_eq!(push.flag(), MoveFlag::DoublePush);
+        assert_eq!(push.promotion(), None);
+
+        for kind in [
+            PieceKind::Knight,
+            PieceKind::Bishop,
+            PieceKind::Rook,
+            PieceKind::Queen,
+            PieceKind::King,
+        ] {
+            let promotion = Move::new_promotion(Square::B7, Square::A8, kind);
+            assert_eq!(promotion.promotion(), Some(kind));
+            assert_eq!(promotion.flag(), MoveFlag::Normal);
+            assert_eq!(Move::from_bits(promotion.to_bits()), Some(promotion));
+        }
+        assert_eq!(Move::from_bits(14 << 12), None);
+
+        let drop = Move::new_drop(PieceKind::Knight, Square::F3);
+        assert_eq!((drop.from(), drop.to()), (Square::F3, Square::F3));
+        assert_eq!(drop.dropped(), Some(PieceKind::Knight));
+        assert_eq!((drop.promotion(), drop.flag()), (None, MoveFlag::Normal));
+        assert_eq!(Move::from_bits(drop.to_bits()), Some(drop));
+        assert_eq!(drop.to_uci(), "N@f3");
+    }
+
+    #[test]
+    fn uci_round_trip() {
+        let fen = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P1PPP/R2Q1RK1 b kq - 0 1";
+        let position = FEN::new(fen).parse_position().unwrap();
+
+        for &mv in &position.legal_moves() {
+            assert_eq!(position.parse_uci(&mv.to_uci()), Ok(mv));
+        }
+
+        let promotion = position.parse_uci("b2a1q").unwrap();
+        assert_eq!(promotion.promotion(), Some(PieceKind::Queen));
+        assert_eq!(promotion.to_uci(), "b2a1q");
+
+        let castling = FEN::new("r3k2r/8/8/8/8/8/8/4K3 b kq - 0 1")
+            .parse_position()
+            .unwrap();
+        let castle = castling.parse_uci("e8g8").unwrap();
+        assert_eq!(castle.flag(), MoveFlag::Castle);
+
+        assert!(matches!(
+            position.parse_uci("e2e4"),
+            Err(UciMoveError::Illegal(_))
+        ));
+        assert!(matches!(
+            position.parse_uci("e9e4"),
+            Err(UciMoveError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,262 @@
+//! Opening books in the [Polyglot](http://hgm.nubati.net/book_format.html) `.bin` format.
+//!
+//! A book is a list of 16 byte entries sorted by key, each holding a position key,
+//! a move and a weight, all big endian. Positions are keyed by
+//! [`Position::zobrist_key`], so a book should be built with [`Book::from_games`]
+//! or another tool using the same keys. Books keyed with Polyglot's own random
+//! numbers read without error but won't match any position.
+
+use alloc::{collections::BTreeMap, vec::Vec};
+#[cfg(feature = "std")]
+use std::{io, path::Path};
+
+use crate::{pgn, Move, PieceKind, Position};
+
+/// A single book entry: one move from one position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub key: u64,
+    /// The move in Polyglot's encoding, see [`Entry::encode_move`].
+    pub mv: u16,
+    /// How often the move should be picked, relative to the other moves.
+    pub weight: u16,
+    /// Left for engines to store learning data, unused here.
+    pub learn: u32,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BookError {
+    #[error("book is {0} bytes, which isn't a whole number of entries")]
+    Truncated(usize),
+    #[error(transparent)]
+    Pgn(#[from] pgn::PgnError),
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl Entry {
+    const SIZE: usize = 16;
+
+    /// Encodes a [`Move`] made in `position` the way Polyglot does.
+    ///
+    /// Squares take 6 bits each with `to` lowest, then 3 bits for the promotion.
+    /// Castling is written as the king moving onto its own rook.
+    pub fn encode_move(position: &Position, mv: Move) -> u16 {
+        let to = match mv.castle_side() {
+            Some(side) => position.castling_rook(position.side_to_move(), side),
+            None => mv.to(),
+        };
+        let promotion = match mv.promotion() {
+            Some(PieceKind::Knight) => 1,
+            Some(PieceKind::Bishop) => 2,
+            Some(PieceKind::Rook) => 3,
+            Some(PieceKind::Queen) => 4,
+            _ => 0,
+        };
+        to.as_u8() as u16 | (mv.from().as_u8() as u16) << 6 | promotion << 12
+    }
+
+    /// Finds the legal [`Move`] this entry describes in `position`.
+    pub fn decode_move(&self, position: &Position) -> Option<Move> {
+        position
+            .legal_moves()
+            .iter()
+            .copied()
+            .find(|&mv| Self::encode_move(position, mv) == self.mv)
+    }
+
+    fn read(bytes: &[u8]) -> Self {
+        let int = |range: core::ops::Range<usize>| {
+            bytes[range]
+                .iter()
+                .fold(0u64, |int, &byte| int << 8 | byte as u64)
+        };
+        Self {
+            key: int(0..8),
+            mv: int(8..10) as u16,
+            weight: int(10..12) as u16,
+            learn: int(12..16) as u32,
+        }
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.key.to_be_bytes());
+        bytes.extend_from_slice(&self.mv.to_be_bytes());
+        bytes.extend_from_slice(&self.weight.to_be_bytes());
+        bytes.extend_from_slice(&self.learn.to_be_bytes());
+    }
+}
+
+/// An opening book, with entries sorted by key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Book {
+    entries: Vec<Entry>,
+}
+
+impl Book {
+    /// Reads a book from the bytes of a `.bin` file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        if !bytes.len().is_multiple_of(Entry::SIZE) {
+            return Err(BookError::Truncated(bytes.len()));
+        }
+        let entries = bytes.chunks_exact(Entry::SIZE).map(Entry::read).collect();
+        Ok(Self::from_entries(entries))
+    }
+
+    /// Reads a book from a `.bin` file.
+    #[cfg(feature = "std")]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, BookError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Creates a book from entries in any order.
+    pub fn from_entries(mut entries: Vec<Entry>) -> Self {
+        entries.sort_by_key(|entry| (entry.key, core::cmp::Reverse(entry.weight)));
+        Self { entries }
+    }
+
+    /// Builds a book from the first `max_plies` moves of each game's main line,
+    /// weighting each move by how many times it was played.
+    pub fn from_games<'a>(
+        games: impl IntoIterator<Item = &'a pgn::Game>,
+        max_plies: usize,
+    ) -> Result<Self, BookError> {
+        let mut counts = BTreeMap::<(u64, u16), u32>::new();
+        for game in games {
+            let mut position = game.start_position()?;
+            for node in game.moves.iter().take(max_plies) {
+                let mv = Entry::encode_move(&position, node.mv);
+                *counts.entry((position.zobrist_key(), mv)).or_default() += 1;
+                position.make_move(node.mv);
+            }
+        }
+
+        let entries = counts
+            .into_iter()
+            .map(|((key, mv), count)| Entry {
+                key,
+                mv,
+                weight: count.min(u16::MAX as u32) as u16,
+                learn: 0,
+            })
+            .collect();
+        Ok(Self::from_entries(entries))
+    }
+
+    /// Writes the book in the `.bin` format.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.entries.len() * Entry::SIZE);
+        for entry in &self.entries {
+            entry.write(&mut bytes);
+        }
+        bytes
+    }
+
+    /// Every entry in the book.
+    #[inline]
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// The entries for positions with `key`, heaviest first.
+    pub fn lookup(&self, key: u64) -> &[Entry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = self.entries.partition_point(|entry| entry.key <= key);
+        &self.entries[start..end]
+    }
+
+    /// The legal book moves in `position` with their weights, heaviest first.
+    pub fn moves(&self, position: &Position) -> Vec<(Move, u16)> {
+        self.lookup(position.zobrist_key())
+            .iter()
+            .filter_map(|entry| Some((entry.decode_move(position)?, entry.weight)))
+            .collect()
+    }
+
+    /// Picks a book move in `position` at random, favouring heavier moves.
+    ///
+    /// `random` can be any random number, it only needs to be uniform.
+    pub fn choose(&self, position: &Position, random: u64) -> Option<Move> {
+        let moves = self.moves(position);
+        let total = moves.iter().map(|&(_, weight)| weight as u64).sum::<u64>();
+        if total == 0 {
+            return moves.first().map(|&(mv, _)| mv);
+        }
+
+        let mut pick = random % total;
+        for (mv, weight) in moves {
+            match pick.checked_sub(weight as u64) {
+                Some(rest) => pick = rest,
+                None => return Some(mv),
+            }
+        }
+        unreachable!("the pick is always below the total weight")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Square;
+
+    const GAMES: &str = "1. e4 e5 2. Nf3 Nc6 *\n\n1. e4 c5 *\n\n1. d4 d5 *\n";
+
+    fn book() -> Book {
+        let games = pgn::games(GAMES).collect::<Result<Vec<_>, _>>().unwrap();
+        Book::from_games(&games, 2).unwrap()
+    }
+
+    #[test]
+    fn build_and_choose() {
+        let book = book();
+        let start = Position::start();
+
+        let moves = book.moves(&start);
+        let e4 = start.parse_uci("e2e4").unwrap();
+        let d4 = start.parse_uci("d2d4").unwrap();
+        assert_eq!(moves, [(e4, 2), (d4, 1)]);
+
+        assert_eq!(book.choose(&start, 0), Some(e4));
+        assert_eq!(book.choose(&start, 1), Some(e4));
+        assert_eq!(book.choose(&start, 2), Some(d4));
+
+        // only two plies were taken from each game
+        let mut position = start.clone();
+        position.make_move(e4);
+        assert_eq!(book.moves(&position).len(), 2);
+        position.make_move(position.parse_uci("e7e5").unwrap());
+        assert_eq!(book.choose(&position, 0), None);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let book = book();
+        let bytes = book.to_bytes();
+        assert_eq!(bytes.len(), book.entries().len() * 16);
+        assert_eq!(Book::from_bytes(&bytes).unwrap(), book);
+
+        assert!(matches!(
+            Book::from_bytes(&bytes[1..]),
+            Err(BookError::Truncated(_))
+        ));
+    }
+
+    #[test]
+    fn castling_moves_onto_rook() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        let position = crate::FEN::new(fen).parse_position().unwrap();
+        let castle = position.parse_uci("e1g1").unwrap();
+
+        let encoded = Entry::encode_move(&position, castle);
+        assert_eq!(Square::from_raw((encoded & 0x3F) as u8), Square::H1);
+
+        let entry = Entry {
+            key: position.zobrist_key(),
+            mv: encoded,
+            weight: 1,
+            learn: 0,
+        };
+        assert_eq!(entry.decode_move(&position), Some(castle));
+    }
+}
@@ -0,0 +1,910 @@
+//! Reading and writing games in [Portable Game Notation](https://en.wikipedia.org/wiki/Portable_Game_Notation).
+
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+use core::{fmt, time::Duration};
+
+use crate::{
+    annotation::{self, Annotation},
+    clock::{self, TimeControl},
+    fen,
+    san::SanError,
+    variant, Color, Move, Position, FEN,
+};
+
+/// How a game ended, as written at the end of the movetext.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    /// The game is still going, or the result is unknown.
+    Unknown,
+}
+
+impl Outcome {
+    fn from_token(token: &str) -> Option<Self> {
+        match token {
+            "1-0" => Some(Self::WhiteWins),
+            "0-1" => Some(Self::BlackWins),
+            "1/2-1/2" => Some(Self::Draw),
+            "*" => Some(Self::Unknown),
+            _ => None,
+        }
+    }
+
+    /// The notation for the outcome, as used in the movetext and `Result` tag.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::WhiteWins => "1-0",
+            Self::BlackWins => "0-1",
+            Self::Draw => "1/2-1/2",
+            Self::Unknown => "*",
+        }
+    }
+}
+
+/// An engine's evaluation of a position, from White's point of view,
+/// as given by an `[%eval]` command in a comment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Eval {
+    /// Written in pawns, as in `[%eval -0.35]`.
+    Centipawns(i32),
+    /// Moves until mate, negative when Black mates, as in `[%eval #-3]`.
+    Mate(i32),
+}
+
+impl Eval {
+    /// Parses the value of an `[%eval]` command, ignoring any search depth after a comma.
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.split(',').next()?.trim();
+        if let Some(mate) = value.strip_prefix('#') {
+            return mate.parse().ok().map(Self::Mate);
+        }
+
+        let (negative, value) = match value.strip_prefix('-') {
+            Some(value) => (true, value),
+            None => (false, value.strip_prefix('+').unwrap_or(value)),
+        };
+        let (pawns, fraction) = value.split_once('.').unwrap_or((value, ""));
+        // the sign has already been taken off, so only digits are left
+        let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if pawns.is_empty() || !digits(pawns) || !digits(fraction) {
+            return None;
+        }
+        let mut centipawns = pawns.parse::<i32>().ok()?.checked_mul(100)?;
+        for (digit, scale) in fraction.bytes().zip([10, 1]) {
+            centipawns = centipawns.checked_add((digit - b'0') as i32 * scale)?;
+        }
+        Some(Self::Centipawns(if negative {
+            -centipawns
+        } else {
+            centipawns
+        }))
+    }
+}
+
+impl fmt::Display for Eval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Centipawns(cp) => {
+                let sign = if cp < 0 { "-" } else { "" };
+                let cp = cp.unsigned_abs();
+                write!(f, "{sign}{}.{:02}", cp / 100, cp % 100)
+            }
+            Self::Mate(moves) => write!(f, "#{moves}"),
+        }
+    }
+}
+
+/// A move in the game tree, with its annotations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub mv: Move,
+    /// [Numeric Annotation Glyphs](https://en.wikipedia.org/wiki/Numeric_Annotation_Glyphs),
+    /// with `!` and `?` style suffixes converted to their numbers.
+    pub nags: Vec<u8>,
+    /// Comments before the move, only possible at the start of a line.
+    pub comments_before: Vec<String>,
+    /// Comments after the move.
+    pub comments: Vec<String>,
+    /// Lines played instead of this move.
+    pub variations: Vec<Vec<Node>>,
+    /// Time left on the mover's clock, from a `[%clk]` command after the move.
+    pub clock: Option<Duration>,
+    /// The evaluation after the move, from an `[%eval]` command.
+    pub eval: Option<Eval>,
+    /// Arrows and marked squares for the position after the move,
+    /// from `[%cal]` and `[%csl]` commands.
+    pub annotations: Vec<Annotation>,
+}
+
+impl Node {
+    /// Creates a [`Node`] without any annotations.
+    pub fn new(mv: Move) -> Self {
+        Self {
+            mv,
+            nags: Vec::new(),
+            comments_before: Vec::new(),
+            comments: Vec::new(),
+            variations: Vec::new(),
+            clock: None,
+            eval: None,
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Adds a comment after the move, taking out any `[%clk]`, `[%eval]`,
+    /// `[%cal]` and `[%csl]` commands.
+    fn add_comment(&mut self, comment: String) {
+        let mut text = String::new();
+        let mut rest = comment.as_str();
+        let mut found = false;
+
+        while let Some(start) = rest.find("[%") {
+            let Some(end) = rest[start..].find(']') else {
+                break;
+            };
+            let command = &rest[start + 2..start + end];
+            let (name, value) = command.split_once(' ').unwrap_or((command, ""));
+            let parsed = match name {
+                "clk" => clock::parse_clock(value).map(|time| self.clock = Some(time)),
+                "eval" => Eval::parse(value).map(|eval| self.eval = Some(eval)),
+                "cal" | "csl" => annotation::parse_command(name, value)
+                    .map(|annotations| self.annotations.extend(annotations)),
+                _ => None,
+            };
+            let keep = if parsed.is_some() {
+                start
+            } else {
+                start + end + 1
+            };
+            text.push_str(&rest[..keep]);
+            text.push(' ');
+            found |= parsed.is_some();
+            rest = &rest[start + end + 1..];
+        }
+        text.push_str(rest);
+
+        if !found {
+            self.comments.push(comment);
+            return;
+        }
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !text.is_empty() {
+            self.comments.push(text);
+        }
+    }
+
+    /// The `[%eval]`, `[%clk]`, `[%csl]` and `[%cal]` commands for the move, as one comment.
+    fn commands(&self) -> Option<String> {
+        let eval = self.eval.map(|eval| format!("[%eval {eval}]"));
+        let clock = self
+            .clock
+            .map(|time| format!("[%clk {}]", clock::format_clock(time)));
+        let annotations = annotation::commands(&self.annotations);
+        let commands = [eval, clock, annotations]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        (!commands.is_empty()).then(|| commands.join(" "))
+    }
+}
+
+/// A single game read from, or to be written as, PGN.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    /// Tag pairs in the order they are written.
+    pub tags: Vec<(String, String)>,
+    /// The main line of the game.
+    pub moves: Vec<Node>,
+    pub outcome: Outcome,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PgnError {
+    #[error("tag pair is not closed")]
+    UnterminatedTag,
+    #[error("comment is not closed")]
+    UnterminatedComment,
+    #[error("variation is not closed")]
+    UnterminatedVariation,
+    #[error("unexpected ')'")]
+    UnexpectedClose,
+    #[error("annotation or variation before any move")]
+    NothingToAnnotate,
+    #[error("unknown token '{0}'")]
+    UnknownToken(String),
+    #[error("invalid FEN tag")]
+    Fen,
+    #[error("unknown variant '{0}'")]
+    UnknownVariant(String),
+    #[error(transparent)]
+    San(#[from] SanError),
+}
+
+impl From<fen::ParseError> for PgnError {
+    fn from(_: fen::ParseError) -> Self {
+        Self::Fen
+    }
+}
+
+impl Game {
+    /// Creates a [`Game`] with the Seven Tag Roster filled with unknown values.
+    pub fn new() -> Self {
+        let tags = ["Event", "Site", "Date", "Round", "White", "Black", "Result"]
+            .into_iter()
+            .map(|name| {
+                let value = if name == "Result" { "*" } else { "?" };
+                (name.to_owned(), value.to_owned())
+            })
+            .collect();
+
+        Self {
+            tags,
+            moves: Vec::new(),
+            outcome: Outcome::Unknown,
+        }
+    }
+
+    /// Parses the first game in `text`.
+    pub fn parse(text: &str) -> Result<Self, PgnError> {
+        games(text).next().unwrap_or_else(|| Ok(Self::new()))
+    }
+
+    /// The time control from the `TimeControl` tag, if it has one that can be read.
+    pub fn time_control(&self) -> Option<TimeControl> {
+        self.tag("TimeControl")?.parse().ok()
+    }
+
+    /// Finds the value of a tag.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the value of a tag, adding it to the end if it isn't there.
+    pub fn set_tag(&mut self, name: &str, value: impl Into<String>) {
+        let value = value.into();
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value,
+            None => self.tags.push((name.to_owned(), value)),
+        }
+    }
+
+    /// The position the game starts from, taken from the `FEN` tag if there is one,
+    /// following the rules named by the `Variant` tag.
+    pub fn start_position(&self) -> Result<Position, PgnError> {
+        let variant = match self.tag("Variant") {
+            Some(name) => Some(
+                variant::from_name(name)
+                    .ok_or_else(|| PgnError::UnknownVariant(name.to_owned()))?,
+            ),
+            None => None,
+        };
+        match (self.tag("FEN"), variant) {
+            (Some(fen), Some(variant)) => Ok(FEN::try_from(fen)?.parse_position_as(variant)?),
+            (Some(fen), None) => Ok(FEN::try_from(fen)?.parse_position()?),
+            (None, Some(variant)) => Ok(Position::start_variant(variant)),
+            (None, None) => Ok(Position::start()),
+        }
+    }
+
+    /// The position at the end of the main line.
+    pub fn end_position(&self) -> Result<Position, PgnError> {
+        let mut position = self.start_position()?;
+        for node in &self.moves {
+            position.make_move(node.mv);
+        }
+        Ok(position)
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses every game in `text`, one after the other.
+pub fn games(text: &str) -> Games<'_> {
+    Games {
+        lexer: Lexer { src: text, pos: 0 },
+        peeked: None,
+    }
+}
+
+/// Parses every game in `text` across threads, giving the same results
+/// in the same order as [`games`].
+///
+/// The text is split into chunks just before a line of tags that follows movetext,
+/// and each chunk is parsed on its own, so it needs games to start with their tags.
+/// Games without any tags are still read, but stay in the chunk before them.
+///
+/// ```
+/// # use board::pgn;
+/// # use rayon::prelude::*;
+/// let text = "[Event \"A\"]\n\n1. e4 e5 1-0\n\n[Event \"B\"]\n\n1. d4 0-1\n";
+/// let plies = pgn::par_games(text)
+///     .map(|game| game.map_or(0, |game| game.moves.len()))
+///     .collect::<Vec<_>>();
+/// assert_eq!(plies, [2, 1]);
+/// ```
+#[cfg(feature = "rayon")]
+pub fn par_games(
+    text: &str,
+) -> impl rayon::iter::ParallelIterator<Item = Result<Game, PgnError>> + '_ {
+    use rayon::prelude::*;
+
+    let chunks = rayon::current_num_threads() * 8;
+    let mut starts = (1..chunks)
+        .filter_map(|i| next_game_start(text, text.len() * i / chunks))
+        .collect::<Vec<_>>();
+    starts.dedup();
+
+    let ends = starts.clone().into_iter().chain([text.len()]);
+    let ranges = [0].into_iter().chain(starts).zip(ends).collect::<Vec<_>>();
+    ranges
+        .into_par_iter()
+        .flat_map_iter(move |(start, end)| games(&text[start..end]))
+}
+
+/// The start of the first line of tags at or after `from` that follows movetext,
+/// which begins a new game.
+#[cfg(feature = "rayon")]
+fn next_game_start(text: &str, from: usize) -> Option<usize> {
+    // the start of the line `from` is in, or the next one, found in the bytes
+    // as `from` may be in the middle of a character
+    let mut line_start = text.as_bytes()[..from]
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |i| i + 1);
+    let mut after_movetext = false;
+
+    for line in text[line_start..].split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        // a tag looks like `[Name "`, which a comment is unlikely to start a line with
+        let is_tag = trimmed.strip_prefix('[').is_some_and(|tag| {
+            let value = tag.trim_start_matches(|c: char| c.is_ascii_alphanumeric() || c == '_');
+            value.len() < tag.len() && value.trim_start().starts_with('"')
+        });
+        if is_tag && after_movetext && line_start > 0 {
+            return Some(line_start);
+        }
+        if !trimmed.is_empty() {
+            after_movetext = !is_tag;
+        }
+        line_start += line.len();
+    }
+    None
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    Tag(&'a str, String),
+    Comment(String),
+    Nag(u8),
+    MoveNumber,
+    San(&'a str),
+    Open,
+    Close,
+    Outcome(Outcome),
+}
+
+struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn next_token(&mut self) -> Option<Result<Token<'a>, PgnError>> {
+        // skip whitespace and escaped lines
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+
+            let at_line_start = self.pos == 0 || self.src[..self.pos].ends_with('\n');
+            if at_line_start && trimmed.starts_with('%') {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                break;
+            }
+        }
+
+        let rest = self.rest();
+        let first = rest.chars().next()?;
+
+        let token = match first {
+            '[' => self.tag(),
+            '{' => {
+                let Some(end) = rest.find('}') else {
+                    self.pos = self.src.len();
+                    return Some(Err(PgnError::UnterminatedComment));
+                };
+                self.pos += end + 1;
+                Ok(Token::Comment(rest[1..end].trim().to_owned()))
+            }
+            ';' => {
+                let end = rest.find('\n').unwrap_or(rest.len());
+                self.pos += end;
+                Ok(Token::Comment(rest[1..end].trim().to_owned()))
+            }
+            '(' => {
+                self.pos += 1;
+                Ok(Token::Open)
+            }
+            ')' => {
+                self.pos += 1;
+                Ok(Token::Close)
+            }
+            '$' => {
+                let digits = rest[1..]
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len() - 1);
+                self.pos += digits + 1;
+                rest[1..=digits]
+                    .parse()
+                    .map(Token::Nag)
+                    .map_err(|_| PgnError::UnknownToken(rest[..=digits].to_owned()))
+            }
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || "{}()[];$".contains(c))
+                    .unwrap_or(rest.len());
+                self.pos += end;
+                Self::word(&rest[..end])
+            }
+        };
+
+        Some(token)
+    }
+
+    fn tag(&mut self) -> Result<Token<'a>, PgnError> {
+        let rest = self.rest();
+        // an unterminated tag swallows the rest of the input
+        let pos = core::mem::replace(&mut self.pos, self.src.len());
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '"')
+            .ok_or(PgnError::UnterminatedTag)?;
+        let name = &rest[1..name_end];
+
+        let quote = rest.find('"').ok_or(PgnError::UnterminatedTag)?;
+        let mut value = String::new();
+        let mut chars = rest[quote + 1..].char_indices();
+        let value_end = loop {
+            match chars.next() {
+                Some((_, '\\')) => value.extend(chars.next().map(|(_, c)| c)),
+                Some((i, '"')) => break quote + 1 + i,
+                Some((_, c)) => value.push(c),
+                None => return Err(PgnError::UnterminatedTag),
+            }
+        };
+
+        let close = rest[value_end..]
+            .find(']')
+            .ok_or(PgnError::UnterminatedTag)?;
+        self.pos = pos + value_end + close + 1;
+
+        Ok(Token::Tag(name, value))
+    }
+
+    fn word(word: &'a str) -> Result<Token<'a>, PgnError> {
+        if let Some(outcome) = Outcome::from_token(word) {
+            return Ok(Token::Outcome(outcome));
+        }
+
+        if word.starts_with(|c: char| c.is_ascii_digit()) && !word.starts_with("0-0") {
+            let number = word.trim_end_matches('.');
+            if number.chars().all(|c| c.is_ascii_digit()) {
+                return Ok(Token::MoveNumber);
+            }
+            // some writers leave no space after the move number, such as `1.e4`
+            if let Some((_, san)) = word.split_once('.') {
+                return Ok(Token::San(san.trim_start_matches('.')));
+            }
+        }
+
+        let suffix = match word.find(['!', '?']) {
+            Some(i) => &word[i..],
+            None => "",
+        };
+        let nag = match suffix {
+            "" => None,
+            "!" => Some(1),
+            "?" => Some(2),
+            "!!" => Some(3),
+            "??" => Some(4),
+            "!?" => Some(5),
+            "?!" => Some(6),
+            _ => return Err(PgnError::UnknownToken(word.to_owned())),
+        };
+
+        match nag {
+            // a bare suffix written apart from the move
+            Some(nag) if suffix.len() == word.len() => Ok(Token::Nag(nag)),
+            _ => Ok(Token::San(word)),
+        }
+    }
+}
+
+/// An iterator over the games in a PGN string, see [`games`].
+pub struct Games<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<Result<Token<'a>, PgnError>>,
+}
+
+impl<'a> Games<'a> {
+    fn peek(&mut self) -> Option<&Result<Token<'a>, PgnError>> {
+        if self.peeked.is_none() {
+            self.peeked = self.lexer.next_token();
+        }
+        self.peeked.as_ref()
+    }
+
+    fn next_token(&mut self) -> Option<Result<Token<'a>, PgnError>> {
+        self.peeked.take().or_else(|| self.lexer.next_token())
+    }
+
+    /// Skips to the start of the next game after an error.
+    fn recover(&mut self) {
+        loop {
+            match self.next_token() {
+                None | Some(Ok(Token::Outcome(_))) => return,
+                // a tag belongs to the next game
+                Some(Ok(tag @ Token::Tag(..))) => {
+                    self.peeked = Some(Ok(tag));
+                    return;
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn game(&mut self) -> Result<Game, PgnError> {
+        let mut game = Game {
+            tags: Vec::new(),
+            moves: Vec::new(),
+            outcome: Outcome::Unknown,
+        };
+
+        while let Some(Ok(Token::Tag(..))) = self.peek() {
+            if let Some(Ok(Token::Tag(name, value))) = self.next_token() {
+                game.tags.push((name.to_owned(), value));
+            }
+        }
+
+        game.moves = self.line(game.start_position()?)?;
+
+        match self.next_token() {
+            Some(Ok(Token::Outcome(outcome))) => game.outcome = outcome,
+            Some(Ok(Token::Close)) => return Err(PgnError::UnexpectedClose),
+            Some(Err(e)) => return Err(e),
+            // a tag without a result starts the next game
+            Some(Ok(token)) => self.peeked = Some(Ok(token)),
+            None => {}
+        }
+
+        Ok(game)
+    }
+
+    /// Parses a line of moves starting from `position`,
+    /// until the end of the variation or game.
+    fn line(&mut self, mut position: Position) -> Result<Vec<Node>, PgnError> {
+        let mut nodes = Vec::<Node>::new();
+        let mut comments_before = Vec::new();
+        // the position before the last move, where its variations start
+        let mut previous = None;
+
+        loop {
+            match self.peek() {
+                None
+                | Some(Ok(Token::Close | Token::Outcome(_) | Token::Tag(..)))
+                | Some(Err(_)) => break,
+                Some(Ok(_)) => {}
+            }
+
+            match self.next_token() {
+                Some(Ok(Token::San(san))) => {
+                    let mv = position.parse_san(san)?;
+                    previous = Some(position.clone());
+                    position.make_move(mv);
+
+                    let mut node = Node::new(mv);
+                    node.comments_before = core::mem::take(&mut comments_before);
+                    // suffixes like `!?` on the move itself
+                    if let Some(i) = san.find(['!', '?']) {
+                        if let Ok(Token::Nag(nag)) = Lexer::word(&san[i..]) {
+                            node.nags.push(nag);
+                        }
+                    }
+                    nodes.push(node);
+                }
+                Some(Ok(Token::Comment(comment))) => match nodes.last_mut() {
+                    Some(node) => node.add_comment(comment),
+                    None => comments_before.push(comment),
+                },
+                Some(Ok(Token::Nag(nag))) => nodes
+                    .last_mut()
+                    .ok_or(PgnError::NothingToAnnotate)?
+                    .nags
+                    .push(nag),
+                Some(Ok(Token::Open)) => {
+                    let start = previous.clone().ok_or(PgnError::NothingToAnnotate)?;
+                    let variation = self.line(start)?;
+                    match self.next_token() {
+                        Some(Ok(Token::Close)) => {}
+                        Some(Err(e)) => return Err(e),
+                        _ => return Err(PgnError::UnterminatedVariation),
+                    }
+                    nodes.last_mut().unwrap().variations.push(variation);
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(Err(_)) = self.peek() {
+            return Err(self.next_token().unwrap().unwrap_err());
+        }
+
+        Ok(nodes)
+    }
+}
+
+impl Iterator for Games<'_> {
+    type Item = Result<Game, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.peek()?;
+
+        let game = self.game();
+        if game.is_err() {
+            self.recover();
+        }
+        Some(game)
+    }
+}
+
+/// Collects movetext tokens, wrapping lines before they get too long.
+struct Wrapper {
+    text: String,
+    line_len: usize,
+    /// Opening parentheses to attach to the next token.
+    prefix: String,
+}
+
+impl Wrapper {
+    const MAX_LINE: usize = 80;
+
+    fn push(&mut self, token: &str) {
+        let len = self.prefix.len() + token.len();
+        if self.line_len > 0 {
+            if self.line_len + 1 + len > Self::MAX_LINE {
+                self.text.push('\n');
+                self.line_len = 0;
+            } else {
+                self.text.push(' ');
+                self.line_len += 1;
+            }
+        }
+        self.text.push_str(&self.prefix);
+        self.text.push_str(token);
+        self.line_len += len;
+        self.prefix.clear();
+    }
+
+    fn open_variation(&mut self) {
+        self.prefix.push('(');
+    }
+
+    fn close_variation(&mut self) {
+        self.text.push(')');
+        self.line_len += 1;
+    }
+}
+
+fn write_line(out: &mut Wrapper, mut position: Position, nodes: &[Node]) {
+    // black moves need a number at the start of a line, or after an interruption
+    let mut needs_number = true;
+
+    for node in nodes {
+        for comment in &node.comments_before {
+            out.push(&format!("{{{comment}}}"));
+        }
+
+        let number = position.fullmove_number();
+        match position.side_to_move() {
+            Color::White => out.push(&format!("{number}.")),
+            Color::Black if needs_number => out.push(&format!("{number}...")),
+            Color::Black => {}
+        }
+        needs_number = false;
+
+        out.push(&position.san(node.mv));
+
+        for nag in &node.nags {
+            out.push(&format!("${nag}"));
+        }
+        for comment in node.commands().iter().chain(&node.comments) {
+            out.push(&format!("{{{comment}}}"));
+            needs_number = true;
+        }
+        for variation in node.variations.iter().filter(|v| !v.is_empty()) {
+            out.open_variation();
+            write_line(out, position.clone(), variation);
+            out.close_variation();
+            needs_number = true;
+        }
+
+        position.make_move(node.mv);
+    }
+}
+
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{name} \"{value}\"]")?;
+        }
+        if !self.tags.is_empty() {
+            writeln!(f)?;
+        }
+
+        let position = self.start_position().map_err(|_| fmt::Error)?;
+        let mut out = Wrapper {
+            text: String::new(),
+            line_len: 0,
+            prefix: String::new(),
+        };
+        write_line(&mut out, position, &self.moves);
+        out.push(self.outcome.as_str());
+
+        writeln!(f, "{}", out.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PGN: &str = r#"[Event "Test \"quoted\""]
+[Site "?"]
+[Result "1-0"]
+
+{Opening comment} 1. e4 e5 2. Nf3 $1 {Develops} (2. f4!? exf4) 2... Nc6 3. Bb5 a6
+; the Ruy Lopez
+4. Ba4 1-0
+
+[Event "Second"]
+
+1.d4 d5 *
+"#;
+
+    #[test]
+    fn parse_games() {
+        let games = games(PGN).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(games.len(), 2);
+
+        let game = &games[0];
+        assert_eq!(game.tag("Event"), Some("Test \"quoted\""));
+        assert_eq!(game.outcome, Outcome::WhiteWins);
+        assert_eq!(game.moves.len(), 7);
+        assert_eq!(game.moves[0].comments_before, ["Opening comment"]);
+        assert_eq!(game.moves[2].nags, [1]);
+        assert_eq!(game.moves[2].comments, ["Develops"]);
+        assert_eq!(game.moves[2].variations[0].len(), 2);
+        assert_eq!(game.moves[2].variations[0][0].nags, [5]);
+        assert_eq!(game.moves[5].comments, ["the Ruy Lopez"]);
+
+        assert_eq!(games[1].moves.len(), 2);
+        assert_eq!(games[1].outcome, Outcome::Unknown);
+    }
+
+    #[test]
+    fn write_round_trip() {
+        let game = Game::parse(PGN).unwrap();
+        let written = game.to_string();
+
+        assert_eq!(
+            written,
+            "[Event \"Test \\\"quoted\\\"\"]\n[Site \"?\"]\n[Result \"1-0\"]\n\n\
+             {Opening comment} 1. e4 e5 2. Nf3 $1 {Develops} (2. f4 $5 exf4) 2... Nc6 3. Bb5\n\
+             a6 {the Ruy Lopez} 4. Ba4 1-0\n"
+        );
+        assert_eq!(Game::parse(&written).unwrap(), game);
+    }
+
+    #[test]
+    fn annotation_commands() {
+        let text = "1. e4 { Aiming at f7 [%csl Gf7] [%cal Gf1c4,Rd1h5] } *";
+        let game = Game::parse(text).unwrap();
+        let node = &game.moves[0];
+        assert_eq!(node.comments, ["Aiming at f7"]);
+        assert_eq!(node.annotations.len(), 3);
+        assert_eq!(node.annotations[0].to_string(), "Gf7");
+
+        let written = game.to_string();
+        assert!(written.starts_with("1. e4 {[%csl Gf7] [%cal Gf1c4,Rd1h5]} {Aiming at f7}"));
+        assert_eq!(Game::parse(&written).unwrap(), game);
+    }
+
+    #[test]
+    fn clock_and_eval_commands() {
+        let text =
+            "1. e4 { [%eval 0.17] [%clk 0:03:00] } 1... c5 { [%eval 0.25,20] [%clk 0:02:58.5] }\n\
+                    2. Qh5 { Hopeful [%clk 0:02:30] [%foo 1] } 2... Nf6 { [%eval #-2] } *";
+        let game = Game::parse(text).unwrap();
+        let moves = &game.moves;
+
+        assert_eq!(moves[0].eval, Some(Eval::Centipawns(17)));
+        assert_eq!(moves[0].clock, Some(Duration::from_secs(180)));
+        assert!(moves[0].comments.is_empty());
+        assert_eq!(moves[1].eval, Some(Eval::Centipawns(25)));
+        assert_eq!(moves[1].clock, Some(Duration::from_millis(178_500)));
+        assert_eq!(moves[2].clock, Some(Duration::from_secs(150)));
+        assert_eq!(moves[2].comments, ["Hopeful [%foo 1]"]);
+        assert_eq!(moves[3].eval, Some(Eval::Mate(-2)));
+
+        assert_eq!(Eval::parse("-0.05"), Some(Eval::Centipawns(-5)));
+        assert_eq!(Eval::parse("1.5"), Some(Eval::Centipawns(150)));
+        assert_eq!(Eval::Centipawns(-5).to_string(), "-0.05");
+        assert_eq!(Eval::parse("x"), None);
+        assert_eq!(Eval::parse("--1.5"), None);
+        assert_eq!(Eval::parse("-+1.5"), None);
+        assert_eq!(Eval::parse("21474836.99"), None);
+        let overflowing = Game::parse("1. e4 { [%eval 21474836.99] } *").unwrap();
+        assert_eq!(overflowing.moves[0].eval, None);
+
+        let written = game.to_string();
+        assert!(written.starts_with("1. e4 {[%eval 0.17] [%clk 0:03:00]} 1... c5"));
+        assert_eq!(Game::parse(&written).unwrap(), game);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_games() {
+        use rayon::prelude::*;
+
+        let mut text = String::new();
+        for i in 0..200 {
+            text.push_str(match i % 4 {
+                0 => PGN,
+                1 => "[Event \"Broken\"]\n\n1. e4 e4 *\n\n",
+                2 => "[Event \"Clocks\"]\n{ [%clk 0:01:00]\n[Note] }\n1. e4 { [%clk 0:00:59]\n} *\n\n",
+                _ => "1. Nf3 *\n\n",
+            });
+        }
+
+        let serial = games(&text).collect::<Vec<_>>();
+        let parallel = par_games(&text).collect::<Vec<_>>();
+        assert_eq!(serial.len(), 250);
+        assert_eq!(parallel, serial);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn game_starts_between_characters() {
+        let text = "[White \"Müller\"]\n\n1. e4 *\n\n[White \"Ødegård\"]\n\n1. d4 *\n";
+        let second = text.find("[White \"Ø").unwrap();
+        assert_eq!(next_game_start(text, 0), Some(second));
+        for from in 0..=text.len() {
+            let start = next_game_start(text, from);
+            assert!(start.is_none() || start == Some(second));
+        }
+    }
+
+    #[test]
+    fn errors_skip_to_next_game() {
+        let text = "1. e4 e4 *\n\n[Event \"Fine\"]\n1. d4 *";
+        let results = games(text).collect::<Vec<_>>();
+
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results[0], Err(PgnError::San(_))));
+        assert_eq!(results[1].as_ref().unwrap().tag("Event"), Some("Fine"));
+    }
+}
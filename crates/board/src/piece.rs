@@ -1,7 +1,7 @@
 use bitfield_struct::bitfield;
 
 #[bitfield(u8)]
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Hash)]
 pub struct Piece {
     #[bits(1)]
     pub color: Color,
@@ -38,6 +38,7 @@ impl Piece {
 
 #[repr(usize)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Color {
     /// White Pieces
     White = 0,
@@ -46,10 +47,19 @@ pub enum Color {
 }
 
 impl Color {
-    pub(crate) const COUNT: usize = std::mem::variant_count::<Self>();
+    pub(crate) const COUNT: usize = core::mem::variant_count::<Self>();
 
     pub(crate) const ALL: [Self; Self::COUNT] = [Self::White, Self::Black];
 
+    /// The other [`Color`].
+    #[inline]
+    pub const fn opposite(self) -> Self {
+        match self {
+            Self::White => Self::Black,
+            Self::Black => Self::White,
+        }
+    }
+
     const fn into_bits(self) -> u8 {
         self as _
     }
@@ -58,7 +68,7 @@ impl Color {
         let value = value as usize;
 
         if value < Self::COUNT {
-            unsafe { std::mem::transmute(value) }
+            unsafe { core::mem::transmute::<usize, Self>(value) }
         } else {
             panic!("failed to create Color from bits, out of range");
         }
@@ -67,6 +77,7 @@ impl Color {
 
 #[repr(usize)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PieceKind {
     /// All pawns
     Pawn = 0,
@@ -83,7 +94,7 @@ pub enum PieceKind {
 }
 
 impl PieceKind {
-    pub(crate) const COUNT: usize = std::mem::variant_count::<Self>();
+    pub(crate) const COUNT: usize = core::mem::variant_count::<Self>();
 
     pub(crate) const ALL: [Self; Self::COUNT] = [
         Self::Pawn,
@@ -94,6 +105,13 @@ impl PieceKind {
         Self::King,
     ];
 
+    /// The usual worth of the piece in centipawns, with the king counting as nothing.
+    #[inline]
+    pub const fn value(self) -> i32 {
+        const VALUES: [i32; PieceKind::COUNT] = [100, 320, 330, 500, 900, 0];
+        VALUES[self as usize]
+    }
+
     const fn into_bits(self) -> u8 {
         self as _
     }
@@ -102,27 +120,15 @@ impl PieceKind {
         let value = value as usize;
 
         if value < Self::COUNT {
-            unsafe { std::mem::transmute(value) }
+            unsafe { core::mem::transmute::<usize, Self>(value) }
         } else {
             panic!("failed to create PieceKind from bits, out of range");
         }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn piece_creation() {
-        let p = Piece::new_with(Color::White, PieceKind::King);
-        assert_eq!(p.color(), Color::White);
-        assert_eq!(p.kind(), PieceKind::King);
-    }
-}
-
 /// Convience macro for creating pieces in pseudo-FEN notation
-/// 
+///
 /// # Example
 /// ```rust
 /// # use board::Color::*;
@@ -152,3 +158,15 @@ macro_rules! piece {
 }
 
 pub(crate) use piece;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn piece_creation() {
+        let p = Piece::new_with(Color::White, PieceKind::King);
+        assert_eq!(p.color(), Color::White);
+        assert_eq!(p.kind(), PieceKind::King);
+    }
+}
@@ -0,0 +1,1344 @@
+use alloc::vec::Vec;
+
+use spin::Lazy;
+
+use crate::{
+    attacks::king_attacks,
+    builder::PositionBuilder,
+    features::Features,
+    movegen,
+    variant::{Standard, Variant},
+    zobrist, BitBoard, Board, CastleSide, CastlingRights, Color, Move, MoveFlag, MoveList, Piece,
+    PieceKind, Rank, Square, ValidationError, FEN,
+};
+
+/// The state lost when making a move, needed to unmake it again.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Undo {
+    /// None for a null move.
+    mv: Option<Move>,
+    captured: Option<Piece>,
+    castling: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    key: u64,
+    pawn_key: u64,
+    promoted: BitBoard,
+    /// How many pieces the move blew up, see [`Position::exploded`].
+    explosions: u8,
+    kings: [Option<Square>; Color::COUNT],
+    checks: [u8; Color::COUNT],
+}
+
+/// A [`Board`] along with the rest of the state needed to play a game.
+#[derive(Clone, Debug)]
+pub struct Position {
+    pub(crate) board: Board,
+    pub(crate) side_to_move: Color,
+    pub(crate) castling: CastlingRights,
+    pub(crate) en_passant: Option<Square>,
+    pub(crate) halfmove_clock: u32,
+    pub(crate) fullmove_number: u32,
+    pub(crate) key: u64,
+    /// The Zobrist key of just the pawns, see [`Position::pawn_key`].
+    pub(crate) pawn_key: u64,
+    pub(crate) history: Vec<Undo>,
+    /// Where each player's castling rooks start, indexed by color then [`CastleSide`].
+    pub(crate) castling_rooks: [[Square; 2]; Color::COUNT],
+    pub(crate) chess960: bool,
+    /// How many of each piece are on the board, indexed by color then kind.
+    pub(crate) counts: [[u8; PieceKind::COUNT]; Color::COUNT],
+    /// The summed [`PieceKind::value`] of each color's pieces.
+    pub(crate) material: [i32; Color::COUNT],
+    /// Where each color's king stands, if it has one.
+    pub(crate) kings: [Option<Square>; Color::COUNT],
+    pub(crate) variant: &'static dyn Variant,
+    /// How many of each piece are in hand to be dropped, indexed by color then kind.
+    pub(crate) hands: [[u8; PieceKind::COUNT]; Color::COUNT],
+    /// Pieces that were pawns, see [`Position::promoted`].
+    pub(crate) promoted: BitBoard,
+    /// Pieces removed by explosions in Atomic, most recent last, to put back when unmaking.
+    pub(crate) exploded: Vec<(Square, Piece)>,
+    /// How many times each color has given check, see [`Position::checks_given`].
+    pub(crate) checks: [u8; Color::COUNT],
+}
+
+/// The rooks' starting squares in standard chess.
+pub(crate) const STANDARD_CASTLING_ROOKS: [[Square; 2]; Color::COUNT] =
+    [[Square::H1, Square::A1], [Square::H8, Square::A8]];
+
+/// The starting position, set up the first time it is asked for, see [`Position::START`].
+pub(crate) static START: Lazy<Position> = Lazy::new(|| {
+    FEN::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+        .parse_position()
+        .expect("Failed to parse FEN for starting position")
+});
+
+impl Position {
+    /// The starting position for a game of chess, the [`Position`] counterpart of
+    /// [`Board::START`].
+    ///
+    /// It can't be a `const` as it owns its history, so it is parsed the first time it
+    /// is dereferenced and shared after that. Use [`Position::start`] for a copy to play
+    /// moves on.
+    pub const START: &'static Lazy<Position> = &START;
+
+    /// The starting position for a game of chess.
+    ///
+    /// The position is only parsed once, so this is as cheap as a clone.
+    pub fn start() -> Self {
+        START.clone()
+    }
+
+    /// The starting position for a game of `variant`.
+    pub fn start_variant(variant: &'static dyn Variant) -> Self {
+        FEN::new(variant.start_fen())
+            .parse_position_as(variant)
+            .expect("Failed to parse FEN for starting position")
+    }
+
+    /// The Chess960 starting position numbered `index`, see [`Board::chess960`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not below 960.
+    pub fn chess960(index: u16) -> Self {
+        let board = Board::chess960(index);
+        let rooks = (board.rooks() & board.whites())
+            .into_iter()
+            .collect::<Vec<_>>();
+        let (queen_rook, king_rook) = (rooks[0], rooks[1]);
+
+        let mut position = Self {
+            board,
+            side_to_move: Color::White,
+            castling: CastlingRights::ALL,
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            key: 0,
+            pawn_key: 0,
+            history: Vec::new(),
+            castling_rooks: [
+                [king_rook, queen_rook],
+                [
+                    Square::new(Rank::Eighth, king_rook.file()),
+                    Square::new(Rank::Eighth, queen_rook.file()),
+                ],
+            ],
+            chess960: true,
+            counts: Default::default(),
+            material: Default::default(),
+            kings: Default::default(),
+            variant: &Standard,
+            hands: Default::default(),
+            promoted: BitBoard::EMPTY,
+            exploded: Vec::new(),
+            checks: [0; Color::COUNT],
+        };
+        position.refresh();
+        position
+    }
+
+    /// A standard chess position with just `pieces` on the board, for setting up
+    /// studies and tests without writing a FEN.
+    ///
+    /// There is no en passant square and the game is on its first move,
+    /// see [`PositionBuilder`] to set anything else.
+    /// The position is checked with [`Position::validate`].
+    ///
+    /// ```
+    /// # use board::{CastlingRights, Color, Piece, PieceKind, Position, Square};
+    /// let king = |color| Piece::new_with(color, PieceKind::King);
+    /// let position = Position::from_pieces(
+    ///     [
+    ///         (Square::E1, king(Color::White)),
+    ///         (Square::E8, king(Color::Black)),
+    ///         (Square::E2, Piece::new_with(Color::White, PieceKind::Pawn)),
+    ///     ],
+    ///     Color::White,
+    ///     CastlingRights::NONE,
+    /// )
+    /// .unwrap();
+    /// assert_eq!(position.to_fen().as_str(), "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+    /// ```
+    pub fn from_pieces(
+        pieces: impl IntoIterator<Item = (Square, Piece)>,
+        side_to_move: Color,
+        castling: CastlingRights,
+    ) -> Result<Self, ValidationError> {
+        PositionBuilder::new()
+            .pieces(pieces.into_iter().map(|(square, piece)| (piece, square)))
+            .side_to_move(side_to_move)
+            .castling(castling)
+            .build()
+    }
+
+    /// The [`Board`] holding the pieces.
+    #[inline]
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// The position encoded as inputs for a neural network.
+    #[inline]
+    pub fn features(&self) -> Features<'_> {
+        Features::new(self)
+    }
+
+    /// The rules the game is played by.
+    #[inline]
+    pub fn variant(&self) -> &'static dyn Variant {
+        self.variant
+    }
+
+    /// How many of `piece` are in hand, waiting to be dropped onto the board.
+    ///
+    /// Always 0 unless the [`Variant::has_drops`].
+    #[inline]
+    pub fn in_hand(&self, piece: Piece) -> u32 {
+        self.hands[piece.color() as usize][piece.kind() as usize] as u32
+    }
+
+    /// The pieces that were promoted from pawns, which go back to being pawns
+    /// when captured into a hand.
+    #[inline]
+    pub fn promoted(&self) -> BitBoard {
+        self.promoted
+    }
+
+    /// The [`Color`] of the player to move.
+    #[inline]
+    pub fn side_to_move(&self) -> Color {
+        self.side_to_move
+    }
+
+    /// The remaining [`CastlingRights`] for both players.
+    #[inline]
+    pub fn castling(&self) -> CastlingRights {
+        self.castling
+    }
+
+    /// The square the rook that `color` castles with towards `side` started on.
+    #[inline]
+    pub fn castling_rook(&self, color: Color, side: CastleSide) -> Square {
+        self.castling_rooks[color as usize][side as usize]
+    }
+
+    /// Returns `true` if castling follows Chess960 rules,
+    /// which also changes how castling moves are written in UCI.
+    #[inline]
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    /// The square a pawn can be captured on en passant.
+    ///
+    /// This is set after any double pawn push, even if no capture is possible.
+    #[inline]
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    /// The number of half moves since the last capture or pawn move.
+    #[inline]
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    /// The number of the full move, starting at 1 and incremented after black moves.
+    #[inline]
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    /// The last move made that can still be unmade, None if there isn't one
+    /// or it was a null move.
+    #[inline]
+    pub fn last_move(&self) -> Option<Move> {
+        self.history.last()?.mv
+    }
+
+    /// The Zobrist hash of the position, kept up to date as moves are made.
+    #[inline]
+    pub fn zobrist_key(&self) -> u64 {
+        self.key
+    }
+
+    /// The summed [`PieceKind::value`] of every piece `color` has left.
+    #[inline]
+    pub fn material(&self, color: Color) -> i32 {
+        self.material[color as usize]
+    }
+
+    /// The Zobrist key of the pawns alone, for caching pawn structure evaluation.
+    ///
+    /// Like [`Position::zobrist_key`] it is kept up to date as moves are made.
+    #[inline]
+    pub fn pawn_key(&self) -> u64 {
+        self.pawn_key
+    }
+
+    /// How many times `color` has given check, which is only counted in
+    /// variants like [`ThreeCheck`](crate::variant::ThreeCheck).
+    #[inline]
+    pub fn checks_given(&self, color: Color) -> u32 {
+        self.checks[color as usize] as u32
+    }
+
+    /// Where the king of `color` stands.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `color` has no king.
+    #[inline]
+    pub fn king_square(&self, color: Color) -> Square {
+        self.kings[color as usize].expect("there should be a king")
+    }
+
+    /// Where the king of `color` stands, or None if it has no king.
+    #[inline]
+    pub(crate) fn find_king(&self, color: Color) -> Option<Square> {
+        self.kings[color as usize]
+    }
+
+    /// How many of `piece` are on the board.
+    #[inline]
+    pub fn count(&self, piece: Piece) -> u32 {
+        self.counts[piece.color() as usize][piece.kind() as usize] as u32
+    }
+
+    /// Recomputes everything derived from the board from scratch,
+    /// for positions that weren't reached by making moves.
+    pub(crate) fn refresh(&mut self) {
+        self.counts = Default::default();
+        self.material = Default::default();
+        let board = self.board;
+        for piece in board.iter().flatten() {
+            self.add_count(piece, 1);
+        }
+        self.kings = Color::ALL.map(|color| board.find_king(color));
+        self.key = zobrist::hash(self);
+        self.pawn_key = zobrist::pawn_hash(&self.board);
+    }
+
+    /// Adds `n` of `piece` to its owner's hand, keeping the hash in sync.
+    #[inline]
+    fn add_to_hand(&mut self, piece: Piece, n: i8) {
+        let count = &mut self.hands[piece.color() as usize][piece.kind() as usize];
+        self.key ^= zobrist::hand(piece, *count);
+        *count = count.wrapping_add_signed(n);
+        self.key ^= zobrist::hand(piece, *count);
+    }
+
+    /// Adds `n` of `piece` to the piece counts and material.
+    #[inline]
+    fn add_count(&mut self, piece: Piece, n: i8) {
+        let (color, kind) = (piece.color() as usize, piece.kind());
+        self.counts[color][kind as usize] =
+            self.counts[color][kind as usize].wrapping_add_signed(n);
+        self.material[color] += n as i32 * kind.value();
+    }
+
+    /// Generates all legal moves for the side to move.
+    pub fn legal_moves(&self) -> MoveList {
+        let mut moves = MoveList::new();
+        movegen::generate_legal(self, &mut moves);
+        moves
+    }
+
+    /// Returns `true` if the king of `color` is attacked, by the rules of the [`Variant`].
+    ///
+    /// A side without a king is never in check.
+    pub fn in_check(&self, color: Color) -> bool {
+        self.variant.in_check(self, color)
+    }
+
+    /// The pieces of `color` pinned to their king by an enemy slider,
+    /// which can only move along the line between them.
+    pub fn pinned(&self, color: Color) -> BitBoard {
+        match self.find_king(color) {
+            Some(king) => {
+                let ours = self.board.colors(color);
+                let theirs = self.board.colors(color.opposite());
+                self.board.slider_blockers(king, theirs) & ours
+            }
+            None => BitBoard::EMPTY,
+        }
+    }
+
+    /// The pieces of the side to move that would give check by moving off the line
+    /// between one of our sliders and the enemy king.
+    pub fn discovered_check_candidates(&self) -> BitBoard {
+        let ours = self.board.colors(self.side_to_move);
+        match self.find_king(self.side_to_move.opposite()) {
+            Some(king) => self.board.slider_blockers(king, ours) & ours,
+            None => BitBoard::EMPTY,
+        }
+    }
+
+    /// Returns `true` if the side to move is in check and has no legal moves.
+    pub fn is_checkmate(&self) -> bool {
+        self.in_check(self.side_to_move) && self.legal_moves().is_empty()
+    }
+
+    /// Returns `true` if the side to move is not in check but has no legal moves.
+    pub fn is_stalemate(&self) -> bool {
+        !self.in_check(self.side_to_move) && self.legal_moves().is_empty()
+    }
+
+    /// Returns `true` if the position occurred before in the moves made to reach it,
+    /// looking back no further than a null move.
+    ///
+    /// Unlike [`Game::repetitions`](crate::Game::repetitions) this only needs
+    /// the position to have been seen once before, which the search treats as a draw.
+    pub fn is_repetition(&self) -> bool {
+        // a capture or pawn move means no earlier position can repeat,
+        // and positions with the other side to move can't be equal
+        self.history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .take_while(|undo| undo.mv.is_some())
+            .skip(1)
+            .step_by(2)
+            .any(|undo| undo.key == self.key)
+    }
+
+    /// Returns `true` if neither player has enough material left to win,
+    /// whatever moves are played, by the rules of the [`Variant`].
+    pub fn is_insufficient_material(&self) -> bool {
+        self.variant.is_insufficient_material(self)
+    }
+
+    /// Whether neither player can checkmate in standard chess.
+    ///
+    /// That is when only kings and a single minor piece remain,
+    /// or kings and bishops that all stand on squares of the same color.
+    pub(crate) fn standard_insufficient_material(&self) -> bool {
+        let board = &self.board;
+        if !(board.pawns() | board.rooks() | board.queens()).is_empty() {
+            return false;
+        }
+
+        let minors = board.knights() | board.bishops();
+        if !minors.has_many() {
+            return true;
+        }
+        let bishops = board.bishops();
+        board.knights().is_empty()
+            && ((bishops & BitBoard::LIGHT_SQUARES).is_empty()
+                || (bishops & BitBoard::DARK_SQUARES).is_empty())
+    }
+
+    /// Plays a [`Move`], which must be legal in this position.
+    ///
+    /// The move is remembered so it can be taken back with [`Position::unmake_move`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no piece on the move's `from` square, unless it is a drop.
+    pub fn make_move(&mut self, mv: Move) {
+        let us = self.side_to_move;
+        let (from, to) = (mv.from(), mv.to());
+        let piece = match mv.dropped() {
+            Some(kind) => Piece::new_with(us, kind),
+            None => self
+                .board
+                .piece_on(from)
+                .expect("there should be a piece to move"),
+        };
+
+        let captured_on = match mv.flag() {
+            MoveFlag::EnPassant => Square::new(from.rank(), to.file()),
+            _ => to,
+        };
+        let captured = match mv.flag() {
+            MoveFlag::Castle => None,
+            _ => self.board.piece_on(captured_on),
+        };
+
+        self.history.push(Undo {
+            mv: Some(mv),
+            captured,
+            castling: self.castling,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            key: self.key,
+            pawn_key: self.pawn_key,
+            promoted: self.promoted,
+            explosions: 0,
+            kings: self.kings,
+            checks: self.checks,
+        });
+
+        // the old square was hashed based on the board before this move
+        self.key ^= zobrist::capturable_en_passant(&self.board, self.en_passant, us);
+
+        if let Some(captured) = captured {
+            self.toggle(captured, captured_on);
+            self.add_count(captured, -1);
+            if captured.kind() == PieceKind::King {
+                // only possible in Antichess, where they may have another
+                self.kings[captured.color() as usize] = self.board.find_king(captured.color());
+            }
+            if self.variant.has_drops() {
+                self.add_to_hand(
+                    Piece::new_with(us, self.hand_kind(captured, captured_on)),
+                    1,
+                );
+            }
+            self.promoted -= BitBoard::from_square(captured_on);
+        }
+
+        if let Some(side) = mv.castle_side() {
+            // in Chess960 the king and rook can land on each other's squares,
+            // so both are lifted before either is placed
+            let (rook_from, rook_to) = self.castling_rook_squares(us, side);
+            let rook = Piece::new_with(us, PieceKind::Rook);
+            self.toggle(piece, from);
+            self.toggle(rook, rook_from);
+            self.toggle(piece, to);
+            self.toggle(rook, rook_to);
+        } else if mv.dropped().is_some() {
+            self.add_to_hand(piece, -1);
+            self.add_count(piece, 1);
+            self.toggle(piece, to);
+        } else {
+            self.toggle(piece, from);
+            let placed = match mv.promotion() {
+                Some(kind) => {
+                    self.add_count(piece, -1);
+                    let promoted = Piece::new_with(us, kind);
+                    self.add_count(promoted, 1);
+                    if kind == PieceKind::King {
+                        self.kings[us as usize].get_or_insert(to);
+                    }
+                    promoted
+                }
+                None => piece,
+            };
+            self.toggle(placed, to);
+
+            if mv.promotion().is_some() || self.promoted.is_on(from) {
+                self.promoted -= BitBoard::from_square(from);
+                self.promoted |= BitBoard::from_square(to);
+            }
+        }
+
+        self.key ^= zobrist::castling(self.castling);
+
+        // moving the king or a rook, or capturing a rook, loses castling rights
+        if piece.kind() == PieceKind::King {
+            self.castling.remove(us);
+            self.kings[us as usize] = Some(to);
+        }
+        if captured.is_some() && self.variant.has_explosions() {
+            let explosions = self.explode(to);
+            self.history
+                .last_mut()
+                .expect("the move was just pushed")
+                .explosions = explosions;
+        }
+        for color in Color::ALL {
+            for side in [CastleSide::King, CastleSide::Queen] {
+                let rook = self.castling_rook(color, side);
+                if rook == from || rook == to {
+                    self.castling.set(color, side, false);
+                }
+            }
+        }
+        self.key ^= zobrist::castling(self.castling);
+
+        // only set after a double push from the usual rank, and only hashed if the opponent can take
+        self.en_passant = match mv.flag() {
+            MoveFlag::DoublePush if matches!(from.rank(), Rank::Second | Rank::Seventh) => {
+                Some(Square::new(Rank::Third.relative_to(us), from.file()))
+            }
+            _ => None,
+        };
+        self.key ^= zobrist::capturable_en_passant(&self.board, self.en_passant, us.opposite());
+
+        if piece.kind() == PieceKind::Pawn || captured.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+
+        if us == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = us.opposite();
+        self.key ^= zobrist::side();
+
+        if self.variant.check_limit().is_some() && self.in_check(us.opposite()) {
+            let count = &mut self.checks[us as usize];
+            self.key ^= zobrist::checks(us, *count);
+            *count = count.saturating_add(1);
+            self.key ^= zobrist::checks(us, *count);
+        }
+    }
+
+    /// The en passant square, if a pawn could capture there, which is when it matters
+    /// for repetitions and the hash.
+    fn capturable_en_passant(&self) -> Option<Square> {
+        self.en_passant.filter(|&square| {
+            zobrist::capturable_en_passant(&self.board, Some(square), self.side_to_move) != 0
+        })
+    }
+
+    /// Passes the turn to the other side without moving,
+    /// which must not be done while in check.
+    ///
+    /// Used by the search to test whether a position is good enough
+    /// that even doing nothing keeps it ahead.
+    /// Take it back with [`Position::unmake_null_move`].
+    pub fn make_null_move(&mut self) {
+        debug_assert!(!self.in_check(self.side_to_move));
+        self.history.push(Undo {
+            mv: None,
+            captured: None,
+            castling: self.castling,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            key: self.key,
+            pawn_key: self.pawn_key,
+            promoted: self.promoted,
+            explosions: 0,
+            kings: self.kings,
+            checks: self.checks,
+        });
+
+        self.key ^= zobrist::capturable_en_passant(&self.board, self.en_passant, self.side_to_move);
+        self.en_passant = None;
+        self.halfmove_clock += 1;
+        if self.side_to_move == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = self.side_to_move.opposite();
+        self.key ^= zobrist::side();
+    }
+
+    /// Takes back a move made with [`Position::make_null_move`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the last move made wasn't a null move.
+    pub fn unmake_null_move(&mut self) {
+        let undo = self
+            .history
+            .pop()
+            .expect("a null move should have been made");
+        assert!(undo.mv.is_none(), "the last move should be a null move");
+
+        self.side_to_move = self.side_to_move.opposite();
+        if self.side_to_move == Color::Black {
+            self.fullmove_number -= 1;
+        }
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.key = undo.key;
+        self.pawn_key = undo.pawn_key;
+    }
+
+    /// The position if the side to move passed, for asking what the other side
+    /// would play if it were their move again, such as to find the threat in a position.
+    ///
+    /// Unlike [`Position::make_null_move`] this returns None while in check,
+    /// as passing would leave the king to be taken.
+    /// The moves made to reach the position are not kept.
+    pub fn pass(&self) -> Option<Self> {
+        if self.in_check(self.side_to_move) {
+            return None;
+        }
+        let mut position = self.scratch();
+        position.make_null_move();
+        position.history.clear();
+        Some(position)
+    }
+
+    /// Blows up the capturing piece on `center` along with every piece next to it
+    /// apart from pawns, as in Atomic, returning how many pieces were removed.
+    ///
+    /// The pieces are kept in [`Position::exploded`] to be put back when unmaking.
+    fn explode(&mut self, center: Square) -> u8 {
+        let around = king_attacks(center) & self.board.occupied() & !self.board.pawns();
+        let mut explosions = 0;
+        for square in BitBoard::from_square(center) | around {
+            let piece = self.board.piece_on(square).expect("the square is occupied");
+            let color = piece.color();
+            self.toggle(piece, square);
+            self.add_count(piece, -1);
+            if piece.kind() == PieceKind::King {
+                self.castling.remove(color);
+                self.kings[color as usize] = None;
+            }
+            for side in [CastleSide::King, CastleSide::Queen] {
+                if self.castling_rook(color, side) == square {
+                    self.castling.set(color, side, false);
+                }
+            }
+            self.promoted -= BitBoard::from_square(square);
+            self.exploded.push((square, piece));
+            explosions += 1;
+        }
+        explosions
+    }
+
+    /// Toggles a [`Piece`] on the board, keeping the hash in sync.
+    #[inline]
+    fn toggle(&mut self, piece: Piece, square: Square) {
+        self.board.toggle_square(piece, square);
+        self.key ^= zobrist::piece(piece, square);
+        if piece.kind() == PieceKind::Pawn {
+            self.pawn_key ^= zobrist::piece(piece, square);
+        }
+    }
+
+    /// Takes back the last move made with [`Position::make_move`].
+    ///
+    /// Returns the move that was taken back, or None if no moves have been made.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the last move was a null move, see [`Position::unmake_null_move`].
+    pub fn unmake_move(&mut self) -> Option<Move> {
+        let undo = self.history.pop()?;
+        let mv = undo.mv.expect("the last move should not be a null move");
+        let (from, to) = (mv.from(), mv.to());
+
+        let us = self.side_to_move.opposite();
+        self.side_to_move = us;
+        if us == Color::Black {
+            self.fullmove_number -= 1;
+        }
+
+        self.castling = undo.castling;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.key = undo.key;
+        self.pawn_key = undo.pawn_key;
+        self.promoted = undo.promoted;
+        self.kings = undo.kings;
+        self.checks = undo.checks;
+
+        for _ in 0..undo.explosions {
+            let (square, piece) = self.exploded.pop().expect("the explosion should be kept");
+            self.board.toggle_square(piece, square);
+            self.add_count(piece, 1);
+        }
+
+        if let Some(side) = mv.castle_side() {
+            let (rook_from, rook_to) = self.castling_rook_squares(us, side);
+            let king = Piece::new_with(us, PieceKind::King);
+            let rook = Piece::new_with(us, PieceKind::Rook);
+            self.board.toggle_square(king, to);
+            self.board.toggle_square(rook, rook_to);
+            self.board.toggle_square(king, from);
+            self.board.toggle_square(rook, rook_from);
+            return Some(mv);
+        }
+
+        if let Some(kind) = mv.dropped() {
+            let piece = Piece::new_with(us, kind);
+            self.board.toggle_square(piece, to);
+            self.add_count(piece, -1);
+            self.hands[us as usize][kind as usize] += 1;
+            return Some(mv);
+        }
+
+        let placed = self
+            .board
+            .piece_on(to)
+            .expect("the moved piece should still be there");
+        self.board.toggle_square(placed, to);
+        let piece = match mv.promotion() {
+            Some(_) => {
+                let pawn = Piece::new_with(us, PieceKind::Pawn);
+                self.add_count(placed, -1);
+                self.add_count(pawn, 1);
+                pawn
+            }
+            None => placed,
+        };
+        self.board.toggle_square(piece, from);
+
+        if let Some(captured) = undo.captured {
+            let captured_on = match mv.flag() {
+                MoveFlag::EnPassant => Square::new(from.rank(), to.file()),
+                _ => to,
+            };
+            self.board.toggle_square(captured, captured_on);
+            self.add_count(captured, 1);
+            if self.variant.has_drops() {
+                self.hands[us as usize][self.hand_kind(captured, captured_on) as usize] -= 1;
+            }
+        }
+
+        Some(mv)
+    }
+
+    /// The kind of piece capturing `captured` on `square` adds to the hand,
+    /// which is a pawn if it was promoted.
+    fn hand_kind(&self, captured: Piece, square: Square) -> PieceKind {
+        if self.promoted.is_on(square) {
+            PieceKind::Pawn
+        } else {
+            captured.kind()
+        }
+    }
+
+    /// Finds the legal move that turns `before` into `after`,
+    /// for sources that only give snapshots of the board, such as FENs or electronic boards.
+    ///
+    /// Only the pieces are compared, along with the pieces in hand in variants with drops,
+    /// so castling, en passant and promotions are told apart by where the pieces end up.
+    /// Returns None if no legal move gives `after`'s pieces, or if more than one does.
+    pub fn infer_move(before: &Position, after: &Position) -> Option<Move> {
+        let mut found = None;
+        for &mv in before.legal_moves().iter() {
+            let mut position = before.scratch();
+            position.make_move(mv);
+            if position.board == after.board
+                && position.hands == after.hands
+                && found.replace(mv).is_some()
+            {
+                return None;
+            }
+        }
+        found
+    }
+
+    /// The same position with the colors reversed: the board is flipped vertically,
+    /// white and black swap pieces, and the other side is to move.
+    ///
+    /// Castling rights and the en passant square follow the pieces, so the result is
+    /// exactly as good for the other side. An evaluation should give it the same score
+    /// from the side to move's point of view.
+    ///
+    /// The moves made to reach the position are not kept.
+    pub fn swap_colors(&self) -> Self {
+        let mut castling = CastlingRights::NONE;
+        for color in Color::ALL {
+            for side in [CastleSide::King, CastleSide::Queen] {
+                castling.set(color.opposite(), side, self.castling.has(color, side));
+            }
+        }
+        let [white_rooks, black_rooks] = self.castling_rooks;
+        let [white_hand, black_hand] = self.hands;
+
+        Self {
+            board: self.board.flip_vertical().swap_colors(),
+            side_to_move: self.side_to_move.opposite(),
+            castling,
+            en_passant: self.en_passant.map(Square::flip_vertical),
+            castling_rooks: [
+                black_rooks.map(Square::flip_vertical),
+                white_rooks.map(Square::flip_vertical),
+            ],
+            hands: [black_hand, white_hand],
+            checks: [self.checks[1], self.checks[0]],
+            promoted: self.promoted.flip_vertical(),
+            ..self.clone()
+        }
+        .without_history()
+    }
+
+    /// The position mirrored left to right, with the h-file where the a-file was.
+    ///
+    /// Castling rights are dropped, as the kings and rooks end up on the wrong sides.
+    /// The moves made to reach the position are not kept.
+    pub fn mirror_horizontal(&self) -> Self {
+        Self {
+            board: self.board.mirror_horizontal(),
+            castling: CastlingRights::NONE,
+            en_passant: self.en_passant.map(Square::mirror_horizontal),
+            promoted: self.promoted.mirror_horizontal(),
+            ..self.clone()
+        }
+        .without_history()
+    }
+
+    /// Finishes a transformed copy of the position, which can't unmake any moves.
+    fn without_history(mut self) -> Self {
+        self.history.clear();
+        self.exploded.clear();
+        self.refresh();
+        self
+    }
+
+    /// A copy of the position to try moves on, without the moves made to reach it.
+    pub(crate) fn scratch(&self) -> Self {
+        Self {
+            history: Vec::new(),
+            exploded: Vec::new(),
+            ..*self
+        }
+    }
+
+    /// The squares the rook moves between when `color` castles towards `side`.
+    fn castling_rook_squares(&self, color: Color, side: CastleSide) -> (Square, Square) {
+        let from = self.castling_rook(color, side);
+        let (_, file) = side.destination_files();
+        (from, Square::new(from.rank(), file))
+    }
+}
+
+/// Positions are equal when they count as a repetition: the same pieces are on the same
+/// squares, with the same player to move, castling rights and en passant capture.
+///
+/// An en passant square nothing can capture on is ignored, as are the move clocks and
+/// the moves made to reach the position. Variants also compare the pieces in hand and
+/// the checks given.
+impl PartialEq for Position {
+    fn eq(&self, other: &Self) -> bool {
+        self.board == other.board
+            && self.side_to_move == other.side_to_move
+            && self.castling == other.castling
+            && self.capturable_en_passant() == other.capturable_en_passant()
+            && self.hands == other.hands
+            && self.checks == other.checks
+            && self.variant.name() == other.variant.name()
+    }
+}
+
+impl Eq for Position {}
+
+/// Hashes the [`Position::zobrist_key`], which covers everything compared for equality.
+impl core::hash::Hash for Position {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        state.write_u64(self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::movegen::perft;
+
+    #[test]
+    fn start_matches_board_start() {
+        assert_eq!(*Position::START.board(), Board::START);
+        assert_eq!(Position::START.side_to_move(), Color::White);
+        assert_eq!(Position::start().board(), Position::START.board());
+    }
+
+    #[test]
+    fn make_unmake_restores_position() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut position = FEN::new(fen).parse_position().unwrap();
+        let board = *position.board();
+        let before = board.to_fen();
+
+        for mv in &position.legal_moves() {
+            position.make_move(*mv);
+            assert_eq!(position.side_to_move(), Color::Black);
+            assert_eq!(position.unmake_move(), Some(*mv));
+
+            assert_eq!(position.board().to_fen(), before, "{mv:?} was not undone");
+            assert_eq!(position.castling(), CastlingRights::ALL);
+            assert_eq!(position.side_to_move(), Color::White);
+        }
+
+        assert_eq!(position.unmake_move(), None);
+    }
+
+    #[test]
+    fn check_mate_and_stalemate() {
+        let position = |fen: &str| FEN::new(fen).parse_position().unwrap();
+
+        let start = Position::start();
+        assert!(!start.in_check(Color::White) && !start.is_checkmate() && !start.is_stalemate());
+
+        let check = position("4k3/8/8/8/8/8/8/4RK2 b - - 0 1");
+        assert!(check.in_check(Color::Black));
+        assert!(!check.in_check(Color::White));
+        assert!(!check.is_checkmate());
+
+        let mate = position("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1");
+        assert!(mate.is_checkmate() && !mate.is_stalemate());
+
+        let stalemate = position("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        assert!(stalemate.is_stalemate() && !stalemate.is_checkmate());
+    }
+
+    #[test]
+    fn incremental_zobrist_key() {
+        fn walk(position: &mut Position, depth: u32) {
+            assert_eq!(position.zobrist_key(), zobrist::hash(position));
+            assert_eq!(position.pawn_key(), zobrist::pawn_hash(position.board()));
+            if depth == 0 {
+                return;
+            }
+
+            for &mv in &position.legal_moves() {
+                let key = position.zobrist_key();
+                position.make_move(mv);
+                walk(position, depth - 1);
+                position.unmake_move();
+                assert_eq!(position.zobrist_key(), key);
+            }
+        }
+
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        walk(&mut FEN::new(fen).parse_position().unwrap(), 2);
+    }
+
+    #[test]
+    fn material_follows_moves() {
+        let fen = "r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1";
+        let mut position = FEN::new(fen).parse_position().unwrap();
+        let pawn = Piece::new_with(Color::White, PieceKind::Pawn);
+        let queen = Piece::new_with(Color::White, PieceKind::Queen);
+        let rook = Piece::new_with(Color::Black, PieceKind::Rook);
+        assert_eq!(position.material(Color::White), 100);
+        assert_eq!(position.material(Color::Black), 500);
+
+        // capture a rook while promoting
+        position.make_move(Move::new_promotion(
+            Square::B7,
+            Square::A8,
+            PieceKind::Queen,
+        ));
+        assert_eq!(position.material(Color::White), 900);
+        assert_eq!(position.material(Color::Black), 0);
+        assert_eq!((position.count(pawn), position.count(queen)), (0, 1));
+        assert_eq!(position.count(rook), 0);
+
+        position.unmake_move();
+        assert_eq!(position.material(Color::White), 100);
+        assert_eq!(position.material(Color::Black), 500);
+        assert_eq!((position.count(pawn), position.count(queen)), (1, 0));
+        assert_eq!(position.count(rook), 1);
+
+        let start = Position::start();
+        assert_eq!(start.count(pawn), 8);
+        assert_eq!(start.material(Color::White), start.material(Color::Black));
+    }
+
+    #[test]
+    fn king_square_follows_moves() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        let mut position = FEN::new(fen).parse_position().unwrap();
+        assert_eq!(position.king_square(Color::White), Square::E1);
+        assert_eq!(position.board().king_square(Color::Black), Square::E8);
+
+        let castle = position.parse_uci("e1g1").unwrap();
+        position.make_move(castle);
+        assert_eq!(position.king_square(Color::White), Square::G1);
+        position.make_move(position.parse_uci("e8d7").unwrap());
+        assert_eq!(position.king_square(Color::Black), Square::D7);
+
+        position.unmake_move();
+        position.unmake_move();
+        assert_eq!(position.king_square(Color::White), Square::E1);
+        assert_eq!(position.king_square(Color::Black), Square::E8);
+
+        // Chess960 castling onto the rook's square
+        let mut position = FEN::new("4k3/8/8/8/8/8/8/5KR1 w G - 0 1")
+            .parse_position()
+            .unwrap();
+        position.make_move(position.parse_uci("f1g1").unwrap());
+        assert_eq!(position.king_square(Color::White), Square::G1);
+        for color in Color::ALL {
+            assert_eq!(
+                position.king_square(color),
+                position.board().king_square(color)
+            );
+        }
+    }
+
+    #[test]
+    fn transforms() {
+        let position = |fen: &str| FEN::new(fen).parse_position().unwrap();
+
+        let fen = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b Kq e3 0 1";
+        let swapped = position(fen).swap_colors();
+        assert_eq!(
+            swapped.to_fen().as_str(),
+            "rnbqkbnr/pppp1ppp/8/3Pp3/8/8/PPP1PPPP/RNBQKBNR w Qk e6 0 1"
+        );
+        assert_eq!(
+            swapped.legal_moves().len(),
+            position(fen).legal_moves().len()
+        );
+        assert_eq!(
+            swapped.swap_colors().zobrist_key(),
+            position(fen).zobrist_key()
+        );
+        assert_eq!(perft(&mut swapped.clone(), 3), perft(&mut position(fen), 3));
+
+        let mirrored = position("4k3/8/8/3pP3/8/8/8/R3K2R w KQ d6 0 1").mirror_horizontal();
+        assert_eq!(
+            mirrored.to_fen().as_str(),
+            "3k4/8/8/3Pp3/8/8/8/R2K3R w - e6 0 1"
+        );
+        assert_eq!(mirrored.king_square(Color::White), Square::D1);
+
+        let board = Board::start();
+        assert_eq!(
+            board.rotate_180().to_fen().as_str(),
+            "RNBKQBNR/PPPPPPPP/8/8/8/8/pppppppp/rnbkqbnr"
+        );
+        assert_eq!(board.flip_vertical().swap_colors().to_fen(), board.to_fen());
+        assert_eq!(
+            board.mirror_horizontal().flip_vertical().to_fen(),
+            board.rotate_180().to_fen()
+        );
+    }
+
+    #[test]
+    fn en_passant_only_hashed_when_capturable() {
+        let key = |fen| FEN::new(fen).parse_position().unwrap().zobrist_key();
+
+        // no black pawn can take on e3
+        assert_eq!(
+            key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+        );
+        // but the pawn on d4 can
+        assert_ne!(
+            key("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+            key("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+        );
+
+        let mut position = Position::start();
+        position.make_move(Move::new(Square::E2, Square::E4, MoveFlag::DoublePush));
+        assert_eq!(position.en_passant(), Some(Square::E3));
+        assert_eq!(position.zobrist_key(), zobrist::hash(&position));
+        position.make_move(Move::new(Square::G8, Square::F6, MoveFlag::Normal));
+        assert_eq!(position.en_passant(), None);
+        assert_eq!(position.zobrist_key(), zobrist::hash(&position));
+    }
+
+    #[test]
+    fn transpositions_share_keys() {
+        let play = |moves: [(Square, Square); 4]| {
+            let mut position = Position::start();
+            for (from, to) in moves {
+                position.make_move(Move::new(from, to, MoveFlag::Normal));
+            }
+            position.zobrist_key()
+        };
+
+        let a = play([
+            (Square::G1, Square::F3),
+            (Square::G8, Square::F6),
+            (Square::B1, Square::C3),
+            (Square::B8, Square::C6),
+        ]);
+        let b = play([
+            (Square::B1, Square::C3),
+            (Square::B8, Square::C6),
+            (Square::G1, Square::F3),
+            (Square::G8, Square::F6),
+        ]);
+
+        assert_eq!(a, b);
+        assert_ne!(a, Position::start().zobrist_key());
+    }
+
+    #[test]
+    fn infer_moves() {
+        let position = |fen: &str| FEN::new(fen).parse_position().unwrap();
+        let infer = |before: &str, after: &str| {
+            let before = position(before);
+            Position::infer_move(&before, &position(after)).map(|mv| before.uci(mv))
+        };
+
+        assert_eq!(
+            infer(
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+                "r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1"
+            ),
+            Some("e1g1".into())
+        );
+        assert_eq!(
+            infer(
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                "4k3/8/3P4/8/8/8/8/4K3 b - - 0 1"
+            ),
+            Some("e5d6".into())
+        );
+        assert_eq!(
+            infer(
+                "4k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+                "N3k3/8/8/8/8/8/8/4K3 b - - 0 1"
+            ),
+            Some("a7a8n".into())
+        );
+
+        // nothing changed, or something no single move could do
+        let start = Position::start();
+        assert_eq!(Position::infer_move(&start, &start), None);
+        assert_eq!(
+            infer(
+                "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+                "4k3/8/8/8/8/8/8/3K3R b - - 0 1"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn equal_when_repeated() {
+        use std::collections::HashSet;
+
+        let mut position = Position::start();
+        let mut seen = HashSet::from([position.clone()]);
+        for san in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+            position.make_move(position.parse_san(san).unwrap());
+            seen.insert(position.clone());
+        }
+        // back to the start, with different move counters
+        assert_eq!(seen.len(), 4);
+        assert_eq!(position, Position::start());
+        assert!(position.is_repetition());
+        position.unmake_move();
+        assert!(!position.is_repetition());
+
+        // en passant only matters when a pawn can take
+        let position = |fen: &str| FEN::new(fen).parse_position().unwrap();
+        let no_ep = "4k3/8/8/8/4P3/8/8/4K3 b - - 0 1";
+        assert_eq!(
+            position("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1"),
+            position(no_ep)
+        );
+        let no_ep = "4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1";
+        assert_ne!(
+            position("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1"),
+            position(no_ep)
+        );
+        assert_ne!(
+            position("4k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+            position("4k3/8/8/8/8/8/8/4K3 b - - 0 1")
+        );
+    }
+
+    #[test]
+    fn pins_and_discovered_checks() {
+        let fen = "R2B3k/6p1/4r3/8/8/4N3/8/B3K3 w - - 0 1";
+        let position = FEN::new(fen).parse_position().unwrap();
+
+        assert_eq!(
+            position.pinned(Color::White),
+            BitBoard::from_square(Square::E3)
+        );
+        assert_eq!(
+            position.pinned(Color::Black),
+            BitBoard::from_square(Square::G7)
+        );
+        assert_eq!(
+            position.discovered_check_candidates(),
+            BitBoard::from_square(Square::D8)
+        );
+    }
+
+    #[test]
+    fn null_move() {
+        let fen = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3";
+        let mut position = FEN::new(fen).parse_position().unwrap();
+        let key = position.zobrist_key();
+
+        position.make_null_move();
+        assert_eq!(position.side_to_move(), Color::White);
+        assert_eq!(position.en_passant(), None);
+        assert_eq!(position.zobrist_key(), zobrist::hash(&position));
+
+        position.unmake_null_move();
+        assert_eq!(position.zobrist_key(), key);
+        assert_eq!(position.fen_string(), fen);
+
+        let passed = position.pass().unwrap();
+        assert_eq!(passed.side_to_move(), Color::White);
+        assert_eq!(passed.zobrist_key(), zobrist::hash(&passed));
+        assert_eq!(position.fen_string(), fen);
+        // black can't pass while in check
+        let check = FEN::new("4k3/8/8/8/8/8/8/4RK2 b - - 0 1")
+            .parse_position()
+            .unwrap();
+        assert!(check.pass().is_none());
+    }
+
+    #[test]
+    fn castling_moves_rook() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        let mut position = FEN::new(fen).parse_position().unwrap();
+
+        position.make_move(Move::new(Square::E1, Square::G1, MoveFlag::Castle));
+
+        assert_eq!(
+            position.board().to_fen(),
+            FEN::new("r3k2r/8/8/8/8/8/8/R4RK1")
+        );
+        assert!(!position.castling().white_king());
+        assert!(!position.castling().white_queen());
+        assert!(position.castling().black_king());
+    }
+
+    #[test]
+    fn from_pieces() {
+        let piece = Piece::new_with;
+        let pieces = [
+            (Square::E1, piece(Color::White, PieceKind::King)),
+            (Square::H1, piece(Color::White, PieceKind::Rook)),
+            (Square::E8, piece(Color::Black, PieceKind::King)),
+        ];
+        let mut castling = CastlingRights::NONE;
+        castling.set(Color::White, CastleSide::King, true);
+        let position = Position::from_pieces(pieces, Color::Black, castling).unwrap();
+        assert_eq!(
+            position,
+            FEN::new("4k3/8/8/8/8/8/8/4K2R b K - 0 1")
+                .parse_position()
+                .unwrap()
+        );
+
+        assert_eq!(
+            Position::from_pieces(pieces[..2].iter().copied(), Color::White, castling),
+            Err(ValidationError::KingCount {
+                color: Color::Black,
+                count: 0
+            })
+        );
+    }
+
+    #[test]
+    fn chess960_castling() {
+        // the king castles without moving, and the rook lands where the king was
+        let fen = "1r4kr/8/8/8/8/8/8/1R4KR w HBhb - 0 1";
+        let mut position = FEN::new(fen).parse_position().unwrap();
+        assert!(position.is_chess960());
+        assert_eq!(
+            position.castling_rook(Color::White, CastleSide::Queen),
+            Square::B1
+        );
+
+        let short = position.parse_uci("g1h1").unwrap();
+        assert_eq!(short, Move::new(Square::G1, Square::G1, MoveFlag::Castle));
+        assert_eq!(position.uci(short), "g1h1");
+        assert_eq!(position.san(short), "O-O");
+
+        let long = position.parse_san("O-O-O").unwrap();
+        assert_eq!(position.uci(long), "g1b1");
+
+        let before = *position.board();
+        let key = position.zobrist_key();
+        position.make_move(short);
+        assert_eq!(
+            position.board().to_fen(),
+            FEN::new("1r4kr/8/8/8/8/8/8/1R3RK1")
+        );
+        assert_eq!(position.zobrist_key(), zobrist::hash(&position));
+
+        position.unmake_move();
+        assert_eq!(position.board().to_fen(), before.to_fen());
+        assert_eq!(position.zobrist_key(), key);
+
+        position.make_move(long);
+        assert_eq!(
+            position.board().to_fen(),
+            FEN::new("1r4kr/8/8/8/8/8/8/2KR3R")
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! [`proptest`] support, enabled with the `proptest` feature.
+//!
+//! Boards always pass [`Board::validate`], and positions are reached by playing
+//! random legal moves from a Chess960 start, so they shrink towards fewer moves.
+
+use alloc::vec::Vec;
+
+use proptest::{
+    arbitrary::{any, Arbitrary},
+    collection::vec,
+    sample::select,
+    strategy::{BoxedStrategy, Just, Strategy},
+};
+
+use crate::{
+    builder::BoardBuilder, BitBoard, Board, Color, Piece, PieceKind, Position, Rank, Square,
+};
+
+/// The most moves played to reach a [`Position`].
+const MAX_MOVES: usize = 100;
+
+impl Arbitrary for Square {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (0..64u8).prop_map(Square::from_raw).boxed()
+    }
+}
+
+impl Arbitrary for Piece {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (select(&Color::ALL[..]), select(&PieceKind::ALL[..]))
+            .prop_map(|(color, kind)| Piece::new_with(color, kind))
+            .boxed()
+    }
+}
+
+impl Arbitrary for BitBoard {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        any::<u64>().prop_map(BitBoard).boxed()
+    }
+}
+
+impl Arbitrary for Board {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// A king of each color and up to 30 other pieces, on random squares.
+    ///
+    /// Pawns that land on the first or last rank are left off.
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let squares = Just((0..64).map(Square::from_raw).collect::<Vec<_>>()).prop_shuffle();
+        let others = any::<Piece>().prop_filter("kings are placed first", |piece| {
+            piece.kind() != PieceKind::King
+        });
+        (squares, vec(others, 0..=30))
+            .prop_map(|(squares, others)| {
+                let kings = Color::ALL.map(|color| Piece::new_with(color, PieceKind::King));
+                let pieces =
+                    kings
+                        .into_iter()
+                        .chain(others)
+                        .zip(squares)
+                        .filter(|(piece, square)| {
+                            piece.kind() != PieceKind::Pawn
+                                || !matches!(square.rank(), Rank::First | Rank::Eighth)
+                        });
+                BoardBuilder::new()
+                    .pieces(pieces)
+                    .build()
+                    .expect("kings and pawns are placed legally")
+            })
+            .boxed()
+    }
+}
+
+impl Arbitrary for Position {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// A Chess960 start, which includes the standard one, followed by up to 100 random
+    /// legal moves, stopping early if the game ends.
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        (0..960u16, vec(any::<usize>(), 0..=MAX_MOVES))
+            .prop_map(|(start, choices)| {
+                let mut position = Position::chess960(start);
+                for choice in choices {
+                    let moves = position.legal_moves();
+                    if moves.is_empty() {
+                        break;
+                    }
+                    position.make_move(moves[choice % moves.len()]);
+                }
+                position
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::proptest;
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn boards_are_valid(board in any::<Board>()) {
+            assert_eq!(board.validate(), Ok(()));
+        }
+
+        #[test]
+        fn positions_are_legal(position in any::<Position>()) {
+            assert_eq!(position.validate(), Ok(()));
+        }
+    }
+}
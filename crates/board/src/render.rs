@@ -0,0 +1,537 @@
+//! Drawing boards as images.
+//!
+//! A [`Renderer`] draws a [`Board`] as an SVG, optionally highlighting the last move and
+//! a king in check, and drawing [`Annotation`]s such as arrows between squares. With the `raster` feature
+//! it can also draw PNGs, see [`Renderer::png`].
+//!
+//! Whole games can be drawn too, as one SVG for each position with [`Renderer::frames`],
+//! or animated as a GIF with the `gif` feature, see [`Renderer::gif`].
+//!
+//! ```
+//! # use board::{render::Renderer, Position, Square};
+//! let position = Position::start();
+//! let svg = Renderer::new()
+//!     .size(240)
+//!     .arrow(Square::E2, Square::E4)
+//!     .svg(position.board());
+//! assert!(svg.starts_with("<svg"));
+//! ```
+
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+#[cfg(feature = "gif")]
+use std::time::Duration;
+
+use crate::{
+    annotation::{Annotation, Brush},
+    Board, Color, Game, Move, Piece, PieceKind, Position, Square,
+};
+
+/// Each square is this many units wide, the size the pieces are drawn at.
+const SQUARE: f32 = 45.0;
+
+const LIGHT: &str = "#f0d9b5";
+const DARK: &str = "#b58863";
+const HIGHLIGHT: &str = "#9bc700";
+
+/// How an image of a board is drawn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Renderer {
+    size: u32,
+    flipped: bool,
+    coordinates: bool,
+    last_move: Option<Move>,
+    check: Option<Square>,
+    annotations: Vec<Annotation>,
+}
+
+impl Renderer {
+    /// A 360 pixel board from white's side, with coordinates and nothing highlighted.
+    pub fn new() -> Self {
+        Self {
+            size: 360,
+            flipped: false,
+            coordinates: true,
+            last_move: None,
+            check: None,
+            annotations: Vec::new(),
+        }
+    }
+
+    /// How wide and tall the image is, in pixels.
+    pub fn size(mut self, size: u32) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Draws the board from black's side.
+    pub fn flipped(mut self, flipped: bool) -> Self {
+        self.flipped = flipped;
+        self
+    }
+
+    /// Whether the files and ranks are written along the edges.
+    pub fn coordinates(mut self, coordinates: bool) -> Self {
+        self.coordinates = coordinates;
+        self
+    }
+
+    /// Highlights the squares `mv` moved between.
+    pub fn last_move(mut self, mv: Move) -> Self {
+        self.last_move = Some(mv);
+        self
+    }
+
+    /// Marks the king on `square` as being in check.
+    pub fn check(mut self, square: Square) -> Self {
+        self.check = Some(square);
+        self
+    }
+
+    /// Draws a green arrow from one square to another.
+    pub fn arrow(self, from: Square, to: Square) -> Self {
+        self.annotate(Annotation::Arrow {
+            from,
+            to,
+            brush: Brush::Green,
+        })
+    }
+
+    /// Draws an arrow, circle or highlight, such as one from [`Node::annotations`].
+    ///
+    /// Highlights are drawn under the pieces, and circles and arrows over them.
+    ///
+    /// [`Node::annotations`]: crate::pgn::Node::annotations
+    pub fn annotate(mut self, annotation: Annotation) -> Self {
+        self.annotations.push(annotation);
+        self
+    }
+
+    /// Draws `board` as an SVG document.
+    pub fn svg(&self, board: &Board) -> String {
+        let mut svg = String::new();
+        let full = 8.0 * SQUARE;
+        // writing to a string can't fail
+        let _ = write!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {full} {full}">"#,
+            self.size
+        );
+
+        for square in (0..64).map(Square::from_raw) {
+            let (x, y) = self.corner(square);
+            let color = if is_light(square) { LIGHT } else { DARK };
+            let _ = write!(
+                svg,
+                r#"<rect x="{x}" y="{y}" width="{SQUARE}" height="{SQUARE}" fill="{color}"/>"#
+            );
+        }
+
+        if let Some(mv) = self.last_move {
+            let from = mv.dropped().is_none().then(|| mv.from());
+            for square in from.into_iter().chain([mv.to()]) {
+                let (x, y) = self.corner(square);
+                let _ = write!(
+                    svg,
+                    r#"<rect x="{x}" y="{y}" width="{SQUARE}" height="{SQUARE}" fill="{HIGHLIGHT}" fill-opacity="0.4"/>"#
+                );
+            }
+        }
+        if let Some(square) = self.check {
+            let (x, y) = self.center(square);
+            let _ = write!(
+                svg,
+                r##"<circle cx="{x}" cy="{y}" r="{}" fill="#ff0000" fill-opacity="0.5"/>"##,
+                SQUARE / 2.0
+            );
+        }
+        for &annotation in &self.annotations {
+            if let Annotation::Highlight { square, brush } = annotation {
+                let (x, y) = self.corner(square);
+                let _ = write!(
+                    svg,
+                    r#"<rect x="{x}" y="{y}" width="{SQUARE}" height="{SQUARE}" fill="{}" fill-opacity="0.5"/>"#,
+                    color(brush)
+                );
+            }
+        }
+        if self.coordinates {
+            self.write_coordinates(&mut svg);
+        }
+
+        for (piece, square) in board.iter().zip((0..64).map(Square::from_raw)) {
+            if let Some(piece) = piece {
+                let (x, y) = self.corner(square);
+                write_piece(&mut svg, piece, x, y);
+            }
+        }
+
+        for &annotation in &self.annotations {
+            match annotation {
+                Annotation::Circle { square, brush } => {
+                    let (x, y) = self.center(square);
+                    let _ = write!(
+                        svg,
+                        r#"<circle cx="{x}" cy="{y}" r="{}" fill="none" stroke="{}" stroke-width="3.5" opacity="0.8"/>"#,
+                        SQUARE / 2.0 - 2.0,
+                        color(brush)
+                    );
+                }
+                Annotation::Arrow { from, to, brush } => {
+                    self.write_arrow(&mut svg, from, to, color(brush))
+                }
+                Annotation::Highlight { .. } => {}
+            }
+        }
+        svg.push_str("</svg>");
+        svg
+    }
+
+    /// Draws `board` as a PNG.
+    ///
+    /// Coordinates are left out, as no fonts are loaded to write them with.
+    #[cfg(feature = "raster")]
+    pub fn png(&self, board: &Board) -> Result<Vec<u8>, RenderError> {
+        self.pixmap(board)?
+            .encode_png()
+            .map_err(|e| RenderError::Png(e.to_string()))
+    }
+
+    /// Draws every position of `game` as an SVG, from the start to the end,
+    /// highlighting the move that was just played in each.
+    ///
+    /// Kings in check are marked, while annotations are left out.
+    pub fn frames(&self, game: &Game) -> Vec<String> {
+        self.frame_boards(game)
+            .iter()
+            .map(|(renderer, board)| renderer.svg(board))
+            .collect()
+    }
+
+    /// Animates `game` as a looping GIF, showing each position of [`Renderer::frames`]
+    /// for `delay`, and the last one for three times as long.
+    ///
+    /// GIFs can be at most 65535 pixels wide.
+    #[cfg(feature = "gif")]
+    pub fn gif(&self, game: &Game, delay: Duration) -> Result<Vec<u8>, RenderError> {
+        let size = u16::try_from(self.size).map_err(|_| RenderError::TooLarge)?;
+        // delays are counted in hundredths of a second
+        let delay = u16::try_from(delay.as_millis() / 10).unwrap_or(u16::MAX);
+
+        let frames = self.frame_boards(game);
+        let mut bytes = Vec::new();
+        let mut encoder = gif::Encoder::new(&mut bytes, size, size, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        for (index, (renderer, board)) in frames.iter().enumerate() {
+            let mut pixels = renderer.pixmap(board)?.take();
+            let mut frame = gif::Frame::from_rgba_speed(size, size, &mut pixels, 10);
+            frame.delay = if index + 1 == frames.len() {
+                delay.saturating_mul(3)
+            } else {
+                delay
+            };
+            encoder.write_frame(&frame)?;
+        }
+        drop(encoder);
+        Ok(bytes)
+    }
+
+    /// How each position of `game` is drawn, along with its pieces.
+    fn frame_boards(&self, game: &Game) -> Vec<(Renderer, Board)> {
+        let mut start = game.clone();
+        while start.undo().is_some() {}
+
+        let frame = |position: &Position, last_move| {
+            let us = position.side_to_move();
+            let renderer = Renderer {
+                last_move,
+                check: position.in_check(us).then(|| position.king_square(us)),
+                annotations: Vec::new(),
+                ..self.clone()
+            };
+            (renderer, *position.board())
+        };
+        let mut position = start.position().clone();
+        let mut frames = alloc::vec![frame(&position, None)];
+        for &mv in game.moves() {
+            position.make_move(mv);
+            frames.push(frame(&position, Some(mv)));
+        }
+        frames
+    }
+
+    #[cfg(feature = "raster")]
+    fn pixmap(&self, board: &Board) -> Result<resvg::tiny_skia::Pixmap, RenderError> {
+        use resvg::{tiny_skia, usvg};
+
+        let svg = self.svg(board);
+        let tree = usvg::Tree::from_str(&svg, &usvg::Options::default())?;
+        let mut pixmap = tiny_skia::Pixmap::new(self.size, self.size).ok_or(RenderError::Empty)?;
+        resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+        Ok(pixmap)
+    }
+
+    /// The top left corner of `square`.
+    fn corner(&self, square: Square) -> (f32, f32) {
+        let (file, rank) = (square.file() as u8, square.rank() as u8);
+        let (column, row) = if self.flipped {
+            (7 - file, rank)
+        } else {
+            (file, 7 - rank)
+        };
+        (f32::from(column) * SQUARE, f32::from(row) * SQUARE)
+    }
+
+    fn center(&self, square: Square) -> (f32, f32) {
+        let (x, y) = self.corner(square);
+        (x + SQUARE / 2.0, y + SQUARE / 2.0)
+    }
+
+    /// Writes the files along the bottom edge and the ranks down the left,
+    /// in the colour of the other squares.
+    fn write_coordinates(&self, svg: &mut String) {
+        let (bottom, left) = if self.flipped { (7, 7) } else { (0, 0) };
+        for index in 0..8u8 {
+            let file = Square::from_raw(bottom * 8 + index);
+            let (x, y) = self.corner(file);
+            let _ = write!(
+                svg,
+                r#"<text x="{}" y="{}" font-family="sans-serif" font-size="9" text-anchor="end" fill="{}">{}</text>"#,
+                x + SQUARE - 2.0,
+                y + SQUARE - 2.0,
+                if is_light(file) { DARK } else { LIGHT },
+                char::from(b'a' + index),
+            );
+
+            let rank = Square::from_raw(index * 8 + left);
+            let (x, y) = self.corner(rank);
+            let _ = write!(
+                svg,
+                r#"<text x="{}" y="{}" font-family="sans-serif" font-size="9" fill="{}">{}</text>"#,
+                x + 2.0,
+                y + 10.0,
+                if is_light(rank) { DARK } else { LIGHT },
+                index + 1,
+            );
+        }
+    }
+
+    /// Writes an arrow between the centres of the squares, with its head ending at `to`.
+    fn write_arrow(&self, svg: &mut String, from: Square, to: Square, color: &str) {
+        const WIDTH: f32 = 7.0;
+        const HEAD: f32 = 18.0;
+
+        if from == to {
+            return;
+        }
+        let (start, end) = (self.center(from), self.center(to));
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let length = (dx * dx + dy * dy).sqrt();
+        let (ux, uy) = (dx / length, dy / length);
+        // the shaft stops where the head starts, with the head as wide as it is long
+        let base = (end.0 - ux * HEAD, end.1 - uy * HEAD);
+        let (px, py) = (-uy * HEAD / 2.0, ux * HEAD / 2.0);
+
+        let _ = write!(
+            svg,
+            r#"<g fill="{color}" stroke="{color}" opacity="0.8"><line x1="{:.1}" y1="{:.1}" x2="{:.1}" y2="{:.1}" stroke-width="{WIDTH}"/><polygon stroke="none" points="{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}"/></g>"#,
+            start.0,
+            start.1,
+            base.0,
+            base.1,
+            end.0,
+            end.1,
+            base.0 + px,
+            base.1 + py,
+            base.0 - px,
+            base.1 - py,
+        );
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "raster")]
+#[derive(Debug, thiserror::Error)]
+pub enum RenderError {
+    #[error("the image has no pixels")]
+    Empty,
+    #[error(transparent)]
+    Svg(#[from] resvg::usvg::Error),
+    #[error("couldn't encode the PNG: {0}")]
+    Png(String),
+    #[cfg(feature = "gif")]
+    #[error("the image is too large for a GIF")]
+    TooLarge,
+    #[cfg(feature = "gif")]
+    #[error(transparent)]
+    Gif(#[from] gif::EncodingError),
+}
+
+/// The colour a brush draws in, as Lichess draws them.
+fn color(brush: Brush) -> &'static str {
+    match brush {
+        Brush::Green => "#15781b",
+        Brush::Red => "#882020",
+        Brush::Yellow => "#e68f00",
+        Brush::Blue => "#003088",
+    }
+}
+
+fn is_light(square: Square) -> bool {
+    (square.file() as u8 + square.rank() as u8) % 2 == 1
+}
+
+/// Writes `piece` in the square whose top left corner is at `x`, `y`.
+fn write_piece(svg: &mut String, piece: Piece, x: f32, y: f32) {
+    let (fill, detail) = match piece.color() {
+        Color::White => ("#ffffff", "#000000"),
+        Color::Black => ("#000000", "#ffffff"),
+    };
+    let _ = write!(
+        svg,
+        r##"<g transform="translate({x} {y})" fill="{fill}" stroke="#000000" stroke-width="1.5" stroke-linejoin="round"><path d="{}"/>"##,
+        outline(piece.kind())
+    );
+    match piece.kind() {
+        PieceKind::Knight => {
+            let _ = write!(
+                svg,
+                r#"<circle cx="17" cy="17" r="1.5" fill="{detail}" stroke="none"/>"#
+            );
+        }
+        PieceKind::King => {
+            svg.push_str(r#"<path d="M 22.5,6 V 15 M 19,9 H 26" fill="none"/>"#);
+        }
+        _ => {}
+    }
+    svg.push_str("</g>");
+}
+
+/// The outline of a kind of piece, drawn in a 45 unit square.
+fn outline(kind: PieceKind) -> &'static str {
+    match kind {
+        PieceKind::Pawn => {
+            "M 22.5,9 C 20.3,9 18.5,10.8 18.5,13 C 18.5,13.9 18.8,14.7 19.3,15.4 C 17.3,16.5 16,18.6 16,21 \
+             C 16,23 16.9,24.8 18.4,26 C 15.4,27.1 11,31.6 11,39.5 H 34 C 34,31.6 29.6,27.1 26.6,26 \
+             C 28.1,24.8 29,23 29,21 C 29,18.6 27.7,16.5 25.7,15.4 C 26.2,14.7 26.5,13.9 26.5,13 \
+             C 26.5,10.8 24.7,9 22.5,9 Z"
+        }
+        PieceKind::Knight => {
+            "M 13,39 H 35 C 35,30 34,21 31,16 C 28,11 24,9 20,9 L 19,12 L 16,10 L 16,14 \
+             C 13,16 11,20 10,23 C 9,26 10,28 12,28 C 14,28 15,26 18,25 C 20,24 21,24 22,23 \
+             C 21,27 17,29 15,32 C 14,34 13,36 13,39 Z"
+        }
+        PieceKind::Bishop => {
+            "M 10,39 H 35 V 36 H 10 Z M 15,36 C 15,32 17,30 18,29 C 15,26 14,21 22.5,13 \
+             C 31,21 30,26 27,29 C 28,30 30,32 30,36 Z M 22.5,6 A 2.5,2.5 0 1 1 22.5,11 \
+             A 2.5,2.5 0 1 1 22.5,6 Z"
+        }
+        PieceKind::Rook => {
+            "M 9,39 H 36 V 36 H 9 Z M 12,36 V 32 H 33 V 36 Z M 14,32 L 15,16 H 30 L 31,32 Z \
+             M 11,16 V 9 H 15 V 11 H 20 V 9 H 25 V 11 H 30 V 9 H 34 V 16 Z"
+        }
+        PieceKind::Queen => {
+            "M 10,39 H 35 V 36 H 10 Z M 11,36 L 8,14 L 14,26 L 15.5,11 L 20,25 L 22.5,9 L 25,25 \
+             L 29.5,11 L 31,26 L 37,14 L 34,36 Z M 8,12 A 2,2 0 1 1 8,16 A 2,2 0 1 1 8,12 Z \
+             M 15.5,9 A 2,2 0 1 1 15.5,13 A 2,2 0 1 1 15.5,9 Z M 22.5,7 A 2,2 0 1 1 22.5,11 \
+             A 2,2 0 1 1 22.5,7 Z M 29.5,9 A 2,2 0 1 1 29.5,13 A 2,2 0 1 1 29.5,9 Z \
+             M 37,12 A 2,2 0 1 1 37,16 A 2,2 0 1 1 37,12 Z"
+        }
+        PieceKind::King => {
+            "M 10,39 H 35 V 36 H 10 Z M 11,36 C 8,31 7,25 13,22 C 17,20 20,22 22.5,25 \
+             C 25,22 28,20 32,22 C 38,25 37,31 34,36 Z M 22.5,25 C 21,20 20.5,17 22.5,15 \
+             C 24.5,17 24,20 22.5,25 Z"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Position;
+
+    #[test]
+    fn svg() {
+        let position = Position::start();
+        let svg = Renderer::new().svg(position.board());
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<rect").count(), 64);
+        assert_eq!(svg.matches("<path").count(), 32 + 2);
+        assert_eq!(svg.matches("<text").count(), 16);
+
+        let e4 = position.parse_uci("e2e4").unwrap();
+        let svg = Renderer::new()
+            .coordinates(false)
+            .last_move(e4)
+            .arrow(Square::G1, Square::F3)
+            .svg(position.board());
+        assert_eq!(svg.matches("<rect").count(), 66);
+        assert_eq!(svg.matches("<polygon").count(), 1);
+        assert!(!svg.contains("<text"));
+
+        let svg = Renderer::new()
+            .annotate(Annotation::Highlight {
+                square: Square::E4,
+                brush: Brush::Yellow,
+            })
+            .annotate(Annotation::Circle {
+                square: Square::D5,
+                brush: Brush::Red,
+            })
+            .svg(position.board());
+        assert!(svg.contains(r##"fill="#e68f00""##));
+        assert!(svg.contains(r##"stroke="#882020""##));
+    }
+
+    #[test]
+    fn orientation() {
+        let white = Renderer::new();
+        let black = Renderer::new().flipped(true);
+        assert_eq!(white.corner(Square::A1), (0.0, 315.0));
+        assert_eq!(black.corner(Square::A1), (315.0, 0.0));
+        assert_eq!(black.corner(Square::H8), (0.0, 315.0));
+    }
+
+    #[test]
+    fn frames() {
+        let mut game = Game::start();
+        for uci in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+            game.play(game.position().parse_uci(uci).unwrap());
+        }
+        let frames = Renderer::new().arrow(Square::A1, Square::A8).frames(&game);
+        assert_eq!(frames.len(), 5);
+        assert!(frames.iter().all(|frame| !frame.contains("<polygon")));
+        // the last move is highlighted, and checkmate is marked
+        assert_eq!(frames[0].matches("<rect").count(), 64);
+        assert_eq!(frames[4].matches("<rect").count(), 66);
+        let check = "fill=\"#ff0000\"";
+        assert!(frames[4].contains(check) && !frames[3].contains(check));
+    }
+
+    #[cfg(feature = "gif")]
+    #[test]
+    fn gif() {
+        let mut game = Game::start();
+        game.play(game.position().parse_uci("e2e4").unwrap());
+        let gif = Renderer::new()
+            .size(64)
+            .gif(&game, Duration::from_millis(500))
+            .unwrap();
+        assert!(gif.starts_with(b"GIF89a"));
+    }
+
+    #[cfg(feature = "raster")]
+    #[test]
+    fn png() {
+        let png = Renderer::new()
+            .size(80)
+            .png(Position::start().board())
+            .unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+}
@@ -0,0 +1,248 @@
+//! [Standard Algebraic Notation](https://en.wikipedia.org/wiki/Algebraic_notation_(chess)),
+//! such as `Nf3`, `exd5`, `O-O` or `e8=Q+`, and `N@f3` for a drop in Crazyhouse.
+
+use alloc::{
+    borrow::ToOwned,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{CastleSide, Color, File, Move, MoveFlag, Piece, PieceKind, Position, Rank, Square};
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum SanError {
+    #[error("'{0}' is not valid SAN")]
+    Invalid(String),
+    #[error("'{0}' is not a legal move")]
+    Illegal(String),
+    #[error("'{0}' could be more than one move")]
+    Ambiguous(String),
+}
+
+fn kind_char(kind: PieceKind) -> char {
+    Piece::new_with(Color::White, kind).as_char()
+}
+
+impl Position {
+    /// Writes a legal [`Move`] in SAN, including any check or mate suffix.
+    pub fn san(&self, mv: Move) -> String {
+        let mut san = String::new();
+        let (from, to) = (mv.from(), mv.to());
+
+        if let Some(side) = mv.castle_side() {
+            san.push_str(match side {
+                CastleSide::King => "O-O",
+                CastleSide::Queen => "O-O-O",
+            });
+        } else if let Some(kind) = mv.dropped() {
+            san.push(kind_char(kind));
+            san.push('@');
+            san.push_str(&to.to_string());
+        } else {
+            let kind = self
+                .board
+                .kind_on(from)
+                .expect("a piece should be on `from`");
+            let is_capture = self.board.occupied().is_on(to) || mv.flag() == MoveFlag::EnPassant;
+
+            if kind == PieceKind::Pawn {
+                if is_capture {
+                    san.push(from.file().as_char());
+                }
+            } else {
+                san.push(kind_char(kind));
+
+                // other pieces of the same kind that could also reach `to`
+                let others = self
+                    .legal_moves()
+                    .iter()
+                    .filter(|other| {
+                        other.to() == to
+                            && other.from() != from
+                            && self.board.kind_on(other.from()) == Some(kind)
+                    })
+                    .map(|other| other.from())
+                    .collect::<Vec<_>>();
+
+                if !others.is_empty() {
+                    if others.iter().all(|sq| sq.file() != from.file()) {
+                        san.push(from.file().as_char());
+                    } else if others.iter().all(|sq| sq.rank() != from.rank()) {
+                        san.push_str(&from.rank().to_string());
+                    } else {
+                        san.push_str(&from.to_string());
+                    }
+                }
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&to.to_string());
+
+            if let Some(promotion) = mv.promotion() {
+                san.push('=');
+                san.push(kind_char(promotion));
+            }
+        }
+
+        let mut after = self.clone();
+        after.make_move(mv);
+        if after.in_check(after.side_to_move()) {
+            san.push(if after.legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+
+        san
+    }
+
+    /// Finds the legal [`Move`] written in SAN.
+    ///
+    /// Check, mate and annotation suffixes are ignored,
+    /// and castling may be written with zeros.
+    pub fn parse_san(&self, text: &str) -> Result<Move, SanError> {
+        let invalid = || SanError::Invalid(text.to_owned());
+        let san = text.trim_end_matches(['+', '#', '!', '?']);
+
+        let legal = self.legal_moves();
+
+        let castle = match san {
+            "O-O" | "0-0" => Some(CastleSide::King),
+            "O-O-O" | "0-0-0" => Some(CastleSide::Queen),
+            _ => None,
+        };
+        if castle.is_some() {
+            return legal
+                .iter()
+                .copied()
+                .find(|mv| mv.castle_side() == castle)
+                .ok_or_else(|| SanError::Illegal(text.to_owned()));
+        }
+
+        let mut bytes = san.as_bytes();
+
+        // a drop, where a pawn can be written without its letter
+        if let Some(at) = san.find('@') {
+            let kind = match &bytes[..at] {
+                b"" | b"P" => PieceKind::Pawn,
+                b"N" => PieceKind::Knight,
+                b"B" => PieceKind::Bishop,
+                b"R" => PieceKind::Rook,
+                b"Q" => PieceKind::Queen,
+                _ => return Err(invalid()),
+            };
+            let to = san[at + 1..].parse::<Square>().map_err(|_| invalid())?;
+            let mv = Move::new_drop(kind, to);
+            return legal
+                .contains(&mv)
+                .then_some(mv)
+                .ok_or_else(|| SanError::Illegal(text.to_owned()));
+        }
+
+        let kind = match bytes.first() {
+            Some(b'N') => PieceKind::Knight,
+            Some(b'B') => PieceKind::Bishop,
+            Some(b'R') => PieceKind::Rook,
+            Some(b'Q') => PieceKind::Queen,
+            Some(b'K') => PieceKind::King,
+            Some(b'a'..=b'h') => PieceKind::Pawn,
+            _ => return Err(invalid()),
+        };
+        if kind != PieceKind::Pawn {
+            bytes = &bytes[1..];
+        }
+
+        let mut promotion = None;
+        if let [rest @ .., b'=', p] | [rest @ .., p @ (b'N' | b'B' | b'R' | b'Q')] = bytes {
+            promotion = Some(match p {
+                b'N' => PieceKind::Knight,
+                b'B' => PieceKind::Bishop,
+                b'R' => PieceKind::Rook,
+                b'Q' => PieceKind::Queen,
+                b'K' => PieceKind::King,
+                _ => return Err(invalid()),
+            });
+            bytes = rest;
+        }
+
+        // the destination is always the last two characters
+        let [rest @ .., file, rank] = bytes else {
+            return Err(invalid());
+        };
+        let to = core::str::from_utf8(&[*file, *rank])
+            .ok()
+            .and_then(|to| to.parse::<Square>().ok())
+            .ok_or_else(invalid)?;
+
+        // anything left is disambiguation, with an optional capture
+        let mut from_file = None;
+        let mut from_rank = None;
+        for &b in rest {
+            match b {
+                b'a'..=b'h' => from_file = File::from_char(b as char),
+                b'1'..=b'8' => from_rank = Rank::new(b - b'0'),
+                b'x' | b':' | b'-' => {}
+                _ => return Err(invalid()),
+            }
+        }
+
+        let mut candidates = legal.iter().copied().filter(|mv| {
+            mv.to() == to
+                && mv.flag() != MoveFlag::Castle
+                && mv.promotion() == promotion
+                && self.board.kind_on(mv.from()) == Some(kind)
+                && from_file.is_none_or(|file| mv.from().file() == file)
+                && from_rank.is_none_or(|rank| mv.from().rank() == rank)
+        });
+
+        match (candidates.next(), candidates.next()) {
+            (Some(mv), None) => Ok(mv),
+            (Some(_), Some(_)) => Err(SanError::Ambiguous(text.to_owned())),
+            (None, _) => Err(SanError::Illegal(text.to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FEN;
+
+    #[test]
+    fn round_trip_all_moves() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let position = FEN::new(fen).parse_position().unwrap();
+
+        for &mv in &position.legal_moves() {
+            let san = position.san(mv);
+            assert_eq!(position.parse_san(&san), Ok(mv), "{san} did not round trip");
+        }
+    }
+
+    #[test]
+    fn disambiguation_and_suffixes() {
+        let fen = "4k3/8/8/8/8/8/4K3/R6R w - - 0 1";
+        let position = FEN::new(fen).parse_position().unwrap();
+
+        let mv = position.parse_san("Rad1").unwrap();
+        assert_eq!(mv.from(), Square::A1);
+        assert_eq!(position.san(mv), "Rad1");
+        assert_eq!(
+            position.parse_san("Rd1"),
+            Err(SanError::Ambiguous("Rd1".into()))
+        );
+
+        let castling = FEN::new("4k3/8/8/8/8/8/8/R3K2R w KQ - 0 1")
+            .parse_position()
+            .unwrap();
+        assert_eq!(castling.san(castling.parse_san("0-0").unwrap()), "O-O");
+
+        let mate = FEN::new("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1")
+            .parse_position()
+            .unwrap();
+        assert_eq!(mate.san(mate.parse_san("Ra8").unwrap()), "Ra8#");
+    }
+}
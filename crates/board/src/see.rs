@@ -0,0 +1,161 @@
+//! Static exchange evaluation, and telling quiet positions from tactical ones with it.
+
+use crate::{BitBoard, Color, Move, MoveFlag, PieceKind, Position, Square};
+
+impl Position {
+    /// [Static exchange evaluation](https://www.chessprogramming.org/Static_Exchange_Evaluation):
+    /// the material won by `mv`, in centipawns, if both sides keep recapturing on its square
+    /// with their least valuable piece, stopping whenever that would lose more.
+    ///
+    /// Pins are ignored, and a king only recaptures when nothing can take it back.
+    pub fn see(&self, mv: Move) -> i32 {
+        if mv.castle_side().is_some() {
+            return 0;
+        }
+
+        let board = self.board();
+        let (from, to) = (mv.from(), mv.to());
+        let victim = match mv.flag() {
+            MoveFlag::EnPassant => Some(PieceKind::Pawn),
+            _ => board.kind_on(to),
+        };
+        let mut occupied = board.occupied();
+        if mv.flag() == MoveFlag::EnPassant {
+            occupied.toggle(Square::new(from.rank(), to.file()));
+        }
+        let attacker = board.kind_on(from).expect("a piece should be on `from`");
+        self.exchange(from, to, attacker, victim, occupied, self.side_to_move())
+    }
+
+    /// The material `side` wins by taking `victim` on `to` with the `attacker` on `from`,
+    /// both sides then recapturing while it pays.
+    fn exchange(
+        &self,
+        mut from: Square,
+        to: Square,
+        mut attacker: PieceKind,
+        victim: Option<PieceKind>,
+        mut occupied: BitBoard,
+        mut side: Color,
+    ) -> i32 {
+        let board = self.board();
+        let mut gain = [0; 32];
+        gain[0] = victim.map_or(0, PieceKind::value);
+        let mut depth = 0;
+
+        loop {
+            depth += 1;
+            // what the next side gains by taking the piece that just captured
+            gain[depth] = attacker.value() - gain[depth - 1];
+            if depth == gain.len() - 1 {
+                break;
+            }
+
+            occupied.toggle(from);
+            side = side.opposite();
+            // recomputing finds sliders that were hidden behind the last capture
+            let attackers = board.attackers_to(to, occupied) & occupied;
+            let Some((kind, square)) = self.least_valuable(attackers, side) else {
+                break;
+            };
+            if kind == PieceKind::King && !(attackers & board.colors(side.opposite())).is_empty() {
+                break;
+            }
+            attacker = kind;
+            from = square;
+        }
+
+        while depth > 1 {
+            depth -= 1;
+            gain[depth - 1] = -(-gain[depth - 1]).max(gain[depth]);
+        }
+        gain[0]
+    }
+
+    /// The least valuable of `side`'s pieces among `attackers`, and where it is.
+    fn least_valuable(&self, attackers: BitBoard, side: Color) -> Option<(PieceKind, Square)> {
+        let board = self.board();
+        let ours = attackers & board.colors(side);
+        PieceKind::ALL
+            .into_iter()
+            .find_map(|kind| Some((kind, (ours & board.pieces(kind)).lsb()?)))
+    }
+
+    /// Returns `true` if the static evaluation of the position can be trusted:
+    /// the side to move isn't in check, has no capture or promotion that wins material,
+    /// and has no piece the opponent could win by taking it.
+    ///
+    /// Material is judged by [`Position::see`].
+    pub fn is_quiet(&self) -> bool {
+        let us = self.side_to_move();
+        if self.in_check(us) {
+            return false;
+        }
+
+        let board = self.board();
+        let winning = self.legal_moves().iter().any(|&mv| {
+            mv.promotion().is_some()
+                || ((board.occupied().is_on(mv.to()) || mv.flag() == MoveFlag::EnPassant)
+                    && self.see(mv) > 0)
+        });
+        if winning {
+            return false;
+        }
+
+        // the opponent's best capture of each of our pieces, as if it were their move
+        let them = us.opposite();
+        let hanging = (board.colors(us) & !board.kings())
+            .into_iter()
+            .any(|square| {
+                let attackers = board.attackers_to(square, board.occupied());
+                self.least_valuable(attackers, them)
+                    .is_some_and(|(kind, from)| {
+                        self.exchange(
+                            from,
+                            square,
+                            kind,
+                            board.kind_on(square),
+                            board.occupied(),
+                            them,
+                        ) > 0
+                    })
+            });
+        !hanging
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Position, FEN};
+
+    fn position(fen: &str) -> Position {
+        FEN::new(fen).parse_position().unwrap()
+    }
+
+    #[test]
+    fn static_exchange() {
+        // the pawn on d5 is defended, so taking it with the queen loses the queen
+        let pos = position("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1");
+        assert_eq!(pos.see(pos.parse_uci("d2d5").unwrap()), 100 - 900);
+
+        // an undefended rook is simply won
+        let pos = position("4k3/8/8/3r4/8/4N3/8/4K3 w - - 0 1");
+        assert_eq!(pos.see(pos.parse_uci("e3d5").unwrap()), 500);
+    }
+
+    #[test]
+    fn quiet_positions() {
+        assert!(Position::start().is_quiet());
+
+        // a defended pawn can't be won
+        assert!(position("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1").is_quiet());
+        // but an undefended rook can
+        assert!(!position("4k3/8/8/3r4/8/4N3/8/4K3 w - - 0 1").is_quiet());
+        // even with the rook to move, since it is still attacked
+        assert!(!position("4k3/8/8/3r4/8/4N3/8/4K3 b - - 0 1").is_quiet());
+        assert!(position("4k3/8/8/3r4/8/8/8/4K3 w - - 0 1").is_quiet());
+        // checks and promotions aren't quiet either
+        assert!(!position("4k3/8/8/8/8/8/8/R3K2r w - - 0 1").is_quiet());
+        assert!(!position("7k/4P3/8/8/8/8/8/4K3 w - - 0 1").is_quiet());
+    }
+}
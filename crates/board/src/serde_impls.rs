@@ -0,0 +1,311 @@
+//! [`serde`] support, enabled with the `serde` feature.
+//!
+//! Human readable formats use the same text as FEN and UCI,
+//! while binary formats get a compact encoding.
+//! A [`Position`] is stored without its move history.
+
+use alloc::{format, string::String, vec::Vec};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    variant, BitBoard, Board, CastlingRights, Color, Move, MoveFlag, Piece, PieceKind, Position,
+    Square, FEN,
+};
+
+impl Serialize for Square {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u8(self.as_u8())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Square {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?
+                .parse()
+                .map_err(de::Error::custom)
+        } else {
+            let raw = u8::deserialize(deserializer)?;
+            Square::try_from_raw(raw)
+                .ok_or_else(|| de::Error::custom(format!("{raw} is not a square")))
+        }
+    }
+}
+
+/// The [`Piece`] for a FEN letter, uppercase for white.
+fn piece_from_char(c: char) -> Option<Piece> {
+    let kind = match c.to_ascii_lowercase() {
+        'p' => PieceKind::Pawn,
+        'n' => PieceKind::Knight,
+        'b' => PieceKind::Bishop,
+        'r' => PieceKind::Rook,
+        'q' => PieceKind::Queen,
+        'k' => PieceKind::King,
+        _ => return None,
+    };
+    let color = if c.is_ascii_uppercase() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    Some(Piece::new_with(color, kind))
+}
+
+impl Serialize for Piece {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_char(self.as_char())
+        } else {
+            serializer.serialize_u8(self.into_bits())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Piece {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let c = char::deserialize(deserializer)?;
+            piece_from_char(c).ok_or_else(|| de::Error::custom(format!("'{c}' is not a piece")))
+        } else {
+            let bits = u8::deserialize(deserializer)?;
+            if (bits >> 1) as usize >= PieceKind::COUNT {
+                return Err(de::Error::custom(format!("{bits} is not a piece")));
+            }
+            Ok(Piece::from_bits(bits))
+        }
+    }
+}
+
+impl Serialize for BitBoard {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for BitBoard {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u64::deserialize(deserializer).map(BitBoard)
+    }
+}
+
+/// The parts of a [`Move`] spelled out, for human readable formats.
+#[derive(Serialize, Deserialize)]
+struct MoveFields {
+    from: Square,
+    to: Square,
+    promotion: Option<PieceKind>,
+    flag: MoveFlag,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dropped: Option<PieceKind>,
+}
+
+impl Serialize for Move {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            MoveFields {
+                from: self.from(),
+                to: self.to(),
+                promotion: self.promotion(),
+                flag: self.flag(),
+                dropped: self.dropped(),
+            }
+            .serialize(serializer)
+        } else {
+            serializer.serialize_u16(self.to_bits())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Move {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let fields = MoveFields::deserialize(deserializer)?;
+            Ok(match (fields.dropped, fields.promotion) {
+                (Some(kind), _) => Move::new_drop(kind, fields.to),
+                (None, Some(kind)) => Move::new_promotion(fields.from, fields.to, kind),
+                (None, None) => Move::new(fields.from, fields.to, fields.flag),
+            })
+        } else {
+            let bits = u16::deserialize(deserializer)?;
+            Move::from_bits(bits).ok_or_else(|| de::Error::custom(format!("{bits} is not a move")))
+        }
+    }
+}
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(&self.to_fen())
+        } else {
+            (self.pieces, self.colors).serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            String::deserialize(deserializer)?
+                .parse::<FEN>()
+                .and_then(|fen| fen.parse_board())
+                .map_err(de::Error::custom)
+        } else {
+            let (pieces, colors) = Deserialize::deserialize(deserializer)?;
+            Ok(Board::from_bitboards(pieces, colors))
+        }
+    }
+}
+
+/// Everything stored for a [`Position`] in binary formats.
+#[derive(Serialize, Deserialize)]
+struct PositionFields {
+    board: Board,
+    side_to_move: Color,
+    castling: u8,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    castling_rooks: [[Square; 2]; Color::COUNT],
+    chess960: bool,
+    variant: String,
+    hands: [[u8; PieceKind::COUNT]; Color::COUNT],
+    promoted: BitBoard,
+    checks: [u8; Color::COUNT],
+}
+
+impl Serialize for Position {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.fen_string())
+        } else {
+            PositionFields {
+                board: self.board,
+                side_to_move: self.side_to_move,
+                castling: self.castling.into_bits(),
+                en_passant: self.en_passant,
+                halfmove_clock: self.halfmove_clock,
+                fullmove_number: self.fullmove_number,
+                castling_rooks: self.castling_rooks,
+                chess960: self.chess960,
+                variant: self.variant.name().into(),
+                hands: self.hands,
+                promoted: self.promoted,
+                checks: self.checks,
+            }
+            .serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Position {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            return String::deserialize(deserializer)?
+                .parse::<FEN>()
+                .and_then(FEN::parse_position)
+                .map_err(de::Error::custom);
+        }
+
+        let fields = PositionFields::deserialize(deserializer)?;
+        let variant = variant::from_name(&fields.variant)
+            .ok_or_else(|| de::Error::custom(format!("unknown variant {}", fields.variant)))?;
+        let mut position = Position {
+            board: fields.board,
+            side_to_move: fields.side_to_move,
+            castling: CastlingRights::from_bits(fields.castling & CastlingRights::ALL.into_bits()),
+            en_passant: fields.en_passant,
+            halfmove_clock: fields.halfmove_clock,
+            fullmove_number: fields.fullmove_number,
+            key: 0,
+            pawn_key: 0,
+            history: Vec::new(),
+            castling_rooks: fields.castling_rooks,
+            chess960: fields.chess960,
+            counts: Default::default(),
+            material: Default::default(),
+            kings: Default::default(),
+            variant,
+            hands: fields.hands,
+            promoted: fields.promoted,
+            exploded: Vec::new(),
+            checks: fields.checks,
+        };
+        position.refresh();
+        Ok(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binary_round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T) -> T {
+        let bytes = postcard::to_allocvec(value).unwrap();
+        postcard::from_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn squares_and_pieces() {
+        assert_eq!(serde_json::to_string(&Square::E4).unwrap(), r#""e4""#);
+        assert_eq!(postcard::to_allocvec(&Square::E4).unwrap(), [28]);
+        assert!(serde_json::from_str::<Square>(r#""i9""#).is_err());
+
+        let piece = Piece::new_with(Color::Black, PieceKind::Knight);
+        assert_eq!(serde_json::to_string(&piece).unwrap(), r#""n""#);
+        assert_eq!(serde_json::from_str::<Piece>(r#""n""#).unwrap(), piece);
+        assert_eq!(binary_round_trip(&piece), piece);
+    }
+
+    #[test]
+    fn moves() {
+        let castle = Move::new(Square::E1, Square::G1, MoveFlag::Castle);
+        let json = serde_json::to_string(&castle).unwrap();
+        assert_eq!(
+            json,
+            r#"{"from":"e1","to":"g1","promotion":null,"flag":"Castle"}"#
+        );
+        assert_eq!(serde_json::from_str::<Move>(&json).unwrap(), castle);
+
+        let promotion = Move::new_promotion(Square::B7, Square::A8, PieceKind::Queen);
+        assert_eq!(binary_round_trip(&promotion), promotion);
+        assert_eq!(binary_round_trip(&castle), castle);
+    }
+
+    #[test]
+    fn positions() {
+        let fen = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w Kq e6 0 2";
+        let position = FEN::new(fen).parse_position().unwrap();
+
+        let json = serde_json::to_string(&position).unwrap();
+        assert_eq!(json, format!("\"{fen}\""));
+        let parsed = serde_json::from_str::<Position>(&json).unwrap();
+        assert_eq!(parsed.zobrist_key(), position.zobrist_key());
+
+        for position in [position, Position::chess960(0)] {
+            let copy = binary_round_trip(&position);
+            assert_eq!(copy.fen_string(), position.fen_string());
+            assert_eq!(copy.zobrist_key(), position.zobrist_key());
+            assert_eq!(copy.is_chess960(), position.is_chess960());
+        }
+
+        assert_eq!(
+            Position::chess960(0).fen_string(),
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1"
+        );
+        assert!(serde_json::from_str::<Board>(r#""8/8/9""#).is_err());
+
+        // an Atomic game won by blowing up black's king
+        let exploded = FEN::new("8/8/8/8/8/8/8/K1R5 b - - 0 1")
+            .parse_position_as(&variant::Atomic)
+            .unwrap();
+        let copy = binary_round_trip(&exploded);
+        assert_eq!(copy.to_fen(), exploded.to_fen());
+        assert_eq!(copy.variant().name(), "atomic");
+        assert_eq!(copy.validate(), Ok(()));
+    }
+}
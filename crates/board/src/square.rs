@@ -1,3 +1,186 @@
+use alloc::{borrow::ToOwned, string::String};
+
+use crate::Color;
+
+/// A row of the board, from the first rank where White starts to the eighth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum Rank {
+    First,
+    Second,
+    Third,
+    Fourth,
+    Fifth,
+    Sixth,
+    Seventh,
+    Eighth,
+}
+
+impl Rank {
+    pub const ALL: [Self; 8] = [
+        Self::First,
+        Self::Second,
+        Self::Third,
+        Self::Fourth,
+        Self::Fifth,
+        Self::Sixth,
+        Self::Seventh,
+        Self::Eighth,
+    ];
+
+    /// The rank written as `number` in algebraic notation, from 1 to 8.
+    #[inline]
+    pub const fn new(number: u8) -> Option<Self> {
+        match number {
+            1..=8 => Some(Self::ALL[number as usize - 1]),
+            _ => None,
+        }
+    }
+
+    /// The number the rank is written as, from 1 to 8.
+    #[inline]
+    pub const fn number(self) -> u8 {
+        self as u8 + 1
+    }
+
+    /// The rank's index from 0 for the first rank, for indexing arrays.
+    #[inline]
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The rank `by` ranks further up the board, or None if that would be off it.
+    #[inline]
+    pub const fn offset(self, by: i8) -> Option<Self> {
+        let index = self as i8 + by;
+        if 0 <= index && index < 8 {
+            Some(Self::ALL[index as usize])
+        } else {
+            None
+        }
+    }
+
+    /// How many ranks apart two ranks are.
+    #[inline]
+    pub const fn distance(self, other: Self) -> u8 {
+        (self as u8).abs_diff(other as u8)
+    }
+
+    /// The rank at the other end of the board, such as the seventh for the second.
+    #[inline]
+    pub const fn flip(self) -> Self {
+        Self::ALL[7 - self as usize]
+    }
+
+    /// The rank as `color` sees it, counting from their side of the board,
+    /// so White's second rank is Black's seventh.
+    #[inline]
+    pub const fn relative_to(self, color: Color) -> Self {
+        match color {
+            Color::White => self,
+            Color::Black => self.flip(),
+        }
+    }
+}
+
+impl core::fmt::Display for Rank {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.number())
+    }
+}
+
+/// A column of the board, from the a-file on White's left to the h-file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum File {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+}
+
+impl File {
+    pub const ALL: [Self; 8] = [
+        Self::A,
+        Self::B,
+        Self::C,
+        Self::D,
+        Self::E,
+        Self::F,
+        Self::G,
+        Self::H,
+    ];
+
+    /// The file numbered `number` from 1 for the a-file to 8 for the h-file.
+    #[inline]
+    pub const fn new(number: u8) -> Option<Self> {
+        match number {
+            1..=8 => Some(Self::ALL[number as usize - 1]),
+            _ => None,
+        }
+    }
+
+    /// The file for a lowercase letter from `a` to `h`.
+    #[inline]
+    pub const fn from_char(letter: char) -> Option<Self> {
+        match letter {
+            'a'..='h' => Some(Self::ALL[letter as usize - 'a' as usize]),
+            _ => None,
+        }
+    }
+
+    /// The file's number, from 1 for the a-file to 8 for the h-file.
+    #[inline]
+    pub const fn number(self) -> u8 {
+        self as u8 + 1
+    }
+
+    /// The file's index from 0 for the a-file, for indexing arrays.
+    #[inline]
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The file's lowercase letter.
+    #[inline]
+    pub const fn as_char(self) -> char {
+        (b'a' + self as u8) as char
+    }
+
+    /// The file `by` files towards the h-file, or None if that would be off the board.
+    #[inline]
+    pub const fn offset(self, by: i8) -> Option<Self> {
+        let index = self as i8 + by;
+        if 0 <= index && index < 8 {
+            Some(Self::ALL[index as usize])
+        } else {
+            None
+        }
+    }
+
+    /// How many files apart two files are.
+    #[inline]
+    pub const fn distance(self, other: Self) -> u8 {
+        (self as u8).abs_diff(other as u8)
+    }
+
+    /// The file on the other side of the board, such as the d-file for the e-file.
+    #[inline]
+    pub const fn mirror(self) -> Self {
+        Self::ALL[7 - self as usize]
+    }
+}
+
+impl core::fmt::Display for File {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.as_char())
+    }
+}
+
 #[rustfmt::skip]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -14,27 +197,18 @@ pub enum Square {
 
 impl Square {
     /// Create a [`Square`] from `rank` and `file`.
-    /// 
-    /// # Panics
-    /// 
-    /// Panics if either rank or file are out of range 1..=8
     #[inline]
-    pub fn new(rank: u8, file: u8) -> Self {
-        assert!(1 <= rank && rank <= 8, "rank should be between 1..=8");
-        assert!(1 <= file && file <= 8, "file should be between 1..=8");
-
-        let raw = (rank - 1) * 8 + (file - 1);
-
-        Self::from_raw(raw)
+    pub const fn new(rank: Rank, file: File) -> Self {
+        Self::from_raw(rank as u8 * 8 + file as u8)
     }
 
     /// Creates a [`Square`] from a raw value.
-    /// 
+    ///
     /// # Panics
-    /// 
+    ///
     /// Panics if the value cannot be represented as an enum variant.
     #[inline]
-    pub fn from_raw(raw: u8) -> Self {
+    pub const fn from_raw(raw: u8) -> Self {
         assert!(
             raw < core::mem::variant_count::<Self>() as u8,
             "raw value must be a valid enum variant"
@@ -42,14 +216,14 @@ impl Square {
         // SAFETY:
         // Checked that raw value can be casted to a valid variant
         // Both raw and square are the same repr type (u8)
-        unsafe { core::mem::transmute(raw) }
+        unsafe { core::mem::transmute::<u8, Self>(raw) }
     }
 
     /// Tries to create a [`Square`] from a raw value.
-    /// 
+    ///
     /// If it fails, None is returned.
     #[inline]
-    pub fn try_from_raw(raw: u8) -> Option<Self> {
+    pub const fn try_from_raw(raw: u8) -> Option<Self> {
         if raw < core::mem::variant_count::<Self>() as u8 {
             Some(Self::from_raw(raw))
         } else {
@@ -59,14 +233,94 @@ impl Square {
 
     /// Gets the correct bit for the [`Square`].
     #[inline]
-    pub fn bit(&self) -> u64 {
+    pub const fn bit(&self) -> u64 {
         1 << self.as_u8()
     }
 
+    /// The [`Rank`] of the [`Square`].
+    #[inline]
+    pub const fn rank(&self) -> Rank {
+        Rank::ALL[self.index() / 8]
+    }
+
+    /// The [`File`] of the [`Square`].
     #[inline]
-    fn as_u8(self) -> u8 {
+    pub const fn file(&self) -> File {
+        File::ALL[self.index() % 8]
+    }
+
+    /// How many king moves apart two squares are.
+    #[inline]
+    pub const fn distance(self, other: Self) -> u8 {
+        let ranks = self.rank().distance(other.rank());
+        let files = self.file().distance(other.file());
+        if ranks > files {
+            ranks
+        } else {
+            files
+        }
+    }
+
+    /// Moves the [`Square`] by a number of ranks and files.
+    ///
+    /// If the new square would be off the board, None is returned.
+    #[inline]
+    pub const fn offset(&self, ranks: i8, files: i8) -> Option<Self> {
+        let rank = (self.as_u8() / 8) as i8 + ranks;
+        let file = (self.as_u8() % 8) as i8 + files;
+
+        if 0 <= rank && rank < 8 && 0 <= file && file < 8 {
+            Some(Self::from_raw((rank * 8 + file) as u8))
+        } else {
+            None
+        }
+    }
+
+    /// The square on the same file at the other end of the board, such as e2 for e7.
+    #[inline]
+    pub const fn flip_vertical(self) -> Self {
+        Self::from_raw(self.as_u8() ^ 56)
+    }
+
+    /// The square on the same rank at the other side of the board, such as d1 for e1.
+    #[inline]
+    pub const fn mirror_horizontal(self) -> Self {
+        Self::from_raw(self.as_u8() ^ 7)
+    }
+
+    #[inline]
+    pub(crate) const fn as_u8(self) -> u8 {
         self as u8
     }
+
+    #[inline]
+    pub(crate) const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("'{0}' is not a square")]
+pub struct ParseSquareError(String);
+
+impl core::str::FromStr for Square {
+    type Err = ParseSquareError;
+
+    /// Parses algebraic coordinates, such as `e4`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match *s.as_bytes() {
+            [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+                Ok(Self::from_raw((rank - b'1') * 8 + (file - b'a')))
+            }
+            _ => Err(ParseSquareError(s.to_owned())),
+        }
+    }
+}
+
+impl core::fmt::Display for Square {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}{}", self.file(), self.rank())
+    }
 }
 
 #[cfg(test)]
@@ -74,16 +328,45 @@ mod tests {
     use super::*;
 
     #[test]
-    fn in_range_creation() {
-        assert_eq!(Square::new(1, 1), Square::A1);
-        assert_eq!(Square::new(8, 8), Square::H8);
+    fn creation() {
+        assert_eq!(Square::new(Rank::First, File::A), Square::A1);
+        assert_eq!(Square::new(Rank::Eighth, File::H), Square::H8);
+        assert_eq!(Rank::new(0), None);
+        assert_eq!(File::new(9), None);
     }
 
     #[test]
-    #[should_panic]
-    fn out_of_range_creation() {
-        Square::new(0, 0);
-        Square::new(9, 9);
+    fn rank_and_file() {
+        assert_eq!(Square::E4.rank(), Rank::Fourth);
+        assert_eq!(Square::E4.file(), File::E);
+        assert_eq!(Square::E4.rank().number(), 4);
+        assert_eq!(Square::A1.offset(1, 2), Some(Square::C2));
+        assert_eq!(Square::H8.offset(0, 1), None);
+
+        assert_eq!(Rank::Second.relative_to(Color::Black), Rank::Seventh);
+        assert_eq!(Rank::Eighth.offset(1), None);
+        assert_eq!(
+            File::from_char('c').and_then(|c| c.offset(-2)),
+            Some(File::A)
+        );
+        assert_eq!(File::B.distance(File::G), 5);
+        assert_eq!(Square::B1.distance(Square::G3), 5);
+        assert_eq!(
+            Rank::ALL.iter().map(Rank::to_string).collect::<String>(),
+            "12345678"
+        );
+    }
+
+    #[test]
+    fn algebraic_coordinates() {
+        assert_eq!("e4".parse(), Ok(Square::E4));
+        assert_eq!("h8".parse(), Ok(Square::H8));
+        assert!("i1".parse::<Square>().is_err());
+        assert!("e".parse::<Square>().is_err());
+        assert!("e44".parse::<Square>().is_err());
+
+        assert_eq!(Square::E4.to_string(), "e4");
+        assert_eq!(format!("{}", Square::A1), "a1");
     }
 
     #[test]
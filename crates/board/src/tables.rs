@@ -0,0 +1,177 @@
+//! Lookup tables for the squares along ranks, files and diagonals,
+//! all generated at compile time.
+
+use crate::{BitBoard, Square};
+
+/// One of the eight compass directions a slider can move in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
+    /// The step in ranks and files taken by this direction.
+    #[inline]
+    pub const fn offset(self) -> (i8, i8) {
+        match self {
+            Direction::North => (1, 0),
+            Direction::NorthEast => (1, 1),
+            Direction::East => (0, 1),
+            Direction::SouthEast => (-1, 1),
+            Direction::South => (-1, 0),
+            Direction::SouthWest => (-1, -1),
+            Direction::West => (0, -1),
+            Direction::NorthWest => (1, -1),
+        }
+    }
+
+    /// The direction pointing the other way.
+    #[inline]
+    pub const fn opposite(self) -> Self {
+        Self::ALL[(self as usize + 4) % 8]
+    }
+}
+
+const fn ray_table() -> [[u64; 64]; 8] {
+    let mut table = [[0; 64]; 8];
+    let mut d = 0;
+    while d < 8 {
+        let (ranks, files) = Direction::ALL[d].offset();
+        let mut raw = 0;
+        while raw < 64 {
+            let mut square = Square::from_raw(raw as u8);
+            while let Some(to) = square.offset(ranks, files) {
+                table[d][raw] |= to.bit();
+                square = to;
+            }
+            raw += 1;
+        }
+        d += 1;
+    }
+    table
+}
+
+/// Builds the `between` and `line` tables by walking every ray.
+const fn pair_tables() -> ([[u64; 64]; 64], [[u64; 64]; 64]) {
+    let mut between = [[0; 64]; 64];
+    let mut line = [[0; 64]; 64];
+    let mut raw = 0;
+    while raw < 64 {
+        let from = Square::from_raw(raw as u8);
+        let mut d = 0;
+        while d < 8 {
+            let direction = Direction::ALL[d];
+            let (ranks, files) = direction.offset();
+            let full = RAYS[d][raw] | RAYS[direction.opposite() as usize][raw] | from.bit();
+
+            let mut passed = 0;
+            let mut square = from;
+            while let Some(to) = square.offset(ranks, files) {
+                between[raw][to.index()] = passed;
+                line[raw][to.index()] = full;
+                passed |= to.bit();
+                square = to;
+            }
+            d += 1;
+        }
+        raw += 1;
+    }
+    (between, line)
+}
+
+static RAYS: [[u64; 64]; 8] = ray_table();
+static BETWEEN: [[u64; 64]; 64] = pair_tables().0;
+static LINE: [[u64; 64]; 64] = pair_tables().1;
+
+/// The squares from `square` to the edge of the board in `direction`,
+/// not including `square` itself.
+#[inline]
+pub fn ray(direction: Direction, square: Square) -> BitBoard {
+    BitBoard(RAYS[direction as usize][square.index()])
+}
+
+/// The squares strictly between `a` and `b`.
+///
+/// Empty if the squares don't share a rank, file or diagonal.
+#[inline]
+pub fn between(a: Square, b: Square) -> BitBoard {
+    BitBoard(BETWEEN[a.index()][b.index()])
+}
+
+/// The full line across the board passing through `a` and `b`.
+///
+/// Empty if the squares don't share a rank, file or diagonal.
+#[inline]
+pub fn line(a: Square, b: Square) -> BitBoard {
+    BitBoard(LINE[a.index()][b.index()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rays() {
+        assert_eq!(ray(Direction::North, Square::A1).count(), 7);
+        assert_eq!(ray(Direction::NorthEast, Square::A1).count(), 7);
+        assert_eq!(ray(Direction::South, Square::A1), BitBoard::EMPTY);
+        assert_eq!(
+            ray(Direction::SouthWest, Square::C3),
+            BitBoard::from_square(Square::B2) | BitBoard::from_square(Square::A1)
+        );
+    }
+
+    #[test]
+    fn between_and_line() {
+        assert_eq!(
+            between(Square::A1, Square::D4),
+            BitBoard::from_square(Square::B2) | BitBoard::from_square(Square::C3)
+        );
+        assert_eq!(
+            between(Square::D4, Square::A1),
+            between(Square::A1, Square::D4)
+        );
+        assert_eq!(between(Square::A1, Square::A2), BitBoard::EMPTY);
+        assert_eq!(between(Square::A1, Square::B3), BitBoard::EMPTY);
+
+        assert_eq!(line(Square::B2, Square::C3).count(), 8);
+        assert_eq!(line(Square::E1, Square::E4).count(), 8);
+        assert!(line(Square::E1, Square::E4).is_on(Square::E1));
+        assert_eq!(line(Square::A1, Square::B3), BitBoard::EMPTY);
+        assert_eq!(line(Square::A1, Square::A1), BitBoard::EMPTY);
+    }
+
+    #[test]
+    fn matches_slider_attacks() {
+        for a in BitBoard(!0) {
+            for b in BitBoard(!0) {
+                let expected = if crate::attacks::queen_attacks(a, BitBoard::EMPTY).is_on(b) {
+                    crate::attacks::queen_attacks(a, BitBoard::from_square(b))
+                        & crate::attacks::queen_attacks(b, BitBoard::from_square(a))
+                        & line(a, b)
+                } else {
+                    BitBoard::EMPTY
+                };
+                assert_eq!(between(a, b), expected, "{a} {b}");
+            }
+        }
+    }
+}
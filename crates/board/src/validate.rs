@@ -0,0 +1,238 @@
+//! Consistency checks for boards and positions built by hand.
+
+use crate::{BitBoard, Board, CastleSide, Color, Piece, PieceKind, Position, Rank, Square};
+
+/// An invariant broken by a [`Board`] or [`Position`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationError {
+    #[error("{0} holds more than one kind of piece")]
+    OverlappingPieces(Square),
+    #[error("{0} holds pieces of both colors")]
+    OverlappingColors(Square),
+    #[error("{0} has a piece without a color, or a color without a piece")]
+    ColorMismatch(Square),
+    #[error("{color:?} has {count} kings")]
+    KingCount { color: Color, count: u32 },
+    #[error("pawn on the back rank at {0}")]
+    PawnOnBackRank(Square),
+    #[error("the side not to move is in check")]
+    OpponentInCheck,
+    #[error("{color:?} can't castle {side:?} side without the king and rook in place")]
+    InvalidCastling { color: Color, side: CastleSide },
+    #[error("no pawn could have just moved past {0}")]
+    InvalidEnPassant(Square),
+    #[error("'{}' can't be in hand", .0.as_char())]
+    InvalidHand(Piece),
+    #[error("{0:?} can't have given that many checks")]
+    InvalidChecks(Color),
+}
+
+pub(crate) const BACK_RANKS: BitBoard = BitBoard(BitBoard::RANK_1.0 | BitBoard::RANK_8.0);
+
+impl Board {
+    /// Checks the board could come from a game of chess.
+    ///
+    /// Every square holds at most one piece with exactly one color,
+    /// each side has one king and no pawns stand on the first or last rank.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_layout()?;
+
+        for color in Color::ALL {
+            let count = (self.kings() & self.colors(color)).count();
+            if count != 1 {
+                return Err(ValidationError::KingCount { color, count });
+            }
+        }
+
+        let stranded = self.pawns() & BACK_RANKS;
+        if !stranded.is_empty() {
+            return Err(ValidationError::PawnOnBackRank(first(stranded)));
+        }
+
+        Ok(())
+    }
+
+    /// Checks every square holds at most one piece with exactly one color,
+    /// the part of [`Board::validate`] every [`Variant`](crate::Variant) shares.
+    pub(crate) fn validate_layout(&self) -> Result<(), ValidationError> {
+        let mut seen = BitBoard::EMPTY;
+        for kind in PieceKind::ALL {
+            let overlap = seen & self.pieces(kind);
+            if !overlap.is_empty() {
+                return Err(ValidationError::OverlappingPieces(first(overlap)));
+            }
+            seen |= self.pieces(kind);
+        }
+
+        let overlap = self.whites() & self.blacks();
+        if !overlap.is_empty() {
+            return Err(ValidationError::OverlappingColors(first(overlap)));
+        }
+        let mismatch = seen ^ self.occupied();
+        if !mismatch.is_empty() {
+            return Err(ValidationError::ColorMismatch(first(mismatch)));
+        }
+
+        Ok(())
+    }
+}
+
+/// The first square of a set known not to be empty.
+pub(crate) fn first(bb: BitBoard) -> Square {
+    bb.lsb().expect("only called on non-empty sets")
+}
+
+impl Position {
+    /// Checks the position could come from a game of chess.
+    ///
+    /// As well as [`Board::validate`], or the [`Variant`](crate::Variant)'s own rules
+    /// for the board, the side that just moved can't be left in check,
+    /// castling rights need the king and rook on their starting ranks,
+    /// and an en passant square must sit behind a pawn that just double pushed.
+    /// Only a variant with drops can have pieces in hand, and never a king,
+    /// and no more checks can have been given than the variant counts to.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        self.variant.validate(self)?;
+
+        for color in Color::ALL {
+            for kind in PieceKind::ALL {
+                let piece = Piece::new_with(color, kind);
+                let allowed = self.variant.has_drops() && kind != PieceKind::King;
+                if self.in_hand(piece) > 0 && !allowed {
+                    return Err(ValidationError::InvalidHand(piece));
+                }
+            }
+        }
+
+        for color in Color::ALL {
+            let limit = self.variant.check_limit().unwrap_or(0);
+            if self.checks[color as usize] > limit {
+                return Err(ValidationError::InvalidChecks(color));
+            }
+        }
+
+        if self.in_check(self.side_to_move.opposite()) {
+            return Err(ValidationError::OpponentInCheck);
+        }
+
+        for color in Color::ALL {
+            let back_rank = Rank::First.relative_to(color);
+            let ours = self.board.colors(color);
+            let king = self.find_king(color);
+
+            for side in [CastleSide::King, CastleSide::Queen] {
+                if !self.castling.has(color, side) {
+                    continue;
+                }
+                let rook = self.castling_rook(color, side);
+                let in_place = king.is_some_and(|king| king.rank() == back_rank)
+                    && (self.board.rooks() & ours).is_on(rook);
+                if !in_place {
+                    return Err(ValidationError::InvalidCastling { color, side });
+                }
+            }
+        }
+
+        if let Some(ep) = self.en_passant {
+            // the pawn moved from `behind`, past `ep` to `ahead`
+            let (rank, forward) = match self.side_to_move {
+                Color::White => (Rank::Sixth, -1),
+                Color::Black => (Rank::Third, 1),
+            };
+            let them = self.side_to_move.opposite();
+            let ahead = ep.offset(forward, 0);
+            let behind = ep.offset(-forward, 0);
+
+            let plausible = ep.rank() == rank
+                && !self.board.occupied().is_on(ep)
+                && behind.is_some_and(|sq| !self.board.occupied().is_on(sq))
+                && ahead.is_some_and(|sq| (self.board.pawns() & self.board.colors(them)).is_on(sq));
+            if !plausible {
+                return Err(ValidationError::InvalidEnPassant(ep));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Piece, FEN};
+
+    fn position(fen: &str) -> Position {
+        FEN::new(fen).parse_position_unchecked().unwrap()
+    }
+
+    #[test]
+    fn valid() {
+        assert_eq!(Position::start().validate(), Ok(()));
+        assert_eq!(Position::chess960(0).validate(), Ok(()));
+        assert_eq!(
+            position("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2").validate(),
+            Ok(())
+        );
+    }
+
+    // broken boards can only be built by writing to the bitboards
+    #[allow(deprecated)]
+    #[test]
+    fn boards() {
+        let mut board = Board::start();
+        board.pieces_mut(PieceKind::Queen).toggle(Square::E1);
+        assert_eq!(
+            board.validate(),
+            Err(ValidationError::OverlappingPieces(Square::E1))
+        );
+
+        let mut board = Board::start();
+        board.colors_mut(Color::Black).toggle(Square::E4);
+        assert_eq!(
+            board.validate(),
+            Err(ValidationError::ColorMismatch(Square::E4))
+        );
+
+        let mut board = Board::start();
+        board.remove_piece(Square::E8);
+        assert_eq!(
+            board.validate(),
+            Err(ValidationError::KingCount {
+                color: Color::Black,
+                count: 0
+            })
+        );
+
+        let mut board = Board::start();
+        let pawn = Piece::new_with(Color::White, PieceKind::Pawn);
+        board.remove_piece(Square::A8);
+        board.set_piece(pawn, Square::A8).unwrap();
+        assert_eq!(
+            board.validate(),
+            Err(ValidationError::PawnOnBackRank(Square::A8))
+        );
+    }
+
+    #[test]
+    fn positions() {
+        assert_eq!(
+            position("4k3/8/8/8/8/8/8/4K2r b - - 0 1").validate(),
+            Err(ValidationError::OpponentInCheck)
+        );
+        assert_eq!(
+            position("4k3/8/8/8/8/8/8/4K3 w K - 0 1").validate(),
+            Err(ValidationError::InvalidCastling {
+                color: Color::White,
+                side: CastleSide::King
+            })
+        );
+        assert_eq!(
+            position("4k3/8/8/8/8/8/8/4K3 w - e6 0 1").validate(),
+            Err(ValidationError::InvalidEnPassant(Square::E6))
+        );
+        assert_eq!(
+            position("4k3/8/8/4p3/8/8/8/4K3 w - e3 0 1").validate(),
+            Err(ValidationError::InvalidEnPassant(Square::E3))
+        );
+    }
+}
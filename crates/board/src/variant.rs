@@ -0,0 +1,752 @@
+//! Chess variants, which change some of the rules while keeping the board and pieces.
+//!
+//! Every [`Position`] follows the rules of a [`Variant`], standard chess unless chosen otherwise
+//! with [`Position::start_variant`] or [`FEN::parse_position_as`](crate::FEN::parse_position_as).
+
+use core::fmt::Debug;
+
+use crate::{
+    attacks::{king_attacks, pawn_attacks},
+    validate::{first, BACK_RANKS},
+    BitBoard, Color, Move, MoveFlag, PieceKind, Position, ValidationError,
+};
+
+/// The rules a [`Position`] is played by, where they differ from standard chess.
+///
+/// Each method is a hook into move generation or the end of the game,
+/// defaulting to the standard rules.
+pub trait Variant: Debug + Send + Sync {
+    /// The variant's name in lowercase, as in a PGN `Variant` tag.
+    fn name(&self) -> &'static str;
+
+    /// The FEN of the starting position.
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    }
+
+    /// Whether captured pieces go to the capturer's hand,
+    /// to be dropped back onto the board later instead of moving.
+    fn has_drops(&self) -> bool {
+        false
+    }
+
+    /// The player who has won by the variant's own rules,
+    /// checked before checkmate and stalemate.
+    fn winner(&self, _position: &Position) -> Option<Color> {
+        None
+    }
+
+    /// The kinds of piece a pawn can promote to, best first.
+    fn promotions(&self) -> &'static [PieceKind] {
+        &PROMOTIONS
+    }
+
+    /// Whether a player has to capture when they can.
+    fn captures_are_compulsory(&self) -> bool {
+        false
+    }
+
+    /// Whether a player left without a legal move wins, rather than drawing,
+    /// when not in check.
+    fn stalemate_is_win(&self) -> bool {
+        false
+    }
+
+    /// How many checks a player has to give to win, when checks are counted.
+    fn check_limit(&self) -> Option<u8> {
+        None
+    }
+
+    /// Whether a capture blows up the pieces around it.
+    fn has_explosions(&self) -> bool {
+        false
+    }
+
+    /// Whether pawns on their side's first rank can move two squares,
+    /// which never gives the opponent an en passant capture.
+    fn double_pushes_from_first_rank(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` if the king of `color` is attacked.
+    fn in_check(&self, position: &Position, color: Color) -> bool {
+        position
+            .find_king(color)
+            .is_some_and(|king| position.board().is_attacked_by(king, color.opposite()))
+    }
+
+    /// Whether moves are legal by the variant's own rules, see [`Variant::is_legal`],
+    /// rather than by keeping the king out of check.
+    fn has_own_legality(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` if a pseudo-legal move can be played,
+    /// only asked when the variant [has its own legality](Variant::has_own_legality).
+    fn is_legal(&self, _position: &Position, _mv: Move) -> bool {
+        true
+    }
+
+    /// Returns `true` if neither player has enough material left to win.
+    fn is_insufficient_material(&self, position: &Position) -> bool {
+        position.standard_insufficient_material()
+    }
+
+    /// Checks the board could come from a game of the variant, see [`Position::validate`].
+    fn validate(&self, position: &Position) -> Result<(), ValidationError> {
+        position.board().validate()
+    }
+}
+
+/// The pieces a pawn promotes to in standard chess.
+const PROMOTIONS: [PieceKind; 4] = [
+    PieceKind::Queen,
+    PieceKind::Rook,
+    PieceKind::Bishop,
+    PieceKind::Knight,
+];
+
+/// Standard chess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Standard;
+
+impl Variant for Standard {
+    fn name(&self) -> &'static str {
+        "standard"
+    }
+}
+
+/// [Crazyhouse](https://en.wikipedia.org/wiki/Crazyhouse), where captured pieces change sides
+/// and can be dropped back onto any empty square as a move.
+///
+/// Pawns can't be dropped on the first or last rank,
+/// and a promoted piece goes back to being a pawn when it is captured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crazyhouse;
+
+impl Variant for Crazyhouse {
+    fn name(&self) -> &'static str {
+        "crazyhouse"
+    }
+
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1"
+    }
+
+    fn has_drops(&self) -> bool {
+        true
+    }
+
+    fn is_insufficient_material(&self, _position: &Position) -> bool {
+        // captured pieces come back
+        false
+    }
+}
+
+/// [King of the Hill](https://lichess.org/variant/kingOfTheHill),
+/// which is also won by bringing the king to one of the four centre squares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KingOfTheHill;
+
+impl Variant for KingOfTheHill {
+    fn name(&self) -> &'static str {
+        "kingofthehill"
+    }
+
+    fn winner(&self, position: &Position) -> Option<Color> {
+        Color::ALL.into_iter().find(|&color| {
+            position
+                .find_king(color)
+                .is_some_and(|king| BitBoard::CENTER.is_on(king))
+        })
+    }
+
+    fn is_insufficient_material(&self, _position: &Position) -> bool {
+        // a bare king can still walk to the centre
+        false
+    }
+}
+
+/// [Atomic](https://lichess.org/variant/atomic), where a capture blows up the capturing piece
+/// and every piece around it apart from pawns, and blowing up the enemy king wins.
+///
+/// Kings can't capture, and a king is never in check while next to the enemy king,
+/// since capturing it would blow up both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Atomic;
+
+impl Variant for Atomic {
+    fn name(&self) -> &'static str {
+        "atomic"
+    }
+
+    fn winner(&self, position: &Position) -> Option<Color> {
+        Color::ALL.into_iter().find(|&color| {
+            position.find_king(color).is_some() && position.find_king(color.opposite()).is_none()
+        })
+    }
+
+    fn has_explosions(&self) -> bool {
+        true
+    }
+
+    fn in_check(&self, position: &Position, color: Color) -> bool {
+        let board = position.board();
+        let (Some(king), Some(their_king)) = (
+            position.find_king(color),
+            position.find_king(color.opposite()),
+        ) else {
+            return false;
+        };
+        if king_attacks(king).is_on(their_king) {
+            return false;
+        }
+        let attackers = board.attackers_to(king, board.occupied()) - board.kings();
+        !(attackers & board.colors(color.opposite())).is_empty()
+    }
+
+    fn has_own_legality(&self) -> bool {
+        true
+    }
+
+    fn is_legal(&self, position: &Position, mv: Move) -> bool {
+        let us = position.side_to_move();
+        let board = position.board();
+        if board.kings().is_on(mv.from())
+            && board.colors(us.opposite()).is_on(mv.to())
+            && mv.dropped().is_none()
+        {
+            return false;
+        }
+
+        let mut after = position.scratch();
+        after.make_move(mv);
+        match (after.find_king(us), after.find_king(us.opposite())) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(_), Some(_)) => !self.in_check(&after, us),
+        }
+    }
+
+    fn is_insufficient_material(&self, position: &Position) -> bool {
+        Color::ALL
+            .into_iter()
+            .all(|color| atomic_cannot_win(position, color))
+    }
+
+    fn validate(&self, position: &Position) -> Result<(), ValidationError> {
+        let board = position.board();
+        board.validate_layout()?;
+
+        for color in Color::ALL {
+            let count = (board.kings() & board.colors(color)).count();
+            // the side to move's king may have just been blown up, ending the game
+            let missing = count == 0 && color == position.side_to_move();
+            if count != 1 && !missing {
+                return Err(ValidationError::KingCount { color, count });
+            }
+        }
+
+        let stranded = board.pawns() & BACK_RANKS;
+        if !stranded.is_empty() {
+            return Err(ValidationError::PawnOnBackRank(first(stranded)));
+        }
+        Ok(())
+    }
+}
+
+/// Whether `color` can never blow up the enemy king in [`Atomic`].
+fn atomic_cannot_win(position: &Position, color: Color) -> bool {
+    let board = position.board();
+    let ours = board.colors(color) - board.kings();
+    let theirs = board.colors(color.opposite()) - board.kings();
+    if ours.is_empty() {
+        // the king alone can't capture anything
+        return true;
+    }
+    // a single minor piece can't get at a bare king
+    let minors = board.knights() | board.bishops();
+    theirs.is_empty() && !ours.has_many() && !(ours & minors).is_empty()
+}
+
+/// [Horde](https://lichess.org/variant/horde), where white has no king and a horde of pawns,
+/// and black wins by capturing all of them.
+///
+/// White's pawns on the first rank can move two squares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Horde;
+
+impl Variant for Horde {
+    fn name(&self) -> &'static str {
+        "horde"
+    }
+
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"
+    }
+
+    fn winner(&self, position: &Position) -> Option<Color> {
+        position.board().whites().is_empty().then_some(Color::Black)
+    }
+
+    fn double_pushes_from_first_rank(&self) -> bool {
+        true
+    }
+
+    fn is_insufficient_material(&self, _position: &Position) -> bool {
+        false
+    }
+
+    fn validate(&self, position: &Position) -> Result<(), ValidationError> {
+        let board = position.board();
+        board.validate_layout()?;
+
+        for (color, kings) in [(Color::White, 0), (Color::Black, 1)] {
+            let count = (board.kings() & board.colors(color)).count();
+            if count != kings {
+                return Err(ValidationError::KingCount { color, count });
+            }
+        }
+
+        // white's pawns start on the first rank, but can't stay on the last
+        let stranded =
+            board.pawns() & ((board.whites() & BitBoard::RANK_8) | (board.blacks() & BACK_RANKS));
+        if !stranded.is_empty() {
+            return Err(ValidationError::PawnOnBackRank(first(stranded)));
+        }
+        Ok(())
+    }
+}
+
+/// [Antichess](https://lichess.org/variant/antichess), also called Giveaway,
+/// which is won by losing every piece or having no move to play.
+///
+/// Captures are compulsory and there is no check, so the king is an ordinary piece
+/// that can be captured. Pawns can promote to a king, and nobody can castle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Antichess;
+
+impl Variant for Antichess {
+    fn name(&self) -> &'static str {
+        "antichess"
+    }
+
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"
+    }
+
+    fn winner(&self, position: &Position) -> Option<Color> {
+        Color::ALL
+            .into_iter()
+            .find(|&color| position.board().colors(color).is_empty())
+    }
+
+    fn promotions(&self) -> &'static [PieceKind] {
+        &[
+            PieceKind::Queen,
+            PieceKind::Rook,
+            PieceKind::Bishop,
+            PieceKind::Knight,
+            PieceKind::King,
+        ]
+    }
+
+    fn captures_are_compulsory(&self) -> bool {
+        true
+    }
+
+    fn stalemate_is_win(&self) -> bool {
+        true
+    }
+
+    fn in_check(&self, _position: &Position, _color: Color) -> bool {
+        false
+    }
+
+    fn has_own_legality(&self) -> bool {
+        true
+    }
+
+    fn is_legal(&self, position: &Position, mv: Move) -> bool {
+        let capture = mv.flag() == MoveFlag::EnPassant
+            || position
+                .board()
+                .colors(position.side_to_move().opposite())
+                .is_on(mv.to());
+        mv.flag() != MoveFlag::Castle && (capture || !can_capture(position))
+    }
+
+    fn is_insufficient_material(&self, position: &Position) -> bool {
+        // bishops on squares of different colors can never capture each other
+        let board = position.board();
+        let only_bishops = board.occupied() == board.bishops();
+        let colors = Color::ALL.map(|color| {
+            let bishops = board.colors(color);
+            (
+                !(bishops & BitBoard::LIGHT_SQUARES).is_empty(),
+                !(bishops & BitBoard::DARK_SQUARES).is_empty(),
+            )
+        });
+        only_bishops
+            && matches!(
+                colors,
+                [(true, false), (false, true)] | [(false, true), (true, false)]
+            )
+    }
+
+    fn validate(&self, position: &Position) -> Result<(), ValidationError> {
+        let board = position.board();
+        board.validate_layout()?;
+        let stranded = board.pawns() & BACK_RANKS;
+        if !stranded.is_empty() {
+            return Err(ValidationError::PawnOnBackRank(first(stranded)));
+        }
+        Ok(())
+    }
+}
+
+/// Whether the side to move has any capture, which [`Antichess`] makes them play.
+fn can_capture(position: &Position) -> bool {
+    let board = position.board();
+    let us = position.side_to_move();
+    let our_pawns = board.pawns() & board.colors(us);
+    let en_passant = position
+        .en_passant()
+        .is_some_and(|ep| !(pawn_attacks(us.opposite(), ep) & our_pawns).is_empty());
+    en_passant
+        || board
+            .colors(us.opposite())
+            .into_iter()
+            .any(|square| board.is_attacked_by(square, us))
+}
+
+/// [Three-check](https://lichess.org/variant/threeCheck), which is also won by giving check
+/// three times.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThreeCheck;
+
+impl Variant for ThreeCheck {
+    fn name(&self) -> &'static str {
+        "threecheck"
+    }
+
+    fn start_fen(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1"
+    }
+
+    fn winner(&self, position: &Position) -> Option<Color> {
+        Color::ALL
+            .into_iter()
+            .find(|&color| position.checks_given(color) >= 3)
+    }
+
+    fn check_limit(&self) -> Option<u8> {
+        Some(3)
+    }
+
+    fn is_insufficient_material(&self, position: &Position) -> bool {
+        // any other piece can give check
+        let board = position.board();
+        board.occupied() == board.kings()
+    }
+}
+
+/// Every variant that can be played.
+pub const ALL: [&dyn Variant; 7] = [
+    &Standard,
+    &Crazyhouse,
+    &KingOfTheHill,
+    &Atomic,
+    &Horde,
+    &Antichess,
+    &ThreeCheck,
+];
+
+/// Finds a variant by its [`Variant::name`], ignoring case, spaces and dashes,
+/// so `King of the Hill` is found as well as `kingofthehill`.
+///
+/// `chess` is accepted for standard chess, as some GUIs send it.
+pub fn from_name(name: &str) -> Option<&'static dyn Variant> {
+    let letters = name.bytes().filter(|&b| b != b' ' && b != b'-');
+    let matches = |target: &str| {
+        let mut letters = letters.clone();
+        target
+            .bytes()
+            .all(|b| letters.next().is_some_and(|c| c.eq_ignore_ascii_case(&b)))
+            && letters.next().is_none()
+    };
+    if matches("chess") {
+        return Some(&Standard);
+    }
+    ALL.into_iter().find(|variant| matches(variant.name()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        movegen::perft, zobrist, Game, GameResult, IllegalMoveReason, Move, Piece, Square, FEN,
+    };
+
+    fn crazyhouse(fen: &str) -> Position {
+        let position = FEN::new(fen).parse_position().unwrap();
+        assert_eq!(position.variant().name(), "crazyhouse");
+        position
+    }
+
+    #[test]
+    fn crazyhouse_perft() {
+        let mut drops = crazyhouse("2k5/8/8/8/8/8/8/4K3[QRBNPqrbnp] w - - 0 1");
+        assert_eq!(perft(&mut drops, 1), 301);
+        assert_eq!(perft(&mut drops, 2), 75353);
+
+        let mut start = Position::start_variant(&Crazyhouse);
+        assert_eq!(perft(&mut start, 4), 197281);
+    }
+
+    #[test]
+    fn captures_go_to_hand() {
+        let start = "4k3/8/8/8/8/8/1pN5/R3K3[] b - - 0 1";
+        let mut position = crazyhouse(start);
+        let white_pawn = Piece::new_with(Color::White, PieceKind::Pawn);
+        let black_rook = Piece::new_with(Color::Black, PieceKind::Rook);
+
+        position.make_move(position.parse_uci("b2a1q").unwrap());
+        assert!(position.promoted().is_on(Square::A1));
+        assert_eq!(position.in_hand(black_rook), 1);
+        assert_eq!(
+            position.to_fen(),
+            FEN::new("4k3/8/8/8/8/8/2N5/q~3K3[r] w - - 0 2")
+        );
+
+        // the promoted queen goes back to being a pawn when captured
+        position.make_move(position.parse_san("Nxa1").unwrap());
+        assert_eq!(position.in_hand(white_pawn), 1);
+        assert!(position.promoted().is_empty());
+        position.make_move(position.parse_san("R@e7").unwrap());
+        assert_eq!(position.in_hand(black_rook), 0);
+        assert!(position.in_check(Color::White));
+        assert_eq!(
+            position.parse_san("@e2"),
+            Ok(Move::new_drop(PieceKind::Pawn, Square::E2))
+        );
+        assert_eq!(position.zobrist_key(), zobrist::hash(&position));
+
+        while position.unmake_move().is_some() {}
+        assert_eq!(position.to_fen(), FEN::new(start));
+        assert_eq!(position.zobrist_key(), zobrist::hash(&position));
+    }
+
+    #[test]
+    fn drops_and_hashes() {
+        fn walk(position: &mut Position, depth: u32) {
+            assert_eq!(position.zobrist_key(), zobrist::hash(position));
+            if depth == 0 {
+                return;
+            }
+            let fen = position.to_fen();
+            for &mv in &position.legal_moves() {
+                assert_eq!(position.parse_san(&position.san(mv)), Ok(mv));
+                assert_eq!(position.parse_uci(&position.uci(mv)), Ok(mv));
+                position.make_move(mv);
+                walk(position, depth - 1);
+                position.unmake_move();
+            }
+            assert_eq!(position.to_fen(), fen);
+        }
+
+        let mut position =
+            crazyhouse("r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[Pn] b KQkq - 0 7");
+        walk(&mut position, 2);
+    }
+
+    #[test]
+    fn pockets_in_fen() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[] w KQkq - 0 1",
+            "r1bqk2r/pppp1ppp/2n1p3/4P3/1b1Pn3/2NB1N2/PPP2PPP/R1BQK2R[QRPn] b KQkq - 0 7",
+            "4k3/8/8/8/8/8/8/Q~3K3[] w - - 0 1",
+        ] {
+            assert_eq!(crazyhouse(fen).to_fen(), FEN::new(fen));
+        }
+        let ninth_rank = crazyhouse("4k3/8/8/8/8/8/8/4K3/Nq w - - 0 1");
+        assert_eq!(
+            ninth_rank.to_fen(),
+            FEN::new("4k3/8/8/8/8/8/8/4K3[Nq] w - - 0 1")
+        );
+
+        let standard = FEN::new("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        assert_eq!(
+            standard
+                .clone()
+                .parse_position_as(&Crazyhouse)
+                .unwrap()
+                .to_fen(),
+            FEN::new("4k3/8/8/8/8/8/8/4K3[] w - - 0 1")
+        );
+        assert!(FEN::new("4k3/8/8/8/8/8/8/4K3[K] w - - 0 1")
+            .parse_position()
+            .is_err());
+        assert!(FEN::new("4k3/8/8/8/8/8/8/4K3[N] w - - 0 1")
+            .parse_position_as(&Standard)
+            .is_err());
+        assert_eq!(from_name("Crazyhouse").unwrap().name(), "crazyhouse");
+        assert_eq!(from_name("chess").unwrap().name(), "standard");
+        assert_eq!(
+            from_name("King of the Hill").unwrap().name(),
+            "kingofthehill"
+        );
+        assert!(from_name("atomics").is_none());
+    }
+
+    #[test]
+    fn king_of_the_hill() {
+        let fen = FEN::new("4k3/8/8/8/8/3K4/8/8 w - - 0 1");
+        let mut position = fen.parse_position_as(&KingOfTheHill).unwrap();
+        assert!(!position.is_insufficient_material());
+        position.make_move(position.parse_san("Kd4").unwrap());
+        assert_eq!(KingOfTheHill.winner(&position), Some(Color::White));
+        assert!(position.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn atomic_perft() {
+        for (fen, counts) in [
+            (
+                "rn2kb1r/1pp1p2p/p2q1pp1/3P4/2P3b1/4PN2/PP3PPP/R2QKB1R b KQkq - 0 1",
+                [40, 1238, 45237],
+            ),
+            (
+                "rn1qkb1r/p5pp/2p5/3p4/N3P3/5P2/PPP4P/R1BQK3 w Qkq - 0 1",
+                [28, 833, 23353],
+            ),
+        ] {
+            let mut position = FEN::new(fen).parse_position_as(&Atomic).unwrap();
+            for (depth, count) in (1..).zip(counts) {
+                assert_eq!(perft(&mut position, depth), count, "{fen} at depth {depth}");
+            }
+        }
+    }
+
+    #[test]
+    fn explosions() {
+        let start = "4k3/8/2p5/3pn3/8/8/3Q4/4K2R w K - 0 1";
+        let mut position = FEN::new(start).parse_position_as(&Atomic).unwrap();
+        let key = position.zobrist_key();
+
+        // the queen and knight go, but the pawn next to them survives
+        position.make_move(position.parse_san("Qxd5").unwrap());
+        assert_eq!(
+            position.to_fen(),
+            FEN::new("4k3/8/2p5/8/8/8/8/4K2R b K - 0 1")
+        );
+        assert_eq!(position.zobrist_key(), zobrist::hash(&position));
+
+        position.unmake_move();
+        assert_eq!(position.to_fen(), FEN::new(start));
+        assert_eq!(position.zobrist_key(), key);
+
+        // blowing up the king wins even out of check, but a king can't capture
+        let fen = FEN::new("8/8/8/8/8/2k5/1qn5/K1R5 w - - 0 1");
+        let mut position = fen.clone().parse_position_as(&Atomic).unwrap();
+        assert!(position.parse_uci("a1b2").is_err());
+        position.make_move(position.parse_san("Rxc2").unwrap());
+        assert_eq!(Atomic.winner(&position), Some(Color::White));
+        assert!(position.legal_moves().is_empty());
+
+        // the finished game reads back, but only the loser can be missing their king
+        let exploded = position.to_fen();
+        let parsed = exploded.clone().parse_position_as(&Atomic).unwrap();
+        assert_eq!(parsed.to_fen(), exploded);
+        assert_eq!(Atomic.winner(&parsed), Some(Color::White));
+        assert!(FEN::new("8/8/8/8/8/8/8/K1R5 w - - 0 2")
+            .parse_position_as(&Atomic)
+            .is_err());
+
+        position.unmake_move();
+        assert_eq!(position.to_fen(), fen);
+    }
+
+    #[test]
+    fn antichess() {
+        let antichess = |fen| FEN::new(fen).parse_position_as(&Antichess).unwrap();
+        let mut start = Position::start_variant(&Antichess);
+        assert_eq!(perft(&mut start, 3), 8067);
+
+        // the king has to take, as nothing is in check
+        let position = antichess("8/1p6/8/8/8/8/5k2/4K3 w - - 0 1");
+        assert_eq!(position.legal_moves().len(), 1);
+        assert_eq!(
+            position.why_illegal(Move::new(Square::E1, Square::D1, MoveFlag::Normal)),
+            Some(IllegalMoveReason::MustCapture)
+        );
+
+        // promoting to a king, and taking the last piece loses
+        let mut position = antichess("8/8/8/8/8/8/1p6/R7 b - - 0 1");
+        assert_eq!(position.legal_moves().len(), 5);
+        let mv = position.parse_san("bxa1=K").unwrap();
+        assert_eq!(position.uci(mv), "b2a1k");
+        position.make_move(mv);
+        assert_eq!(Antichess.winner(&position), Some(Color::White));
+        assert!(position.legal_moves().is_empty());
+        position.unmake_move();
+        assert_eq!(position.find_king(Color::Black), None);
+
+        // being left without a move wins
+        let stuck = antichess("8/8/8/8/8/p7/P7/8 w - - 0 1");
+        assert_eq!(
+            Game::new(stuck).result(),
+            Some(GameResult::VariantWin {
+                winner: Color::White
+            })
+        );
+    }
+
+    #[test]
+    fn three_check() {
+        let start = Position::start_variant(&ThreeCheck);
+        assert_eq!(start.to_fen(), FEN::new(ThreeCheck.start_fen()));
+
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w Q - 0 1 +2+0";
+        let mut position = FEN::new(fen).parse_position().unwrap();
+        assert_eq!(position.variant().name(), "threecheck");
+        assert_eq!(position.checks_given(Color::White), 2);
+        assert_eq!(position.zobrist_key(), zobrist::hash(&position));
+
+        position.make_move(position.parse_san("Ra8+").unwrap());
+        assert_eq!(position.checks_given(Color::White), 3);
+        assert_eq!(ThreeCheck.winner(&position), Some(Color::White));
+        assert!(position.legal_moves().is_empty());
+        assert_eq!(
+            position.to_fen(),
+            FEN::new("R3k3/8/8/8/8/8/8/4K3 b - - 0+3 1 1")
+        );
+        assert_eq!(position.zobrist_key(), zobrist::hash(&position));
+
+        position.unmake_move();
+        assert_eq!(position.checks_given(Color::White), 2);
+        assert_eq!(
+            position.to_fen(),
+            FEN::new("4k3/8/8/8/8/8/8/R3K3 w Q - 1+3 0 1")
+        );
+        assert!(FEN::new("4k3/8/8/8/8/8/8/R3K3 w Q - 4+3 0 1")
+            .parse_position()
+            .is_err());
+        assert!(FEN::new(fen).parse_position_as(&Standard).is_err());
+    }
+
+    #[test]
+    fn horde_perft() {
+        let mut position = Position::start_variant(&Horde);
+        assert_eq!(position.validate(), Ok(()));
+        for (depth, count) in [(1, 8), (2, 128), (3, 1274), (4, 23310)] {
+            assert_eq!(perft(&mut position, depth), count);
+        }
+
+        // black wins by taking the last white piece
+        let fen = FEN::new("4k3/8/8/8/8/8/4p3/3P4 b - - 0 1");
+        let mut position = fen.parse_position_as(&Horde).unwrap();
+        position.make_move(position.parse_uci("e2d1q").unwrap());
+        assert_eq!(Horde.winner(&position), Some(Color::Black));
+    }
+}
@@ -0,0 +1,193 @@
+//! [Zobrist hashing](https://www.chessprogramming.org/Zobrist_Hashing) of positions.
+//!
+//! The keys are generated at compile time from a fixed seed,
+//! so hashes are the same across runs and platforms.
+
+use crate::{attacks, Board, CastlingRights, Color, Piece, PieceKind, Position, Square};
+
+struct Keys {
+    pieces: [[[u64; 64]; PieceKind::COUNT]; Color::COUNT],
+    castling: [u64; 16],
+    en_passant: [u64; 8],
+    side: u64,
+    /// Indexed by how many of the piece are in hand, with none having no key.
+    hands: [[[u64; HAND_KEYS]; PieceKind::COUNT]; Color::COUNT],
+    /// Indexed by how many checks have been given, with none having no key.
+    checks: [[u64; CHECK_KEYS]; Color::COUNT],
+}
+
+/// More than the 30 pieces besides the kings that could ever be in one hand.
+const HAND_KEYS: usize = 32;
+
+/// Enough for the checks needed to win Three-check.
+const CHECK_KEYS: usize = 4;
+
+/// [SplitMix64](https://prng.di.unimi.it/splitmix64.c), usable in const contexts.
+const fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+const fn generate() -> Keys {
+    let mut state = 0x4348_4553_5354_4552;
+    let mut keys = Keys {
+        pieces: [[[0; 64]; PieceKind::COUNT]; Color::COUNT],
+        castling: [0; 16],
+        en_passant: [0; 8],
+        side: 0,
+        hands: [[[0; HAND_KEYS]; PieceKind::COUNT]; Color::COUNT],
+        checks: [[0; CHECK_KEYS]; Color::COUNT],
+    };
+
+    let mut color = 0;
+    while color < Color::COUNT {
+        let mut kind = 0;
+        while kind < PieceKind::COUNT {
+            let mut square = 0;
+            while square < 64 {
+                keys.pieces[color][kind][square] = split_mix(&mut state);
+                square += 1;
+            }
+            kind += 1;
+        }
+        color += 1;
+    }
+
+    let mut i = 0;
+    while i < 16 {
+        keys.castling[i] = split_mix(&mut state);
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < 8 {
+        keys.en_passant[i] = split_mix(&mut state);
+        i += 1;
+    }
+
+    keys.side = split_mix(&mut state);
+
+    let mut color = 0;
+    while color < Color::COUNT {
+        let mut kind = 0;
+        while kind < PieceKind::COUNT {
+            let mut count = 1;
+            while count < HAND_KEYS {
+                keys.hands[color][kind][count] = split_mix(&mut state);
+                count += 1;
+            }
+            kind += 1;
+        }
+        color += 1;
+    }
+
+    let mut color = 0;
+    while color < Color::COUNT {
+        let mut count = 1;
+        while count < CHECK_KEYS {
+            keys.checks[color][count] = split_mix(&mut state);
+            count += 1;
+        }
+        color += 1;
+    }
+    keys
+}
+
+static KEYS: Keys = generate();
+
+/// The key for a [`Piece`] on a [`Square`].
+#[inline]
+pub fn piece(piece: Piece, square: Square) -> u64 {
+    KEYS.pieces[piece.color() as usize][piece.kind() as usize][square.index()]
+}
+
+/// The key for a set of [`CastlingRights`].
+#[inline]
+pub fn castling(rights: CastlingRights) -> u64 {
+    KEYS.castling[rights.into_bits() as usize & 0b1111]
+}
+
+/// The key for an en passant square, which only depends on its file.
+#[inline]
+pub fn en_passant(square: Square) -> u64 {
+    KEYS.en_passant[square.file().index()]
+}
+
+/// The key for the en passant `square` if a pawn of `capturer` could take there, or 0.
+///
+/// A square nothing can capture on makes no difference to the moves that can be played,
+/// so leaving it out lets the position match the same one reached another way.
+/// Pins aren't considered.
+#[inline]
+pub(crate) fn capturable_en_passant(board: &Board, square: Option<Square>, capturer: Color) -> u64 {
+    match square {
+        Some(square)
+            if !(attacks::pawn_attacks(capturer.opposite(), square)
+                & board.pawns()
+                & board.colors(capturer))
+            .is_empty() =>
+        {
+            en_passant(square)
+        }
+        _ => 0,
+    }
+}
+
+/// The key for holding `count` of `piece` in hand, 0 for none.
+#[inline]
+pub fn hand(piece: Piece, count: u8) -> u64 {
+    KEYS.hands[piece.color() as usize][piece.kind() as usize][count as usize % HAND_KEYS]
+}
+
+/// The key for `color` having given `count` checks, 0 for none.
+#[inline]
+pub fn checks(color: Color, count: u8) -> u64 {
+    KEYS.checks[color as usize][(count as usize).min(CHECK_KEYS - 1)]
+}
+
+/// The key toggled when it is black to move.
+#[inline]
+pub fn side() -> u64 {
+    KEYS.side
+}
+
+/// Computes the key of a [`Position`] from scratch.
+///
+/// The en passant square is only included when it can be captured on.
+pub fn hash(position: &Position) -> u64 {
+    let board = position.board();
+
+    let mut key = board
+        .occupied()
+        .into_iter()
+        .map(|square| piece(board.piece_on(square).unwrap(), square))
+        .fold(0, |key, piece| key ^ piece);
+
+    key ^= castling(position.castling());
+    key ^= capturable_en_passant(board, position.en_passant(), position.side_to_move());
+    if position.side_to_move() == Color::Black {
+        key ^= side();
+    }
+    for color in Color::ALL {
+        for kind in PieceKind::ALL {
+            let piece = Piece::new_with(color, kind);
+            key ^= hand(piece, position.in_hand(piece) as u8);
+        }
+        key ^= checks(color, position.checks_given(color) as u8);
+    }
+
+    key
+}
+
+/// Computes the key of just the pawns on a [`Board`] from scratch,
+/// see [`Position::pawn_key`].
+pub fn pawn_hash(board: &Board) -> u64 {
+    board
+        .pawns()
+        .into_iter()
+        .map(|square| piece(board.piece_on(square).unwrap(), square))
+        .fold(0, |key, piece| key ^ piece)
+}
@@ -0,0 +1,27 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use board::FEN;
+use engine::{bench, evaluate, EvalParams};
+
+fn evaluation(c: &mut Criterion) {
+    let params = EvalParams::default();
+    let positions = engine::BENCH_POSITIONS.map(|fen| FEN::new(fen).parse_position().unwrap());
+    c.bench_function("eval/bench_positions", |b| {
+        b.iter(|| {
+            positions
+                .iter()
+                .map(|position| params.evaluate(black_box(position)))
+                .sum::<i32>()
+        })
+    });
+}
+
+fn search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search");
+    group.sample_size(10);
+    group.bench_function("bench_depth_5", |b| b.iter(|| bench(&evaluate, 5)));
+    group.finish();
+}
+
+criterion_group!(benches, evaluation, search);
+criterion_main!(benches);
@@ -0,0 +1,172 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread::JoinHandle,
+};
+
+use board::Position;
+
+use crate::{search, Chesster, SearchLimits, SearchResult};
+
+/// A search running in the background until stopped, see [`Chesster::analyze`].
+///
+/// Iterating yields a [`SearchResult`] each time the search completes another depth,
+/// waiting for it if necessary, and ends once the search has stopped.
+/// Dropping the analysis stops the search.
+pub struct Analysis {
+    stop: Arc<AtomicBool>,
+    results: Receiver<SearchResult>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Analysis {
+    /// Asks the search to stop, after which the iterator ends
+    /// once the results already found have been taken.
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = &self.thread {
+            thread.thread().unpark();
+        }
+    }
+
+    /// A flag that stops the search when set, for stopping it from another thread
+    /// while this one waits on the results.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// The latest result that has arrived, without waiting for the next.
+    ///
+    /// Earlier results that haven't been taken yet are skipped.
+    pub fn latest(&mut self) -> Option<SearchResult> {
+        self.results.try_iter().last()
+    }
+}
+
+impl Iterator for Analysis {
+    type Item = SearchResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.results.recv().ok()
+    }
+}
+
+impl Drop for Analysis {
+    fn drop(&mut self) {
+        self.stop();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Chesster {
+    /// Searches `position` on another thread until stopped, reporting each depth as it's
+    /// completed. This is for analysing a position, rather than choosing a move to play.
+    ///
+    /// The search uses the engine's current options and shares its transposition table.
+    pub fn analyze(&self, position: &Position) -> Analysis {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, results) = mpsc::channel();
+
+        let position = position.clone();
+        let eval = Arc::clone(&self.eval);
+        let tt = Arc::clone(&self.tt);
+        let (options, threads) = (self.options, self.threads);
+        let limits = SearchLimits {
+            multi_pv: self.multi_pv,
+            ..SearchLimits::default()
+        };
+        let flag = Arc::clone(&stop);
+
+        let thread = std::thread::spawn(move || {
+            search(
+                &position,
+                &*eval,
+                &limits,
+                options,
+                &tt,
+                threads,
+                &flag,
+                |result| {
+                    // nobody is listening any more, so there's no point carrying on
+                    if sender.send(result.clone()).is_err() {
+                        flag.store(true, Ordering::Relaxed);
+                    }
+                },
+            );
+            // the search can run out of depth first, but the results only end when stopped
+            while !flag.load(Ordering::Relaxed) {
+                std::thread::park();
+            }
+        });
+
+        Analysis {
+            stop,
+            results,
+            thread: Some(thread),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::FEN;
+
+    #[test]
+    fn deepens_until_stopped() {
+        let engine = Chesster::new();
+        let mut analysis = engine.analyze(&Position::start());
+
+        let depths = analysis
+            .by_ref()
+            .take(4)
+            .map(|result| result.depth)
+            .collect::<Vec<_>>();
+        assert_eq!(depths, [1, 2, 3, 4]);
+
+        analysis.stop();
+        // anything still to come is from a deeper search, then the iterator ends
+        assert!(analysis.all(|result| result.depth > 4));
+    }
+
+    #[test]
+    fn finds_mate() {
+        let engine = Chesster::new();
+        let position = FEN::new("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1")
+            .parse_position()
+            .unwrap();
+        let result = engine
+            .analyze(&position)
+            .find(|result| {
+                result
+                    .best_line()
+                    .is_some_and(|line| line.mate_in() == Some(1))
+            })
+            .unwrap();
+        assert_eq!(position.uci(result.best_move().unwrap()), "a1a8");
+    }
+
+    #[test]
+    fn deepens_past_a_mate_until_stopped() {
+        let engine = Chesster::new();
+        let position = FEN::new("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1")
+            .parse_position()
+            .unwrap();
+        let mut analysis = engine.analyze(&position);
+
+        let depths = analysis
+            .by_ref()
+            .take(5)
+            .map(|result| result.depth)
+            .collect::<Vec<_>>();
+        assert_eq!(depths, [1, 2, 3, 4, 5]);
+
+        analysis.stop();
+        assert!(analysis.all(|result| result.depth > 5));
+    }
+}
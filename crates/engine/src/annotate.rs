@@ -0,0 +1,218 @@
+//! Marking the mistakes in a game, by how much worse each move was than the best one.
+
+use std::{fmt, sync::atomic::AtomicBool};
+
+use board::{
+    pgn::{self, PgnError},
+    Move, Position,
+};
+
+use crate::{search, Evaluator, Score, SearchLimits, SearchOptions, TranspositionTable};
+
+/// Scores are capped at this many centipawns, so missing a mate counts as a large
+/// loss without dwarfing every other move.
+const SCORE_CAP: i32 = 1000;
+
+/// How bad a move was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Judgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    /// The [Numeric Annotation Glyph](https://en.wikipedia.org/wiki/Numeric_Annotation_Glyphs)
+    /// for the judgement, written `?!`, `?` and `??`.
+    pub fn nag(self) -> u8 {
+        match self {
+            Self::Inaccuracy => 6,
+            Self::Mistake => 2,
+            Self::Blunder => 4,
+        }
+    }
+}
+
+impl fmt::Display for Judgement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Inaccuracy => "Inaccuracy",
+            Self::Mistake => "Mistake",
+            Self::Blunder => "Blunder",
+        })
+    }
+}
+
+/// The least centipawn loss for each [`Judgement`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    pub inaccuracy: i32,
+    pub mistake: i32,
+    pub blunder: i32,
+}
+
+impl Thresholds {
+    /// The judgement for a move losing `loss` centipawns, None for a good enough move.
+    pub fn judge(&self, loss: i32) -> Option<Judgement> {
+        if loss >= self.blunder {
+            Some(Judgement::Blunder)
+        } else if loss >= self.mistake {
+            Some(Judgement::Mistake)
+        } else if loss >= self.inaccuracy {
+            Some(Judgement::Inaccuracy)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            inaccuracy: 50,
+            mistake: 100,
+            blunder: 300,
+        }
+    }
+}
+
+/// What the engine thought of one move of a game.
+///
+/// Scores are in centipawns for the player making the move, capped for mates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    /// The move the engine would have played.
+    pub best: Move,
+    /// The score before the move, assuming the best move is played.
+    pub best_score: i32,
+    /// The score after the move that was played.
+    pub played_score: i32,
+    /// How much the move lost compared to the best move, never negative.
+    pub loss: i32,
+    pub judgement: Option<Judgement>,
+}
+
+/// Searches every position of the main line of `game` to `depth`, judging each move.
+///
+/// Judged moves get the matching NAG, unless they already have one,
+/// and a comment giving the loss and the best move.
+/// Returns an annotation for each move of the main line.
+pub fn annotate<E: Evaluator + Sync + ?Sized>(
+    game: &mut pgn::Game,
+    eval: &E,
+    depth: u32,
+    thresholds: &Thresholds,
+) -> Result<Vec<Annotation>, PgnError> {
+    let tt = TranspositionTable::new(16);
+    let stop = AtomicBool::new(false);
+    let limits = SearchLimits {
+        depth: Some(depth.max(1)),
+        multi_pv: 1,
+        ..SearchLimits::default()
+    };
+    // the best move and score for the player to move
+    let analyse = |position: &Position| {
+        let result = search(
+            position,
+            eval,
+            &limits,
+            SearchOptions::default(),
+            &tt,
+            1,
+            &stop,
+            |_| {},
+        );
+        match result.best_line() {
+            Some(line) => (line.pv.first().copied(), cap(line.score)),
+            // no legal moves, so checkmate or stalemate
+            None if position.in_check(position.side_to_move()) => (None, -SCORE_CAP),
+            None => (None, 0),
+        }
+    };
+
+    let mut position = game.start_position()?;
+    let mut before = analyse(&position);
+    let mut annotations = Vec::with_capacity(game.moves.len());
+
+    for node in &mut game.moves {
+        let (Some(best), best_score) = before else {
+            break;
+        };
+        let best_san = position.san(best);
+        position.make_move(node.mv);
+        let after = analyse(&position);
+
+        let played_score = -after.1;
+        let loss = if node.mv == best {
+            0
+        } else {
+            (best_score - played_score).max(0)
+        };
+        let judgement = thresholds.judge(loss);
+
+        if let Some(judgement) = judgement {
+            if node.nags.is_empty() {
+                node.nags.push(judgement.nag());
+            }
+            node.comments.push(format!(
+                "{judgement}, losing {loss} centipawns. {best_san} was best."
+            ));
+        }
+
+        annotations.push(Annotation {
+            best,
+            best_score,
+            played_score,
+            loss,
+            judgement,
+        });
+        before = after;
+    }
+
+    Ok(annotations)
+}
+
+/// Caps a score to [`SCORE_CAP`], which mates always reach.
+fn cap(score: Score) -> i32 {
+    if score.is_mate() {
+        SCORE_CAP * score.raw().signum()
+    } else {
+        score.raw().clamp(-SCORE_CAP, SCORE_CAP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EvalParams;
+
+    #[test]
+    fn scholars_mate() {
+        let mut game = pgn::Game::parse("1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0").unwrap();
+        let annotations =
+            annotate(&mut game, &EvalParams::default(), 3, &Thresholds::default()).unwrap();
+        assert_eq!(annotations.len(), 7);
+
+        // Nf6 allows mate, which the best move doesn't
+        let nf6 = &annotations[5];
+        assert_eq!(nf6.judgement, Some(Judgement::Blunder));
+        assert_eq!(nf6.played_score, -SCORE_CAP);
+        assert_eq!(game.moves[5].nags, [4]);
+        assert!(game.moves[5].comments[0].starts_with("Blunder, losing"));
+
+        // and the mate itself is the best move
+        let mate = &annotations[6];
+        assert_eq!(mate.best, game.moves[6].mv);
+        assert_eq!((mate.loss, mate.judgement), (0, None));
+        assert!(game.to_string().contains("Nf6 $4"));
+    }
+
+    #[test]
+    fn thresholds() {
+        let thresholds = Thresholds::default();
+        assert_eq!(thresholds.judge(49), None);
+        assert_eq!(thresholds.judge(50), Some(Judgement::Inaccuracy));
+        assert_eq!(thresholds.judge(299), Some(Judgement::Mistake));
+        assert_eq!(thresholds.judge(1000), Some(Judgement::Blunder));
+    }
+}
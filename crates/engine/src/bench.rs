@@ -0,0 +1,91 @@
+use std::time::{Duration, Instant};
+
+use board::{Position, FEN};
+
+use crate::{search_deterministic, Evaluator, SearchLimits, SearchOptions, SearchStats};
+
+/// The depth searched by `chesster bench` when none is given.
+pub const BENCH_DEPTH: u32 = 7;
+
+/// The positions searched by [`bench`], taken from Stockfish's benchmark.
+pub const BENCH_POSITIONS: [&str; 12] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 10",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 11",
+    "4rrk1/pp1n3p/3q2pQ/2p1pb2/2PP4/2P3N1/P2B2PP/4RRK1 b - - 7 19",
+    "rq3rk1/ppp2ppp/1bnpb3/3N2B1/3NP3/7P/PPPQ1PP1/2KR3R w - - 7 14",
+    "r1bq1r1k/1pp1n1pp/1p1p4/4p2Q/4Pp2/1BNP4/PPP2PPP/3R1RK1 w - - 2 14",
+    "r3r1k1/2p2ppp/p1p1bn2/8/1q2P3/2NPQN2/PPP3PP/R4RK1 b - - 2 15",
+    "r1bbk1nr/pp3p1p/2n5/1N4p1/2Np1B2/8/PPP2PPP/2KR1B1R w kq - 0 13",
+    "r1bq1rk1/ppp1nppp/4n3/3p3Q/3P4/1BP1B3/PP1N2PP/R4RK1 w - - 1 16",
+    "4r1k1/r1q2ppp/ppp2n2/4P3/5Rb1/1N1BQ3/PPP3PP/R5K1 w - - 1 17",
+    "6k1/6p1/6Pp/ppp5/3pn2P/1P3K2/1PP2P2/8 b - - 0 1",
+    "8/8/8/8/5kp1/P7/8/1K1N4 w - - 0 80",
+];
+
+/// The totals from running [`bench`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchResult {
+    pub nodes: u64,
+    pub time: Duration,
+    /// The statistics of every search added together.
+    pub stats: SearchStats,
+}
+
+impl BenchResult {
+    /// Nodes searched per second.
+    pub fn nps(&self) -> u64 {
+        self.nodes * 1000 / (self.time.as_millis() as u64).max(1)
+    }
+}
+
+/// Searches each of the [`BENCH_POSITIONS`] to `depth` with [`search_deterministic`],
+/// so the node count only changes when the search itself does.
+///
+/// The node count can be used as a signature to check that a change is purely a speed up,
+/// as well as for measuring speed.
+pub fn bench<E: Evaluator + Sync + ?Sized>(eval: &E, depth: u32) -> BenchResult {
+    let limits = SearchLimits {
+        depth: Some(depth),
+        multi_pv: 1,
+        ..SearchLimits::default()
+    };
+
+    let start = Instant::now();
+    let mut nodes = 0;
+    let mut stats = SearchStats::default();
+    for fen in BENCH_POSITIONS {
+        let position: Position = FEN::new(fen)
+            .parse_position()
+            .expect("valid bench position");
+        let result = search_deterministic(&position, eval, &limits, SearchOptions::default());
+        nodes += result.nodes;
+        stats.merge(&result.stats);
+    }
+
+    BenchResult {
+        nodes,
+        time: start.elapsed(),
+        stats,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluate;
+
+    #[test]
+    fn deterministic_node_count() {
+        let first = bench(&evaluate, 3);
+        let second = bench(&evaluate, 3);
+        assert!(first.nodes > 0);
+        assert_eq!(first.nodes, second.nodes);
+        assert_eq!(first.stats, second.stats);
+
+        let stats = &first.stats;
+        assert_eq!(stats.depth_nodes[1..].iter().sum::<u64>(), first.nodes);
+        assert!(stats.tt_hits <= stats.tt_probes);
+        assert!(stats.cutoffs.iter().sum::<u64>() > 0);
+    }
+}
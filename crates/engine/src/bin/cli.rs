@@ -0,0 +1,311 @@
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    process::ExitCode,
+    sync::atomic::AtomicBool,
+    time::Duration,
+};
+
+use board::{pgn, Color, Draw, Game, GameResult, Move, Position, FEN};
+use engine::{search, EvalParams, Line, SearchLimits, SearchOptions, TranspositionTable};
+
+const USAGE: &str = "usage: chesster-cli [--color white|black|both] [--movetime MS] [--fen FEN]
+                    [--pgn FILE] [--hash MB] [--params FILE]";
+
+const HELP: &str = "moves can be written in SAN (Nf3) or UCI (g1f3)
+  undo   take back your last move
+  hint   ask the engine for a move
+  flip   turn the board around
+  quit   save the game and leave";
+
+/// Where the game is saved when `--pgn` isn't given.
+const DEFAULT_PGN: &str = "game.pgn";
+
+struct Args {
+    /// The side the engine plays, None when the player makes every move.
+    engine: Option<Color>,
+    move_time: Duration,
+    fen: Option<String>,
+    pgn: String,
+    hash_mb: usize,
+    params: Option<String>,
+}
+
+fn parse_args() -> Option<Args> {
+    let mut args = std::env::args().skip(1);
+    let mut parsed = Args {
+        engine: Some(Color::Black),
+        move_time: Duration::from_secs(1),
+        fen: None,
+        pgn: DEFAULT_PGN.to_owned(),
+        hash_mb: 64,
+        params: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--color" => {
+                parsed.engine = match args.next()?.as_str() {
+                    "white" => Some(Color::Black),
+                    "black" => Some(Color::White),
+                    "both" => None,
+                    _ => return None,
+                }
+            }
+            "--movetime" => parsed.move_time = Duration::from_millis(args.next()?.parse().ok()?),
+            "--fen" => parsed.fen = Some(args.next()?),
+            "--pgn" => parsed.pgn = args.next()?,
+            "--hash" => parsed.hash_mb = args.next()?.parse().ok().filter(|&mb| mb > 0)?,
+            "--params" => parsed.params = Some(args.next()?),
+            _ => return None,
+        }
+    }
+    Some(parsed)
+}
+
+/// A game between the player and the engine.
+struct Session {
+    args: Args,
+    params: EvalParams,
+    tt: TranspositionTable,
+    start: Position,
+    game: Game,
+    /// The side drawn at the bottom of the board.
+    perspective: Color,
+    /// Shown under the board until the next command.
+    message: String,
+}
+
+impl Session {
+    /// Searches the current position for as long as the player allows the engine.
+    fn think(&self) -> Option<Line> {
+        let limits = SearchLimits::new().move_time(self.args.move_time);
+        let position = self.game.position();
+        let stop = AtomicBool::new(false);
+        let result = search(
+            position,
+            &self.params,
+            &limits,
+            SearchOptions::default(),
+            &self.tt,
+            1,
+            &stop,
+            |_| {},
+        );
+        result.best_line().cloned()
+    }
+
+    /// The game's result, once it is over without needing a claim.
+    fn result(&self) -> Option<GameResult> {
+        self.game.result().filter(|result| result.is_automatic())
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let position = self.game.position();
+        let last_move = self
+            .game
+            .moves()
+            .last()
+            .map(|mv| [mv.from(), mv.to()].into_iter().collect());
+
+        // clear the screen and start again at the top
+        write!(out, "\x1b[2J\x1b[H")?;
+        let display = position
+            .board()
+            .display()
+            .unicode(true)
+            .perspective(self.perspective);
+        match last_move {
+            Some(squares) => writeln!(out, "{}", display.highlight(squares))?,
+            None => writeln!(out, "{display}")?,
+        }
+
+        let mut replay = self.start.clone();
+        let mut moves = String::new();
+        for (ply, &mv) in self.game.moves().iter().enumerate() {
+            if replay.side_to_move() == Color::White || ply == 0 {
+                let dots = if replay.side_to_move() == Color::White {
+                    "."
+                } else {
+                    "..."
+                };
+                moves.push_str(&format!("{}{dots} ", replay.fullmove_number()));
+            }
+            moves.push_str(&replay.san(mv));
+            moves.push(' ');
+            replay.make_move(mv);
+        }
+        writeln!(out, "{}", moves.trim_end())?;
+
+        match self.result() {
+            Some(result) => writeln!(out, "{}", describe(result))?,
+            None if position.in_check(position.side_to_move()) => {
+                writeln!(out, "{:?} to move, in check", position.side_to_move())?
+            }
+            None => writeln!(out, "{:?} to move", position.side_to_move())?,
+        }
+        if !self.message.is_empty() {
+            writeln!(out, "{}", self.message)?;
+        }
+        write!(out, "> ")?;
+        out.flush()
+    }
+
+    /// Runs one command, returning false to quit.
+    fn command(&mut self, input: &str) -> bool {
+        self.message.clear();
+        match input {
+            "" => {}
+            "quit" | "exit" | "q" => return false,
+            "help" | "?" => self.message = HELP.to_owned(),
+            "flip" => self.perspective = self.perspective.opposite(),
+            "undo" | "u" => {
+                // take back the engine's reply too, so it is the player's move again
+                let undone = self.game.undo().is_some();
+                if self.args.engine == Some(self.game.position().side_to_move()) {
+                    self.game.undo();
+                }
+                if !undone {
+                    self.message = "no moves to take back".to_owned();
+                }
+            }
+            "hint" | "h" => {
+                self.message = match self.think() {
+                    Some(line) => format!(
+                        "hint: {} ({}), expecting {}",
+                        self.game.position().san(line.pv[0]),
+                        pgn::Eval::from(line.score),
+                        line.san(self.game.position())
+                    ),
+                    None => "no legal moves".to_owned(),
+                }
+            }
+            _ if self.result().is_some() => self.message = "the game is over".to_owned(),
+            text => match parse_move(self.game.position(), text) {
+                Some(mv) => self.game.play(mv),
+                None => self.message = format!("'{text}' isn't a legal move, try 'help'"),
+            },
+        }
+        true
+    }
+
+    /// Lets the engine move, if it's its turn.
+    fn reply(&mut self) {
+        if self.result().is_some() || self.args.engine != Some(self.game.position().side_to_move())
+        {
+            return;
+        }
+        if let Some(line) = self.think() {
+            self.game.play(line.pv[0]);
+        }
+    }
+
+    /// The game so far as a PGN.
+    fn pgn(&self) -> pgn::Game {
+        let mut game = pgn::Game::new();
+        let name = |color| {
+            if self.args.engine == Some(color) {
+                "chesster"
+            } else {
+                "player"
+            }
+        };
+        game.set_tag("Event", "chesster-cli");
+        game.set_tag("White", name(Color::White));
+        game.set_tag("Black", name(Color::Black));
+        if let Some(fen) = &self.args.fen {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", fen.as_str());
+        }
+        game.outcome = self
+            .result()
+            .map_or(pgn::Outcome::Unknown, GameResult::outcome);
+        game.set_tag("Result", game.outcome.as_str());
+        game.moves = self
+            .game
+            .moves()
+            .iter()
+            .map(|&mv| pgn::Node::new(mv))
+            .collect();
+        game
+    }
+}
+
+/// Reads a move in SAN, or failing that UCI.
+fn parse_move(position: &Position, text: &str) -> Option<Move> {
+    position
+        .parse_san(text)
+        .or_else(|_| position.parse_uci(text))
+        .ok()
+}
+
+fn describe(result: GameResult) -> String {
+    match result {
+        GameResult::Checkmate { winner } => format!("checkmate, {winner:?} wins"),
+        GameResult::VariantWin { winner } => format!("{winner:?} wins"),
+        GameResult::Draw(Draw::Stalemate) => "draw by stalemate".to_owned(),
+        GameResult::Draw(Draw::InsufficientMaterial) => "draw by insufficient material".to_owned(),
+        GameResult::Draw(_) => "draw by repetition or the move rules".to_owned(),
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let params = match &args.params {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|e| format!("{path}: {e}"))?
+            .parse::<EvalParams>()
+            .map_err(|e| format!("{path}: {e}"))?,
+        None => EvalParams::default(),
+    };
+    let start = match &args.fen {
+        Some(fen) => FEN::try_from(fen.as_str())
+            .and_then(FEN::parse_position)
+            .map_err(|e| format!("{fen}: {e}"))?,
+        None => Position::start(),
+    };
+
+    let mut session = Session {
+        tt: TranspositionTable::new(args.hash_mb),
+        perspective: args.engine.map_or(Color::White, Color::opposite),
+        params,
+        game: Game::new(start.clone()),
+        start,
+        message: "type 'help' for the commands".to_owned(),
+        args,
+    };
+
+    let mut stdout = io::stdout();
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        session.reply();
+        session.draw(&mut stdout).map_err(|e| e.to_string())?;
+        let Some(line) = lines.next() else {
+            writeln!(stdout).map_err(|e| e.to_string())?;
+            break;
+        };
+        let line = line.map_err(|e| e.to_string())?;
+        if !session.command(line.trim()) {
+            break;
+        }
+    }
+
+    if !session.game.moves().is_empty() {
+        let path = &session.args.pgn;
+        fs::write(path, session.pgn().to_string()).map_err(|e| format!("{path}: {e}"))?;
+        println!("saved the game to {path}");
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let Some(args) = parse_args() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use board::{openings::Book, pgn::Outcome};
+use engine::{Adjudication, EvalParams, SelfPlay, TimeLimits};
+
+const USAGE: &str = "usage: selfplay [--games N] [--concurrency N] [--seed N] [--out FILE]
+                [--nodes N] [--depth N] [--movetime MS] [--tc TIMECONTROL] [--hash MB]
+                [--book FILE] [--book-plies N] [--random-plies N] [--params FILE]
+                [--resign SCORE MOVES] [--draw SCORE MOVES AFTER] [--max-moves N]
+                [--no-adjudication]";
+
+struct Args {
+    games: usize,
+    concurrency: usize,
+    seed: u64,
+    out: Option<String>,
+    book: Option<String>,
+    params: Option<String>,
+    settings: SelfPlay,
+}
+
+fn parse_args() -> Option<Args> {
+    let mut args = std::env::args().skip(1);
+    let mut parsed = Args {
+        games: 1,
+        concurrency: 1,
+        seed: 0,
+        out: None,
+        book: None,
+        params: None,
+        settings: SelfPlay::default(),
+    };
+    let settings = &mut parsed.settings;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--games" => parsed.games = args.next()?.parse().ok()?,
+            "--concurrency" => parsed.concurrency = args.next()?.parse().ok().filter(|&n| n > 0)?,
+            "--seed" => parsed.seed = args.next()?.parse().ok()?,
+            "--out" => parsed.out = Some(args.next()?),
+            "--nodes" => settings.limits.nodes = Some(args.next()?.parse().ok()?),
+            "--depth" => settings.limits.depth = Some(args.next()?.parse().ok()?),
+            "--movetime" => {
+                let time = Duration::from_millis(args.next()?.parse().ok()?);
+                settings.limits.time = Some(TimeLimits::fixed(time));
+            }
+            "--tc" => {
+                settings.time_control = args.next()?.parse().ok()?;
+                settings.limits.nodes = None;
+            }
+            "--hash" => settings.hash_mb = args.next()?.parse().ok().filter(|&mb| mb > 0)?,
+            "--book" => parsed.book = Some(args.next()?),
+            "--book-plies" => settings.book_plies = args.next()?.parse().ok()?,
+            "--random-plies" => settings.random_plies = args.next()?.parse().ok()?,
+            "--params" => parsed.params = Some(args.next()?),
+            "--resign" => {
+                settings.adjudication.resign_score = Some(args.next()?.parse().ok()?);
+                settings.adjudication.resign_moves = args.next()?.parse().ok()?;
+            }
+            "--draw" => {
+                settings.adjudication.draw_score = Some(args.next()?.parse().ok()?);
+                settings.adjudication.draw_moves = args.next()?.parse().ok()?;
+                settings.adjudication.draw_after = args.next()?.parse().ok()?;
+            }
+            "--max-moves" => settings.adjudication.max_moves = Some(args.next()?.parse().ok()?),
+            "--no-adjudication" => settings.adjudication = Adjudication::none(),
+            _ => return None,
+        }
+    }
+    Some(parsed)
+}
+
+fn run(mut args: Args) -> Result<(), String> {
+    let params = match &args.params {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| format!("{path}: {e}"))?
+            .parse::<EvalParams>()
+            .map_err(|e| format!("{path}: {e}"))?,
+        None => EvalParams::default(),
+    };
+    if let Some(path) = &args.book {
+        args.settings.book = Some(Book::open(path).map_err(|e| format!("{path}: {e}"))?);
+    }
+
+    let out: Box<dyn Write + Send> = match &args.out {
+        Some(path) => Box::new(File::create(path).map_err(|e| format!("{path}: {e}"))?),
+        None => Box::new(io::stdout()),
+    };
+    let out = Mutex::new(out);
+    // wins for White, wins for Black and draws
+    let score = Mutex::new([0; 3]);
+    let next = AtomicUsize::new(0);
+
+    let error = std::thread::scope(|scope| {
+        let workers = (0..args.concurrency)
+            .map(|_| {
+                scope.spawn(|| -> io::Result<()> {
+                    loop {
+                        let round = next.fetch_add(1, Ordering::Relaxed);
+                        if round >= args.games {
+                            return Ok(());
+                        }
+
+                        let mut game = args.settings.play(&params, args.seed + round as u64);
+                        game.set_tag("Event", "chesster self-play");
+                        game.set_tag("Round", (round + 1).to_string());
+                        game.set_tag("White", "chesster");
+                        game.set_tag("Black", "chesster");
+
+                        let mut score = score.lock().unwrap();
+                        match game.outcome {
+                            Outcome::WhiteWins => score[0] += 1,
+                            Outcome::BlackWins => score[1] += 1,
+                            _ => score[2] += 1,
+                        }
+                        eprintln!(
+                            "game {} of {}: {} ({}), +{} -{} ={}",
+                            round + 1,
+                            args.games,
+                            game.outcome.as_str(),
+                            game.tag("Termination").unwrap_or("?"),
+                            score[0],
+                            score[1],
+                            score[2],
+                        );
+                        drop(score);
+
+                        writeln!(out.lock().unwrap(), "{game}")?;
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        workers
+            .into_iter()
+            .filter_map(|worker| worker.join().unwrap().err())
+            .next()
+    });
+    match error {
+        Some(e) => Err(e.to_string()),
+        None => out.into_inner().unwrap().flush().map_err(|e| e.to_string()),
+    }
+}
+
+fn main() -> ExitCode {
+    let Some(args) = parse_args() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! Knowledge of endgames the evaluation can't work out for itself.
+//!
+//! [`Kpk`] knows the result of every king and pawn against king position, and [`scale`]
+//! pulls the endgame score of that and other drawish endings towards a draw.
+
+use std::sync::LazyLock;
+
+use board::{attacks, BitBoard, Board, Color, File, PieceKind, Position, Rank, Square};
+
+use crate::{Tablebase, Wdl};
+
+/// The endgame scale of an ending with nothing special about it,
+/// which drawish endings are scaled down from.
+pub const FULL_SCALE: i32 = 64;
+
+/// The scale of an ending that is almost always drawn, but can still be lost.
+const DRAWISH_SCALE: i32 = 8;
+
+/// A bitbase of king and pawn against king, generated the first time it is probed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Kpk;
+
+/// A bit for each position, set where white wins, see [`kpk_index`].
+static KPK_WINS: LazyLock<Box<[u64]>> = LazyLock::new(generate_kpk);
+
+/// Every position the bitbase holds: the side to move and both kings,
+/// with the pawn white's and on files a to d, where every other position can be mirrored.
+const KPK_POSITIONS: usize = 2 * 64 * 64 * 24;
+
+fn kpk_index(us: Color, white_king: Square, black_king: Square, pawn: Square) -> usize {
+    let pawn = (pawn.rank().index() - 1) * 4 + pawn.file().index();
+    us as usize | (white_king as usize) << 1 | (black_king as usize) << 7 | pawn << 13
+}
+
+impl Kpk {
+    /// Whether the side with the pawn wins, with `us` to move.
+    pub fn wins(&self, strong: Color, us: Color, kings: [Square; 2], pawn: Square) -> bool {
+        // look the position up with the pawn white's and on the queen side
+        let normalize = |square: Square| {
+            let square = match strong {
+                Color::White => square,
+                Color::Black => square.flip_vertical(),
+            };
+            if pawn.file() > File::D {
+                square.mirror_horizontal()
+            } else {
+                square
+            }
+        };
+        let us = if us == strong {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let [white_king, black_king] = kings.map(normalize);
+        let index = kpk_index(us, white_king, black_king, normalize(pawn));
+        KPK_WINS[index / 64] & (1 << (index % 64)) != 0
+    }
+}
+
+impl Tablebase for Kpk {
+    fn max_pieces(&self) -> u32 {
+        3
+    }
+
+    fn probe_wdl(&self, position: &Position) -> Option<Wdl> {
+        let board = position.board();
+        if board.occupied().count() != 3 {
+            return None;
+        }
+        let pawn = board.pawns().lsb()?;
+        let strong = if board.colors(Color::White).is_on(pawn) {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let king = |color| (board.kings() & board.colors(color)).lsb();
+        let kings = [king(strong)?, king(strong.opposite())?];
+
+        let us = position.side_to_move();
+        Some(match self.wins(strong, us, kings, pawn) {
+            false => Wdl::Draw,
+            true if us == strong => Wdl::Win,
+            true => Wdl::Loss,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    /// Not a legal position.
+    Invalid,
+    Unknown,
+    Draw,
+    Win,
+}
+
+/// A position in the bitbase, with white's king and pawn against black's king.
+#[derive(Debug, Clone, Copy)]
+struct KpkPosition {
+    us: Color,
+    white_king: Square,
+    black_king: Square,
+    pawn: Square,
+}
+
+impl KpkPosition {
+    fn index(&self) -> usize {
+        kpk_index(self.us, self.white_king, self.black_king, self.pawn)
+    }
+
+    /// The outcome that can be seen without looking at any moves.
+    fn classify(&self) -> Outcome {
+        let Self {
+            us,
+            white_king,
+            black_king,
+            pawn,
+        } = *self;
+        let promotion = Square::new(Rank::Eighth, pawn.file());
+        let pawn_attacks = attacks::pawn_attacks(Color::White, pawn);
+
+        if white_king.distance(black_king) <= 1
+            || pawn == white_king
+            || pawn == black_king
+            || (us == Color::White && pawn_attacks.is_on(black_king))
+        {
+            Outcome::Invalid
+        } else if us == Color::White
+            && pawn.rank() == Rank::Seventh
+            && ![white_king, black_king].contains(&promotion)
+            && (black_king.distance(promotion) > 1 || white_king.distance(promotion) == 1)
+        {
+            // the pawn queens and can't be taken
+            Outcome::Win
+        } else if us == Color::Black
+            && ((self.black_moves().is_empty() && !pawn_attacks.is_on(black_king))
+                || (black_king.distance(pawn) == 1 && white_king.distance(pawn) > 1))
+        {
+            // stalemate, or the pawn is lost
+            Outcome::Draw
+        } else {
+            Outcome::Unknown
+        }
+    }
+
+    /// The squares black's king can move to, other than taking the pawn.
+    fn black_moves(&self) -> BitBoard {
+        attacks::king_attacks(self.black_king)
+            - attacks::king_attacks(self.white_king)
+            - attacks::pawn_attacks(Color::White, self.pawn)
+            - BitBoard::from_square(self.pawn)
+    }
+
+    /// The positions after every move from this one, except for promotions.
+    fn children(&self) -> Vec<Self> {
+        let mut children = Vec::new();
+        let next = Self {
+            us: self.us.opposite(),
+            ..*self
+        };
+        match self.us {
+            Color::White => {
+                let king_moves = attacks::king_attacks(self.white_king)
+                    - attacks::king_attacks(self.black_king)
+                    - BitBoard::from_square(self.pawn);
+                for white_king in king_moves {
+                    children.push(Self { white_king, ..next });
+                }
+
+                let kings = [self.white_king, self.black_king];
+                let mut pawn = self.pawn;
+                while pawn.rank() < Rank::Seventh {
+                    pawn = pawn
+                        .offset(1, 0)
+                        .expect("the pawn is short of the last rank");
+                    if kings.contains(&pawn) {
+                        break;
+                    }
+                    children.push(Self { pawn, ..next });
+                    // only the first push can be a double push
+                    if self.pawn.rank() != Rank::Second || pawn.rank() == Rank::Fourth {
+                        break;
+                    }
+                }
+            }
+            Color::Black => {
+                for black_king in self.black_moves() {
+                    children.push(Self { black_king, ..next });
+                }
+            }
+        }
+        children
+    }
+}
+
+/// Works out every position of the bitbase by going over the unknown positions
+/// until nothing changes, leaving those still unknown as draws.
+fn generate_kpk() -> Box<[u64]> {
+    let mut positions = Vec::with_capacity(KPK_POSITIONS);
+    for rank in &Rank::ALL[1..7] {
+        for file in &File::ALL[..4] {
+            for black_king in 0..64 {
+                for white_king in 0..64 {
+                    for us in [Color::White, Color::Black] {
+                        positions.push(KpkPosition {
+                            us,
+                            white_king: Square::from_raw(white_king),
+                            black_king: Square::from_raw(black_king),
+                            pawn: Square::new(*rank, *file),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    debug_assert!(positions.iter().enumerate().all(|(i, p)| p.index() == i));
+
+    let mut outcomes: Vec<Outcome> = positions.iter().map(KpkPosition::classify).collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for position in &positions {
+            let index = position.index();
+            if outcomes[index] != Outcome::Unknown {
+                continue;
+            }
+            let children: Vec<Outcome> = position
+                .children()
+                .iter()
+                .map(|child| outcomes[child.index()])
+                .collect();
+            // white needs a move that wins, and black a move that draws
+            let (good, bad) = match position.us {
+                Color::White => (Outcome::Win, Outcome::Draw),
+                Color::Black => (Outcome::Draw, Outcome::Win),
+            };
+            let result = if children.contains(&good) {
+                good
+            } else if children.iter().all(|&outcome| outcome == bad) {
+                bad
+            } else {
+                continue;
+            };
+            outcomes[index] = result;
+            changed = true;
+        }
+    }
+
+    let mut wins = vec![0_u64; KPK_POSITIONS / 64].into_boxed_slice();
+    for (index, outcome) in outcomes.into_iter().enumerate() {
+        if outcome == Outcome::Win {
+            wins[index / 64] |= 1 << (index % 64);
+        }
+    }
+    wins
+}
+
+/// How much of the endgame score of `position` to keep, out of [`FULL_SCALE`].
+///
+/// Endings that material alone makes look won, but which are usually drawn, are scaled down:
+/// king and pawn against king when [`Kpk`] says it's drawn, a side without pawns
+/// that is up by no more than a minor piece, opposite coloured bishops,
+/// and rook and pawn against rook with the defending king in front of the pawn.
+pub(crate) fn scale(position: &Position) -> i32 {
+    let board = position.board();
+    let pieces = |color| {
+        let ours = board.colors(color);
+        [
+            PieceKind::Knight,
+            PieceKind::Bishop,
+            PieceKind::Rook,
+            PieceKind::Queen,
+        ]
+        .map(|kind| (board.pieces(kind) & ours).count())
+    };
+    let non_pawn = |color| {
+        let [knights, bishops, rooks, queens] = pieces(color);
+        knights as i32 * PieceKind::Knight.value()
+            + bishops as i32 * PieceKind::Bishop.value()
+            + rooks as i32 * PieceKind::Rook.value()
+            + queens as i32 * PieceKind::Queen.value()
+    };
+    let pawns = |color: Color| board.pawns() & board.colors(color);
+
+    let strong = if position.material(Color::White) >= position.material(Color::Black) {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let weak = strong.opposite();
+
+    if board.occupied().count() == 3 && pawns(strong).count() == 1 {
+        return match Kpk.probe_wdl(position) {
+            Some(Wdl::Draw) => 0,
+            _ => FULL_SCALE,
+        };
+    }
+
+    if pawns(strong).is_empty() && pawns(weak).is_empty() {
+        let advantage = non_pawn(strong) - non_pawn(weak);
+        if advantage <= PieceKind::Bishop.value() {
+            // a lone minor piece can't mate, and more than that rarely wins
+            return if non_pawn(strong) < PieceKind::Rook.value() {
+                0
+            } else {
+                DRAWISH_SCALE
+            };
+        }
+    }
+
+    let [white, black] = [Color::White, Color::Black].map(pieces);
+    let bishops = board.bishops();
+    if white == black && white[1] == 1 {
+        let light = (bishops & BitBoard::LIGHT_SQUARES).count();
+        if light == 1 {
+            // opposite coloured bishops
+            let extra_pawns = pawns(strong).count().saturating_sub(pawns(weak).count());
+            return match white {
+                [0, 1, 0, 0] if extra_pawns <= 1 => 2 * DRAWISH_SCALE,
+                [0, 1, 0, 0] => 4 * DRAWISH_SCALE,
+                _ => 6 * DRAWISH_SCALE,
+            };
+        }
+    }
+
+    if white == [0, 0, 1, 0] && black == white && pawns(weak).is_empty() {
+        if let (Some(pawn), false) = (pawns(strong).lsb(), pawns(strong).has_many()) {
+            if is_rook_pawn_draw(board, strong, pawn) {
+                return DRAWISH_SCALE;
+            }
+        }
+    }
+
+    FULL_SCALE
+}
+
+/// Whether the defending king in a rook and pawn against rook ending stands in front of
+/// `pawn`, or by the queening square of a pawn on the edge.
+fn is_rook_pawn_draw(board: &Board, strong: Color, pawn: Square) -> bool {
+    let weak = strong.opposite();
+    let Some(king) = (board.kings() & board.colors(weak)).lsb() else {
+        return false;
+    };
+    let relative = |square: Square| square.rank().relative_to(strong);
+    let promotion = Square::new(Rank::Eighth.relative_to(strong), pawn.file());
+
+    let in_front = king.file() == pawn.file() && relative(king) > relative(pawn);
+    let edge = matches!(pawn.file(), File::A | File::H) && king.distance(promotion) <= 1;
+    in_front || edge
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::FEN;
+
+    fn position(fen: &str) -> Position {
+        FEN::new(fen).parse_position().unwrap()
+    }
+
+    #[test]
+    fn kpk() {
+        for (fen, wdl) in [
+            // the king escorts the pawn in
+            ("8/8/8/8/8/3K4/3P4/5k2 w - - 0 1", Wdl::Win),
+            // the opposition holds
+            ("8/3k4/8/3K4/3P4/8/8/8 w - - 0 1", Wdl::Draw),
+            ("8/3k4/8/3K4/3P4/8/8/8 b - - 0 1", Wdl::Loss),
+            // rook pawns are drawn with the king in the corner
+            ("k7/8/1K6/P7/8/8/8/8 w - - 0 1", Wdl::Draw),
+            // and the same for black on the king side
+            ("8/8/8/4p3/4k3/8/4K3/8 b - - 0 1", Wdl::Draw),
+            ("8/8/8/4p3/4k3/8/4K3/8 w - - 0 1", Wdl::Loss),
+        ] {
+            assert_eq!(Kpk.probe_wdl(&position(fen)), Some(wdl), "{fen}");
+        }
+        assert_eq!(Kpk.probe_wdl(&Position::start()), None);
+    }
+
+    #[test]
+    fn drawish_endings_are_scaled() {
+        for (fen, expected) in [
+            ("8/3k4/8/3K4/3P4/8/8/8 w - - 0 1", 0),
+            ("8/8/8/8/8/3K4/3P4/5k2 w - - 0 1", FULL_SCALE),
+            ("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1", 0),
+            ("4k3/8/8/8/8/8/8/2R1KN2 w - - 0 1", FULL_SCALE),
+            ("4k1b1/p7/8/8/8/8/PP6/2B1K3 w - - 0 1", 2 * DRAWISH_SCALE),
+            ("3k4/8/8/r7/8/8/4P3/4K2R w - - 0 1", FULL_SCALE),
+            ("8/4k3/8/r7/8/8/4P3/4K2R w - - 0 1", DRAWISH_SCALE),
+            ("rk6/8/8/P7/8/8/8/4K2R w - - 0 1", DRAWISH_SCALE),
+        ] {
+            assert_eq!(scale(&position(fen)), expected, "{fen}");
+        }
+    }
+}
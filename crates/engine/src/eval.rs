@@ -0,0 +1,860 @@
+//! Static evaluation of a [`Position`], with every weight held in [`EvalParams`].
+//!
+//! Every weight has a middlegame and an endgame value, see [`Tapered`],
+//! which are blended by how much material is left on the board, see [`phase`].
+//!
+//! The weights can be written out and read back in a simple text format,
+//! one `name = values...` line per weight, so they can be tuned outside the engine.
+
+use std::{
+    fmt,
+    ops::{Add, AddAssign, Mul},
+    path::Path,
+    str::FromStr,
+};
+
+use board::{attacks, BitBoard, Board, Color, PieceKind, Position, Rank, Square};
+
+use crate::{endgame, PawnEntry, PawnTable, FULL_SCALE};
+
+/// The [`phase`] of a position with every piece still on the board.
+pub const MAX_PHASE: i32 = 24;
+
+/// A weight with a value for the middlegame and one for the endgame.
+///
+/// A position is scored with both, and the two scores are blended by its [`phase`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tapered {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Tapered {
+    pub const fn new(mg: i32, eg: i32) -> Self {
+        Self { mg, eg }
+    }
+
+    /// The same value in both phases.
+    pub const fn flat(value: i32) -> Self {
+        Self::new(value, value)
+    }
+
+    /// The value for a position at `phase`, moving from the middlegame value
+    /// at [`MAX_PHASE`] to the endgame value at 0.
+    pub const fn blend(self, phase: i32) -> i32 {
+        self.blend_scaled(phase, FULL_SCALE)
+    }
+
+    /// Like [`Tapered::blend`], with the endgame value scaled by `scale` out of [`FULL_SCALE`].
+    pub const fn blend_scaled(self, phase: i32, scale: i32) -> i32 {
+        let mg = self.mg * phase * FULL_SCALE;
+        let eg = self.eg * (MAX_PHASE - phase) * scale;
+        (mg + eg) / (MAX_PHASE * FULL_SCALE)
+    }
+}
+
+impl Add for Tapered {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.mg + other.mg, self.eg + other.eg)
+    }
+}
+
+impl AddAssign for Tapered {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Mul<i32> for Tapered {
+    type Output = Self;
+
+    fn mul(self, count: i32) -> Self {
+        Self::new(self.mg * count, self.eg * count)
+    }
+}
+
+/// Pairs up middlegame and endgame values.
+const fn tapered<const N: usize>(mg: [i32; N], eg: [i32; N]) -> [Tapered; N] {
+    let mut weights = [Tapered::new(0, 0); N];
+    let mut i = 0;
+    while i < N {
+        weights[i] = Tapered::new(mg[i], eg[i]);
+        i += 1;
+    }
+    weights
+}
+
+/// How far from the endgame `board` is, from [`MAX_PHASE`] with every piece on the board
+/// down to 0 with only pawns and kings left.
+///
+/// Minor pieces count 1, rooks 2 and queens 4.
+pub fn phase(board: &Board) -> i32 {
+    let pieces = [
+        (board.knights(), 1),
+        (board.bishops(), 1),
+        (board.rooks(), 2),
+        (board.queens(), 4),
+    ];
+    let phase: i32 = pieces
+        .into_iter()
+        .map(|(pieces, weight)| pieces.count() as i32 * weight)
+        .sum();
+    // promotions can take it past the starting material
+    phase.min(MAX_PHASE)
+}
+
+/// A weight for each [`Square`].
+///
+/// Tables are written from white's point of view with rank 8 first,
+/// the way a board is usually drawn, and mirrored for black.
+pub type SquareTable = [Tapered; 64];
+
+/// The weights used by [`EvalParams::evaluate`], all in centipawns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalParams {
+    /// The value of each [`PieceKind`], indexed by kind.
+    pub piece_values: [Tapered; 6],
+    /// A bonus for each [`PieceKind`] standing on a square, indexed by kind.
+    pub piece_squares: [SquareTable; 6],
+    /// Added for every pawn behind another on the same file.
+    pub doubled_pawn: Tapered,
+    /// Added for every pawn without friendly pawns on either neighbouring file.
+    pub isolated_pawn: Tapered,
+    /// Added for a pawn with no enemy pawns in front of it or on neighbouring files,
+    /// indexed by how many ranks it has advanced.
+    pub passed_pawn: [Tapered; 8],
+    /// Added for every pawn sheltering the king while it is on its back rank,
+    /// see [`PawnEntry::shield`].
+    pub pawn_shield: Tapered,
+    /// Added for every square a piece attacks that isn't its own
+    /// or attacked by an enemy pawn, indexed by kind.
+    pub mobility: [Tapered; 6],
+    /// Added for a knight on an outpost, a square in the enemy half defended by our pawns
+    /// that no enemy pawn can ever attack.
+    pub knight_outpost: Tapered,
+    /// Added for a bishop on an outpost, see [`EvalParams::knight_outpost`].
+    pub bishop_outpost: Tapered,
+    /// Added for a rook on a file without pawns.
+    pub rook_open_file: Tapered,
+    /// Added for a rook on a file with only enemy pawns.
+    pub rook_semi_open_file: Tapered,
+    /// Added for a rook on the seventh rank while the enemy king is on its back rank
+    /// or enemy pawns are still on the seventh.
+    pub rook_seventh_rank: Tapered,
+}
+
+#[rustfmt::skip]
+const PAWN_SQUARES: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     10,  10,  20,  30,  30,  20,  10,  10,
+      5,   5,  10,  25,  25,  10,   5,   5,
+      0,   0,   0,  20,  20,   0,   0,   0,
+      5,  -5, -10,   0,   0, -10,  -5,   5,
+      5,  10,  10, -20, -20,  10,  10,   5,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_SQUARES: [i32; 64] = [
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   5,  15,  20,  20,  15,   5, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   5,  10,  15,  15,  10,   5, -30,
+    -40, -20,   0,   5,   5,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+];
+
+#[rustfmt::skip]
+const BISHOP_SQUARES: [i32; 64] = [
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   5,   5,  10,  10,   5,   5, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,  10,  10,  10,  10,  10,  10, -10,
+    -10,   5,   0,   0,   0,   0,   5, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const ROOK_SQUARES: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+      5,  10,  10,  10,  10,  10,  10,   5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+      0,   0,   0,   5,   5,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const QUEEN_SQUARES: [i32; 64] = [
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+     -5,   0,   5,   5,   5,   5,   0,  -5,
+      0,   0,   5,   5,   5,   5,   0,  -5,
+    -10,   5,   5,   5,   5,   5,   0, -10,
+    -10,   0,   5,   0,   0,   0,   0, -10,
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+];
+
+#[rustfmt::skip]
+const KING_SQUARES: [i32; 64] = [
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+     20,  20,   0,   0,   0,   0,  20,  20,
+     20,  30,  10,   0,   0,  10,  30,  20,
+];
+
+#[rustfmt::skip]
+const PAWN_ENDGAME_SQUARES: [i32; 64] = [
+      0,   0,   0,   0,   0,   0,   0,   0,
+     60,  60,  60,  60,  60,  60,  60,  60,
+     35,  35,  35,  35,  35,  35,  35,  35,
+     20,  20,  20,  20,  20,  20,  20,  20,
+     10,  10,  10,  10,  10,  10,  10,  10,
+      5,   5,   5,   5,   5,   5,   5,   5,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+];
+
+#[rustfmt::skip]
+const KING_ENDGAME_SQUARES: [i32; 64] = [
+    -50, -40, -30, -20, -20, -30, -40, -50,
+    -30, -20, -10,   0,   0, -10, -20, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -30,   0,   0,   0,   0, -30, -30,
+    -50, -30, -30, -30, -30, -30, -30, -50,
+];
+
+/// Scores positions for the side to move, so the search can be used with any evaluation.
+///
+/// Closures and functions taking a [`Position`] are evaluators too,
+/// such as [`evaluate`] and [`material`].
+pub trait Evaluator {
+    /// Scores `position` in centipawns for the side to move.
+    fn evaluate(&self, position: &Position) -> i32;
+
+    /// Scores `position` like [`Evaluator::evaluate`], given a cache of pawn structure
+    /// that a search thread keeps between positions.
+    ///
+    /// Evaluators that don't look at pawn structure can leave this as it is.
+    #[inline]
+    fn evaluate_cached(&self, position: &Position, _pawns: &mut PawnTable) -> i32 {
+        self.evaluate(position)
+    }
+}
+
+impl<F: Fn(&Position) -> i32> Evaluator for F {
+    #[inline]
+    fn evaluate(&self, position: &Position) -> i32 {
+        self(position)
+    }
+}
+
+impl Evaluator for EvalParams {
+    #[inline]
+    fn evaluate(&self, position: &Position) -> i32 {
+        EvalParams::evaluate(self, position)
+    }
+
+    #[inline]
+    fn evaluate_cached(&self, position: &Position, pawns: &mut PawnTable) -> i32 {
+        let pawns = *pawns.probe(position);
+        self.evaluate_with(position, &pawns)
+    }
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        Self {
+            piece_values: tapered([100, 320, 330, 500, 900, 0], [120, 300, 320, 540, 950, 0]),
+            piece_squares: [
+                tapered(PAWN_SQUARES, PAWN_ENDGAME_SQUARES),
+                tapered(KNIGHT_SQUARES, KNIGHT_SQUARES),
+                tapered(BISHOP_SQUARES, BISHOP_SQUARES),
+                tapered(ROOK_SQUARES, ROOK_SQUARES),
+                tapered(QUEEN_SQUARES, QUEEN_SQUARES),
+                tapered(KING_SQUARES, KING_ENDGAME_SQUARES),
+            ],
+            doubled_pawn: Tapered::new(-15, -25),
+            isolated_pawn: Tapered::new(-15, -20),
+            passed_pawn: tapered(
+                [0, 5, 10, 20, 35, 60, 100, 0],
+                [0, 10, 20, 35, 60, 100, 150, 0],
+            ),
+            pawn_shield: Tapered::new(10, 0),
+            mobility: tapered([0, 4, 5, 2, 1, 0], [0, 4, 5, 4, 2, 0]),
+            knight_outpost: Tapered::new(25, 15),
+            bishop_outpost: Tapered::new(15, 10),
+            rook_open_file: Tapered::new(25, 10),
+            rook_semi_open_file: Tapered::new(10, 5),
+            rook_seventh_rank: Tapered::new(20, 30),
+        }
+    }
+}
+
+impl EvalParams {
+    /// Scores `position` for the side to move.
+    ///
+    /// The pawn structure is analysed from scratch,
+    /// see [`Evaluator::evaluate_cached`] to reuse it.
+    pub fn evaluate(&self, position: &Position) -> i32 {
+        self.evaluate_with(position, &PawnEntry::analyze(position.board()))
+    }
+
+    /// Scores `position` for the side to move, given its pawn structure.
+    fn evaluate_with(&self, position: &Position, pawns: &PawnEntry) -> i32 {
+        let board = position.board();
+        let mut score = Tapered::default();
+        self.terms(board, pawns, |_, weight, count| score += weight * count);
+        let score = score.blend_scaled(phase(board), endgame::scale(position));
+
+        match position.side_to_move() {
+            Color::White => score,
+            Color::Black => -score,
+        }
+    }
+
+    /// How many times each weight counts towards the evaluation of `position`,
+    /// from white's point of view and in the order of [`EvalParams::weights`],
+    /// times how far the position is into that weight's phase and the endgame scale.
+    ///
+    /// The evaluation is linear, so white's score is the sum of every weight
+    /// times its coefficient, divided by [`MAX_PHASE`] times [`FULL_SCALE`]
+    /// and rounded towards zero, which is what makes tuning the weights quick.
+    pub fn coefficients(position: &Position) -> Vec<i32> {
+        let board = position.board();
+        let phase = phase(board);
+        let scale = endgame::scale(position);
+        let mut coefficients = vec![0; WEIGHT_COUNT];
+        Self::default().terms(board, &PawnEntry::analyze(board), |index, _, count| {
+            coefficients[index] += count * phase * FULL_SCALE;
+            coefficients[TERM_COUNT + index] += count * (MAX_PHASE - phase) * scale;
+        });
+        coefficients
+    }
+
+    /// Passes every term of the evaluation to `term`, from white's point of view:
+    /// the index of its middlegame weight in [`EvalParams::weights`], the weight,
+    /// and how many times it counts, negated for black.
+    fn terms(&self, board: &Board, pawns: &PawnEntry, mut term: impl FnMut(usize, Tapered, i32)) {
+        for (us, sign) in [(Color::White, 1), (Color::Black, -1)] {
+            self.side_terms(board, pawns, us, &mut |index, weight, count| {
+                term(index, weight, sign * count)
+            });
+        }
+    }
+
+    /// Every term for the pieces of `us`, from their own point of view.
+    fn side_terms(
+        &self,
+        board: &Board,
+        pawns: &PawnEntry,
+        us: Color,
+        term: &mut impl FnMut(usize, Tapered, i32),
+    ) {
+        let them = us.opposite();
+        let ours = board.colors(us);
+        let occupied = board.occupied();
+        let our_pawns = board.pawns() & ours;
+        let their_pawns = board.pawns() & board.colors(them);
+        let unsafe_squares = their_pawns.pawn_attacks(them);
+
+        for (index, piece) in board.iter().enumerate() {
+            let Some(piece) = piece.filter(|piece| piece.color() == us) else {
+                continue;
+            };
+            let square = Square::from_raw(index as u8);
+            let kind = piece.kind() as usize;
+            let table = table_index(square, us);
+
+            term(PIECE_VALUES + kind, self.piece_values[kind], 1);
+            term(
+                PIECE_SQUARES + kind * 64 + table,
+                self.piece_squares[kind][table],
+                1,
+            );
+
+            let attacks = match piece.kind() {
+                PieceKind::Knight => attacks::knight_attacks(square),
+                PieceKind::Bishop => attacks::bishop_attacks(square, occupied),
+                PieceKind::Rook => attacks::rook_attacks(square, occupied),
+                PieceKind::Queen => attacks::queen_attacks(square, occupied),
+                PieceKind::Pawn | PieceKind::King => continue,
+            };
+            let mobility = (attacks - ours - unsafe_squares).count() as i32;
+            term(MOBILITY + kind, self.mobility[kind], mobility);
+
+            match piece.kind() {
+                PieceKind::Knight if is_outpost(square, us, our_pawns, their_pawns) => {
+                    term(KNIGHT_OUTPOST, self.knight_outpost, 1)
+                }
+                PieceKind::Bishop if is_outpost(square, us, our_pawns, their_pawns) => {
+                    term(BISHOP_OUTPOST, self.bishop_outpost, 1)
+                }
+                PieceKind::Rook => self.rook_terms(board, square, us, term),
+                _ => {}
+            }
+        }
+
+        self.pawn_terms(board, pawns, us, term);
+    }
+
+    fn rook_terms(
+        &self,
+        board: &Board,
+        square: Square,
+        us: Color,
+        term: &mut impl FnMut(usize, Tapered, i32),
+    ) {
+        let them = us.opposite();
+        let file = BitBoard::file(square.file());
+        if (board.pawns() & file).is_empty() {
+            term(ROOK_OPEN_FILE, self.rook_open_file, 1);
+        } else if (board.pawns() & board.colors(us) & file).is_empty() {
+            term(ROOK_SEMI_OPEN_FILE, self.rook_semi_open_file, 1);
+        }
+
+        let seventh = Rank::Seventh.relative_to(us);
+        if square.rank() == seventh {
+            let their_king = board.kings() & board.colors(them);
+            let trapped = !(their_king & BitBoard::rank(Rank::Eighth.relative_to(us))).is_empty();
+            let pawns = !(board.pawns() & board.colors(them) & BitBoard::rank(seventh)).is_empty();
+            if trapped || pawns {
+                term(ROOK_SEVENTH_RANK, self.rook_seventh_rank, 1);
+            }
+        }
+    }
+
+    fn pawn_terms(
+        &self,
+        board: &Board,
+        pawns: &PawnEntry,
+        us: Color,
+        term: &mut impl FnMut(usize, Tapered, i32),
+    ) {
+        let side = us as usize;
+        term(DOUBLED_PAWN, self.doubled_pawn, pawns.doubled[side] as i32);
+        term(
+            ISOLATED_PAWN,
+            self.isolated_pawn,
+            pawns.isolated[side] as i32,
+        );
+
+        for pawn in pawns.passed[side] {
+            let advanced = pawn.rank().relative_to(us).index();
+            term(PASSED_PAWN + advanced, self.passed_pawn[advanced], 1);
+        }
+
+        let back_rank = Rank::First.relative_to(us);
+        if let Some(king) = (board.kings() & board.colors(us)).lsb() {
+            if king.rank() == back_rank {
+                let shield = pawns.shield[side][king.file().index()];
+                term(PAWN_SHIELD, self.pawn_shield, shield as i32);
+            }
+        }
+    }
+
+    /// Reads weights from a file written by [`Display`](fmt::Display).
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ParamsError> {
+        std::fs::read_to_string(path)?.parse()
+    }
+}
+
+/// Whether a piece of `color` on `square` is on an outpost: in the enemy half of the board
+/// short of the back rank, defended by a pawn, and out of reach of every enemy pawn.
+fn is_outpost(square: Square, color: Color, ours: BitBoard, theirs: BitBoard) -> bool {
+    let rank = square.rank().relative_to(color);
+    let defended = !(attacks::pawn_attacks(color.opposite(), square) & ours).is_empty();
+    let attackable = theirs.into_iter().any(|enemy| {
+        enemy.file().distance(square.file()) == 1 && enemy.rank().relative_to(color) > rank
+    });
+    (Rank::Fourth..=Rank::Sixth).contains(&rank) && defended && !attackable
+}
+
+/// Where `square` is in a [`SquareTable`] for `color`.
+fn table_index(square: Square, color: Color) -> usize {
+    let rank = match color {
+        Color::White => square.rank().flip(),
+        Color::Black => square.rank(),
+    };
+    rank.index() * 8 + square.file().index()
+}
+
+/// Evaluates `position` with the default [`EvalParams`].
+pub fn evaluate(position: &Position) -> i32 {
+    use std::sync::LazyLock;
+
+    static DEFAULT: LazyLock<EvalParams> = LazyLock::new(EvalParams::default);
+    DEFAULT.evaluate(position)
+}
+
+/// Counts material, scored for the side to move.
+pub fn material(position: &Position) -> i32 {
+    let us = position.side_to_move();
+    position.material(us) - position.material(us.opposite())
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ParamsError {
+    #[error("unknown weight '{0}'")]
+    Unknown(String),
+    #[error("expected `name = values` on line {0}")]
+    Syntax(usize),
+    #[error("invalid value '{value}' for '{name}'")]
+    InvalidValue { name: String, value: String },
+    #[error("'{name}' needs {expected} values, found {found}")]
+    WrongCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+// where each term's middlegame weights start in `EvalParams::weights`, in the order of
+// `weights_mut`, with the endgame weights following all of them in the same order
+const PIECE_VALUES: usize = 0;
+const DOUBLED_PAWN: usize = PIECE_VALUES + 6;
+const ISOLATED_PAWN: usize = DOUBLED_PAWN + 1;
+const PASSED_PAWN: usize = ISOLATED_PAWN + 1;
+const PAWN_SHIELD: usize = PASSED_PAWN + 8;
+const MOBILITY: usize = PAWN_SHIELD + 1;
+const KNIGHT_OUTPOST: usize = MOBILITY + 6;
+const BISHOP_OUTPOST: usize = KNIGHT_OUTPOST + 1;
+const ROOK_OPEN_FILE: usize = BISHOP_OUTPOST + 1;
+const ROOK_SEMI_OPEN_FILE: usize = ROOK_OPEN_FILE + 1;
+const ROOK_SEVENTH_RANK: usize = ROOK_SEMI_OPEN_FILE + 1;
+const PIECE_SQUARES: usize = ROOK_SEVENTH_RANK + 1;
+const TERM_COUNT: usize = PIECE_SQUARES + 6 * 64;
+const WEIGHT_COUNT: usize = 2 * TERM_COUNT;
+
+const TABLE_NAMES: [&str; 6] = [
+    "pawn_squares",
+    "knight_squares",
+    "bishop_squares",
+    "rook_squares",
+    "queen_squares",
+    "king_squares",
+];
+
+impl EvalParams {
+    /// Every weight by name, in the order they are written:
+    /// the middlegame values named `mg_<term>`, then the endgame values named `eg_<term>`.
+    fn weights_mut(&mut self) -> Vec<(String, Vec<&mut i32>)> {
+        let mut terms: Vec<(&'static str, Vec<&mut Tapered>)> = vec![
+            ("piece_values", self.piece_values.iter_mut().collect()),
+            ("doubled_pawn", vec![&mut self.doubled_pawn]),
+            ("isolated_pawn", vec![&mut self.isolated_pawn]),
+            ("passed_pawn", self.passed_pawn.iter_mut().collect()),
+            ("pawn_shield", vec![&mut self.pawn_shield]),
+            ("mobility", self.mobility.iter_mut().collect()),
+            ("knight_outpost", vec![&mut self.knight_outpost]),
+            ("bishop_outpost", vec![&mut self.bishop_outpost]),
+            ("rook_open_file", vec![&mut self.rook_open_file]),
+            ("rook_semi_open_file", vec![&mut self.rook_semi_open_file]),
+            ("rook_seventh_rank", vec![&mut self.rook_seventh_rank]),
+        ];
+        for (name, table) in TABLE_NAMES.into_iter().zip(&mut self.piece_squares) {
+            terms.push((name, table.iter_mut().collect()));
+        }
+
+        let (mg, eg): (Vec<_>, Vec<_>) = terms
+            .into_iter()
+            .map(|(name, weights)| {
+                let (mg, eg) = weights
+                    .into_iter()
+                    .map(|Tapered { mg, eg }| (mg, eg))
+                    .unzip();
+                ((format!("mg_{name}"), mg), (format!("eg_{name}"), eg))
+            })
+            .unzip();
+        mg.into_iter().chain(eg).collect()
+    }
+
+    /// Every weight as one list, in the order they are written.
+    pub fn weights(&self) -> Vec<i32> {
+        let mut params = self.clone();
+        let weights = params.weights_mut();
+        weights
+            .into_iter()
+            .flat_map(|(_, weight)| weight.into_iter().map(|weight| *weight))
+            .collect()
+    }
+
+    /// Sets every weight from a list in the order of [`EvalParams::weights`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` isn't the same length as [`EvalParams::weights`].
+    pub fn set_weights(&mut self, values: &[i32]) {
+        assert_eq!(values.len(), WEIGHT_COUNT, "every weight should be given");
+        let mut values = values.iter();
+        for (_, weight) in self.weights_mut() {
+            for (weight, value) in weight.into_iter().zip(&mut values) {
+                *weight = *value;
+            }
+        }
+    }
+}
+
+impl FromStr for EvalParams {
+    type Err = ParamsError;
+
+    /// Parses weights written by [`Display`](fmt::Display).
+    ///
+    /// Weights that aren't mentioned keep their default value,
+    /// and anything after a `#` is a comment.
+    /// A term named without its `mg_` or `eg_` prefix sets both phases.
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        // values can continue onto the following lines, until the next name
+        let mut entries: Vec<(&str, Vec<&str>)> = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            match (line.split_once('='), entries.last_mut()) {
+                (Some((name, values)), _) => {
+                    entries.push((name.trim(), values.split_whitespace().collect()))
+                }
+                (None, Some((_, values))) => values.extend(line.split_whitespace()),
+                (None, None) => return Err(ParamsError::Syntax(number + 1)),
+            }
+        }
+
+        let mut params = Self::default();
+        let mut weights = params.weights_mut();
+
+        for (name, values) in entries {
+            let matches = |n: &str| {
+                n == name || [n.strip_prefix("mg_"), n.strip_prefix("eg_")].contains(&Some(name))
+            };
+            let mut found = false;
+            for (_, weight) in weights.iter_mut().filter(|(n, _)| matches(n)) {
+                found = true;
+                if values.len() != weight.len() {
+                    return Err(ParamsError::WrongCount {
+                        name: name.to_owned(),
+                        expected: weight.len(),
+                        found: values.len(),
+                    });
+                }
+                for (weight, value) in weight.iter_mut().zip(&values) {
+                    **weight = value.parse().map_err(|_| ParamsError::InvalidValue {
+                        name: name.to_owned(),
+                        value: (*value).to_owned(),
+                    })?;
+                }
+            }
+            if !found {
+                return Err(ParamsError::Unknown(name.to_owned()));
+            }
+        }
+
+        drop(weights);
+        Ok(params)
+    }
+}
+
+impl fmt::Display for EvalParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut params = self.clone();
+        for (name, weight) in params.weights_mut() {
+            write!(f, "{name} =")?;
+            for (i, value) in weight.iter().enumerate() {
+                // break square tables into ranks
+                if weight.len() == 64 && i % 8 == 0 {
+                    write!(f, "\n   ")?;
+                }
+                write!(f, " {value}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::FEN;
+
+    fn position(fen: &str) -> Position {
+        FEN::new(fen).parse_position().unwrap()
+    }
+
+    #[test]
+    fn material_is_relative() {
+        let position = position("4k3/8/8/8/8/8/8/RQ2K3 b - - 0 1");
+        assert_eq!(material(&position), -1400);
+    }
+
+    #[test]
+    fn evaluators() {
+        let position = position("4k3/8/8/8/8/8/8/RQ2K3 w - - 0 1");
+        let evaluators: [&dyn Evaluator; 3] = [&evaluate, &EvalParams::default(), &material];
+        assert!(evaluators
+            .iter()
+            .all(|eval| eval.evaluate(&position) > 1000));
+
+        let contempt = |position: &Position| material(position) - 10;
+        assert_eq!(contempt.evaluate(&position), 1390);
+    }
+
+    #[test]
+    fn symmetric_positions_are_equal() {
+        assert_eq!(evaluate(&Position::start()), 0);
+
+        let white = position("4k3/pp6/8/8/3P4/2N5/5PPP/4K3 w - - 0 1");
+        let black = position("4k3/5ppp/2n5/3p4/8/8/PP6/4K3 b - - 0 1");
+        assert_eq!(evaluate(&white), evaluate(&black));
+        assert_eq!(white.swap_colors().to_fen(), black.to_fen());
+
+        let kiwipete =
+            position("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        assert_eq!(evaluate(&kiwipete), evaluate(&kiwipete.swap_colors()));
+    }
+
+    #[test]
+    fn linear_in_weights() {
+        let mut params = EvalParams::default();
+        params.set_weights(&(0..WEIGHT_COUNT as i32).collect::<Vec<_>>());
+        assert_eq!(params.weights().len(), WEIGHT_COUNT);
+        assert_eq!(
+            params.pawn_shield,
+            Tapered::new(PAWN_SHIELD as i32, (TERM_COUNT + PAWN_SHIELD) as i32)
+        );
+        assert_eq!(params.piece_squares[5][63].eg, WEIGHT_COUNT as i32 - 1);
+
+        for fen in crate::BENCH_POSITIONS {
+            let position = position(fen);
+            let white = match position.side_to_move() {
+                Color::White => params.evaluate(&position),
+                Color::Black => -params.evaluate(&position),
+            };
+            let sum: i32 = EvalParams::coefficients(&position)
+                .iter()
+                .zip(params.weights())
+                .map(|(coefficient, weight)| coefficient * weight)
+                .sum();
+            assert_eq!(sum / (MAX_PHASE * FULL_SCALE), white, "{fen}");
+        }
+    }
+
+    #[test]
+    fn tapering() {
+        let weight = Tapered::new(100, 40);
+        assert_eq!(weight.blend(MAX_PHASE), 100);
+        assert_eq!(weight.blend(0), 40);
+        assert_eq!(weight.blend(MAX_PHASE / 2), 70);
+
+        assert_eq!(phase(Position::start().board()), MAX_PHASE);
+        assert_eq!(
+            phase(position("4k3/pppp4/8/8/8/8/4PPPP/4K3 w - - 0 1").board()),
+            0
+        );
+
+        // a king in the centre is better off once the pieces are gone
+        assert!(evaluate(&position("8/p7/8/4K3/8/8/P7/k7 w - - 0 1")) > 0);
+
+        // and an extra pawn is nothing if it can't be queened
+        assert!(evaluate(&position("8/8/8/8/8/3K4/3P4/5k2 w - - 0 1")) > 100);
+        assert_eq!(evaluate(&position("8/3k4/8/3K4/3P4/8/8/8 w - - 0 1")), 0);
+    }
+
+    #[test]
+    fn cached_matches_uncached() {
+        let params = EvalParams::default();
+        let mut pawns = PawnTable::new();
+        for fen in crate::BENCH_POSITIONS {
+            let position = position(fen);
+            let expected = params.evaluate(&position);
+            assert_eq!(params.evaluate_cached(&position, &mut pawns), expected);
+            // now from the cache
+            assert_eq!(params.evaluate_cached(&position, &mut pawns), expected);
+        }
+    }
+
+    #[test]
+    fn pawn_structure() {
+        let params = EvalParams {
+            piece_values: [Tapered::default(); 6],
+            piece_squares: [[Tapered::default(); 64]; 6],
+            mobility: [Tapered::default(); 6],
+            ..Default::default()
+        };
+
+        // doubled and isolated on the a file, passed on h, and black's isolated b pawn,
+        // with only pawns left so it is all endgame
+        let position = position("4k3/8/8/7P/1p6/P7/P7/4K3 w - - 0 1");
+        let expected = params.doubled_pawn + params.isolated_pawn * 2 + params.passed_pawn[4];
+        assert_eq!(params.evaluate(&position), expected.eg);
+    }
+
+    #[test]
+    fn outposts_and_rooks() {
+        let mut params = EvalParams::default();
+        params.set_weights(&[0; WEIGHT_COUNT]);
+        params.knight_outpost = Tapered::flat(1);
+        params.rook_open_file = Tapered::flat(10);
+        params.rook_semi_open_file = Tapered::flat(100);
+        params.rook_seventh_rank = Tapered::flat(1000);
+
+        // the knight is defended by the e pawn with no black pawns beside it,
+        // the a file is half open and the c rook is on an open file and the seventh
+        let outpost = position("4k3/2R5/p7/3N4/4P3/8/8/R3K3 w - - 0 1");
+        assert_eq!(params.evaluate(&outpost), 1111);
+
+        // a pawn that can chase the knight away, and one that closes the a file
+        let chased = position("4k3/2R5/4p3/3N4/4P3/P7/8/R3K3 w - - 0 1");
+        assert_eq!(params.evaluate(&chased), 1010);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let mut params = EvalParams::default();
+        params.mobility[PieceKind::Knight as usize].mg = 7;
+        params.piece_squares[0][8].eg = 42;
+
+        assert_eq!(params.to_string().parse::<EvalParams>().unwrap(), params);
+
+        let partial = "# only pawns\ndoubled_pawn = -30\n"
+            .parse::<EvalParams>()
+            .unwrap();
+        assert_eq!(partial.doubled_pawn, Tapered::flat(-30));
+        assert_eq!(partial.isolated_pawn, EvalParams::default().isolated_pawn);
+
+        let endgame = "eg_doubled_pawn = -30".parse::<EvalParams>().unwrap();
+        assert_eq!(
+            endgame.doubled_pawn,
+            Tapered::new(EvalParams::default().doubled_pawn.mg, -30)
+        );
+
+        assert!(matches!(
+            "mobility = 1 2".parse::<EvalParams>(),
+            Err(ParamsError::WrongCount { expected: 6, .. })
+        ));
+        assert!(matches!(
+            "tempo = 10".parse::<EvalParams>(),
+            Err(ParamsError::Unknown(_))
+        ));
+    }
+}
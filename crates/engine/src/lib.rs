@@ -0,0 +1,286 @@
+//! The chesster engine, searching positions from the `board` crate.
+//!
+//! [`Chesster`] implements [`uci::Engine`], so it can be run with [`uci::run`].
+
+mod analysis;
+mod annotate;
+mod bench;
+mod endgame;
+mod eval;
+mod mate;
+mod movepick;
+mod pawns;
+mod score;
+mod search;
+mod selfplay;
+mod stats;
+mod tablebase;
+mod timeman;
+mod tree;
+mod tt;
+
+use std::{
+    str::FromStr,
+    sync::{atomic::AtomicBool, Arc},
+    time::Instant,
+};
+
+use board::{Move, Position};
+use uci::{GoParams, Info, InfoScore, OptionError, OptionKind, Searcher, UciOption};
+
+pub use analysis::Analysis;
+pub use annotate::{annotate, Annotation, Judgement, Thresholds};
+pub use bench::{bench, BenchResult, BENCH_DEPTH, BENCH_POSITIONS};
+pub use endgame::{Kpk, FULL_SCALE};
+pub use eval::{
+    evaluate, material, phase, EvalParams, Evaluator, ParamsError, SquareTable, Tapered, MAX_PHASE,
+};
+pub use mate::solve_mate;
+pub use movepick::{
+    is_tactical, see, ContinuationHistory, Countermoves, History, Killers, MovePicker, PieceTo,
+};
+pub use pawns::{PawnEntry, PawnTable};
+pub use score::Score;
+pub use search::{
+    search, search_deterministic, search_tree, Line, SearchLimits, SearchOptions, SearchResult,
+    DETERMINISTIC_HASH_MB, MAX_PLY,
+};
+pub use selfplay::{clock_go_params, Adjudication, Openings, SelfPlay};
+pub use stats::{SearchStats, CUTOFF_INDEXES};
+pub use tablebase::{Tablebase, Wdl};
+pub use timeman::{TimeLimits, TimeManager};
+pub use tree::{Cutoff, NodeKind, SearchTree, TreeLimits, TreeNode};
+pub use tt::{Bound, TranspositionTable, TtEntry};
+
+/// The most search threads that can be asked for.
+const MAX_THREADS: usize = 256;
+
+/// The largest transposition table that can be asked for, in megabytes.
+const MAX_HASH_MB: usize = 64 * 1024;
+
+/// The most lines that can be searched at once.
+const MAX_MULTI_PV: usize = 256;
+
+/// The most contempt that can be asked for, either way, in centipawns.
+const MAX_CONTEMPT: i32 = 1000;
+
+/// The engine, searching with a configurable evaluation function.
+pub struct Chesster {
+    eval: Arc<dyn Evaluator + Send + Sync>,
+    /// Shared with any running [`Analysis`].
+    tt: Arc<TranspositionTable>,
+    threads: usize,
+    multi_pv: usize,
+    options: SearchOptions,
+    /// Search like [`search_deterministic`], but stopping when told to.
+    deterministic: bool,
+}
+
+impl Chesster {
+    /// Creates an engine that uses the default [`EvalParams`].
+    pub fn new() -> Self {
+        Self::with_params(EvalParams::default())
+    }
+
+    /// Creates an engine that evaluates with tuned [`EvalParams`].
+    pub fn with_params(params: EvalParams) -> Self {
+        Self::with_eval(params)
+    }
+
+    /// Creates an engine that scores positions with any [`Evaluator`].
+    pub fn with_eval(eval: impl Evaluator + Send + Sync + 'static) -> Self {
+        Self {
+            eval: Arc::new(eval),
+            tt: Arc::default(),
+            threads: 1,
+            multi_pv: 1,
+            options: SearchOptions::default(),
+            deterministic: false,
+        }
+    }
+
+    /// Finds the shortest forced mate in at most `moves` moves, see [`solve_mate`].
+    ///
+    /// This is what `go mate` does, before falling back to a normal search.
+    pub fn solve_mate(&self, position: &Position, moves: u32) -> Option<Vec<Move>> {
+        solve_mate(position, moves, &AtomicBool::new(false))
+    }
+}
+
+impl Default for Chesster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses a spin option's value, checking it is within `min..=max`.
+fn spin<T: FromStr + PartialOrd>(
+    name: &str,
+    value: Option<&str>,
+    min: T,
+    max: T,
+) -> Result<T, OptionError> {
+    value
+        .and_then(|value| value.parse().ok())
+        .filter(|value| (min..=max).contains(value))
+        .ok_or_else(|| OptionError::InvalidValue(name.to_owned()))
+}
+
+/// Parses a check option's value.
+fn check(name: &str, value: Option<&str>) -> Result<bool, OptionError> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| OptionError::InvalidValue(name.to_owned()))
+}
+
+/// An `info` line for each of the result's lines.
+fn info(result: &SearchResult) -> impl Iterator<Item = Info> + '_ {
+    let nps = result.nodes * 1000 / (result.time.as_millis() as u64).max(1);
+    let multi_pv = result.lines.len() > 1;
+
+    result.lines.iter().zip(1..).map(move |(line, rank)| Info {
+        depth: Some(result.depth),
+        seldepth: Some(result.seldepth),
+        multipv: multi_pv.then_some(rank),
+        score: Some(line.score.into()),
+        nodes: Some(result.nodes),
+        nps: Some(nps),
+        time: Some(result.time),
+        pv: line.pv.clone(),
+        ..Default::default()
+    })
+}
+
+impl uci::Engine for Chesster {
+    fn name(&self) -> &str {
+        concat!("chesster ", env!("CARGO_PKG_VERSION"))
+    }
+
+    fn author(&self) -> &str {
+        "Joe Loach"
+    }
+
+    fn options(&self) -> Vec<UciOption> {
+        vec![
+            UciOption::new(
+                "Hash",
+                OptionKind::Spin {
+                    default: TranspositionTable::DEFAULT_MB as i64,
+                    min: 1,
+                    max: MAX_HASH_MB as i64,
+                },
+            ),
+            UciOption::new(
+                "Threads",
+                OptionKind::Spin {
+                    default: 1,
+                    min: 1,
+                    max: MAX_THREADS as i64,
+                },
+            ),
+            UciOption::new(
+                "MultiPV",
+                OptionKind::Spin {
+                    default: 1,
+                    min: 1,
+                    max: MAX_MULTI_PV as i64,
+                },
+            ),
+            UciOption::new(
+                "Contempt",
+                OptionKind::Spin {
+                    default: 0,
+                    min: -MAX_CONTEMPT as i64,
+                    max: MAX_CONTEMPT as i64,
+                },
+            ),
+            UciOption::new("Clear Hash", OptionKind::Button),
+            UciOption::new("NullMove", OptionKind::Check { default: true }),
+            UciOption::new("LateMoveReductions", OptionKind::Check { default: true }),
+            UciOption::new("Futility", OptionKind::Check { default: true }),
+            UciOption::new("ReverseFutility", OptionKind::Check { default: true }),
+            UciOption::new("AspirationWindows", OptionKind::Check { default: true }),
+            UciOption::new("Deterministic", OptionKind::Check { default: false }),
+        ]
+    }
+
+    fn set_option(&mut self, name: &str, value: Option<&str>) -> Result<(), OptionError> {
+        match name {
+            "Hash" => {
+                self.tt = Arc::new(TranspositionTable::new(spin(name, value, 1, MAX_HASH_MB)?))
+            }
+            "Threads" => self.threads = spin(name, value, 1, MAX_THREADS)?,
+            "MultiPV" => self.multi_pv = spin(name, value, 1, MAX_MULTI_PV)?,
+            "Contempt" => self.options.contempt = spin(name, value, -MAX_CONTEMPT, MAX_CONTEMPT)?,
+            "Clear Hash" => self.tt.clear(),
+            "NullMove" => self.options.null_move = check(name, value)?,
+            "LateMoveReductions" => self.options.late_move_reductions = check(name, value)?,
+            "Futility" => self.options.futility = check(name, value)?,
+            "ReverseFutility" => self.options.reverse_futility = check(name, value)?,
+            "AspirationWindows" => self.options.aspiration_windows = check(name, value)?,
+            "Deterministic" => self.deterministic = check(name, value)?,
+            _ => return Err(OptionError::Unknown(name.to_owned())),
+        }
+        Ok(())
+    }
+
+    fn new_game(&mut self) {
+        self.tt.clear();
+    }
+
+    fn search(
+        &mut self,
+        position: &Position,
+        params: &GoParams,
+        searcher: &Searcher,
+    ) -> Option<Move> {
+        let mut limits =
+            SearchLimits::from_go(params, position.side_to_move()).multi_pv(self.multi_pv);
+        let stop = searcher.stop_flag();
+
+        // the same `go` repeats the same search, on one thread from an empty table,
+        // searching until a depth or node limit or `stop` rather than watching the clock
+        let threads = if self.deterministic {
+            self.tt.clear();
+            limits.time = None;
+            1
+        } else {
+            self.threads
+        };
+
+        // look for a forced mate first, falling back to a normal search without one
+        if let Some(moves) = params.mate {
+            let start = Instant::now();
+            if let Some(line) = solve_mate(position, moves, &stop) {
+                searcher.info(&Info {
+                    depth: Some(line.len() as u32),
+                    score: Some(InfoScore::Mate(line.len().div_ceil(2) as i32)),
+                    time: Some(start.elapsed()),
+                    pv: line.clone(),
+                    ..Default::default()
+                });
+                return line.first().copied();
+            }
+        }
+
+        let result = search(
+            position,
+            &*self.eval,
+            &limits,
+            self.options,
+            &self.tt,
+            threads,
+            &stop,
+            |result| {
+                let hashfull = self.tt.hashfull();
+                for info in info(result) {
+                    searcher.info(&Info {
+                        hashfull: Some(hashfull),
+                        ..info
+                    });
+                }
+            },
+        );
+        result.best_move()
+    }
+}
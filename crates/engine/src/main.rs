@@ -0,0 +1,103 @@
+use std::process::ExitCode;
+
+use board::{Position, FEN};
+use engine::{EvalParams, SearchLimits, SearchOptions, TreeLimits};
+
+const TREE_USAGE: &str = "usage: chesster tree <depth> [--plies N] [--nodes N] [--dot] [fen]";
+
+fn parse_next<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>) -> Option<T> {
+    args.next()?.parse().ok()
+}
+
+/// Searches a position to a depth and prints the tree of the last iteration,
+/// as JSON or with `--dot` as a GraphViz graph.
+fn tree(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let depth = args.next().and_then(|depth| depth.parse().ok());
+    let depth = depth.ok_or(TREE_USAGE)?;
+    let mut bounds = TreeLimits::default();
+    let mut dot = false;
+    let mut fen = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--plies" => bounds.plies = parse_next(&mut args).ok_or(TREE_USAGE)?,
+            "--nodes" => bounds.nodes = parse_next(&mut args).ok_or(TREE_USAGE)?,
+            "--dot" => dot = true,
+            _ => fen.push(arg),
+        }
+    }
+    let position = match fen.join(" ") {
+        fen if fen.is_empty() => Position::start(),
+        fen => FEN::try_from(fen.as_str())
+            .and_then(FEN::parse_position)
+            .map_err(|e| format!("{fen}: {e}"))?,
+    };
+
+    let limits = SearchLimits::new().depth(depth);
+    let (_, tree) = engine::search_tree(
+        &position,
+        &EvalParams::default(),
+        &limits,
+        SearchOptions::default(),
+        bounds,
+    );
+    if dot {
+        print!("{}", tree.to_dot());
+    } else {
+        println!("{}", tree.to_json());
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        None => match uci::run_stdio(engine::Chesster::new()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        },
+        Some("xboard") => match uci::cecp::run_stdio(engine::Chesster::new()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        },
+        Some("bench") => {
+            let (flags, rest): (Vec<_>, Vec<_>) = args.partition(|arg| arg.starts_with("--"));
+            let stats = flags.iter().any(|flag| flag == "--stats");
+            let depth = match rest.first().map(|depth| depth.parse()) {
+                _ if rest.len() > 1 || flags.len() > usize::from(stats) => None,
+                None => Some(engine::BENCH_DEPTH),
+                Some(depth) => depth.ok(),
+            };
+            let Some(depth) = depth else {
+                eprintln!("usage: chesster bench [depth] [--stats]");
+                return ExitCode::FAILURE;
+            };
+            let result = engine::bench(&engine::EvalParams::default(), depth);
+            println!("Total time (ms) : {}", result.time.as_millis());
+            println!("Nodes searched  : {}", result.nodes);
+            println!("Nodes/second    : {}", result.nps());
+            if stats {
+                println!("\n{}", result.stats);
+            }
+            ExitCode::SUCCESS
+        }
+        Some("tree") => match tree(args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        },
+        Some(command) => {
+            eprintln!(
+                "unknown command '{command}', expected 'bench', 'tree', 'xboard' or no arguments"
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
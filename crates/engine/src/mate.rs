@@ -0,0 +1,116 @@
+//! A solver that only looks for forced mates, ignoring every other way of winning.
+//!
+//! It proves a mate rather than estimating a score, so unlike the main search a mate it
+//! finds is certain, and one it doesn't find doesn't exist within the given number of moves.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use board::{Move, Position};
+
+/// Finds the shortest forced mate for the player to move in at most `moves` of their moves.
+///
+/// Returns the mating line, with the defence that holds out longest,
+/// or None if there is no such mate or `stop` was set before one was found.
+pub fn solve_mate(position: &Position, moves: u32, stop: &AtomicBool) -> Option<Vec<Move>> {
+    let mut position = position.clone();
+    (1..=moves).find_map(|n| {
+        let mut line = attack(&mut position, n, stop)?;
+        line.reverse();
+        Some(line)
+    })
+}
+
+/// Finds a move that mates within `n` moves, returning the line backwards.
+fn attack(position: &mut Position, n: u32, stop: &AtomicBool) -> Option<Vec<Move>> {
+    if stop.load(Ordering::Relaxed) {
+        return None;
+    }
+    let them = position.side_to_move().opposite();
+
+    for &mv in &position.legal_moves() {
+        position.make_move(mv);
+        // the last move has to be mate, so it has to give check
+        let line = if n > 1 || position.in_check(them) {
+            defend(position, n, stop)
+        } else {
+            None
+        };
+        position.unmake_move();
+
+        if let Some(mut line) = line {
+            line.push(mv);
+            return Some(line);
+        }
+    }
+    None
+}
+
+/// Checks every defence loses to a mate within `n` moves, counting the one just played,
+/// returning the longest line backwards.
+fn defend(position: &mut Position, n: u32, stop: &AtomicBool) -> Option<Vec<Move>> {
+    let moves = position.legal_moves();
+    if moves.is_empty() {
+        // mate, or stalemate which isn't
+        return position.in_check(position.side_to_move()).then(Vec::new);
+    }
+    if n == 1 {
+        return None;
+    }
+
+    let mut longest = Vec::new();
+    for &mv in &moves {
+        position.make_move(mv);
+        let line = attack(position, n - 1, stop);
+        position.unmake_move();
+
+        let mut line = line?;
+        line.push(mv);
+        if line.len() > longest.len() {
+            longest = line;
+        }
+    }
+    Some(longest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::FEN;
+
+    fn solve(fen: &str, moves: u32) -> Option<Vec<String>> {
+        let position = FEN::new(fen).parse_position().unwrap();
+        let line = solve_mate(&position, moves, &AtomicBool::new(false))?;
+
+        let mut replay = position.clone();
+        let line = line
+            .into_iter()
+            .map(|mv| {
+                let uci = replay.uci(mv);
+                replay.make_move(mv);
+                uci
+            })
+            .collect();
+        assert!(replay.is_checkmate());
+        Some(line)
+    }
+
+    #[test]
+    fn back_rank_mates() {
+        assert_eq!(
+            solve("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3).unwrap(),
+            ["a1a8"]
+        );
+        // the king has to come closer first
+        let corner = "k7/8/2K5/8/8/8/8/7R w - - 0 1";
+        assert_eq!(solve(corner, 1), None);
+        assert_eq!(solve(corner, 3).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn stalemate_is_not_mate() {
+        // Qf7 would only stalemate, so the queen mates from further away
+        let line = solve("7k/8/6K1/8/8/8/8/5Q2 w - - 0 1", 1).unwrap();
+        assert_ne!(line[0], "f1f7");
+        assert_eq!(solve("7k/8/8/8/8/8/8/K7 w - - 0 1", 3), None);
+    }
+}
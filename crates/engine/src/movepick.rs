@@ -0,0 +1,477 @@
+//! Move ordering: the order moves are searched in decides how much alpha-beta can prune.
+//!
+//! [`MovePicker`] hands out moves in stages, only scoring and sorting
+//! each stage when the search gets to it, since a cutoff often comes
+//! before the later stages are needed.
+
+use board::{Color, Move, MoveFlag, MoveList, Piece, PieceKind, Position, Square};
+
+use crate::MAX_PLY;
+
+/// The largest magnitude a [`History`] or [`ContinuationHistory`] score can reach.
+const MAX_HISTORY: i32 = 16_384;
+
+/// The number of [`PieceTo`] indices, a piece of either color on any square.
+const PIECE_TO: usize = 12 * 64;
+
+/// Captures and promotions, which change the material balance.
+pub fn is_tactical(position: &Position, mv: Move) -> bool {
+    position.board().occupied().is_on(mv.to())
+        || mv.flag() == MoveFlag::EnPassant
+        || mv.promotion().is_some()
+}
+
+/// [Static exchange evaluation](https://www.chessprogramming.org/Static_Exchange_Evaluation),
+/// see [`Position::see`].
+pub fn see(position: &Position, mv: Move) -> i32 {
+    position.see(mv)
+}
+
+/// Captures of the most valuable victim by the least valuable attacker first.
+fn mvv_lva(position: &Position, mv: Move) -> i32 {
+    let board = position.board();
+    let victim = match mv.flag() {
+        MoveFlag::EnPassant => Some(PieceKind::Pawn),
+        _ => board.kind_on(mv.to()),
+    };
+    let attacker = board
+        .kind_on(mv.from())
+        .expect("a piece should be on `from`");
+
+    let mut score = victim.map_or(0, |victim| 10 * (victim as i32 + 1) - attacker as i32);
+    if let Some(promotion) = mv.promotion() {
+        score += 10 * promotion as i32;
+    }
+    score
+}
+
+/// Quiet moves that caused a cutoff at each ply, likely to do so again in sibling positions.
+pub struct Killers([[Option<Move>; 2]; MAX_PLY as usize + 1]);
+
+impl Killers {
+    pub fn new() -> Self {
+        Self([[None; 2]; MAX_PLY as usize + 1])
+    }
+
+    /// The killer moves at `ply`, most recent first.
+    #[inline]
+    pub fn get(&self, ply: u32) -> [Option<Move>; 2] {
+        self.0[ply as usize]
+    }
+
+    /// Remembers a quiet move that caused a cutoff at `ply`.
+    pub fn store(&mut self, ply: u32, mv: Move) {
+        let slots = &mut self.0[ply as usize];
+        if slots[0] != Some(mv) {
+            slots[1] = slots[0];
+            slots[0] = Some(mv);
+        }
+    }
+}
+
+impl Default for Killers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How often each quiet move has caused a cutoff, by color and squares,
+/// used to order quiet moves that aren't killers.
+pub struct History([[[i32; 64]; 64]; 2]);
+
+impl History {
+    pub fn new() -> Self {
+        Self([[[0; 64]; 64]; 2])
+    }
+
+    /// The score of `mv` when played by `color`.
+    #[inline]
+    pub fn get(&self, color: Color, mv: Move) -> i32 {
+        self.0[color as usize][mv.from() as usize][mv.to() as usize]
+    }
+
+    /// Adds `bonus` to the score of `mv`, negative for a penalty.
+    ///
+    /// Scores are pulled back towards zero as they grow, so they stay
+    /// within [`MAX_HISTORY`] and recent results count for more.
+    pub fn update(&mut self, color: Color, mv: Move, bonus: i32) {
+        add_bonus(
+            &mut self.0[color as usize][mv.from() as usize][mv.to() as usize],
+            bonus,
+        );
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Adds `bonus` to a history score, pulling it back towards zero as it grows.
+fn add_bonus(entry: &mut i32, bonus: i32) {
+    let bonus = bonus.clamp(-MAX_HISTORY, MAX_HISTORY);
+    *entry += bonus - *entry * bonus.abs() / MAX_HISTORY;
+}
+
+/// A piece arriving on a square, which is how [`Countermoves`]
+/// and [`ContinuationHistory`] tell moves apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PieceTo {
+    pub piece: Piece,
+    pub to: Square,
+}
+
+impl PieceTo {
+    /// The piece `mv` moves or drops in `position`, before it is made.
+    pub fn new(position: &Position, mv: Move) -> Self {
+        let piece = match mv.dropped() {
+            Some(kind) => Piece::new_with(position.side_to_move(), kind),
+            None => position
+                .board()
+                .piece_on(mv.from())
+                .expect("a piece should be on `from`"),
+        };
+        Self { piece, to: mv.to() }
+    }
+
+    fn index(self) -> usize {
+        (self.piece.color() as usize * 6 + self.piece.kind() as usize) * 64 + self.to as usize
+    }
+}
+
+/// The quiet move that last caused a cutoff in reply to each move.
+pub struct Countermoves([Option<Move>; PIECE_TO]);
+
+impl Countermoves {
+    pub fn new() -> Self {
+        Self([None; PIECE_TO])
+    }
+
+    /// The countermove to `previous`, the move played to reach the position.
+    #[inline]
+    pub fn get(&self, previous: PieceTo) -> Option<Move> {
+        self.0[previous.index()]
+    }
+
+    pub fn store(&mut self, previous: PieceTo, mv: Move) {
+        self.0[previous.index()] = Some(mv);
+    }
+}
+
+impl Default for Countermoves {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How often each quiet move has caused a cutoff when played after another move,
+/// such as the move one or two plies before it.
+///
+/// This tells apart moves that [`History`] lumps together,
+/// since a good reply to one move can be a poor reply to another.
+pub struct ContinuationHistory(Box<[i32]>);
+
+impl ContinuationHistory {
+    pub fn new() -> Self {
+        Self(vec![0; PIECE_TO * PIECE_TO].into_boxed_slice())
+    }
+
+    /// The scores of every move after `previous`, for [`MovePicker::next`].
+    #[inline]
+    pub fn get(&self, previous: PieceTo) -> &[i32] {
+        &self.0[previous.index() * PIECE_TO..][..PIECE_TO]
+    }
+
+    /// Adds `bonus` to the score of `mv` after `previous`, negative for a penalty.
+    pub fn update(&mut self, previous: PieceTo, mv: PieceTo, bonus: i32) {
+        add_bonus(&mut self.0[previous.index() * PIECE_TO + mv.index()], bonus);
+    }
+}
+
+impl Default for ContinuationHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The score of `mv` in a table from [`ContinuationHistory::get`].
+#[inline]
+fn score(table: &[i32], mv: PieceTo) -> i32 {
+    table[mv.index()]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    Hint,
+    GoodCaptures,
+    Killers,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+/// Moves with a score each, handed out best first.
+struct Scored {
+    moves: MoveList,
+    scores: [i32; MoveList::CAPACITY],
+    /// Moves before this have already been handed out.
+    next: usize,
+}
+
+impl Scored {
+    fn new() -> Self {
+        Self {
+            moves: MoveList::new(),
+            scores: [0; MoveList::CAPACITY],
+            next: 0,
+        }
+    }
+
+    fn push(&mut self, mv: Move, score: i32) {
+        self.scores[self.moves.len()] = score;
+        self.moves.push(mv);
+    }
+
+    /// Takes out the best move left, a selection sort being cheapest
+    /// when only the first few moves are usually needed.
+    fn pop_best(&mut self) -> Option<Move> {
+        let best = (self.next..self.moves.len()).max_by_key(|&i| self.scores[i])?;
+        self.moves.swap(self.next, best);
+        self.scores.swap(self.next, best);
+        self.next += 1;
+        Some(self.moves[self.next - 1])
+    }
+}
+
+/// Hands out the legal moves of a position in the order they should be searched:
+///
+/// 1. the hint, usually the transposition table or principal variation move
+/// 2. captures that don't lose material, by [`mvv_lva`]
+/// 3. the [`Killers`] for this ply, then the [`Countermoves`] entry for the last move
+/// 4. the remaining quiet moves, by [`History`] and [`ContinuationHistory`]
+/// 5. captures that lose material
+pub struct MovePicker {
+    stage: Stage,
+    hint: Option<Move>,
+    /// The killers followed by the countermove.
+    refutations: [Option<Move>; 3],
+    /// How many refutations have been handed out.
+    refutation_index: usize,
+    captures: Option<Scored>,
+    quiets: Option<Scored>,
+    bad_captures: Scored,
+    moves: MoveList,
+}
+
+impl MovePicker {
+    /// Creates a picker for the legal `moves` of `position`.
+    pub fn new(
+        position: &Position,
+        moves: MoveList,
+        hint: Option<Move>,
+        killers: [Option<Move>; 2],
+        countermove: Option<Move>,
+    ) -> Self {
+        let hint = hint.filter(|hint| moves.contains(hint));
+        let mut refutations = [killers[0], killers[1], countermove];
+        for index in 0..refutations.len() {
+            refutations[index] = refutations[index].filter(|&mv| {
+                Some(mv) != hint
+                    && !refutations[..index].contains(&Some(mv))
+                    && moves.contains(&mv)
+                    && !is_tactical(position, mv)
+            });
+        }
+
+        Self {
+            stage: Stage::Hint,
+            hint,
+            refutations,
+            refutation_index: 0,
+            captures: None,
+            quiets: None,
+            bad_captures: Scored::new(),
+            moves,
+        }
+    }
+
+    /// The next move to search, or None once every move has been handed out.
+    ///
+    /// `continuations` are the [`ContinuationHistory`] tables for the moves
+    /// one and two plies ago, where there were any.
+    pub fn next(
+        &mut self,
+        position: &Position,
+        history: &History,
+        continuations: [Option<&[i32]>; 2],
+    ) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::Hint => {
+                    self.stage = Stage::GoodCaptures;
+                    if self.hint.is_some() {
+                        return self.hint;
+                    }
+                }
+                Stage::GoodCaptures => {
+                    let captures = self.captures.get_or_insert_with(|| {
+                        let mut good = Scored::new();
+                        for &mv in self.moves.iter() {
+                            if Some(mv) == self.hint || !is_tactical(position, mv) {
+                                continue;
+                            }
+                            if see(position, mv) >= 0 {
+                                good.push(mv, mvv_lva(position, mv));
+                            } else {
+                                self.bad_captures.push(mv, mvv_lva(position, mv));
+                            }
+                        }
+                        good
+                    });
+                    match captures.pop_best() {
+                        Some(mv) => return Some(mv),
+                        None => self.stage = Stage::Killers,
+                    }
+                }
+                Stage::Killers => match self.refutations.get(self.refutation_index) {
+                    Some(&refutation) => {
+                        self.refutation_index += 1;
+                        if refutation.is_some() {
+                            return refutation;
+                        }
+                    }
+                    None => self.stage = Stage::Quiets,
+                },
+                Stage::Quiets => {
+                    let quiets = self.quiets.get_or_insert_with(|| {
+                        let mut quiets = Scored::new();
+                        let us = position.side_to_move();
+                        for &mv in self.moves.iter() {
+                            let skip = Some(mv) == self.hint
+                                || is_tactical(position, mv)
+                                || self.refutations.contains(&Some(mv));
+                            if skip {
+                                continue;
+                            }
+                            let piece_to = PieceTo::new(position, mv);
+                            let continuation: i32 = continuations
+                                .iter()
+                                .flatten()
+                                .map(|table| score(table, piece_to))
+                                .sum();
+                            quiets.push(mv, history.get(us, mv) + continuation);
+                        }
+                        quiets
+                    });
+                    match quiets.pop_best() {
+                        Some(mv) => return Some(mv),
+                        None => self.stage = Stage::BadCaptures,
+                    }
+                }
+                Stage::BadCaptures => match self.bad_captures.pop_best() {
+                    Some(mv) => return Some(mv),
+                    None => self.stage = Stage::Done,
+                },
+                Stage::Done => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::FEN;
+
+    fn position(fen: &str) -> Position {
+        FEN::new(fen).parse_position().unwrap()
+    }
+
+    #[test]
+    fn static_exchange() {
+        // the pawn on d5 is defended, so taking it with the queen loses the queen
+        let pos = position("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1");
+        assert_eq!(see(&pos, pos.parse_uci("d2d5").unwrap()), 100 - 900);
+
+        // an undefended rook is simply won
+        let pos = position("4k3/8/8/3r4/8/4N3/8/4K3 w - - 0 1");
+        assert_eq!(see(&pos, pos.parse_uci("e3d5").unwrap()), 500);
+
+        // knight takes a defended pawn, pawn recaptures, the rook behind doesn't help
+        let pos = position("4k3/8/2p5/3p4/8/4N3/8/3RK3 w - - 0 1");
+        assert_eq!(see(&pos, pos.parse_uci("e3d5").unwrap()), 100 - 320 + 100);
+    }
+
+    #[test]
+    fn picker_stages() {
+        let pos = position("4k3/8/2p5/3p1r2/8/4N3/8/3QK3 w - - 0 1");
+        let mv = |uci| pos.parse_uci(uci).unwrap();
+        let killer = mv("e1e2");
+        let countermove = mv("d1d2");
+        let mut history = History::new();
+        history.update(Color::White, mv("e3g4"), 100);
+        // after the rook came to f5, the knight going to c4 has done better
+        let previous = PieceTo {
+            piece: Piece::new_with(Color::Black, PieceKind::Rook),
+            to: Square::F5,
+        };
+        let mut continuations = ContinuationHistory::new();
+        continuations.update(previous, PieceTo::new(&pos, mv("e3c4")), 500);
+
+        let mut picker = MovePicker::new(
+            &pos,
+            pos.legal_moves(),
+            Some(mv("d1a4")),
+            [Some(killer), None],
+            Some(countermove),
+        );
+        let mut order = Vec::new();
+        let tables = [Some(continuations.get(previous)), None];
+        while let Some(mv) = picker.next(&pos, &history, tables) {
+            order.push(mv);
+        }
+
+        assert_eq!(order.len(), pos.legal_moves().len());
+        assert_eq!(
+            &order[..6],
+            [
+                mv("d1a4"),
+                mv("e3f5"),
+                killer,
+                countermove,
+                mv("e3c4"),
+                mv("e3g4")
+            ]
+        );
+        // captures of the defended pawn lose material, so come last
+        assert_eq!(&order[order.len() - 2..], [mv("e3d5"), mv("d1d5")]);
+    }
+
+    #[test]
+    fn picks_every_drop() {
+        // more legal moves than standard chess can have
+        let pos = position("4k3/8/8/8/8/8/8/R3K2R[QRBNPqrbnp] w KQ - 0 1");
+        let moves = pos.legal_moves();
+        assert!(moves.len() > 256);
+
+        let mut picker = MovePicker::new(&pos, moves.clone(), None, [None, None], None);
+        let history = History::new();
+        let mut picked = 0;
+        while picker.next(&pos, &history, [None, None]).is_some() {
+            picked += 1;
+        }
+        assert_eq!(picked, moves.len());
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = History::new();
+        let mv = board::Move::new(board::Square::G1, board::Square::F3, MoveFlag::Normal);
+        for _ in 0..1000 {
+            history.update(Color::White, mv, 10_000);
+        }
+        assert!(history.get(Color::White, mv) <= MAX_HISTORY);
+        assert_eq!(history.get(Color::Black, mv), 0);
+    }
+}
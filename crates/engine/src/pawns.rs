@@ -0,0 +1,141 @@
+//! A cache of pawn structure, keyed by [`Position::pawn_key`].
+//!
+//! Pawns move rarely compared to the other pieces, so most positions a search evaluates
+//! share their pawn structure with one evaluated shortly before.
+
+use board::{BitBoard, Board, Color, Position, Rank};
+
+/// The pawn structure of a position, which only depends on where the pawns are.
+///
+/// Only counts are stored rather than scores, so an entry can be scored with any weights.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PawnEntry {
+    key: u64,
+    /// Pawns with no enemy pawns in front of them or on neighbouring files, by color.
+    pub passed: [BitBoard; 2],
+    /// How many pawns have no friendly pawns on either neighbouring file, by color.
+    pub isolated: [u8; 2],
+    /// How many pawns are behind another on the same file, by color.
+    pub doubled: [u8; 2],
+    /// How many pawns shelter a king on each file of its back rank, by color then file.
+    ///
+    /// Those are the pawns on the two ranks in front of the back rank,
+    /// on the king's file or either side of it.
+    pub shield: [[u8; 8]; 2],
+}
+
+impl PawnEntry {
+    /// Analyses the pawns on `board` from scratch.
+    pub fn analyze(board: &Board) -> Self {
+        let mut entry = Self::default();
+        for us in [Color::White, Color::Black] {
+            entry.analyze_side(board, us);
+        }
+        entry
+    }
+
+    fn analyze_side(&mut self, board: &Board, us: Color) {
+        let side = us as usize;
+        let ours = board.pawns() & board.colors(us);
+        let theirs = board.pawns() & board.colors(us.opposite());
+        // ranks counted from our side of the board
+        let relative = |rank: Rank| rank.relative_to(us);
+
+        // how many of our pawns are on each file, with an empty file either side
+        let mut files = [0_u8; 10];
+        for pawn in ours {
+            files[pawn.file().number() as usize] += 1;
+        }
+        self.doubled[side] = files.iter().map(|count| count.saturating_sub(1)).sum();
+
+        for pawn in ours {
+            let file = pawn.file().number() as usize;
+            if files[file - 1] == 0 && files[file + 1] == 0 {
+                self.isolated[side] += 1;
+            }
+
+            let blocked = theirs.into_iter().any(|enemy| {
+                enemy.file().distance(pawn.file()) <= 1
+                    && relative(enemy.rank()) > relative(pawn.rank())
+            });
+            if !blocked {
+                self.passed[side] |= BitBoard::from_square(pawn);
+            }
+
+            if matches!(relative(pawn.rank()), Rank::Second | Rank::Third) {
+                for king_file in file.saturating_sub(1).max(1)..=(file + 1).min(8) {
+                    self.shield[side][king_file - 1] += 1;
+                }
+            }
+        }
+    }
+}
+
+/// A fixed size cache of [`PawnEntry`], each search thread having its own.
+pub struct PawnTable {
+    entries: Box<[PawnEntry]>,
+}
+
+impl PawnTable {
+    /// How many entries a table holds, a power of two.
+    pub const ENTRIES: usize = 1 << 14;
+
+    /// Creates an empty table.
+    pub fn new() -> Self {
+        Self {
+            entries: vec![PawnEntry::default(); Self::ENTRIES].into_boxed_slice(),
+        }
+    }
+
+    /// The pawn structure of `position`, analysing it if it isn't cached.
+    pub fn probe(&mut self, position: &Position) -> &PawnEntry {
+        let key = position.pawn_key();
+        let entry = &mut self.entries[key as usize & (Self::ENTRIES - 1)];
+        // an empty entry is the analysis of a board without pawns, whose key is 0
+        if entry.key != key {
+            *entry = PawnEntry {
+                key,
+                ..PawnEntry::analyze(position.board())
+            };
+        }
+        entry
+    }
+}
+
+impl Default for PawnTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::{Square, FEN};
+
+    #[test]
+    fn analysis_is_cached() {
+        // doubled and isolated on the a file, passed on h, and black's isolated b pawn
+        let position = FEN::new("4k3/8/8/7P/1p6/P7/P7/4K3 w - - 0 1")
+            .parse_position()
+            .unwrap();
+        let entry = PawnEntry::analyze(position.board());
+        assert_eq!(entry.doubled, [1, 0]);
+        assert_eq!(entry.isolated, [3, 1]);
+        assert_eq!(
+            entry.passed,
+            [BitBoard::from_square(Square::H5), BitBoard::EMPTY]
+        );
+        // the a2 and a3 pawns shelter a king on the a or b file
+        assert_eq!(entry.shield[0], [2, 2, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(entry.shield[1], [0; 8]);
+
+        let mut table = PawnTable::new();
+        assert_eq!(table.probe(&position).passed, entry.passed);
+        let mut moved = position.clone();
+        moved.make_move(moved.parse_uci("e1d2").unwrap());
+        assert_eq!(moved.pawn_key(), position.pawn_key());
+        let cached = *table.probe(&position);
+        assert_eq!(*table.probe(&moved), cached);
+    }
+}
@@ -0,0 +1,209 @@
+//! Search scores, which are either centipawns or a distance to mate.
+
+use std::{
+    fmt,
+    ops::{Add, Neg, Sub},
+};
+
+use board::pgn::Eval;
+use uci::InfoScore;
+
+use crate::MAX_PLY;
+
+/// A search score from the point of view of the side to move.
+///
+/// Mates are scored [`Score::MATE`] less the plies until mate, and negated when being mated,
+/// so nearer mates are better and every mate outranks every centipawn score.
+/// Adding centipawns to a mate is allowed, as pruning margins do,
+/// but the result should only be compared, not reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Score(i32);
+
+impl Score {
+    /// An even position, and the score of a draw.
+    pub const DRAW: Self = Self(0);
+
+    /// The score of delivering checkmate right now.
+    pub const MATE: Self = Self(32_000);
+
+    /// Above any score a search can return, for the bounds of a full window.
+    pub(crate) const INFINITY: Self = Self(Self::MATE.0 + 1);
+
+    /// Scores at least this far from zero are mates, which pruning margins
+    /// mustn't be applied to.
+    pub(crate) const MATE_BOUND: Self = Self(Self::MATE.0 - MAX_PLY as i32);
+
+    pub const fn centipawns(centipawns: i32) -> Self {
+        Self(centipawns)
+    }
+
+    /// The score for mating in `plies` plies.
+    pub const fn mating(plies: u32) -> Self {
+        Self(Self::MATE.0 - plies as i32)
+    }
+
+    /// The score for being mated in `plies` plies.
+    pub const fn mated(plies: u32) -> Self {
+        Self(-Self::MATE.0 + plies as i32)
+    }
+
+    pub const fn is_mate(self) -> bool {
+        self.0.abs() >= Self::MATE_BOUND.0
+    }
+
+    /// The number of moves until mate, negative when being mated.
+    pub const fn mate_in(self) -> Option<i32> {
+        if !self.is_mate() {
+            None
+        } else if self.0 > 0 {
+            Some((Self::MATE.0 - self.0 + 1) / 2)
+        } else {
+            Some(-(Self::MATE.0 + self.0) / 2)
+        }
+    }
+
+    /// The score in centipawns, or None for a mate.
+    pub const fn as_centipawns(self) -> Option<i32> {
+        if self.is_mate() {
+            None
+        } else {
+            Some(self.0)
+        }
+    }
+
+    /// The score as a number, with mates beyond [`Score::MATE_BOUND`].
+    pub const fn raw(self) -> i32 {
+        self.0
+    }
+
+    pub const fn abs(self) -> Self {
+        Self(self.0.abs())
+    }
+
+    /// The score to store at `ply` in the transposition table.
+    ///
+    /// Mate scores are stored relative to the position rather than the root,
+    /// so they stay correct when the position is reached at a different ply.
+    pub(crate) const fn stored_at(self, ply: u32) -> Self {
+        match self.0 {
+            score if score >= Self::MATE_BOUND.0 => Self(score + ply as i32),
+            score if score <= -Self::MATE_BOUND.0 => Self(score - ply as i32),
+            score => Self(score),
+        }
+    }
+
+    /// A score read from the transposition table at `ply`, see [`Score::stored_at`].
+    pub(crate) const fn loaded_at(self, ply: u32) -> Self {
+        match self.0 {
+            score if score >= Self::MATE_BOUND.0 => Self(score - ply as i32),
+            score if score <= -Self::MATE_BOUND.0 => Self(score + ply as i32),
+            score => Self(score),
+        }
+    }
+}
+
+impl Neg for Score {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+/// Adds a margin in centipawns.
+impl Add<i32> for Score {
+    type Output = Self;
+
+    fn add(self, centipawns: i32) -> Self {
+        Self(self.0 + centipawns)
+    }
+}
+
+/// Takes away a margin in centipawns.
+impl Sub<i32> for Score {
+    type Output = Self;
+
+    fn sub(self, centipawns: i32) -> Self {
+        Self(self.0 - centipawns)
+    }
+}
+
+impl From<Score> for InfoScore {
+    fn from(score: Score) -> Self {
+        match score.mate_in() {
+            Some(moves) => InfoScore::Mate(moves),
+            None => InfoScore::Centipawns(score.0),
+        }
+    }
+}
+
+impl From<Score> for Eval {
+    fn from(score: Score) -> Self {
+        match score.mate_in() {
+            Some(moves) => Eval::Mate(moves),
+            None => Eval::Centipawns(score.0),
+        }
+    }
+}
+
+/// Reads an evaluation back, taking mates to be as far away as they can be in that many moves.
+///
+/// Mates further away than the search can see, as other engines may report,
+/// are read as the furthest mate it can score. A mate in 0 has already happened.
+impl From<Eval> for Score {
+    fn from(eval: Eval) -> Self {
+        let plies = |moves: i32| moves.unsigned_abs().min(MAX_PLY) * 2;
+        match eval {
+            Eval::Centipawns(centipawns) => Score(centipawns),
+            Eval::Mate(0) => Score::mated(0),
+            Eval::Mate(moves) if moves > 0 => Score::mating((plies(moves) - 1).min(MAX_PLY)),
+            Eval::Mate(moves) => Score::mated(plies(moves).min(MAX_PLY)),
+        }
+    }
+}
+
+/// Writes the score as UCI does, as in `score cp 34` or `score mate -3`.
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        InfoScore::from(*self).fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mates() {
+        assert!(Score::mating(1) > Score::mating(3));
+        assert!(Score::mating(99) > Score::centipawns(2000));
+        assert!(Score::mated(4) < Score::centipawns(-2000));
+        assert_eq!(-Score::mating(5), Score::mated(5));
+
+        assert_eq!(Score::mating(1).mate_in(), Some(1));
+        assert_eq!(Score::mating(5).mate_in(), Some(3));
+        assert_eq!(Score::mated(4).mate_in(), Some(-2));
+        assert_eq!(Score::centipawns(34).mate_in(), None);
+        assert_eq!(Score::mating(5).as_centipawns(), None);
+
+        assert_eq!(Score::centipawns(34).to_string(), "score cp 34");
+        assert_eq!(Score::mating(9).to_string(), "score mate 5");
+        assert_eq!(Score::mated(6).to_string(), "score mate -3");
+
+        for score in [
+            Score::mating(7),
+            Score::mated(2),
+            Score::mated(0),
+            Score::centipawns(-50),
+        ] {
+            assert_eq!(Score::from(Eval::from(score)), score);
+        }
+
+        // mates too far away to search are still mates
+        for moves in [i32::MAX, i32::MIN, 1000, -1000] {
+            let score = Score::from(Eval::Mate(moves));
+            assert!(score.is_mate());
+            assert_eq!(score > Score::DRAW, moves > 0);
+        }
+    }
+}
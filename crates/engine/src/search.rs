@@ -0,0 +1,1477 @@
+//! Iterative deepening alpha-beta search.
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
+
+use board::{movegen, Color, Move, MoveList, Piece, PieceKind, Position};
+use uci::GoParams;
+
+use crate::{
+    eval::Evaluator,
+    movepick::{
+        is_tactical, ContinuationHistory, Countermoves, History, Killers, MovePicker, PieceTo,
+    },
+    pawns::PawnTable,
+    score::Score,
+    stats::SearchStats,
+    timeman::{TimeLimits, TimeManager},
+    tree::{Cutoff, NodeKind, SearchTree, TreeLimits, TreeRecorder},
+    tt::{Bound, TranspositionTable, TtEntry},
+};
+
+/// The deepest the search will look, in plies.
+pub const MAX_PLY: u32 = 128;
+
+/// The size of the transposition table used by [`search_deterministic`], in megabytes.
+pub const DETERMINISTIC_HASH_MB: usize = 16;
+
+/// How often, in nodes, the clock and stop flag are checked.
+const CHECK_INTERVAL: u64 = 2048;
+
+/// How far either side of the last iteration's score the first aspiration window reaches,
+/// doubling every time the score falls outside it.
+const ASPIRATION_WINDOW: i32 = 25;
+
+/// The shallowest depth aspiration windows are used at,
+/// the scores of the first iterations moving around too much to be worth it.
+const ASPIRATION_DEPTH: u32 = 4;
+
+/// The shallowest depth a null move is tried at.
+const NULL_MOVE_DEPTH: u32 = 3;
+
+/// The shallowest depth a null move cutoff is verified at,
+/// below this the cutoff is trusted.
+const VERIFY_DEPTH: u32 = 6;
+
+/// How far below alpha the static evaluation must be for futility pruning,
+/// indexed by depth, which is only done at depths with a margin.
+const FUTILITY_MARGINS: [i32; 3] = [0, 150, 300];
+
+/// How far above beta, per ply of depth, the static evaluation must be
+/// for reverse futility pruning.
+const REVERSE_FUTILITY_MARGIN: i32 = 80;
+
+/// The deepest depth reverse futility pruning applies to.
+const REVERSE_FUTILITY_DEPTH: u32 = 6;
+
+/// How many moves are searched at full depth before late moves are reduced.
+const FULL_DEPTH_MOVES: usize = 3;
+
+/// The shallowest depth late moves are reduced at.
+const REDUCTION_DEPTH: u32 = 3;
+
+/// Which search techniques to use, all of them by default, and how to score draws.
+///
+/// Each technique but the aspiration windows trades accuracy for speed,
+/// and turning them off one at a time shows what they're worth.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Let the opponent move twice, and stop if we're still above beta.
+    pub null_move: bool,
+    /// Search quiet moves ordered late at a reduced depth first.
+    pub late_move_reductions: bool,
+    /// Skip quiet moves near the leaves that can't raise alpha.
+    pub futility: bool,
+    /// Stop near the leaves when the evaluation is far above beta.
+    pub reverse_futility: bool,
+    /// Search each iteration in a narrow window around the last one's score,
+    /// searching again with a wider window when the score falls outside it.
+    pub aspiration_windows: bool,
+    /// How many centipawns the side to move at the root thinks a draw is worse
+    /// than an even position, so it plays on rather than repeating or stalemating.
+    /// Negative contempt makes it look for draws instead.
+    pub contempt: i32,
+}
+
+impl SearchOptions {
+    /// A plain alpha-beta search, without any selectivity.
+    pub const NONE: Self = Self {
+        null_move: false,
+        late_move_reductions: false,
+        futility: false,
+        reverse_futility: false,
+        aspiration_windows: false,
+        contempt: 0,
+    };
+
+    /// Every technique turned on.
+    pub const ALL: Self = Self {
+        null_move: true,
+        late_move_reductions: true,
+        futility: true,
+        reverse_futility: true,
+        aspiration_windows: true,
+        contempt: 0,
+    };
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+/// When the search should give up and return its best move so far.
+///
+/// Without any limits the search only ends when it is stopped.
+/// The limits can be set field by field, or chained from [`SearchLimits::new`]:
+///
+/// ```
+/// # use std::time::Duration;
+/// # use engine::SearchLimits;
+/// let limits = SearchLimits::new()
+///     .depth(12)
+///     .move_time(Duration::from_secs(5));
+/// assert_eq!(limits.depth, Some(12));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    /// The deepest iteration to complete.
+    pub depth: Option<u32>,
+    /// Stop after visiting about this many nodes.
+    pub nodes: Option<u64>,
+    /// How long to search for.
+    pub time: Option<TimeLimits>,
+    /// Only consider these moves at the root, or all legal moves if empty.
+    pub search_moves: Vec<Move>,
+    /// How many of the best moves to find lines for, at least one.
+    pub multi_pv: usize,
+}
+
+impl SearchLimits {
+    /// No limits, finding a single line.
+    pub fn new() -> Self {
+        Self {
+            multi_pv: 1,
+            ..Self::default()
+        }
+    }
+
+    /// The limits given by a `go` command, with the clock for `us`.
+    pub fn from_go(params: &GoParams, us: Color) -> Self {
+        Self {
+            depth: params.depth,
+            nodes: params.nodes,
+            time: TimeLimits::from_go(params, us),
+            search_moves: params.search_moves.clone(),
+            multi_pv: 1,
+        }
+    }
+
+    pub fn depth(mut self, depth: u32) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub fn nodes(mut self, nodes: u64) -> Self {
+        self.nodes = Some(nodes);
+        self
+    }
+
+    /// Search for exactly `time`.
+    pub fn move_time(mut self, time: Duration) -> Self {
+        self.time = Some(TimeLimits::fixed(time));
+        self
+    }
+
+    pub fn time(mut self, time: TimeLimits) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    pub fn search_moves(mut self, moves: impl IntoIterator<Item = Move>) -> Self {
+        self.search_moves = moves.into_iter().collect();
+        self
+    }
+
+    pub fn multi_pv(mut self, lines: usize) -> Self {
+        self.multi_pv = lines;
+        self
+    }
+}
+
+/// One of the lines found by a search, see [`SearchLimits::multi_pv`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub score: Score,
+    /// The expected line of play, starting with the move being scored.
+    pub pv: Vec<Move>,
+}
+
+impl Line {
+    /// The number of moves until mate, negative when being mated.
+    pub fn mate_in(&self) -> Option<i32> {
+        self.score.mate_in()
+    }
+
+    /// The PV in numbered SAN, as in `12. Nf3 Nc6 13. Bb5`, played from `position`.
+    pub fn san(&self, position: &Position) -> String {
+        let mut position = position.clone();
+        let mut text = String::new();
+        for (ply, &mv) in self.pv.iter().enumerate() {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            let number = position.fullmove_number();
+            match position.side_to_move() {
+                Color::White => text.push_str(&format!("{number}. ")),
+                Color::Black if ply == 0 => text.push_str(&format!("{number}... ")),
+                Color::Black => {}
+            }
+            text.push_str(&position.san(mv));
+            position.make_move(mv);
+        }
+        text
+    }
+}
+
+/// The outcome of a search, also reported after each completed iteration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// The best lines found, best first.
+    ///
+    /// Empty if there are no legal moves.
+    pub lines: Vec<Line>,
+    /// The last completed depth.
+    pub depth: u32,
+    /// The deepest ply reached, including quiescence search.
+    pub seldepth: u32,
+    pub nodes: u64,
+    pub time: Duration,
+    /// What every thread did, see [`SearchStats`].
+    pub stats: SearchStats,
+}
+
+impl SearchResult {
+    /// The best move found, None if there are no legal moves.
+    pub fn best_move(&self) -> Option<Move> {
+        self.best_line()?.pv.first().copied()
+    }
+
+    /// The best line found, None if there are no legal moves.
+    pub fn best_line(&self) -> Option<&Line> {
+        self.lines.first()
+    }
+
+    /// The moves of the best line, empty if there are no legal moves.
+    pub fn pv(&self) -> Vec<Move> {
+        self.best_line()
+            .map(|line| line.pv.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Searches `position` with iterative deepening until a limit is reached or `stop` is set,
+/// scoring the leaves with `eval`.
+///
+/// With more than one thread this is [Lazy SMP](https://www.chessprogramming.org/Lazy_SMP):
+/// every thread searches the same position, sharing what they find through `tt`,
+/// and the first thread's result is returned.
+/// `report` is called after every iteration the first thread completes.
+#[allow(clippy::too_many_arguments)]
+pub fn search<E: Evaluator + Sync + ?Sized>(
+    position: &Position,
+    eval: &E,
+    limits: &SearchLimits,
+    options: SearchOptions,
+    tt: &TranspositionTable,
+    threads: usize,
+    stop: &AtomicBool,
+    report: impl FnMut(&SearchResult),
+) -> SearchResult {
+    let finished = AtomicBool::new(false);
+    let shared = Shared {
+        eval,
+        limits,
+        options,
+        tt,
+        stop,
+        finished: &finished,
+        nodes: AtomicU64::new(0),
+    };
+
+    let mut result = std::thread::scope(|scope| {
+        let helpers: Vec<_> = (1..threads.max(1))
+            .map(|id| {
+                let shared = &shared;
+                scope.spawn(move || Search::new(shared, id).iterate(position, |_| {}))
+            })
+            .collect();
+
+        let mut result = Search::new(&shared, 0).iterate(position, report);
+        finished.store(true, Ordering::Relaxed);
+        for helper in helpers {
+            let helper = helper.join().expect("a search thread panicked");
+            result.stats.merge(&helper.stats);
+        }
+        result
+    });
+
+    result.nodes = shared.nodes.load(Ordering::Relaxed);
+    result
+}
+
+/// Searches `position` like [`search`], but so the same arguments always give the same
+/// result, on any platform and however many times it's run.
+///
+/// The search runs on one thread with a transposition table of
+/// [`DETERMINISTIC_HASH_MB`] that starts empty, and ignores the time limits,
+/// so `limits` must have a depth or node limit. The result's `time` is left at zero.
+/// Use it to reproduce a search exactly, when debugging or testing.
+///
+/// # Panics
+///
+/// If `limits` has neither a depth nor a node limit.
+pub fn search_deterministic<E: Evaluator + Sync + ?Sized>(
+    position: &Position,
+    eval: &E,
+    limits: &SearchLimits,
+    options: SearchOptions,
+) -> SearchResult {
+    assert!(
+        limits.depth.is_some() || limits.nodes.is_some(),
+        "a deterministic search needs a depth or node limit"
+    );
+    let limits = SearchLimits {
+        time: None,
+        ..limits.clone()
+    };
+    let tt = TranspositionTable::new(DETERMINISTIC_HASH_MB);
+    let stop = AtomicBool::new(false);
+    let mut result = search(position, eval, &limits, options, &tt, 1, &stop, |_| {});
+    result.time = Duration::ZERO;
+    result
+}
+
+/// Searches `position` like [`search`] on one thread with its own transposition table,
+/// recording the tree of the last root search within `bounds` for debugging.
+pub fn search_tree<E: Evaluator + Sync + ?Sized>(
+    position: &Position,
+    eval: &E,
+    limits: &SearchLimits,
+    options: SearchOptions,
+    bounds: TreeLimits,
+) -> (SearchResult, SearchTree) {
+    let tt = TranspositionTable::new(16);
+    let (stop, finished) = (AtomicBool::new(false), AtomicBool::new(false));
+    let shared = Shared {
+        eval,
+        limits,
+        options,
+        tt: &tt,
+        stop: &stop,
+        finished: &finished,
+        nodes: AtomicU64::new(0),
+    };
+
+    let mut search = Search::new(&shared, 0);
+    search.tree = Some(TreeRecorder::new(bounds));
+    let mut result = search.iterate(position, |_| {});
+    result.nodes = shared.nodes.load(Ordering::Relaxed);
+    let tree = search.tree.take().map(TreeRecorder::finish);
+    (result, tree.unwrap_or_default())
+}
+
+/// What every search thread shares.
+struct Shared<'a, E: ?Sized> {
+    eval: &'a E,
+    limits: &'a SearchLimits,
+    options: SearchOptions,
+    tt: &'a TranspositionTable,
+    stop: &'a AtomicBool,
+    /// Set when the first thread is done, to stop the others.
+    finished: &'a AtomicBool,
+    /// Nodes counted by every thread, added in batches.
+    nodes: AtomicU64,
+}
+
+/// State for one thread searching a single position.
+struct Search<'a, E: ?Sized> {
+    shared: &'a Shared<'a, E>,
+    /// Which thread this is, the first thread being 0.
+    id: usize,
+    clock: TimeManager,
+    /// Nodes searched by this thread.
+    nodes: u64,
+    seldepth: u32,
+    aborted: bool,
+    stats: SearchStats,
+    killers: Killers,
+    history: Box<History>,
+    countermoves: Box<Countermoves>,
+    continuation_history: ContinuationHistory,
+    /// The move made at each ply, None for a null move.
+    moved: [Option<PieceTo>; MAX_PLY as usize + 1],
+    pawns: PawnTable,
+    /// Whether the move leading to each ply was a null move.
+    null_moves: [bool; MAX_PLY as usize + 1],
+    /// While verifying a null move cutoff, null moves aren't tried before this ply.
+    null_min_ply: u32,
+    /// Only recorded by [`search_tree`].
+    tree: Option<TreeRecorder>,
+}
+
+impl<'a, E: Evaluator + ?Sized> Search<'a, E> {
+    fn new(shared: &'a Shared<'a, E>, id: usize) -> Self {
+        Self {
+            shared,
+            id,
+            clock: TimeManager::start(shared.limits.time),
+            nodes: 0,
+            seldepth: 0,
+            aborted: false,
+            stats: SearchStats::default(),
+            killers: Killers::new(),
+            history: Box::new(History::new()),
+            countermoves: Box::new(Countermoves::new()),
+            continuation_history: ContinuationHistory::new(),
+            moved: [None; MAX_PLY as usize + 1],
+            pawns: PawnTable::new(),
+            null_moves: [false; MAX_PLY as usize + 1],
+            null_min_ply: 0,
+            tree: None,
+        }
+    }
+
+    /// Nodes searched by every thread so far.
+    fn total_nodes(&self) -> u64 {
+        self.shared.nodes.load(Ordering::Relaxed) + self.nodes % CHECK_INTERVAL
+    }
+
+    /// Counts a node, returning `true` once any limit has been reached.
+    fn visit(&mut self) -> bool {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.shared
+                .nodes
+                .fetch_add(CHECK_INTERVAL, Ordering::Relaxed);
+        }
+        self.should_stop()
+    }
+
+    /// Returns `true` once any limit has been reached, and remembers it.
+    fn should_stop(&mut self) -> bool {
+        if self.aborted {
+            return true;
+        }
+
+        let limits = self.shared.limits;
+        if limits
+            .nodes
+            .is_some_and(|nodes| self.total_nodes() >= nodes)
+        {
+            self.aborted = true;
+        } else if self.nodes.is_multiple_of(CHECK_INTERVAL) {
+            self.aborted = self.shared.stop.load(Ordering::Relaxed)
+                || self.shared.finished.load(Ordering::Relaxed)
+                || self.clock.out_of_time();
+        }
+
+        self.aborted
+    }
+
+    /// Runs iterative deepening until a limit is reached.
+    fn iterate(
+        &mut self,
+        position: &Position,
+        mut report: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        let limits = self.shared.limits;
+        let mut position = position.clone();
+
+        let mut root = position.legal_moves();
+        if !limits.search_moves.is_empty() {
+            let mut allowed = MoveList::new();
+            for &mv in root.iter().filter(|mv| limits.search_moves.contains(mv)) {
+                allowed.push(mv);
+            }
+            root = allowed;
+        }
+
+        // until the first iteration completes, any legal move will do
+        let mut result = SearchResult {
+            lines: root
+                .first()
+                .map(|&mv| Line {
+                    score: Score::DRAW,
+                    pv: vec![mv],
+                })
+                .into_iter()
+                .collect(),
+            depth: 0,
+            seldepth: 0,
+            nodes: 0,
+            time: Duration::ZERO,
+            stats: SearchStats::default(),
+        };
+
+        if !root.is_empty() {
+            let max_depth = limits.depth.unwrap_or(MAX_PLY).min(MAX_PLY);
+            let multi_pv = limits.multi_pv.clamp(1, root.len());
+            // helpers skip ahead so the threads don't all search the same depth
+            let first_depth = (1 + (self.id % 2) as u32).min(max_depth);
+
+            'deepen: for depth in first_depth..=max_depth {
+                #[cfg(feature = "tracing")]
+                let _span = tracing::debug_span!("iteration", thread = self.id, depth).entered();
+                let start_nodes = self.nodes;
+
+                // each line searches the moves not already leading a better one
+                let mut lines = Vec::<Line>::with_capacity(multi_pv);
+                for index in 0..multi_pv {
+                    let mut moves = MoveList::new();
+                    for &mv in root.iter() {
+                        if !lines.iter().any(|line| line.pv[0] == mv) {
+                            moves.push(mv);
+                        }
+                    }
+                    let previous = result.lines.get(index);
+                    let guess = previous.map(|line| line.score);
+                    let previous = previous.map_or(&[][..], |line| &line.pv);
+
+                    let mut pv = Vec::new();
+                    let score =
+                        self.aspirate(&mut position, &mut moves, depth, guess, previous, &mut pv);
+                    if self.aborted {
+                        break 'deepen;
+                    }
+                    complete_pv(&position, &mut pv, self.shared.tt, depth as usize);
+                    lines.push(Line { score, pv });
+                }
+                lines.sort_by_key(|line| -line.score);
+
+                self.stats.complete(depth, self.nodes - start_nodes);
+                #[cfg(feature = "tracing")]
+                self.stats.trace(depth);
+                result = SearchResult {
+                    lines,
+                    depth,
+                    seldepth: self.seldepth,
+                    nodes: self.total_nodes(),
+                    time: self.clock.elapsed(),
+                    stats: self.stats.clone(),
+                };
+                report(&result);
+
+                // a shorter mate can't be found by searching deeper,
+                // but a search without limits goes on until it is stopped
+                let mating = result.lines[0].mate_in().is_some_and(|moves| moves > 0);
+                let bounded =
+                    limits.depth.is_some() || limits.nodes.is_some() || limits.time.is_some();
+                if (mating && multi_pv == 1 && bounded) || !self.clock.can_continue() {
+                    break;
+                }
+            }
+        }
+
+        self.shared
+            .nodes
+            .fetch_add(self.nodes % CHECK_INTERVAL, Ordering::Relaxed);
+        result.time = self.clock.elapsed();
+        result.stats = std::mem::take(&mut self.stats);
+        result
+    }
+
+    /// Searches the root `moves` within the window `alpha..beta`,
+    /// returning the best score even when it falls outside the window.
+    ///
+    /// `pv` is only filled in when a move scores above `alpha`.
+    #[allow(clippy::too_many_arguments)]
+    fn root(
+        &mut self,
+        position: &mut Position,
+        moves: &mut MoveList,
+        depth: u32,
+        mut alpha: Score,
+        beta: Score,
+        line: &[Move],
+        pv: &mut Vec<Move>,
+    ) -> Score {
+        let hint = line.first().copied().or_else(|| {
+            let entry = self.shared.tt.probe(position.zobrist_key(), 0)?;
+            entry.best_move
+        });
+        order(position, moves, hint);
+        self.record(|tree| tree.start(depth, alpha, beta));
+
+        let mut best = -Score::INFINITY;
+        let mut child = Vec::new();
+        for &mv in moves.iter() {
+            self.moved[0] = Some(PieceTo::new(position, mv));
+            position.make_move(mv);
+            let next = follow(line, mv);
+            let score = -self.alpha_beta(position, depth - 1, 1, -beta, -alpha, next, &mut child);
+            position.unmake_move();
+
+            if self.aborted {
+                break;
+            }
+            best = best.max(score);
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(mv);
+                pv.extend_from_slice(&child);
+                if alpha >= beta {
+                    self.record(|tree| tree.cutoff(Cutoff::Beta));
+                    break;
+                }
+            }
+        }
+
+        let aborted = self.aborted;
+        self.record(|tree| tree.leave((!aborted).then_some(best)));
+        best
+    }
+
+    /// Searches the root `moves` for a line, starting with a narrow window around
+    /// the `guess` from the last iteration and widening it until the score is inside.
+    #[allow(clippy::too_many_arguments)]
+    fn aspirate(
+        &mut self,
+        position: &mut Position,
+        moves: &mut MoveList,
+        depth: u32,
+        guess: Option<Score>,
+        line: &[Move],
+        pv: &mut Vec<Move>,
+    ) -> Score {
+        let guess = guess.filter(|guess| {
+            self.shared.options.aspiration_windows && depth >= ASPIRATION_DEPTH && !guess.is_mate()
+        });
+        let mut window = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) = match guess {
+            Some(guess) => (guess - window, guess + window),
+            None => (-Score::INFINITY, Score::INFINITY),
+        };
+
+        loop {
+            let score = self.root(position, moves, depth, alpha, beta, line, pv);
+            if self.aborted {
+                return score;
+            }
+
+            window *= 2;
+            if score <= alpha {
+                self.stats.aspiration_fail_lows += 1;
+                alpha = (score - window).max(-Score::INFINITY);
+            } else if score >= beta {
+                self.stats.aspiration_fail_highs += 1;
+                beta = (score + window).min(Score::INFINITY);
+            } else {
+                return score;
+            }
+        }
+    }
+
+    /// Negamax alpha-beta, following the previous iteration's `line` while it matches.
+    #[allow(clippy::too_many_arguments)]
+    fn alpha_beta(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        ply: u32,
+        alpha: Score,
+        beta: Score,
+        line: &[Move],
+        pv: &mut Vec<Move>,
+    ) -> Score {
+        pv.clear();
+
+        if depth == 0 || ply >= MAX_PLY {
+            return self.quiescence(position, ply, alpha, beta);
+        }
+        if self.tree.is_none() {
+            return self.search_node(position, depth, ply, alpha, beta, line, pv);
+        }
+
+        let kind = if self.null_moves[ply as usize] {
+            NodeKind::Null
+        } else {
+            NodeKind::Main
+        };
+        let mv = position.last_move().filter(|_| kind == NodeKind::Main);
+        self.record(|tree| tree.enter(mv, kind, ply, depth, alpha, beta));
+        let score = self.search_node(position, depth, ply, alpha, beta, line, pv);
+        let aborted = self.aborted;
+        self.record(|tree| tree.leave((!aborted).then_some(score)));
+        score
+    }
+
+    /// The score of a draw `ply` moves from the root, for the side to move there.
+    fn draw(&self, ply: u32) -> Score {
+        let contempt = Score::centipawns(self.shared.options.contempt);
+        if ply.is_multiple_of(2) {
+            -contempt
+        } else {
+            contempt
+        }
+    }
+
+    /// Runs `record` on the tree being recorded, if there is one.
+    #[inline]
+    fn record(&mut self, record: impl FnOnce(&mut TreeRecorder)) {
+        if let Some(tree) = &mut self.tree {
+            record(tree);
+        }
+    }
+
+    /// The body of [`Search::alpha_beta`], for a node with depth left.
+    #[allow(clippy::too_many_arguments)]
+    fn search_node(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        ply: u32,
+        mut alpha: Score,
+        beta: Score,
+        line: &[Move],
+        pv: &mut Vec<Move>,
+    ) -> Score {
+        if self.visit() {
+            return Score::DRAW;
+        }
+        // checked before the table, which may hold a score from a different path
+        if position.is_repetition() {
+            return self.draw(ply);
+        }
+
+        let key = position.zobrist_key();
+        let entry = self.shared.tt.probe(key, ply);
+        self.stats.tt_probes += 1;
+        self.stats.tt_hits += u64::from(entry.is_some());
+        if let Some(entry) = entry.filter(|entry| entry.depth as u32 >= depth) {
+            let cutoff = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => entry.score >= beta,
+                Bound::Upper => entry.score <= alpha,
+            };
+            if cutoff {
+                self.stats.tt_cutoffs += 1;
+                self.record(|tree| tree.cutoff(Cutoff::Transposition));
+                return entry.score;
+            }
+        }
+
+        let us = position.side_to_move();
+        let in_check = position.in_check(us);
+        let moves = position.legal_moves();
+        if moves.is_empty() {
+            return if in_check {
+                Score::mated(ply)
+            } else {
+                self.draw(ply)
+            };
+        }
+        if position.halfmove_clock() >= 100 {
+            return self.draw(ply);
+        }
+
+        let options = self.shared.options;
+        // a null window means this node only has to prove it fails high or low,
+        // and the previous iteration's line is worth searching properly
+        let pv_node = beta > alpha + 1 || !line.is_empty();
+        let static_eval = if in_check {
+            -Score::INFINITY
+        } else {
+            Score::centipawns(self.shared.eval.evaluate_cached(position, &mut self.pawns))
+        };
+        let prunable = !pv_node && !in_check && beta.abs() < Score::MATE_BOUND;
+
+        if prunable
+            && options.reverse_futility
+            && depth <= REVERSE_FUTILITY_DEPTH
+            && static_eval - REVERSE_FUTILITY_MARGIN * depth as i32 >= beta
+        {
+            self.stats.reverse_futility_prunes += 1;
+            self.record(|tree| tree.cutoff(Cutoff::ReverseFutility));
+            return static_eval;
+        }
+
+        if prunable
+            && options.null_move
+            && depth >= NULL_MOVE_DEPTH
+            && ply >= self.null_min_ply
+            && !self.null_moves[ply as usize]
+            && static_eval >= beta
+            && has_pieces(position)
+        {
+            if let Some(score) = self.null_move(position, depth, ply, beta, pv) {
+                self.record(|tree| tree.cutoff(Cutoff::NullMove));
+                return score;
+            }
+        }
+
+        let hint = line
+            .first()
+            .copied()
+            .or(entry.and_then(|entry| entry.best_move));
+        let countermove = self
+            .played(ply, 1)
+            .and_then(|previous| self.countermoves.get(previous));
+        let mut picker = MovePicker::new(position, moves, hint, self.killers.get(ply), countermove);
+        let futile = prunable
+            && options.futility
+            && (depth as usize) < FUTILITY_MARGINS.len()
+            && static_eval + FUTILITY_MARGINS[depth as usize] <= alpha;
+
+        let original_alpha = alpha;
+        // fail-soft: the best score is returned even when it is outside the window,
+        // giving a tighter bound to store and to widen an aspiration window to
+        let mut best = -Score::INFINITY;
+        let mut child = Vec::new();
+        let mut quiets = MoveList::new();
+        let mut searched = 0;
+        while let Some(mv) = picker.next(position, &self.history, self.continuations(ply)) {
+            let quiet = !is_tactical(position, mv);
+            self.moved[ply as usize] = Some(PieceTo::new(position, mv));
+            position.make_move(mv);
+            let checks = position.in_check(position.side_to_move());
+            // quiet moves can't make up the difference this close to the leaves
+            if futile && quiet && !checks && searched > 0 {
+                position.unmake_move();
+                self.stats.futility_prunes += 1;
+                self.record(|tree| tree.skip(mv, ply + 1, Cutoff::Futility));
+                continue;
+            }
+
+            let next = follow(line, mv);
+            let reduction = if options.late_move_reductions
+                && quiet
+                && !in_check
+                && !checks
+                && depth >= REDUCTION_DEPTH
+                && searched >= FULL_DEPTH_MOVES
+            {
+                let late = u32::from(searched >= 2 * FULL_DEPTH_MOVES);
+                let deep = u32::from(depth >= 2 * REDUCTION_DEPTH);
+                (1 + late + deep).min(depth - 2)
+            } else {
+                0
+            };
+
+            // a reduced search that fails low is trusted, otherwise it's searched again
+            let mut score = alpha + 1;
+            if reduction > 0 {
+                self.stats.reductions += 1;
+                score = -self.alpha_beta(
+                    position,
+                    depth - 1 - reduction,
+                    ply + 1,
+                    -alpha - 1,
+                    -alpha,
+                    next,
+                    &mut child,
+                );
+                self.stats.re_searches += u64::from(score > alpha);
+            }
+            if score > alpha {
+                score = -self.alpha_beta(
+                    position,
+                    depth - 1,
+                    ply + 1,
+                    -beta,
+                    -alpha,
+                    next,
+                    &mut child,
+                );
+            }
+            position.unmake_move();
+            searched += 1;
+
+            if self.aborted {
+                return Score::DRAW;
+            }
+            best = best.max(score);
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(mv);
+                pv.extend_from_slice(&child);
+
+                if alpha >= beta {
+                    self.stats.cutoff(searched - 1);
+                    self.record(|tree| tree.cutoff(Cutoff::Beta));
+                    if quiet {
+                        self.reward(position, depth, ply, mv, &quiets);
+                    }
+                    break;
+                }
+            }
+            if quiet {
+                quiets.push(mv);
+            }
+        }
+
+        let bound = if best >= beta {
+            Bound::Lower
+        } else if best > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        let entry = TtEntry {
+            best_move: pv.first().copied(),
+            score: best,
+            depth: depth as u8,
+            bound,
+        };
+        self.shared.tt.store(key, ply, entry);
+
+        best
+    }
+
+    /// The move made `back` plies before `ply`, None for a null move or before the root.
+    fn played(&self, ply: u32, back: u32) -> Option<PieceTo> {
+        self.moved[ply.checked_sub(back)? as usize]
+    }
+
+    /// The continuation histories for the moves one and two plies before `ply`.
+    fn continuations(&self, ply: u32) -> [Option<&[i32]>; 2] {
+        [1, 2].map(|back| {
+            self.played(ply, back)
+                .map(|previous| self.continuation_history.get(previous))
+        })
+    }
+
+    /// Rewards the quiet move `mv` for causing a cutoff at `ply`,
+    /// and punishes the `quiets` tried before it that didn't.
+    fn reward(&mut self, position: &Position, depth: u32, ply: u32, mv: Move, quiets: &MoveList) {
+        let us = position.side_to_move();
+        let bonus = (depth * depth) as i32;
+        self.killers.store(ply, mv);
+        self.history.update(us, mv, bonus);
+        for &tried in quiets.iter() {
+            self.history.update(us, tried, -bonus);
+        }
+
+        if let Some(previous) = self.played(ply, 1) {
+            self.countermoves.store(previous, mv);
+        }
+        let previous = [1, 2].map(|back| self.played(ply, back));
+        for previous in previous.into_iter().flatten() {
+            let history = &mut self.continuation_history;
+            history.update(previous, PieceTo::new(position, mv), bonus);
+            for &tried in quiets.iter() {
+                history.update(previous, PieceTo::new(position, tried), -bonus);
+            }
+        }
+    }
+
+    /// Passes the turn to see if the position is still at least `beta`,
+    /// returning the score to cut off with if it is.
+    ///
+    /// Deep cutoffs are verified with a reduced search of this node that can't
+    /// use null moves, so zugzwang positions aren't pruned.
+    fn null_move(
+        &mut self,
+        position: &mut Position,
+        depth: u32,
+        ply: u32,
+        beta: Score,
+        pv: &mut Vec<Move>,
+    ) -> Option<Score> {
+        let reduction = 2 + depth / 4;
+
+        self.stats.null_moves += 1;
+        position.make_null_move();
+        self.moved[ply as usize] = None;
+        self.null_moves[ply as usize + 1] = true;
+        let mut child = Vec::new();
+        let score = -self.alpha_beta(
+            position,
+            depth.saturating_sub(1 + reduction),
+            ply + 1,
+            -beta,
+            -beta + 1,
+            &[],
+            &mut child,
+        );
+        self.null_moves[ply as usize + 1] = false;
+        position.unmake_null_move();
+
+        if self.aborted || score < beta {
+            return None;
+        }
+        // a mate found by passing isn't a real mate
+        let score = score.min(Score::MATE_BOUND - 1);
+        if depth >= VERIFY_DEPTH && self.null_min_ply == 0 {
+            self.null_min_ply = ply + 3 * (depth - reduction) / 4;
+            let verified =
+                self.alpha_beta(position, depth - reduction, ply, beta - 1, beta, &[], pv);
+            self.null_min_ply = 0;
+            if verified < beta {
+                return None;
+            }
+        }
+        self.stats.null_move_cutoffs += 1;
+        Some(score)
+    }
+
+    /// Searches captures until the position is quiet, so the evaluation isn't
+    /// taken in the middle of an exchange.
+    fn quiescence(
+        &mut self,
+        position: &mut Position,
+        ply: u32,
+        alpha: Score,
+        beta: Score,
+    ) -> Score {
+        if self.tree.is_none() {
+            return self.quiescence_node(position, ply, alpha, beta);
+        }
+
+        let mv = position.last_move();
+        self.record(|tree| tree.enter(mv, NodeKind::Quiescence, ply, 0, alpha, beta));
+        let score = self.quiescence_node(position, ply, alpha, beta);
+        let aborted = self.aborted;
+        self.record(|tree| tree.leave((!aborted).then_some(score)));
+        score
+    }
+
+    /// The body of [`Search::quiescence`].
+    fn quiescence_node(
+        &mut self,
+        position: &mut Position,
+        ply: u32,
+        mut alpha: Score,
+        beta: Score,
+    ) -> Score {
+        self.seldepth = self.seldepth.max(ply);
+        if self.visit() {
+            return Score::DRAW;
+        }
+        self.stats.quiescence_nodes += 1;
+
+        // standing pat isn't an option when every move has to escape check
+        let mut moves = MoveList::new();
+        let in_check = position.in_check(position.side_to_move());
+        let mut best = -Score::INFINITY;
+        if in_check {
+            movegen::generate_evasions(position, &mut moves);
+            if moves.is_empty() {
+                return Score::mated(ply);
+            }
+        } else {
+            let stand_pat =
+                Score::centipawns(self.shared.eval.evaluate_cached(position, &mut self.pawns));
+            if stand_pat >= beta {
+                self.record(|tree| tree.cutoff(Cutoff::StandPat));
+                return stand_pat;
+            }
+            best = stand_pat;
+            alpha = alpha.max(stand_pat);
+
+            movegen::generate_captures(position, &mut moves);
+        }
+
+        if ply >= MAX_PLY {
+            return if in_check { alpha } else { best };
+        }
+
+        order(position, &mut moves, None);
+
+        for &mv in moves.iter() {
+            position.make_move(mv);
+            let score = -self.quiescence(position, ply + 1, -beta, -alpha);
+            position.unmake_move();
+
+            if self.aborted {
+                return Score::DRAW;
+            }
+            best = best.max(score);
+            if score > alpha {
+                alpha = score;
+                if alpha >= beta {
+                    self.record(|tree| tree.cutoff(Cutoff::Beta));
+                    break;
+                }
+            }
+        }
+
+        best
+    }
+}
+
+/// Returns `true` if the side to move has a piece other than pawns and the king,
+/// since without one passing could well be the best move.
+fn has_pieces(position: &Position) -> bool {
+    let us = position.side_to_move();
+    [
+        PieceKind::Knight,
+        PieceKind::Bishop,
+        PieceKind::Rook,
+        PieceKind::Queen,
+    ]
+    .into_iter()
+    .any(|kind| position.count(Piece::new_with(us, kind)) > 0)
+}
+
+/// Checks that `pv` can be played from `position`, dropping the moves from the first
+/// one that can't, then fills it out to `length` moves from the best moves in `tt`.
+///
+/// Transposition table cutoffs leave the PV short, and a key collision
+/// could put an illegal move in it.
+fn complete_pv(position: &Position, pv: &mut Vec<Move>, tt: &TranspositionTable, length: usize) {
+    let mut position = position.clone();
+    let mut keys = Vec::new();
+    let mut played = 0;
+    while played < pv.len().max(length) {
+        let key = position.zobrist_key();
+        let mv = match pv.get(played) {
+            Some(&mv) => Some(mv),
+            // the table's moves can go round in circles
+            None if keys.contains(&key) => None,
+            None => tt
+                .probe(key, played as u32)
+                .and_then(|entry| entry.best_move),
+        };
+        let Some(mv) = mv.filter(|mv| position.legal_moves().contains(mv)) else {
+            break;
+        };
+
+        keys.push(key);
+        position.make_move(mv);
+        if played == pv.len() {
+            pv.push(mv);
+        }
+        played += 1;
+    }
+    pv.truncate(played);
+}
+
+/// The rest of `line` if `mv` continues it.
+fn follow(line: &[Move], mv: Move) -> &[Move] {
+    match line {
+        [first, rest @ ..] if *first == mv => rest,
+        _ => &[],
+    }
+}
+
+/// Sorts `moves` so the best guesses are searched first:
+/// the `hint`, then captures of the most valuable victim by the least valuable attacker.
+fn order(position: &Position, moves: &mut MoveList, hint: Option<Move>) {
+    let board = position.board();
+
+    moves.sort_by_cached_key(|&mv| {
+        if Some(mv) == hint {
+            return i32::MIN;
+        }
+
+        let mut key = 0;
+        if let Some(victim) = board.kind_on(mv.to()) {
+            let attacker = board
+                .kind_on(mv.from())
+                .expect("a piece should be on `from`");
+            key -= 10 * (victim as i32 + 1) - attacker as i32;
+        }
+        if let Some(promotion) = mv.promotion() {
+            key -= 10 * promotion as i32;
+        }
+        key
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evaluate;
+    use board::FEN;
+
+    fn run(fen: &str, depth: u32) -> SearchResult {
+        let position = FEN::new(fen).parse_position().unwrap();
+        let limits = SearchLimits {
+            depth: Some(depth),
+            ..Default::default()
+        };
+        search(
+            &position,
+            &evaluate,
+            &limits,
+            SearchOptions::default(),
+            &TranspositionTable::default(),
+            1,
+            &AtomicBool::new(false),
+            |_| {},
+        )
+    }
+
+    #[test]
+    fn finds_mate_in_one() {
+        let result = run("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 4);
+        let line = result.best_line().unwrap();
+        assert_eq!(line.pv[0].to_uci(), "a1a8");
+        assert_eq!(line.score, Score::mating(1));
+        assert_eq!(line.mate_in(), Some(1));
+        assert_eq!(line.pv.len(), 1);
+    }
+
+    #[test]
+    fn wins_material() {
+        // the queen on d5 is hanging to the knight
+        let result = run("4k3/8/8/3q4/8/4N3/8/4K3 w - - 0 1", 2);
+        assert_eq!(result.best_move().unwrap().to_uci(), "e3d5");
+        assert!(result.lines[0].score > Score::DRAW);
+    }
+
+    #[test]
+    fn principal_variation() {
+        let fen = "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3";
+        let result = run(fen, 5);
+        let pv = result.pv();
+        assert_eq!(pv, result.lines[0].pv);
+        assert!(pv.len() >= 5);
+
+        let mut position = FEN::new(fen).parse_position().unwrap();
+        for &mv in &pv {
+            assert!(position.legal_moves().contains(&mv), "{mv:?} in {pv:?}");
+            position.make_move(mv);
+        }
+
+        let start = Position::start();
+        let mut position = start.clone();
+        let mut moves = Vec::new();
+        for uci in ["e2e4", "e7e5", "g1f3"] {
+            let mv = position.parse_uci(uci).unwrap();
+            position.make_move(mv);
+            moves.push(mv);
+        }
+        // everything from the first move that can't be played is dropped
+        let mut pv = [moves.clone(), moves.clone()].concat();
+        complete_pv(&start, &mut pv, &TranspositionTable::default(), 0);
+        assert_eq!(pv, moves);
+
+        let line = Line {
+            score: Score::DRAW,
+            pv,
+        };
+        assert_eq!(line.san(&start), "1. e4 e5 2. Nf3");
+        let mut position = start.clone();
+        position.make_move(line.pv[0]);
+        let line = Line {
+            score: Score::DRAW,
+            pv: line.pv[1..].to_vec(),
+        };
+        assert_eq!(line.san(&position), "1... e5 2. Nf3");
+    }
+
+    #[test]
+    fn avoids_losing_exchange() {
+        // taking the pawn loses the queen to the other pawn
+        let result = run("4k3/8/2p5/3p4/8/8/3Q4/4K3 w - - 0 1", 1);
+        assert_ne!(result.best_move().unwrap().to_uci(), "d2d5");
+    }
+
+    #[test]
+    fn multi_pv() {
+        // only the knight takes the queen, the rest lose it back or do nothing
+        let position = FEN::new("4k3/8/8/3q4/8/4N3/8/4K3 w - - 0 1")
+            .parse_position()
+            .unwrap();
+        let limits = SearchLimits {
+            depth: Some(2),
+            multi_pv: 3,
+            ..Default::default()
+        };
+        let result = search(
+            &position,
+            &evaluate,
+            &limits,
+            SearchOptions::default(),
+            &TranspositionTable::new(1),
+            1,
+            &AtomicBool::new(false),
+            |_| {},
+        );
+
+        assert_eq!(result.lines.len(), 3);
+        assert_eq!(result.best_move().unwrap().to_uci(), "e3d5");
+        assert!(result.lines.is_sorted_by_key(|line| -line.score));
+
+        let firsts = result
+            .lines
+            .iter()
+            .map(|line| line.pv[0])
+            .collect::<Vec<_>>();
+        assert!(!firsts[1..].contains(&firsts[0]));
+        assert_ne!(firsts[1], firsts[2]);
+    }
+
+    #[test]
+    fn selectivity_saves_nodes() {
+        let position =
+            FEN::new("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4")
+                .parse_position()
+                .unwrap();
+        let limits = SearchLimits {
+            depth: Some(5),
+            ..Default::default()
+        };
+        let run = |options| {
+            search(
+                &position,
+                &evaluate,
+                &limits,
+                options,
+                &TranspositionTable::new(1),
+                1,
+                &AtomicBool::new(false),
+                |_| {},
+            )
+        };
+
+        let plain = run(SearchOptions::NONE);
+        let selective = run(SearchOptions::ALL);
+        assert_eq!(selective.depth, 5);
+        assert!(selective.nodes < plain.nodes);
+
+        // narrow windows are searched again until they hold the score, so it's the same
+        let aspirated = run(SearchOptions {
+            aspiration_windows: true,
+            ..SearchOptions::NONE
+        });
+        assert_eq!(aspirated.lines[0].score, plain.lines[0].score);
+    }
+
+    #[test]
+    fn no_legal_moves() {
+        let result = run("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 3);
+        assert_eq!(result.best_move(), None);
+        assert_eq!(result.depth, 0);
+    }
+
+    #[test]
+    fn limits_stop_the_search() {
+        let position = Position::start();
+        let limits = SearchLimits {
+            nodes: Some(1000),
+            ..Default::default()
+        };
+        let tt = TranspositionTable::new(1);
+        let result = search(
+            &position,
+            &evaluate,
+            &limits,
+            SearchOptions::default(),
+            &tt,
+            1,
+            &AtomicBool::new(false),
+            |_| {},
+        );
+        assert!(result.best_move().is_some());
+        assert!(result.nodes <= 1000);
+
+        let a3 = position.parse_uci("a2a3").unwrap();
+        let limits = SearchLimits::new().depth(3).search_moves([a3]);
+        let result = search(
+            &position,
+            &evaluate,
+            &limits,
+            SearchOptions::default(),
+            &tt,
+            1,
+            &AtomicBool::new(false),
+            |_| {},
+        );
+        assert_eq!(result.best_move(), Some(a3));
+        assert_eq!(result.depth, 3);
+
+        let stopped = search(
+            &position,
+            &evaluate,
+            &SearchLimits::default(),
+            SearchOptions::default(),
+            &tt,
+            4,
+            &AtomicBool::new(true),
+            |_| {},
+        );
+        assert!(stopped.best_move().is_some());
+    }
+
+    #[test]
+    fn searches_drops() {
+        let result = run("4k3/8/8/8/8/8/8/R3K2R[N] w KQ - 0 1", 3);
+        assert_eq!(result.depth, 3);
+        assert!(result.best_move().is_some());
+
+        // dropping the knight forks the king and queen
+        let result = run("4k3/8/8/8/2q5/8/8/4K3[N] w - - 0 1", 3);
+        assert_eq!(result.best_move().unwrap().to_uci(), "N@d6");
+    }
+
+    #[test]
+    fn contempt_scores_repetitions() {
+        let mut position = Position::start();
+        for san in ["Nf3", "Nf6", "Ng1"] {
+            position.make_move(position.parse_san(san).unwrap());
+        }
+        let back = position.parse_san("Ng8").unwrap();
+        let run = |contempt| {
+            let options = SearchOptions {
+                contempt,
+                ..SearchOptions::ALL
+            };
+            search_deterministic(&position, &evaluate, &SearchLimits::new().depth(3), options)
+        };
+
+        // going back repeats the start, which is good enough when hoping for a draw
+        let drawing = run(-500);
+        assert_eq!(drawing.best_move(), Some(back));
+        assert_eq!(drawing.lines[0].score, Score::centipawns(500));
+        let playing_on = run(500);
+        assert_ne!(playing_on.best_move(), Some(back));
+        assert!(playing_on.lines[0].score > -Score::centipawns(500));
+    }
+
+    #[test]
+    fn deterministic_search_repeats_exactly() {
+        let position =
+            FEN::new("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4")
+                .parse_position()
+                .unwrap();
+        let limits = SearchLimits::new()
+            .depth(5)
+            .move_time(Duration::from_millis(1));
+        let first = search_deterministic(&position, &evaluate, &limits, SearchOptions::ALL);
+        assert_eq!(first.depth, 5);
+        assert_eq!(first.time, Duration::ZERO);
+
+        let second = search_deterministic(&position, &evaluate, &limits, SearchOptions::ALL);
+        assert_eq!(first, second);
+
+        let limits = SearchLimits::new().nodes(5000);
+        let first = search_deterministic(&position, &evaluate, &limits, SearchOptions::ALL);
+        let second = search_deterministic(&position, &evaluate, &limits, SearchOptions::ALL);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn threads_share_work() {
+        let position = Position::start();
+        let limits = SearchLimits {
+            depth: Some(4),
+            ..Default::default()
+        };
+        let tt = TranspositionTable::new(1);
+        let mut reports = 0;
+        let result = search(
+            &position,
+            &evaluate,
+            &limits,
+            SearchOptions::default(),
+            &tt,
+            3,
+            &AtomicBool::new(false),
+            |_| reports += 1,
+        );
+
+        assert_eq!(result.depth, 4);
+        assert_eq!(reports, 4);
+        let mut child = position.clone();
+        child.make_move(result.best_move().unwrap());
+        assert!(tt.probe(child.zobrist_key(), 1).is_some());
+    }
+}
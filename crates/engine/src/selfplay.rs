@@ -0,0 +1,496 @@
+//! Playing the engine against itself, for testing and for generating training data.
+//!
+//! Every searched move is written to the PGN with an `[%eval]` from White's point of view,
+//! and a `[%clk]` when the game has a time control. Book and random opening moves
+//! have no evaluation, so they can be told apart from the moves the engine chose.
+
+use std::sync::{atomic::AtomicBool, Arc};
+
+use board::{
+    clock::{Clock, TimeControl},
+    openings::Book,
+    pgn::{self, Outcome},
+    Color, Game, Move, Position,
+};
+use uci::GoParams;
+
+use crate::{
+    search, Evaluator, Score, SearchLimits, SearchOptions, Tablebase, TimeLimits,
+    TranspositionTable, Wdl,
+};
+
+/// When to end a game early because its result is clear.
+///
+/// Scores are from White's point of view and counted over the moves of both sides,
+/// so a game is only adjudicated once the engine agrees with itself playing either colour.
+/// The position itself can end the game sooner, when neither side has the material
+/// to win, or a tablebase knows the result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Adjudication {
+    /// Resign once a side has been at least this many centipawns behind...
+    pub resign_score: Option<i32>,
+    /// ...for this many moves by each side.
+    pub resign_moves: usize,
+    /// Draw once the score has stayed within this many centipawns of zero...
+    pub draw_score: Option<i32>,
+    /// ...for this many moves by each side...
+    pub draw_moves: usize,
+    /// ...as long as at least this many moves have been played.
+    pub draw_after: usize,
+    /// Draw any game that reaches this many moves.
+    pub max_moves: Option<usize>,
+    /// Draw once neither side has the pieces to force mate, such as a knight against a
+    /// bishop, which the rules leave to be played out.
+    pub material_draws: bool,
+    /// Take the result from the tablebase, if there is one,
+    /// once few enough pieces are left for it to know.
+    pub tablebase: bool,
+}
+
+impl Adjudication {
+    /// Never adjudicates, so games are only ended by the rules.
+    pub fn none() -> Self {
+        Self {
+            resign_score: None,
+            resign_moves: 0,
+            draw_score: None,
+            draw_moves: 0,
+            draw_after: 0,
+            max_moves: None,
+            material_draws: false,
+            tablebase: false,
+        }
+    }
+
+    /// The outcome of `game` and why it ended, as in a PGN `Termination` tag,
+    /// if it is over by the rules or can be adjudicated.
+    ///
+    /// `scores` are as for [`Adjudication::adjudicate`].
+    pub fn game_over(
+        &self,
+        game: &Game,
+        scores: &[Option<Score>],
+        tablebase: Option<&dyn Tablebase>,
+    ) -> Option<(Outcome, &'static str)> {
+        if let Some(result) = game.result() {
+            return Some((result.outcome(), "normal"));
+        }
+        self.adjudicate(game.position(), scores, tablebase)
+            .map(|outcome| (outcome, "adjudication"))
+    }
+
+    /// The outcome to adjudicate in `position`, given the score after every move so far,
+    /// None where a move wasn't searched.
+    pub fn adjudicate(
+        &self,
+        position: &Position,
+        scores: &[Option<Score>],
+        tablebase: Option<&dyn Tablebase>,
+    ) -> Option<Outcome> {
+        let pieces = position.board().occupied().count();
+        if let Some(tablebase) = tablebase.filter(|tb| self.tablebase && pieces <= tb.max_pieces())
+        {
+            let us = position.side_to_move();
+            match tablebase.probe_wdl(position) {
+                Some(Wdl::Win) => return Some(win(us)),
+                Some(Wdl::Loss) => return Some(win(us.opposite())),
+                Some(Wdl::Draw) => return Some(Outcome::Draw),
+                None => {}
+            }
+        }
+        if self.material_draws
+            && !can_force_mate(position, Color::White)
+            && !can_force_mate(position, Color::Black)
+        {
+            return Some(Outcome::Draw);
+        }
+
+        if self
+            .max_moves
+            .is_some_and(|moves| scores.len() >= moves * 2)
+        {
+            return Some(Outcome::Draw);
+        }
+        if let Some(resign) = self.resign_score.map(Score::centipawns) {
+            if lasting(scores, self.resign_moves, |score| score >= resign) {
+                return Some(Outcome::WhiteWins);
+            }
+            if lasting(scores, self.resign_moves, |score| score <= -resign) {
+                return Some(Outcome::BlackWins);
+            }
+        }
+        if let Some(draw) = self.draw_score.map(Score::centipawns) {
+            if scores.len() >= self.draw_after * 2
+                && lasting(scores, self.draw_moves, |score| score.abs() <= draw)
+            {
+                return Some(Outcome::Draw);
+            }
+        }
+        None
+    }
+}
+
+impl Default for Adjudication {
+    fn default() -> Self {
+        Self {
+            resign_score: Some(1000),
+            resign_moves: 3,
+            draw_score: Some(10),
+            draw_moves: 8,
+            draw_after: 40,
+            max_moves: None,
+            material_draws: true,
+            tablebase: true,
+        }
+    }
+}
+
+/// The outcome when `color` wins.
+fn win(color: Color) -> Outcome {
+    match color {
+        Color::White => Outcome::WhiteWins,
+        Color::Black => Outcome::BlackWins,
+    }
+}
+
+/// Returns `false` if `color` only has its king and a minor piece or two knights left,
+/// which can't mate without help, and the opponent has no pawns to give that help.
+fn can_force_mate(position: &Position, color: Color) -> bool {
+    let board = position.board();
+    let ours = board.colors(color);
+    let heavy = (board.pawns() | board.rooks() | board.queens()) & ours;
+    let knights = board.knights() & ours;
+    let minors = (board.knights() | board.bishops()) & ours;
+    let helped = !(board.pawns() & board.colors(color.opposite())).is_empty();
+    !heavy.is_empty() || helped || (minors.count() > 1 && minors != knights)
+}
+
+/// Returns `true` if the last `moves` moves by each side were all searched
+/// and scored so that `holds` is true.
+fn lasting(scores: &[Option<Score>], moves: usize, holds: impl Fn(Score) -> bool) -> bool {
+    let plies = moves.max(1) * 2;
+    scores.len() >= plies
+        && scores[scores.len() - plies..]
+            .iter()
+            .all(|score| score.is_some_and(&holds))
+}
+
+/// Picks the first moves of a game from a book, then at random,
+/// the same moves for the same seed.
+#[derive(Debug, Clone)]
+pub struct Openings<'a> {
+    book: Option<&'a Book>,
+    /// The most moves, in plies, to play from the book.
+    book_plies: usize,
+    /// Plies left to play at random once out of book.
+    random_plies: usize,
+    random: u64,
+}
+
+impl<'a> Openings<'a> {
+    pub fn new(book: Option<&'a Book>, book_plies: usize, random_plies: usize, seed: u64) -> Self {
+        Self {
+            book,
+            book_plies,
+            random_plies,
+            random: seed,
+        }
+    }
+
+    /// The next opening move in `game`, None once the opening is over,
+    /// after which it stays over.
+    pub fn next(&mut self, game: &Game) -> Option<Move> {
+        let position = game.position();
+        let book_move = self
+            .book
+            .filter(|_| game.moves().len() < self.book_plies)
+            .and_then(|book| book.choose(position, next_random(&mut self.random)));
+        let mv = book_move.or_else(|| {
+            let moves = position.legal_moves();
+            (self.random_plies > 0 && !moves.is_empty()).then(|| {
+                self.random_plies -= 1;
+                moves[next_random(&mut self.random) as usize % moves.len()]
+            })
+        });
+        if mv.is_none() {
+            self.book = None;
+            self.random_plies = 0;
+        }
+        mv
+    }
+}
+
+/// How the engine plays against itself.
+#[derive(Debug, Clone)]
+pub struct SelfPlay {
+    /// The limits for every move, with the time limit replaced by the clock's when timed.
+    pub limits: SearchLimits,
+    /// The time control both sides play with, which can be untimed.
+    pub time_control: TimeControl,
+    /// Where the first moves are chosen from, at random.
+    pub book: Option<Book>,
+    /// The most moves, in plies, to play from the book.
+    pub book_plies: usize,
+    /// Plies to play at random once out of book, so games don't repeat.
+    pub random_plies: usize,
+    pub adjudication: Adjudication,
+    /// The tablebase for [`Adjudication::tablebase`].
+    pub tablebase: Option<Arc<dyn Tablebase>>,
+    pub options: SearchOptions,
+    /// The size of the transposition table used for each game, in megabytes.
+    pub hash_mb: usize,
+}
+
+impl Default for SelfPlay {
+    fn default() -> Self {
+        Self {
+            limits: SearchLimits {
+                nodes: Some(10_000),
+                multi_pv: 1,
+                ..SearchLimits::default()
+            },
+            time_control: TimeControl::untimed(),
+            book: None,
+            book_plies: 16,
+            random_plies: 8,
+            adjudication: Adjudication::default(),
+            tablebase: None,
+            options: SearchOptions::default(),
+            hash_mb: TranspositionTable::DEFAULT_MB,
+        }
+    }
+}
+
+impl SelfPlay {
+    /// Plays a game from the starting position, scoring positions with `eval`.
+    ///
+    /// `seed` picks the opening moves, so the same seed plays the same opening.
+    /// The `Result`, `Termination` and `TimeControl` tags are set,
+    /// the rest are left for the caller.
+    pub fn play<E: Evaluator + Sync + ?Sized>(&self, eval: &E, seed: u64) -> pgn::Game {
+        let tt = TranspositionTable::new(self.hash_mb);
+        let stop = AtomicBool::new(false);
+        let timed = !self.time_control.is_untimed();
+        let mut clock = Clock::new(self.time_control.clone());
+        let mut openings =
+            Openings::new(self.book.as_ref(), self.book_plies, self.random_plies, seed);
+
+        let mut game = Game::new(Position::start());
+        let mut record = pgn::Game::new();
+        // White's score after each move, None for unsearched moves
+        let mut scores = Vec::new();
+
+        let (outcome, termination) = loop {
+            let tablebase = self.tablebase.as_deref();
+            if let Some(end) = self.adjudication.game_over(&game, &scores, tablebase) {
+                break end;
+            }
+
+            if let Some(mv) = openings.next(&game) {
+                game.play(mv);
+                record.moves.push(pgn::Node::new(mv));
+                scores.push(None);
+                continue;
+            }
+
+            let position = game.position();
+            let us = position.side_to_move();
+            let limits = SearchLimits {
+                time: if timed {
+                    TimeLimits::from_go(&clock_go_params(&clock, us), us)
+                } else {
+                    self.limits.time
+                },
+                ..self.limits.clone()
+            };
+            let result = search(position, eval, &limits, self.options, &tt, 1, &stop, |_| {});
+            let line = result
+                .best_line()
+                .expect("the game isn't over, so there is a legal move");
+
+            if timed && !clock.record_move(us, result.time) {
+                break (win(us.opposite()), "time forfeit");
+            }
+
+            let score = match us {
+                Color::White => line.score,
+                Color::Black => -line.score,
+            };
+            let mut node = pgn::Node::new(line.pv[0]);
+            node.eval = Some(score.into());
+            node.clock = timed.then(|| clock.remaining(us));
+
+            game.play(node.mv);
+            record.moves.push(node);
+            scores.push(Some(score));
+        };
+
+        record.outcome = outcome;
+        record.set_tag("Result", outcome.as_str());
+        if timed {
+            record.set_tag("TimeControl", self.time_control.to_string());
+        }
+        record.set_tag("Termination", termination);
+        record
+    }
+}
+
+/// The `go` command a GUI would send for `us` to move with the time left on `clock`.
+pub fn clock_go_params(clock: &Clock, us: Color) -> GoParams {
+    let increment = |color| clock.stage(color).map(|stage| stage.increment);
+    GoParams {
+        wtime: Some(clock.remaining(Color::White)),
+        btime: Some(clock.remaining(Color::Black)),
+        winc: increment(Color::White),
+        binc: increment(Color::Black),
+        moves_to_go: clock.moves_to_go(us),
+        ..GoParams::default()
+    }
+}
+
+/// The next number from a [SplitMix64](https://prng.di.unimi.it/splitmix64.c) generator.
+fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EvalParams;
+
+    #[test]
+    fn adjudication() {
+        let adjudication = Adjudication {
+            resign_score: Some(500),
+            resign_moves: 2,
+            draw_score: Some(10),
+            draw_moves: 2,
+            draw_after: 3,
+            max_moves: Some(10),
+            ..Adjudication::none()
+        };
+        let start = Position::start();
+        let adjudicate = |centipawns: &[Option<i32>]| {
+            let scores: Vec<_> = centipawns
+                .iter()
+                .map(|cp| cp.map(Score::centipawns))
+                .collect();
+            adjudication.adjudicate(&start, &scores, None)
+        };
+
+        let winning = [None, Some(500), Some(600), Some(700), Some(550)];
+        assert_eq!(adjudicate(&winning[..4]), None);
+        assert_eq!(adjudicate(&winning), Some(Outcome::WhiteWins));
+        assert_eq!(adjudicate(&[Some(-900); 4]), Some(Outcome::BlackWins));
+        assert_eq!(
+            adjudication.adjudicate(&start, &[Some(Score::mated(6)); 4], None),
+            Some(Outcome::BlackWins)
+        );
+
+        // level scores only count once enough moves have been played
+        assert_eq!(adjudicate(&[Some(5); 4]), None);
+        assert_eq!(adjudicate(&[Some(5); 6]), Some(Outcome::Draw));
+        // and an unsearched move breaks the run
+        let book = [Some(5), Some(0), None, Some(5), Some(0), Some(5)];
+        assert_eq!(adjudicate(&book), None);
+
+        assert_eq!(adjudicate(&[None; 20]), Some(Outcome::Draw));
+        assert_eq!(
+            Adjudication::none().adjudicate(&start, &[Some(Score::centipawns(-900)); 100], None),
+            None
+        );
+    }
+
+    /// Knows that the side with the queen wins, in positions of three pieces.
+    #[derive(Debug)]
+    struct QueenWins;
+
+    impl Tablebase for QueenWins {
+        fn max_pieces(&self) -> u32 {
+            3
+        }
+
+        fn probe_wdl(&self, position: &Position) -> Option<Wdl> {
+            let us = position.side_to_move();
+            let queens = position.board().queens();
+            Some(if (queens & position.board().colors(us)).is_empty() {
+                Wdl::Loss
+            } else {
+                Wdl::Win
+            })
+        }
+    }
+
+    #[test]
+    fn adjudicates_positions() {
+        let adjudication = Adjudication::default();
+        let adjudicate = |fen: &str, tablebase: Option<&dyn Tablebase>| {
+            let position = board::FEN::new(fen).parse_position().unwrap();
+            adjudication.adjudicate(&position, &[], tablebase)
+        };
+
+        // neither side can force mate
+        assert_eq!(
+            adjudicate("8/8/4k3/8/3n4/8/2B5/4K3 w - - 0 1", None),
+            Some(Outcome::Draw)
+        );
+        assert_eq!(
+            adjudicate("8/8/4k3/8/8/8/2NN4/4K3 w - - 0 1", None),
+            Some(Outcome::Draw)
+        );
+        // but two bishops can, and a pawn can be helped to promote or block a mate
+        assert_eq!(adjudicate("8/8/4k3/8/8/8/2BB4/4K3 w - - 0 1", None), None);
+        assert_eq!(adjudicate("8/8/4k3/4p3/8/8/2NN4/4K3 w - - 0 1", None), None);
+
+        let queen = "8/8/4k3/8/8/8/3Q4/4K3 b - - 0 1";
+        assert_eq!(adjudicate(queen, None), None);
+        assert_eq!(
+            adjudicate(queen, Some(&QueenWins)),
+            Some(Outcome::WhiteWins)
+        );
+        // too many pieces for the tablebase
+        assert_eq!(
+            adjudicate("8/8/4k3/8/8/3P4/3Q4/4K3 b - - 0 1", Some(&QueenWins)),
+            None
+        );
+        assert_eq!(
+            Adjudication::none().adjudicate(
+                &board::FEN::new(queen).parse_position().unwrap(),
+                &[],
+                Some(&QueenWins)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn plays_a_game() {
+        let settings = SelfPlay {
+            limits: SearchLimits {
+                depth: Some(2),
+                multi_pv: 1,
+                ..SearchLimits::default()
+            },
+            random_plies: 4,
+            adjudication: Adjudication {
+                max_moves: Some(30),
+                ..Adjudication::default()
+            },
+            hash_mb: 1,
+            ..SelfPlay::default()
+        };
+        let game = settings.play(&EvalParams::default(), 7);
+        assert_ne!(game.outcome, Outcome::Unknown);
+        assert!(game.moves.len() <= 60);
+        assert!(game.moves[..4].iter().all(|node| node.eval.is_none()));
+        assert!(game.moves[4..].iter().all(|node| node.eval.is_some()));
+
+        // the same seed plays the same game, which reads back the same
+        assert_eq!(settings.play(&EvalParams::default(), 7), game);
+        assert_eq!(pgn::Game::parse(&game.to_string()).unwrap(), game);
+    }
+}
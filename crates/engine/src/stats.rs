@@ -0,0 +1,170 @@
+//! Counters for where the search spends its nodes, to see what a change to it does
+//! without adding prints.
+//!
+//! Every search thread keeps its own [`SearchStats`], which are added together in
+//! [`SearchResult::stats`](crate::SearchResult::stats). With the `tracing` feature
+//! each iteration is also searched in a `tracing` span, ending with an event of its counters.
+
+use std::fmt;
+
+/// How many move indexes beta cutoffs are counted for, later moves counting towards the last.
+pub const CUTOFF_INDEXES: usize = 8;
+
+/// What the search did, counted as it went.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// Nodes searched by each completed iteration, indexed by depth.
+    pub depth_nodes: Vec<u64>,
+    /// Nodes searched by the quiescence search, which are also counted in the iterations.
+    pub quiescence_nodes: u64,
+    /// How many times the transposition table was looked at in the main search.
+    pub tt_probes: u64,
+    /// How many of the probes found an entry.
+    pub tt_hits: u64,
+    /// How many of the entries found were enough to return without searching.
+    pub tt_cutoffs: u64,
+    /// Beta cutoffs, indexed by how many moves were searched before the one that caused it.
+    pub cutoffs: [u64; CUTOFF_INDEXES],
+    /// Null moves searched, and how many of them let the node be cut off.
+    pub null_moves: u64,
+    pub null_move_cutoffs: u64,
+    /// Nodes returned early by reverse futility pruning.
+    pub reverse_futility_prunes: u64,
+    /// Quiet moves skipped by futility pruning.
+    pub futility_prunes: u64,
+    /// Late moves searched at a reduced depth, and how many of them had to be searched again.
+    pub reductions: u64,
+    pub re_searches: u64,
+    /// Root searches whose score fell below or above the aspiration window.
+    pub aspiration_fail_lows: u64,
+    pub aspiration_fail_highs: u64,
+}
+
+impl SearchStats {
+    /// The share of transposition table probes that found an entry, from 0 to 1.
+    pub fn tt_hit_rate(&self) -> f64 {
+        ratio(self.tt_hits, self.tt_probes)
+    }
+
+    /// The share of beta cutoffs caused by the first move searched, from 0 to 1,
+    /// the higher the better the move ordering.
+    pub fn first_move_cutoff_rate(&self) -> f64 {
+        ratio(self.cutoffs[0], self.cutoffs.iter().sum())
+    }
+
+    /// Counts the nodes of a completed iteration.
+    pub(crate) fn complete(&mut self, depth: u32, nodes: u64) {
+        let depth = depth as usize;
+        if self.depth_nodes.len() <= depth {
+            self.depth_nodes.resize(depth + 1, 0);
+        }
+        self.depth_nodes[depth] += nodes;
+    }
+
+    /// Counts a beta cutoff by the move searched after `searched` others.
+    pub(crate) fn cutoff(&mut self, searched: usize) {
+        self.cutoffs[searched.min(CUTOFF_INDEXES - 1)] += 1;
+    }
+
+    /// Adds the counters of another thread.
+    pub fn merge(&mut self, other: &Self) {
+        for (depth, &nodes) in other.depth_nodes.iter().enumerate() {
+            self.complete(depth as u32, nodes);
+        }
+        for (ours, theirs) in self.cutoffs.iter_mut().zip(other.cutoffs) {
+            *ours += theirs;
+        }
+        let mut other = other.clone();
+        for (ours, theirs) in self.counters_mut().into_iter().zip(other.counters_mut()) {
+            *ours += *theirs;
+        }
+    }
+
+    /// Every counter that is a single number.
+    fn counters_mut(&mut self) -> [&mut u64; 12] {
+        [
+            &mut self.quiescence_nodes,
+            &mut self.tt_probes,
+            &mut self.tt_hits,
+            &mut self.tt_cutoffs,
+            &mut self.null_moves,
+            &mut self.null_move_cutoffs,
+            &mut self.reverse_futility_prunes,
+            &mut self.futility_prunes,
+            &mut self.reductions,
+            &mut self.re_searches,
+            &mut self.aspiration_fail_lows,
+            &mut self.aspiration_fail_highs,
+        ]
+    }
+
+    /// Records the counters so far in an event, inside the span of the iteration at `depth`.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn trace(&self, depth: u32) {
+        tracing::debug!(
+            depth,
+            nodes = self.depth_nodes.get(depth as usize).copied().unwrap_or(0),
+            quiescence_nodes = self.quiescence_nodes,
+            tt_hit_rate = self.tt_hit_rate(),
+            first_move_cutoff_rate = self.first_move_cutoff_rate(),
+            null_move_cutoffs = self.null_move_cutoffs,
+            reverse_futility_prunes = self.reverse_futility_prunes,
+            futility_prunes = self.futility_prunes,
+            reductions = self.reductions,
+            re_searches = self.re_searches,
+            "iteration complete"
+        );
+    }
+}
+
+fn ratio(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// Writes the counters as a report, one line each.
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "nodes by depth:")?;
+        for (depth, nodes) in self.depth_nodes.iter().enumerate().skip(1) {
+            writeln!(f, "  {depth:>3} {nodes:>12}")?;
+        }
+        writeln!(f, "quiescence nodes: {}", self.quiescence_nodes)?;
+        writeln!(
+            f,
+            "tt: {} probes, {:.1}% hits, {} cutoffs",
+            self.tt_probes,
+            100.0 * self.tt_hit_rate(),
+            self.tt_cutoffs
+        )?;
+        let cutoffs = self.cutoffs.map(|count| count.to_string()).join(" ");
+        writeln!(
+            f,
+            "cutoffs by move: {cutoffs} ({:.1}% first)",
+            100.0 * self.first_move_cutoff_rate()
+        )?;
+        writeln!(
+            f,
+            "null moves: {} tried, {} cutoffs",
+            self.null_moves, self.null_move_cutoffs
+        )?;
+        writeln!(
+            f,
+            "futility: {} reverse, {} moves",
+            self.reverse_futility_prunes, self.futility_prunes
+        )?;
+        writeln!(
+            f,
+            "reductions: {}, {} searched again",
+            self.reductions, self.re_searches
+        )?;
+        write!(
+            f,
+            "aspiration: {} fail lows, {} fail highs",
+            self.aspiration_fail_lows, self.aspiration_fail_highs
+        )
+    }
+}
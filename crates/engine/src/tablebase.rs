@@ -0,0 +1,28 @@
+//! Endgame tablebases, which know the result of every position with few enough pieces.
+//!
+//! No tablebases come with the engine, [`Tablebase`] is for plugging them in,
+//! such as to adjudicate games, see [`Adjudication::tablebase`](crate::Adjudication::tablebase).
+
+use std::fmt;
+
+use board::Position;
+
+/// The result of a position with best play, for the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wdl {
+    Loss,
+    Draw,
+    Win,
+}
+
+/// A set of endgame tablebases, such as Syzygy files.
+pub trait Tablebase: fmt::Debug + Send + Sync {
+    /// The most pieces, kings included, a position can have to be in the tablebase.
+    fn max_pieces(&self) -> u32;
+
+    /// The result of `position` for the side to move,
+    /// or None if the position isn't in the tablebase.
+    ///
+    /// Wins the fifty-move rule would turn into draws should be given as draws.
+    fn probe_wdl(&self, position: &Position) -> Option<Wdl>;
+}
@@ -0,0 +1,173 @@
+//! Turning UCI time controls into how long to search.
+//!
+//! A search gets a soft limit, after which no new iteration is started,
+//! and a hard limit, at which it is cut off mid-iteration. Both are fixed
+//! when the search starts and a decision to stop is never taken back,
+//! so the search can't flip between stopping and carrying on.
+
+use std::time::{Duration, Instant};
+
+use board::Color;
+use uci::GoParams;
+
+/// Time kept back from every move for communication with the GUI.
+const OVERHEAD: Duration = Duration::from_millis(50);
+
+/// How many moves the remaining time is spread over without `movestogo`.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// How many times the soft limit a single search may run for.
+const HARD_RATIO: u32 = 4;
+
+/// How long a single search may take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeLimits {
+    /// Don't start another iteration after this long.
+    pub soft: Duration,
+    /// Stop searching after this long, even in the middle of an iteration.
+    pub hard: Duration,
+}
+
+impl TimeLimits {
+    /// Search for exactly `time`.
+    pub fn fixed(time: Duration) -> Self {
+        Self {
+            soft: time,
+            hard: time,
+        }
+    }
+
+    /// The limits for `us` given a `go` command,
+    /// or None if the search should run until it is stopped.
+    pub fn from_go(params: &GoParams, us: Color) -> Option<Self> {
+        if params.infinite {
+            return None;
+        }
+        if let Some(time) = params.move_time {
+            return Some(Self::fixed(time));
+        }
+
+        let (time, increment) = match us {
+            Color::White => (params.wtime?, params.winc),
+            Color::Black => (params.btime?, params.binc),
+        };
+        let available = time.saturating_sub(OVERHEAD);
+
+        // spread the remaining time over the moves left, plus most of the increment
+        let moves = params.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+        let soft = (time / moves + increment.unwrap_or_default() * 3 / 4).min(available);
+        let hard = (soft * HARD_RATIO).min(available / 2).max(soft);
+
+        Some(Self { soft, hard })
+    }
+}
+
+/// Tracks the time spent by a search against its [`TimeLimits`].
+#[derive(Debug, Clone)]
+pub struct TimeManager {
+    start: Instant,
+    limits: Option<TimeLimits>,
+    stopped: bool,
+}
+
+impl TimeManager {
+    /// Starts the clock, with no limits meaning the search is never out of time.
+    pub fn start(limits: Option<TimeLimits>) -> Self {
+        Self {
+            start: Instant::now(),
+            limits,
+            stopped: false,
+        }
+    }
+
+    /// The time since the clock was started.
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Returns `true` once the hard limit has passed, polled during the search.
+    pub fn out_of_time(&mut self) -> bool {
+        if !self.stopped {
+            self.stopped = self
+                .limits
+                .is_some_and(|limits| self.elapsed() >= limits.hard);
+        }
+        self.stopped
+    }
+
+    /// Returns `true` if there is time to start another iteration,
+    /// checked between iterations.
+    pub fn can_continue(&mut self) -> bool {
+        if !self.stopped {
+            self.stopped = self
+                .limits
+                .is_some_and(|limits| self.elapsed() >= limits.soft);
+        }
+        !self.stopped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn from_go() {
+        let infinite = GoParams {
+            infinite: true,
+            wtime: Some(ms(1000)),
+            ..Default::default()
+        };
+        assert_eq!(TimeLimits::from_go(&infinite, Color::White), None);
+
+        let move_time = GoParams {
+            move_time: Some(ms(500)),
+            ..Default::default()
+        };
+        assert_eq!(
+            TimeLimits::from_go(&move_time, Color::Black),
+            Some(TimeLimits::fixed(ms(500)))
+        );
+
+        let clock = GoParams {
+            wtime: Some(ms(60_000)),
+            btime: Some(ms(1_000)),
+            winc: Some(ms(1_000)),
+            moves_to_go: Some(20),
+            ..Default::default()
+        };
+        let white = TimeLimits::from_go(&clock, Color::White).unwrap();
+        assert_eq!(white.soft, ms(3_750));
+        assert_eq!(white.hard, ms(15_000));
+
+        // never plan to use more than the clock has left
+        let black = TimeLimits::from_go(&clock, Color::Black).unwrap();
+        assert!(black.hard <= ms(950));
+        assert!(black.soft <= black.hard);
+
+        assert_eq!(
+            TimeLimits::from_go(&GoParams::default(), Color::White),
+            None
+        );
+    }
+
+    #[test]
+    fn stopping_is_final() {
+        let mut unlimited = TimeManager::start(None);
+        assert!(unlimited.can_continue() && !unlimited.out_of_time());
+
+        let mut manager = TimeManager::start(Some(TimeLimits {
+            soft: Duration::ZERO,
+            hard: Duration::MAX,
+        }));
+        assert!(!manager.out_of_time());
+        assert!(!manager.can_continue());
+        // the soft limit having passed also ends the search
+        assert!(manager.out_of_time());
+    }
+}
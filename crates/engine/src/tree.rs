@@ -0,0 +1,364 @@
+//! A record of the nodes a search visits, for finding out why it pruned or preferred a move.
+//!
+//! [`search_tree`](crate::search_tree) searches like [`search`](crate::search) on one thread,
+//! recording the tree of its last root search, which can be written out as JSON
+//! or as a [GraphViz](https://graphviz.org) graph.
+
+use std::fmt::Write;
+
+use board::Move;
+
+use crate::Score;
+
+/// How much of the tree to record, the rest being searched without a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeLimits {
+    /// The deepest ply recorded.
+    pub plies: u32,
+    /// The most nodes recorded.
+    pub nodes: usize,
+}
+
+impl Default for TreeLimits {
+    fn default() -> Self {
+        Self {
+            plies: 4,
+            nodes: 10_000,
+        }
+    }
+}
+
+/// Where in the search a node was visited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Root,
+    Main,
+    /// Searched after passing the turn, to see if the node can be cut off.
+    Null,
+    Quiescence,
+}
+
+/// Why a node stopped short of searching all of its moves, or why a move wasn't searched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cutoff {
+    /// A transposition table entry was enough.
+    Transposition,
+    /// The static evaluation was far enough above beta.
+    ReverseFutility,
+    /// Passing the turn still scored at least beta.
+    NullMove,
+    /// The static evaluation was already at least beta in quiescence search.
+    StandPat,
+    /// A move scored at least beta, so the rest weren't needed.
+    Beta,
+    /// The move was quiet and too close to the leaves to raise alpha, so it wasn't searched.
+    Futility,
+}
+
+impl Cutoff {
+    pub fn name(self) -> &'static str {
+        match self {
+            Cutoff::Transposition => "transposition",
+            Cutoff::ReverseFutility => "reverse futility",
+            Cutoff::NullMove => "null move",
+            Cutoff::StandPat => "stand pat",
+            Cutoff::Beta => "beta",
+            Cutoff::Futility => "futility",
+        }
+    }
+}
+
+impl NodeKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            NodeKind::Root => "root",
+            NodeKind::Main => "main",
+            NodeKind::Null => "null",
+            NodeKind::Quiescence => "quiescence",
+        }
+    }
+}
+
+/// A node of a [`SearchTree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    /// The move leading to the node, None at the root and after a null move.
+    pub mv: Option<Move>,
+    pub kind: NodeKind,
+    pub ply: u32,
+    /// The depth left to search, 0 in quiescence search.
+    pub depth: u32,
+    /// The window the node was searched with, like the score
+    /// from the point of view of the side to move at the node.
+    pub alpha: Score,
+    pub beta: Score,
+    /// None if the node wasn't searched, or the search was stopped while in it.
+    pub score: Option<Score>,
+    pub cutoff: Option<Cutoff>,
+    /// Where the children are in the [`SearchTree`], in the order they were searched.
+    pub children: Vec<usize>,
+}
+
+/// The nodes recorded by [`search_tree`](crate::search_tree), the root first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchTree {
+    nodes: Vec<TreeNode>,
+}
+
+impl SearchTree {
+    /// The root of the tree, None if nothing was recorded.
+    pub fn root(&self) -> Option<&TreeNode> {
+        self.nodes.first()
+    }
+
+    pub fn node(&self, index: usize) -> &TreeNode {
+        &self.nodes[index]
+    }
+
+    /// Every recorded node, in the order they were visited.
+    pub fn nodes(&self) -> &[TreeNode] {
+        &self.nodes
+    }
+
+    /// The tree as nested JSON objects, each with its children in a `children` list.
+    ///
+    /// Scores and bounds are written as raw numbers, see [`Score::raw`].
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        if !self.nodes.is_empty() {
+            self.write_json(&mut json, 0);
+        }
+        json
+    }
+
+    fn write_json(&self, json: &mut String, index: usize) {
+        let node = &self.nodes[index];
+        let mv = node
+            .mv
+            .map_or("null".to_owned(), |mv| format!("\"{}\"", mv.to_uci()));
+        let score = node
+            .score
+            .map_or("null".to_owned(), |score| score.raw().to_string());
+        let cutoff = node
+            .cutoff
+            .map_or("null".to_owned(), |cutoff| format!("\"{}\"", cutoff.name()));
+        write!(
+            json,
+            "{{\"move\":{mv},\"kind\":\"{}\",\"ply\":{},\"depth\":{},\"alpha\":{},\"beta\":{},\
+             \"score\":{score},\"cutoff\":{cutoff},\"children\":[",
+            node.kind.name(),
+            node.ply,
+            node.depth,
+            node.alpha.raw(),
+            node.beta.raw(),
+        )
+        .expect("writing to a string");
+        for (i, &child) in node.children.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            self.write_json(json, child);
+        }
+        json.push_str("]}");
+    }
+
+    /// The tree as a GraphViz `digraph`, with the moves on the edges and the window,
+    /// score and cutoff in the nodes. Moves that weren't searched are dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph search {\n    node [shape=box fontname=monospace];\n");
+        for (index, node) in self.nodes.iter().enumerate() {
+            let mut label = format!(
+                "{} d{} [{}, {}]",
+                node.kind.name(),
+                node.depth,
+                node.alpha.raw(),
+                node.beta.raw()
+            );
+            if let Some(score) = node.score {
+                write!(label, "\\n{}", score.raw()).expect("writing to a string");
+            }
+            if let Some(cutoff) = node.cutoff {
+                write!(label, "\\n{} cutoff", cutoff.name()).expect("writing to a string");
+            }
+            let style = if node.score.is_none() {
+                " style=dashed"
+            } else {
+                ""
+            };
+            writeln!(dot, "    n{index} [label=\"{label}\"{style}];").expect("writing to a string");
+
+            for &child in &node.children {
+                let mv = self.nodes[child]
+                    .mv
+                    .map_or("null".to_owned(), |mv| mv.to_uci());
+                writeln!(dot, "    n{index} -> n{child} [label=\"{mv}\"];")
+                    .expect("writing to a string");
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Records a [`SearchTree`] as the search enters and leaves nodes.
+pub(crate) struct TreeRecorder {
+    limits: TreeLimits,
+    tree: SearchTree,
+    /// The node each entered node was recorded as, None if it is past the limits.
+    open: Vec<Option<usize>>,
+}
+
+impl TreeRecorder {
+    pub(crate) fn new(limits: TreeLimits) -> Self {
+        Self {
+            limits,
+            tree: SearchTree::default(),
+            open: Vec::new(),
+        }
+    }
+
+    /// Starts again for a new root search.
+    pub(crate) fn start(&mut self, depth: u32, alpha: Score, beta: Score) {
+        self.tree.nodes.clear();
+        self.open.clear();
+        self.enter(None, NodeKind::Root, 0, depth, alpha, beta);
+    }
+
+    /// Enters a node searched as a child of the last node entered.
+    pub(crate) fn enter(
+        &mut self,
+        mv: Option<Move>,
+        kind: NodeKind,
+        ply: u32,
+        depth: u32,
+        alpha: Score,
+        beta: Score,
+    ) {
+        let index = self.add(TreeNode {
+            mv,
+            kind,
+            ply,
+            depth,
+            alpha,
+            beta,
+            score: None,
+            cutoff: None,
+            children: Vec::new(),
+        });
+        self.open.push(index);
+    }
+
+    /// Leaves the last node entered, with its score if it was searched to the end.
+    pub(crate) fn leave(&mut self, score: Option<Score>) {
+        if let Some(Some(index)) = self.open.pop() {
+            self.tree.nodes[index].score = score;
+        }
+    }
+
+    /// Notes why the last node entered stopped early.
+    pub(crate) fn cutoff(&mut self, cutoff: Cutoff) {
+        if let Some(&Some(index)) = self.open.last() {
+            self.tree.nodes[index].cutoff = Some(cutoff);
+        }
+    }
+
+    /// Records a move of the last node entered that wasn't searched.
+    pub(crate) fn skip(&mut self, mv: Move, ply: u32, cutoff: Cutoff) {
+        let Some(&Some(parent)) = self.open.last() else {
+            return;
+        };
+        let parent = &self.tree.nodes[parent];
+        let (alpha, beta) = (-parent.beta, -parent.alpha);
+        let depth = parent.depth.saturating_sub(1);
+        self.add(TreeNode {
+            mv: Some(mv),
+            kind: NodeKind::Main,
+            ply,
+            depth,
+            alpha,
+            beta,
+            score: None,
+            cutoff: Some(cutoff),
+            children: Vec::new(),
+        });
+    }
+
+    /// Adds `node` under the last node entered, if it is within the limits.
+    fn add(&mut self, node: TreeNode) -> Option<usize> {
+        let parent = match self.open.last() {
+            Some(&Some(parent)) => Some(parent),
+            // nothing under a node that wasn't recorded is either
+            Some(None) => return None,
+            None => None,
+        };
+        if node.ply > self.limits.plies || self.tree.nodes.len() >= self.limits.nodes {
+            return None;
+        }
+
+        let index = self.tree.nodes.len();
+        self.tree.nodes.push(node);
+        if let Some(parent) = parent {
+            self.tree.nodes[parent].children.push(index);
+        }
+        Some(index)
+    }
+
+    pub(crate) fn finish(self) -> SearchTree {
+        self.tree
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{evaluate, search_tree, SearchLimits, SearchOptions};
+    use board::Position;
+
+    #[test]
+    fn records_the_last_root_search() {
+        let limits = SearchLimits::new().depth(3);
+        let bounds = TreeLimits {
+            plies: 2,
+            nodes: 500,
+        };
+        let (result, tree) = search_tree(
+            &Position::start(),
+            &evaluate,
+            &limits,
+            SearchOptions::default(),
+            bounds,
+        );
+
+        let root = tree.root().unwrap();
+        assert_eq!((root.kind, root.depth), (NodeKind::Root, 3));
+        assert_eq!(root.score, Some(result.lines[0].score));
+        assert_eq!(root.children.len(), 20);
+        let best = root
+            .children
+            .iter()
+            .map(|&child| tree.node(child))
+            .find(|child| child.mv == result.best_move())
+            .unwrap();
+        assert_eq!(best.score, root.score.map(|score| -score));
+        assert!(tree.nodes().iter().all(|node| node.ply <= 2));
+
+        assert!(tree
+            .to_json()
+            .starts_with(r#"{"move":null,"kind":"root","ply":0,"depth":3,"#));
+        let dot = tree.to_dot();
+        assert_eq!(dot.matches(" -> ").count(), tree.nodes().len() - 1);
+
+        let bounds = TreeLimits {
+            plies: 2,
+            nodes: 10,
+        };
+        let (_, tree) = search_tree(
+            &Position::start(),
+            &evaluate,
+            &limits,
+            SearchOptions::default(),
+            bounds,
+        );
+        assert_eq!(tree.nodes().len(), 10);
+    }
+}
@@ -0,0 +1,214 @@
+//! A [transposition table](https://www.chessprogramming.org/Transposition_Table)
+//! that can be shared between search threads without locking.
+//!
+//! Each slot stores the key XORed with the data, so a slot torn by two threads
+//! writing at once fails the key check instead of returning another position's data.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use board::Move;
+
+use crate::Score;
+
+/// How a stored score relates to the true score of the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is exact.
+    Exact,
+    /// The true score is at least this, the search failed high.
+    Lower,
+    /// The true score is at most this, the search failed low.
+    Upper,
+}
+
+/// What is remembered about a searched position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TtEntry {
+    pub best_move: Option<Move>,
+    pub score: Score,
+    pub depth: u8,
+    pub bound: Bound,
+}
+
+#[derive(Default)]
+struct Slot {
+    /// The position's key XORed with `data`.
+    check: AtomicU64,
+    data: AtomicU64,
+}
+
+/// A fixed size table of searched positions, indexed by Zobrist key.
+pub struct TranspositionTable {
+    slots: Box<[Slot]>,
+}
+
+impl TranspositionTable {
+    /// The size used when no other is given.
+    pub const DEFAULT_MB: usize = 16;
+
+    /// Creates an empty table using about `mb` megabytes.
+    pub fn new(mb: usize) -> Self {
+        let len = (mb * 1024 * 1024 / size_of::<Slot>()).max(1);
+        Self {
+            slots: (0..len).map(|_| Slot::default()).collect(),
+        }
+    }
+
+    /// Forgets every stored position.
+    pub fn clear(&self) {
+        for slot in self.slots.iter() {
+            slot.check.store(0, Ordering::Relaxed);
+            slot.data.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn slot(&self, key: u64) -> &Slot {
+        // maps the key onto the table without a division
+        let index = ((key as u128 * self.slots.len() as u128) >> 64) as usize;
+        &self.slots[index]
+    }
+
+    /// Looks up the position with `key`, searched `ply` moves from the root.
+    pub fn probe(&self, key: u64, ply: u32) -> Option<TtEntry> {
+        let slot = self.slot(key);
+        let data = slot.data.load(Ordering::Relaxed);
+        if slot.check.load(Ordering::Relaxed) ^ data != key || data == 0 {
+            return None;
+        }
+
+        let mut entry = unpack(data)?;
+        entry.score = entry.score.loaded_at(ply);
+        Some(entry)
+    }
+
+    /// Remembers a searched position, replacing what was in its slot
+    /// unless that was the same position searched deeper.
+    pub fn store(&self, key: u64, ply: u32, entry: TtEntry) {
+        let slot = self.slot(key);
+        let old = slot.data.load(Ordering::Relaxed);
+        let same = slot.check.load(Ordering::Relaxed) ^ old == key;
+        if same && unpack(old).is_some_and(|old| old.depth > entry.depth) {
+            return;
+        }
+
+        let data = pack(TtEntry {
+            score: entry.score.stored_at(ply),
+            // keep the old move rather than forgetting it
+            best_move: entry.best_move.or_else(|| {
+                same.then(|| unpack(old).and_then(|old| old.best_move))
+                    .flatten()
+            }),
+            ..entry
+        });
+        slot.check.store(key ^ data, Ordering::Relaxed);
+        slot.data.store(data, Ordering::Relaxed);
+    }
+
+    /// How full the table is, in permille, as sent in UCI `info hashfull`.
+    pub fn hashfull(&self) -> u32 {
+        let sample = self.slots.len().min(1000);
+        let used = self.slots[..sample]
+            .iter()
+            .filter(|slot| slot.data.load(Ordering::Relaxed) != 0)
+            .count();
+        (used * 1000 / sample) as u32
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MB)
+    }
+}
+
+/// Packs an entry into the low 42 bits, with bit 42 set so a used slot is never 0:
+/// 16 bits of move, 16 of score, 8 of depth and 2 of bound.
+fn pack(entry: TtEntry) -> u64 {
+    let mv = entry.best_move.map_or(0, pack_move) as u64;
+    let bound = match entry.bound {
+        Bound::Exact => 0,
+        Bound::Lower => 1,
+        Bound::Upper => 2,
+    };
+    mv | (entry.score.raw() as i16 as u16 as u64) << 16
+        | (entry.depth as u64) << 32
+        | bound << 40
+        | 1 << 42
+}
+
+fn unpack(data: u64) -> Option<TtEntry> {
+    let bound = match data >> 40 & 0b11 {
+        0 => Bound::Exact,
+        1 => Bound::Lower,
+        2 => Bound::Upper,
+        _ => return None,
+    };
+    Some(TtEntry {
+        best_move: unpack_move(data as u16),
+        score: Score::centipawns((data >> 16) as u16 as i16 as i32),
+        depth: (data >> 32) as u8,
+        bound,
+    })
+}
+
+/// A move from a square to itself is only possible when castling,
+/// so 0 is free to mean no move.
+fn pack_move(mv: Move) -> u16 {
+    mv.to_bits()
+}
+
+fn unpack_move(bits: u16) -> Option<Move> {
+    if bits == 0 {
+        return None;
+    }
+    Move::from_bits(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::{PieceKind, Square};
+
+    #[test]
+    fn store_and_probe() {
+        let tt = TranspositionTable::new(1);
+        let entry = TtEntry {
+            best_move: Some(Move::new_promotion(
+                Square::B7,
+                Square::A8,
+                PieceKind::Queen,
+            )),
+            score: Score::centipawns(-120),
+            depth: 5,
+            bound: Bound::Lower,
+        };
+
+        assert_eq!(tt.probe(42, 0), None);
+        tt.store(42, 0, entry);
+        assert_eq!(tt.probe(42, 0), Some(entry));
+        assert_eq!(tt.probe(43, 0), None);
+
+        // a shallower search doesn't replace a deeper one
+        tt.store(42, 0, TtEntry { depth: 2, ..entry });
+        assert_eq!(tt.probe(42, 0), Some(entry));
+
+        tt.clear();
+        assert_eq!(tt.probe(42, 0), None);
+    }
+
+    #[test]
+    fn mate_scores_are_relative() {
+        let tt = TranspositionTable::new(1);
+        let entry = TtEntry {
+            best_move: None,
+            score: Score::mating(5),
+            depth: 1,
+            bound: Bound::Exact,
+        };
+
+        // mate in 5 plies from the root is mate in 2 from a position 3 plies in
+        tt.store(7, 3, entry);
+        assert_eq!(tt.probe(7, 3).unwrap().score, Score::mating(5));
+        assert_eq!(tt.probe(7, 1).unwrap().score, Score::mating(3));
+    }
+}
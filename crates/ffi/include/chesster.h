@@ -0,0 +1,216 @@
+#ifndef CHESSTER_H
+#define CHESSTER_H
+
+/* Generated by cbindgen from crates/ffi, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Returned in place of a move when there isn't one.
+ */
+#define CHESSTER_NO_MOVE 0
+
+/**
+ * A side in the game.
+ */
+typedef enum ChessterColor {
+  CHESSTER_COLOR_WHITE,
+  CHESSTER_COLOR_BLACK,
+} ChessterColor;
+
+/**
+ * An engine with its own transposition table, kept between searches.
+ */
+typedef struct ChessterEngine ChessterEngine;
+
+/**
+ * A chess position along with the moves played to reach it.
+ */
+typedef struct ChessterPosition ChessterPosition;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a position at the standard starting position.
+ */
+struct ChessterPosition *chesster_position_new(void);
+
+/**
+ * Creates a position from FEN, or returns null if it is invalid.
+ *
+ * # Safety
+ *
+ * `fen` must be null or point to a nul terminated string.
+ */
+struct ChessterPosition *chesster_position_from_fen(const char *fen);
+
+/**
+ * Creates a copy of `position`, including the moves that can be unmade.
+ *
+ * # Safety
+ *
+ * `position` must have come from this library and not been freed.
+ */
+struct ChessterPosition *chesster_position_clone(const struct ChessterPosition *position);
+
+/**
+ * Frees a position. Does nothing if `position` is null.
+ *
+ * # Safety
+ *
+ * `position` must be null or have come from this library and not been freed.
+ */
+void chesster_position_free(struct ChessterPosition *position);
+
+/**
+ * Writes the position as FEN into `buf`, returning the length of the FEN.
+ *
+ * # Safety
+ *
+ * `position` must be valid, and `buf` must be valid for writes of `len` bytes.
+ */
+size_t chesster_position_fen(const struct ChessterPosition *position, char *buf, size_t len);
+
+/**
+ * The player to move.
+ *
+ * # Safety
+ *
+ * `position` must be valid.
+ */
+enum ChessterColor chesster_side_to_move(const struct ChessterPosition *position);
+
+/**
+ * Writes up to `len` legal moves into `moves`, returning how many there are in total.
+ *
+ * There are never more than 218 legal moves in standard chess,
+ * but pieces to drop in Crazyhouse can make a few hundred more, up to 512.
+ *
+ * # Safety
+ *
+ * `position` must be valid, and `moves` must be valid for writes of `len` moves.
+ */
+size_t chesster_legal_moves(const struct ChessterPosition *position, uint16_t *moves, size_t len);
+
+/**
+ * Plays a legal move, returning false and leaving the position unchanged if it isn't one.
+ *
+ * # Safety
+ *
+ * `position` must be valid.
+ */
+bool chesster_make_move(struct ChessterPosition *position, uint16_t mv);
+
+/**
+ * Takes back the last move, returning it or [`CHESSTER_NO_MOVE`] if none have been played.
+ *
+ * # Safety
+ *
+ * `position` must be valid.
+ */
+uint16_t chesster_unmake_move(struct ChessterPosition *position);
+
+/**
+ * Finds the legal move written in UCI or SAN, or returns [`CHESSTER_NO_MOVE`].
+ *
+ * # Safety
+ *
+ * `position` must be valid, and `text` must be null or point to a nul terminated string.
+ */
+uint16_t chesster_parse_move(const struct ChessterPosition *position, const char *text);
+
+/**
+ * Writes a legal move in UCI notation into `buf`, returning its length,
+ * or 0 if it isn't legal.
+ *
+ * # Safety
+ *
+ * `position` must be valid, and `buf` must be valid for writes of `len` bytes.
+ */
+size_t chesster_move_uci(const struct ChessterPosition *position,
+                         uint16_t mv,
+                         char *buf,
+                         size_t len);
+
+/**
+ * Writes a legal move in SAN into `buf`, returning its length, or 0 if it isn't legal.
+ *
+ * # Safety
+ *
+ * `position` must be valid, and `buf` must be valid for writes of `len` bytes.
+ */
+size_t chesster_move_san(const struct ChessterPosition *position,
+                         uint16_t mv,
+                         char *buf,
+                         size_t len);
+
+/**
+ * Whether the player to move is in check.
+ *
+ * # Safety
+ *
+ * `position` must be valid.
+ */
+bool chesster_in_check(const struct ChessterPosition *position);
+
+/**
+ * Whether the player to move has been checkmated.
+ *
+ * # Safety
+ *
+ * `position` must be valid.
+ */
+bool chesster_is_checkmate(const struct ChessterPosition *position);
+
+/**
+ * Whether the player to move has no legal moves but isn't in check.
+ *
+ * # Safety
+ *
+ * `position` must be valid.
+ */
+bool chesster_is_stalemate(const struct ChessterPosition *position);
+
+/**
+ * Creates an engine with a transposition table of `hash_mb` megabytes.
+ */
+struct ChessterEngine *chesster_engine_new(size_t hash_mb);
+
+/**
+ * Frees an engine. Does nothing if `engine` is null.
+ *
+ * # Safety
+ *
+ * `engine` must be null or have come from this library and not been freed.
+ */
+void chesster_engine_free(struct ChessterEngine *engine);
+
+/**
+ * Searches for the best move, or returns [`CHESSTER_NO_MOVE`] if there are no legal moves.
+ *
+ * The search stops after `depth` iterations or `movetime_ms` milliseconds,
+ * either of which can be 0 for no limit, but not both.
+ * If `score` isn't null the best move's score in centipawns is written to it.
+ * Mates are written as 32000 less the plies to mate, negated when being mated.
+ *
+ * # Safety
+ *
+ * `engine` and `position` must be valid, and `score` must be null or valid for writes.
+ */
+uint16_t chesster_engine_search(const struct ChessterEngine *engine,
+                                const struct ChessterPosition *position,
+                                uint32_t depth,
+                                uint64_t movetime_ms,
+                                int32_t *score);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHESSTER_H */
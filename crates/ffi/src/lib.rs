@@ -0,0 +1,431 @@
+//! A C ABI for the `board` and `engine` crates, for embedding chesster in C and C++ programs.
+//!
+//! The header is generated into `include/chesster.h` by the build script.
+//!
+//! Positions and engines are opaque pointers created by `*_new` functions and released
+//! with the matching `*_free`. Moves are passed around as their 16 bit packed form,
+//! see [`board::Move::to_bits`], with [`CHESSTER_NO_MOVE`] meaning there isn't one.
+//!
+//! Text is written into caller provided buffers like `snprintf`: the output is always
+//! nul terminated if `len` is non-zero, and the full length is returned so a truncated
+//! write can be retried with a bigger buffer.
+
+use std::{
+    ffi::{c_char, CStr},
+    ptr,
+    sync::atomic::AtomicBool,
+    time::Duration,
+};
+
+use board::{Color, Move, Position, FEN};
+use engine::{SearchLimits, SearchOptions, TimeLimits, TranspositionTable};
+
+/// Returned in place of a move when there isn't one.
+pub const CHESSTER_NO_MOVE: u16 = 0;
+
+/// A chess position along with the moves played to reach it.
+pub struct ChessterPosition(Position);
+
+/// An engine with its own transposition table, kept between searches.
+pub struct ChessterEngine {
+    tt: TranspositionTable,
+}
+
+/// A side in the game.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChessterColor {
+    White,
+    Black,
+}
+
+/// Writes `text` into `buf` like `snprintf`, returning the length of `text`.
+///
+/// # Safety
+///
+/// `buf` must be valid for writes of `len` bytes, or `len` must be 0.
+unsafe fn write_str(text: &str, buf: *mut c_char, len: usize) -> usize {
+    if !buf.is_null() && len > 0 {
+        let count = text.len().min(len - 1);
+        ptr::copy_nonoverlapping(text.as_ptr().cast(), buf, count);
+        *buf.add(count) = 0;
+    }
+    text.len()
+}
+
+/// Reads a nul terminated UTF-8 string, None if it is null or invalid.
+///
+/// # Safety
+///
+/// `text` must be null or point to a nul terminated string.
+unsafe fn read_str<'a>(text: *const c_char) -> Option<&'a str> {
+    if text.is_null() {
+        return None;
+    }
+    CStr::from_ptr(text).to_str().ok()
+}
+
+/// Finds the legal move in `position` with the packed `bits`.
+fn legal_move(position: &Position, bits: u16) -> Option<Move> {
+    let mv = Move::from_bits(bits)?;
+    position.legal_moves().contains(&mv).then_some(mv)
+}
+
+/// Creates a position at the standard starting position.
+#[no_mangle]
+pub extern "C" fn chesster_position_new() -> *mut ChessterPosition {
+    Box::into_raw(Box::new(ChessterPosition(Position::start())))
+}
+
+/// Creates a position from FEN, or returns null if it is invalid.
+///
+/// # Safety
+///
+/// `fen` must be null or point to a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_position_from_fen(fen: *const c_char) -> *mut ChessterPosition {
+    let Some(position) = read_str(fen)
+        .and_then(|fen| FEN::try_from(fen).ok())
+        .and_then(|fen| fen.parse_position().ok())
+    else {
+        return ptr::null_mut();
+    };
+    Box::into_raw(Box::new(ChessterPosition(position)))
+}
+
+/// Creates a copy of `position`, including the moves that can be unmade.
+///
+/// # Safety
+///
+/// `position` must have come from this library and not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_position_clone(
+    position: *const ChessterPosition,
+) -> *mut ChessterPosition {
+    let position = &(*position).0;
+    Box::into_raw(Box::new(ChessterPosition(position.clone())))
+}
+
+/// Frees a position. Does nothing if `position` is null.
+///
+/// # Safety
+///
+/// `position` must be null or have come from this library and not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_position_free(position: *mut ChessterPosition) {
+    if !position.is_null() {
+        drop(Box::from_raw(position));
+    }
+}
+
+/// Writes the position as FEN into `buf`, returning the length of the FEN.
+///
+/// # Safety
+///
+/// `position` must be valid, and `buf` must be valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_position_fen(
+    position: *const ChessterPosition,
+    buf: *mut c_char,
+    len: usize,
+) -> usize {
+    write_str((*position).0.to_fen().as_str(), buf, len)
+}
+
+/// The player to move.
+///
+/// # Safety
+///
+/// `position` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_side_to_move(position: *const ChessterPosition) -> ChessterColor {
+    match (*position).0.side_to_move() {
+        Color::White => ChessterColor::White,
+        Color::Black => ChessterColor::Black,
+    }
+}
+
+/// Writes up to `len` legal moves into `moves`, returning how many there are in total.
+///
+/// There are never more than 218 legal moves in standard chess,
+/// but pieces to drop in Crazyhouse can make a few hundred more, up to 512.
+///
+/// # Safety
+///
+/// `position` must be valid, and `moves` must be valid for writes of `len` moves.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_legal_moves(
+    position: *const ChessterPosition,
+    moves: *mut u16,
+    len: usize,
+) -> usize {
+    let legal = (*position).0.legal_moves();
+    if !moves.is_null() {
+        for (i, mv) in legal.iter().take(len).enumerate() {
+            *moves.add(i) = mv.to_bits();
+        }
+    }
+    legal.len()
+}
+
+/// Plays a legal move, returning false and leaving the position unchanged if it isn't one.
+///
+/// # Safety
+///
+/// `position` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_make_move(position: *mut ChessterPosition, mv: u16) -> bool {
+    let position = &mut (*position).0;
+    match legal_move(position, mv) {
+        Some(mv) => {
+            position.make_move(mv);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Takes back the last move, returning it or [`CHESSTER_NO_MOVE`] if none have been played.
+///
+/// # Safety
+///
+/// `position` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_unmake_move(position: *mut ChessterPosition) -> u16 {
+    (*position)
+        .0
+        .unmake_move()
+        .map_or(CHESSTER_NO_MOVE, |mv| mv.to_bits())
+}
+
+/// Finds the legal move written in UCI or SAN, or returns [`CHESSTER_NO_MOVE`].
+///
+/// # Safety
+///
+/// `position` must be valid, and `text` must be null or point to a nul terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_parse_move(
+    position: *const ChessterPosition,
+    text: *const c_char,
+) -> u16 {
+    let position = &(*position).0;
+    read_str(text)
+        .and_then(|text| {
+            position
+                .parse_uci(text)
+                .ok()
+                .or_else(|| position.parse_san(text).ok())
+        })
+        .map_or(CHESSTER_NO_MOVE, |mv| mv.to_bits())
+}
+
+/// Writes a legal move in UCI notation into `buf`, returning its length,
+/// or 0 if it isn't legal.
+///
+/// # Safety
+///
+/// `position` must be valid, and `buf` must be valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_move_uci(
+    position: *const ChessterPosition,
+    mv: u16,
+    buf: *mut c_char,
+    len: usize,
+) -> usize {
+    let position = &(*position).0;
+    let text = legal_move(position, mv).map(|mv| position.uci(mv));
+    write_str(text.as_deref().unwrap_or_default(), buf, len)
+}
+
+/// Writes a legal move in SAN into `buf`, returning its length, or 0 if it isn't legal.
+///
+/// # Safety
+///
+/// `position` must be valid, and `buf` must be valid for writes of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_move_san(
+    position: *const ChessterPosition,
+    mv: u16,
+    buf: *mut c_char,
+    len: usize,
+) -> usize {
+    let position = &(*position).0;
+    let text = legal_move(position, mv).map(|mv| position.san(mv));
+    write_str(text.as_deref().unwrap_or_default(), buf, len)
+}
+
+/// Whether the player to move is in check.
+///
+/// # Safety
+///
+/// `position` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_in_check(position: *const ChessterPosition) -> bool {
+    let position = &(*position).0;
+    position.in_check(position.side_to_move())
+}
+
+/// Whether the player to move has been checkmated.
+///
+/// # Safety
+///
+/// `position` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_is_checkmate(position: *const ChessterPosition) -> bool {
+    (*position).0.is_checkmate()
+}
+
+/// Whether the player to move has no legal moves but isn't in check.
+///
+/// # Safety
+///
+/// `position` must be valid.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_is_stalemate(position: *const ChessterPosition) -> bool {
+    (*position).0.is_stalemate()
+}
+
+/// Creates an engine with a transposition table of `hash_mb` megabytes.
+#[no_mangle]
+pub extern "C" fn chesster_engine_new(hash_mb: usize) -> *mut ChessterEngine {
+    Box::into_raw(Box::new(ChessterEngine {
+        tt: TranspositionTable::new(hash_mb.max(1)),
+    }))
+}
+
+/// Frees an engine. Does nothing if `engine` is null.
+///
+/// # Safety
+///
+/// `engine` must be null or have come from this library and not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_engine_free(engine: *mut ChessterEngine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
+
+/// Searches for the best move, or returns [`CHESSTER_NO_MOVE`] if there are no legal moves.
+///
+/// The search stops after `depth` iterations or `movetime_ms` milliseconds,
+/// either of which can be 0 for no limit, but not both.
+/// If `score` isn't null the best move's score in centipawns is written to it.
+/// Mates are written as 32000 less the plies to mate, negated when being mated.
+///
+/// # Safety
+///
+/// `engine` and `position` must be valid, and `score` must be null or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn chesster_engine_search(
+    engine: *const ChessterEngine,
+    position: *const ChessterPosition,
+    depth: u32,
+    movetime_ms: u64,
+    score: *mut i32,
+) -> u16 {
+    let engine = &*engine;
+    let position = &(*position).0;
+    let limits = SearchLimits {
+        depth: (depth > 0 || movetime_ms == 0).then_some(depth.max(1)),
+        time: (movetime_ms > 0).then(|| TimeLimits::fixed(Duration::from_millis(movetime_ms))),
+        multi_pv: 1,
+        ..SearchLimits::default()
+    };
+    let stop = AtomicBool::new(false);
+
+    let result = engine::search(
+        position,
+        &engine::evaluate,
+        &limits,
+        SearchOptions::default(),
+        &engine.tt,
+        1,
+        &stop,
+        |_| {},
+    );
+
+    let Some(line) = result.lines.first() else {
+        return CHESSTER_NO_MOVE;
+    };
+    if !score.is_null() {
+        *score = line.score.raw();
+    }
+    line.pv.first().map_or(CHESSTER_NO_MOVE, |mv| mv.to_bits())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(f: impl Fn(*mut c_char, usize) -> usize) -> String {
+        let mut buf = [0 as c_char; 128];
+        let len = f(buf.as_mut_ptr(), buf.len());
+        let text = unsafe { CStr::from_ptr(buf.as_ptr()) };
+        assert_eq!(text.to_bytes().len(), len);
+        text.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn play_and_search() {
+        unsafe {
+            let position = chesster_position_new();
+            assert_eq!(chesster_side_to_move(position), ChessterColor::White);
+
+            let mut moves = [0; 256];
+            assert_eq!(
+                chesster_legal_moves(position, moves.as_mut_ptr(), moves.len()),
+                20
+            );
+
+            let e4 = chesster_parse_move(position, c"e4".as_ptr());
+            assert_eq!(e4, chesster_parse_move(position, c"e2e4".as_ptr()));
+            assert_eq!(
+                text(|buf, len| chesster_move_san(position, e4, buf, len)),
+                "e4"
+            );
+            assert!(chesster_make_move(position, e4));
+            assert!(!chesster_make_move(position, e4));
+            assert_eq!(
+                chesster_parse_move(position, c"e4".as_ptr()),
+                CHESSTER_NO_MOVE
+            );
+
+            let fen = text(|buf, len| chesster_position_fen(position, buf, len));
+            assert_eq!(
+                fen,
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+            );
+            // truncated, but still nul terminated
+            let mut small = [1 as c_char; 4];
+            assert_eq!(
+                chesster_position_fen(position, small.as_mut_ptr(), small.len()),
+                fen.len()
+            );
+            assert_eq!(CStr::from_ptr(small.as_ptr()).to_bytes(), b"rnb");
+
+            assert_eq!(chesster_unmake_move(position), e4);
+            assert_eq!(chesster_unmake_move(position), CHESSTER_NO_MOVE);
+            chesster_position_free(position);
+
+            assert!(chesster_position_from_fen(c"not a fen".as_ptr()).is_null());
+            let mate_in_one =
+                chesster_position_from_fen(c"6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1".as_ptr());
+            let engine = chesster_engine_new(1);
+            let mut score = 0;
+            let best = chesster_engine_search(engine, mate_in_one, 3, 0, &mut score);
+            assert_eq!(
+                text(|buf, len| chesster_move_uci(mate_in_one, best, buf, len)),
+                "a1a8"
+            );
+            assert!(score > 0);
+            assert!(chesster_make_move(mate_in_one, best));
+            assert!(chesster_is_checkmate(mate_in_one));
+            assert_eq!(
+                chesster_engine_search(engine, mate_in_one, 3, 0, ptr::null_mut()),
+                CHESSTER_NO_MOVE
+            );
+            chesster_engine_free(engine);
+            chesster_position_free(mate_in_one);
+        }
+    }
+}
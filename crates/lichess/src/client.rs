@@ -0,0 +1,166 @@
+//! Requests to the Lichess API.
+
+use std::{
+    io::{self, BufRead, BufReader, Read},
+    marker::PhantomData,
+};
+
+use board::{fen, Move};
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{Event, Game, GameEvent};
+
+/// Where requests go unless [`Client::with_base_url`] says otherwise.
+const LICHESS: &str = "https://lichess.org";
+
+#[derive(Debug, thiserror::Error)]
+pub enum LichessError {
+    #[error(transparent)]
+    Http(Box<ureq::Error>),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("unexpected response: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid starting position: {0}")]
+    Fen(#[from] fen::ParseError),
+    #[error("unsupported variant '{0}'")]
+    UnsupportedVariant(String),
+    #[error("lichess sent an illegal move '{0}'")]
+    IllegalMove(String),
+}
+
+impl From<ureq::Error> for LichessError {
+    fn from(e: ureq::Error) -> Self {
+        Self::Http(Box::new(e))
+    }
+}
+
+/// The account a [`Client`]'s token belongs to.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Account {
+    pub id: String,
+    pub username: String,
+}
+
+/// A connection to Lichess, authorised by a bot account's API token.
+#[derive(Debug, Clone)]
+pub struct Client {
+    agent: ureq::Agent,
+    token: String,
+    base_url: String,
+}
+
+impl Client {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            agent: ureq::Agent::new(),
+            token: token.into(),
+            base_url: LICHESS.to_owned(),
+        }
+    }
+
+    /// Sends requests to another server, such as a local Lichess.
+    pub fn with_base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = url.into();
+        self
+    }
+
+    pub fn account(&self) -> Result<Account, LichessError> {
+        let response = self.request("GET", "/api/account").call()?;
+        Ok(serde_json::from_reader(response.into_reader())?)
+    }
+
+    /// Streams the challenges and games of the bot's account, until the connection closes.
+    pub fn stream_events(&self) -> Result<Stream<Event>, LichessError> {
+        self.stream("/api/stream/event")
+    }
+
+    /// Streams the moves and chat of a game, starting with [`GameEvent::GameFull`].
+    pub fn stream_game(&self, game_id: &str) -> Result<Stream<GameEvent>, LichessError> {
+        self.stream(&format!("/api/bot/game/stream/{game_id}"))
+    }
+
+    /// Plays `mv` in `game`, which should be the bot's turn.
+    pub fn make_move(&self, game: &Game, mv: Move) -> Result<(), LichessError> {
+        let uci = game.position().uci(mv);
+        self.post(&format!("/api/bot/game/{}/move/{uci}", game.id()))
+    }
+
+    pub fn accept_challenge(&self, challenge_id: &str) -> Result<(), LichessError> {
+        self.post(&format!("/api/challenge/{challenge_id}/accept"))
+    }
+
+    /// Declines a challenge, giving one of Lichess' reasons such as `variant` or `timeControl`.
+    pub fn decline_challenge(&self, challenge_id: &str, reason: &str) -> Result<(), LichessError> {
+        self.request("POST", &format!("/api/challenge/{challenge_id}/decline"))
+            .send_form(&[("reason", reason)])?;
+        Ok(())
+    }
+
+    pub fn resign(&self, game_id: &str) -> Result<(), LichessError> {
+        self.post(&format!("/api/bot/game/{game_id}/resign"))
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        self.agent
+            .request(method, &format!("{}{path}", self.base_url))
+            .set("Authorization", &format!("Bearer {}", self.token))
+    }
+
+    fn post(&self, path: &str) -> Result<(), LichessError> {
+        self.request("POST", path).call()?;
+        Ok(())
+    }
+
+    fn stream<T: DeserializeOwned>(&self, path: &str) -> Result<Stream<T>, LichessError> {
+        let response = self.request("GET", path).call()?;
+        Ok(Stream::new(response.into_reader()))
+    }
+}
+
+/// Events read as they arrive, one per line.
+pub struct Stream<T> {
+    lines: io::Lines<BufReader<Box<dyn Read + Send + Sync>>>,
+    events: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Stream<T> {
+    fn new(reader: Box<dyn Read + Send + Sync>) -> Self {
+        Self {
+            lines: BufReader::new(reader).lines(),
+            events: PhantomData,
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for Stream<T> {
+    type Item = Result<T, LichessError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            // empty lines keep the connection alive
+            if !line.trim().is_empty() {
+                return Some(serde_json::from_str(&line).map_err(Into::into));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_skip_keep_alives() {
+        let text = "{\"type\":\"gameStart\",\"game\":{\"gameId\":\"a\"}}\n\n\n{\"type\":\"new\"}\nnot json\n";
+        let mut stream = Stream::<Event>::new(Box::new(io::Cursor::new(text)));
+        assert!(matches!(stream.next(), Some(Ok(Event::GameStart { .. }))));
+        assert!(matches!(stream.next(), Some(Ok(Event::Other))));
+        assert!(matches!(stream.next(), Some(Err(LichessError::Json(_)))));
+        assert!(stream.next().is_none());
+    }
+}
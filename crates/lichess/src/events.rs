@@ -0,0 +1,218 @@
+//! The events Lichess streams to bots, one JSON object per line.
+
+use serde::Deserialize;
+
+/// An event from the bot's account, see [`Client::stream_events`](crate::Client::stream_events).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Event {
+    /// A game started, which can be followed with
+    /// [`Client::stream_game`](crate::Client::stream_game).
+    GameStart {
+        game: GameInfo,
+    },
+    GameFinish {
+        game: GameInfo,
+    },
+    /// Someone challenged the bot, or the bot challenged someone.
+    Challenge {
+        challenge: Challenge,
+    },
+    ChallengeCanceled {
+        challenge: Challenge,
+    },
+    ChallengeDeclined {
+        challenge: Challenge,
+    },
+    /// Any event this client doesn't know.
+    #[serde(other)]
+    Other,
+}
+
+/// The game an [`Event::GameStart`] or [`Event::GameFinish`] is about.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameInfo {
+    pub game_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Challenge {
+    pub id: String,
+    pub challenger: Player,
+    pub variant: Variant,
+    #[serde(default)]
+    pub rated: bool,
+    #[serde(default)]
+    pub time_control: TimeControl,
+}
+
+/// How long each player has in a [`Challenge`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum TimeControl {
+    /// `limit` seconds each, with `increment` seconds added after every move.
+    Clock {
+        limit: u64,
+        increment: u64,
+    },
+    Correspondence {
+        days_per_turn: u32,
+    },
+    #[default]
+    Unlimited,
+}
+
+/// A player, either an account or Lichess' own AI.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Player {
+    /// The account's id, its name in lower case, or empty for the AI.
+    pub id: String,
+    pub name: String,
+    pub title: Option<String>,
+    pub rating: Option<u32>,
+    pub ai_level: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Variant {
+    /// Such as `standard`, `chess960` or `kingOfTheHill`.
+    pub key: String,
+}
+
+/// An event from one game, see [`Client::stream_game`](crate::Client::stream_game).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum GameEvent {
+    /// The whole game, always sent first.
+    GameFull(Box<GameFull>),
+    /// The game after a move, or after its clocks or status changed.
+    GameState(GameState),
+    ChatLine(ChatLine),
+    OpponentGone(OpponentGone),
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameFull {
+    pub id: String,
+    pub variant: Variant,
+    #[serde(default)]
+    pub rated: bool,
+    pub white: Player,
+    pub black: Player,
+    /// The FEN the game started from, or `startpos`.
+    pub initial_fen: String,
+    pub state: GameState,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct GameState {
+    /// Every move of the game in UCI notation, separated by spaces.
+    pub moves: String,
+    /// The players' remaining time and increments, in milliseconds.
+    pub wtime: u64,
+    pub btime: u64,
+    pub winc: u64,
+    pub binc: u64,
+    /// Such as `started`, `mate`, `resign` or `draw`.
+    pub status: String,
+    /// `white` or `black` once the game has been won.
+    #[serde(default)]
+    pub winner: Option<String>,
+}
+
+impl GameState {
+    /// Whether the game has finished, or was aborted.
+    pub fn is_over(&self) -> bool {
+        !matches!(self.status.as_str(), "created" | "started")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ChatLine {
+    pub username: String,
+    pub text: String,
+    /// `player` or `spectator`.
+    pub room: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpponentGone {
+    pub gone: bool,
+    /// How long until the bot can claim the win, while the opponent is gone.
+    pub claim_win_in_seconds: Option<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events() {
+        let start = r#"{"type":"gameStart","game":{"gameId":"rCRw1AuO","fullId":"rCRw1AuOvonq","color":"black"}}"#;
+        assert_eq!(
+            serde_json::from_str::<Event>(start).unwrap(),
+            Event::GameStart {
+                game: GameInfo {
+                    game_id: "rCRw1AuO".to_owned()
+                }
+            }
+        );
+
+        let challenge = r#"{"type":"challenge","challenge":{"id":"7pGLxJ4F","status":"created",
+            "challenger":{"id":"lovlas","name":"Lovlas","rating":1506},
+            "variant":{"key":"standard","name":"Standard"},"rated":true,
+            "timeControl":{"type":"clock","limit":300,"increment":25,"show":"5+25"}}}"#;
+        let Event::Challenge { challenge } = serde_json::from_str(challenge).unwrap() else {
+            panic!("should be a challenge");
+        };
+        assert_eq!(challenge.challenger.name, "Lovlas");
+        assert_eq!(
+            challenge.time_control,
+            TimeControl::Clock {
+                limit: 300,
+                increment: 25
+            }
+        );
+
+        let unknown = r#"{"type":"somethingNew","data":1}"#;
+        assert_eq!(
+            serde_json::from_str::<Event>(unknown).unwrap(),
+            Event::Other
+        );
+    }
+
+    #[test]
+    fn game_events() {
+        let full = r#"{"type":"gameFull","id":"5IrD6Gzz","rated":true,
+            "variant":{"key":"standard","name":"Standard","short":"Std"},
+            "white":{"id":"lovlas","name":"Lovlas","title":"IM","rating":2500},
+            "black":{"aiLevel":8},"initialFen":"startpos",
+            "state":{"type":"gameState","moves":"e2e4 c7c5","wtime":7598040,"btime":8395220,
+            "winc":10000,"binc":10000,"status":"started"}}"#;
+        let GameEvent::GameFull(full) = serde_json::from_str(full).unwrap() else {
+            panic!("should be a full game");
+        };
+        assert_eq!(full.black.ai_level, Some(8));
+        assert_eq!(full.state.moves, "e2e4 c7c5");
+        assert!(!full.state.is_over());
+
+        let state = r#"{"type":"gameState","moves":"e2e4 c7c5 f2f4","wtime":7598040,
+            "btime":8395220,"winc":10000,"binc":10000,"status":"resign","winner":"black"}"#;
+        let GameEvent::GameState(state) = serde_json::from_str(state).unwrap() else {
+            panic!("should be a game state");
+        };
+        assert!(state.is_over());
+        assert_eq!(state.winner.as_deref(), Some("black"));
+    }
+}
@@ -0,0 +1,168 @@
+//! Following a game's moves as positions.
+
+use std::time::Duration;
+
+use board::{variant, Color, Move, Position, FEN};
+use uci::GoParams;
+
+use crate::{GameFull, GameState, LichessError};
+
+/// A game the bot is playing, kept up to date from its [`GameState`]s.
+#[derive(Debug, Clone)]
+pub struct Game {
+    id: String,
+    color: Color,
+    initial: Position,
+    position: Position,
+    moves: Vec<Move>,
+    state: GameState,
+}
+
+impl Game {
+    /// Starts following the game from its [`GameFull`], playing as the account `bot`.
+    pub fn new(full: GameFull, bot: &str) -> Result<Self, LichessError> {
+        let variant = match full.variant.key.as_str() {
+            "standard" | "chess960" | "fromPosition" => &variant::Standard,
+            key => variant::from_name(key)
+                .ok_or_else(|| LichessError::UnsupportedVariant(key.to_owned()))?,
+        };
+        let initial = match full.initial_fen.as_str() {
+            "startpos" => Position::start_variant(variant),
+            fen => FEN::new(fen).parse_position_as(variant)?,
+        };
+        let color = if full.white.id.eq_ignore_ascii_case(bot) {
+            Color::White
+        } else {
+            Color::Black
+        };
+
+        let mut game = Self {
+            id: full.id,
+            color,
+            position: initial.clone(),
+            initial,
+            moves: Vec::new(),
+            state: full.state.clone(),
+        };
+        game.update(full.state)?;
+        Ok(game)
+    }
+
+    /// Catches up with the moves in `state`.
+    ///
+    /// Only the new moves are made, unless moves were taken back.
+    pub fn update(&mut self, state: GameState) -> Result<(), LichessError> {
+        let moves = state.moves.split_whitespace().collect::<Vec<_>>();
+        if moves.len() < self.moves.len() {
+            self.position = self.initial.clone();
+            self.moves.clear();
+        }
+        for &text in &moves[self.moves.len()..] {
+            let mv = self
+                .position
+                .parse_uci(text)
+                .map_err(|_| LichessError::IllegalMove(text.to_owned()))?;
+            self.position.make_move(mv);
+            self.moves.push(mv);
+        }
+        self.state = state;
+        Ok(())
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The colour the bot plays.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// The position after every move so far.
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// Whether the game is still going, and it is the bot's move.
+    pub fn is_our_turn(&self) -> bool {
+        !self.state.is_over() && self.position.side_to_move() == self.color
+    }
+
+    /// The clocks as a `go` command, for deciding how long to think.
+    pub fn go_params(&self) -> GoParams {
+        let millis = Duration::from_millis;
+        let state = &self.state;
+        GoParams {
+            wtime: Some(millis(state.wtime)),
+            btime: Some(millis(state.btime)),
+            winc: Some(millis(state.winc)),
+            binc: Some(millis(state.binc)),
+            ..GoParams::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Player, Variant};
+
+    fn state(moves: &str) -> GameState {
+        GameState {
+            moves: moves.to_owned(),
+            wtime: 60_000,
+            btime: 60_000,
+            winc: 1000,
+            binc: 1000,
+            status: "started".to_owned(),
+            winner: None,
+        }
+    }
+
+    #[test]
+    fn following_moves() {
+        let full = GameFull {
+            id: "abcdefgh".to_owned(),
+            variant: Variant {
+                key: "standard".to_owned(),
+            },
+            rated: false,
+            white: Player::default(),
+            black: Player {
+                id: "chesster".to_owned(),
+                ..Player::default()
+            },
+            initial_fen: "startpos".to_owned(),
+            state: state("e2e4"),
+        };
+        let mut game = Game::new(full, "Chesster").unwrap();
+        assert_eq!(game.color(), Color::Black);
+        assert!(game.is_our_turn());
+
+        game.update(state("e2e4 e7e5 e1e2")).unwrap();
+        assert_eq!(game.moves().len(), 3);
+        assert!(game.is_our_turn());
+
+        // a takeback replays the game from the start
+        game.update(state("e2e4 e7e5")).unwrap();
+        let mut position = Position::start();
+        for uci in ["e2e4", "e7e5"] {
+            position.make_move(position.parse_uci(uci).unwrap());
+        }
+        assert_eq!(game.position().zobrist_key(), position.zobrist_key());
+        assert!(!game.is_our_turn());
+
+        assert!(matches!(
+            game.update(state("e2e4 e7e5 e2e5")),
+            Err(LichessError::IllegalMove(_))
+        ));
+    }
+}
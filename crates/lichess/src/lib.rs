@@ -0,0 +1,16 @@
+//! A client for the [Lichess Bot API](https://lichess.org/api#tag/Bot).
+//!
+//! A bot streams its account's [`Event`]s to hear about challenges and new games,
+//! then streams each game's [`GameEvent`]s, following them as a [`Game`] whose
+//! [`Position`](board::Position) it can search, and answers with [`Client::make_move`].
+
+mod client;
+mod events;
+mod game;
+
+pub use client::{Account, Client, LichessError, Stream};
+pub use events::{
+    Challenge, ChatLine, Event, GameEvent, GameFull, GameInfo, GameState, OpponentGone, Player,
+    TimeControl, Variant,
+};
+pub use game::Game;
@@ -0,0 +1,164 @@
+use std::{
+    process::ExitCode,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use engine::{search, EvalParams, SearchLimits, SearchOptions, TranspositionTable};
+use lichess::{Challenge, Client, Event, Game, GameEvent, LichessError};
+
+const USAGE: &str = "usage: bot [--token TOKEN] [--concurrency N] [--hash MB] [--threads N]
+           [--params FILE]
+
+The token is read from LICHESS_TOKEN when --token isn't given.";
+
+struct Args {
+    token: String,
+    /// The most games played at once, more challenges are declined.
+    concurrency: usize,
+    hash_mb: usize,
+    threads: usize,
+    params: Option<String>,
+}
+
+fn parse_args() -> Option<Args> {
+    let mut args = std::env::args().skip(1);
+    let mut parsed = Args {
+        token: std::env::var("LICHESS_TOKEN").unwrap_or_default(),
+        concurrency: 1,
+        hash_mb: 64,
+        threads: 1,
+        params: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--token" => parsed.token = args.next()?,
+            "--concurrency" => parsed.concurrency = args.next()?.parse().ok().filter(|&n| n > 0)?,
+            "--hash" => parsed.hash_mb = args.next()?.parse().ok().filter(|&mb| mb > 0)?,
+            "--threads" => parsed.threads = args.next()?.parse().ok().filter(|&n| n > 0)?,
+            "--params" => parsed.params = Some(args.next()?),
+            _ => return None,
+        }
+    }
+    (!parsed.token.is_empty()).then_some(parsed)
+}
+
+/// Why a challenge is declined, or None to accept it.
+fn decline_reason(challenge: &Challenge) -> Option<&'static str> {
+    match challenge.variant.key.as_str() {
+        "standard" | "chess960" | "fromPosition" => None,
+        _ => Some("variant"),
+    }
+}
+
+/// Plays one game to the end.
+fn play(
+    client: &Client,
+    bot: &str,
+    game_id: &str,
+    params: &EvalParams,
+    args: &Args,
+) -> Result<(), LichessError> {
+    let tt = TranspositionTable::new(args.hash_mb);
+    let stop = AtomicBool::new(false);
+    let mut game: Option<Game> = None;
+
+    for event in client.stream_game(game_id)? {
+        match event? {
+            GameEvent::GameFull(full) => game = Some(Game::new(*full, bot)?),
+            GameEvent::GameState(state) => match &mut game {
+                Some(game) => game.update(state)?,
+                None => continue,
+            },
+            _ => continue,
+        }
+        let Some(game) = &game else { continue };
+        if game.state().is_over() {
+            break;
+        }
+        if !game.is_our_turn() {
+            continue;
+        }
+
+        let limits = SearchLimits::from_go(&game.go_params(), game.color());
+        let result = search(
+            game.position(),
+            params,
+            &limits,
+            SearchOptions::default(),
+            &tt,
+            args.threads,
+            &stop,
+            |_| {},
+        );
+        if let Some(mv) = result.best_move() {
+            client.make_move(game, mv)?;
+        }
+    }
+    Ok(())
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let params = match &args.params {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| format!("{path}: {e}"))?
+            .parse::<EvalParams>()
+            .map_err(|e| format!("{path}: {e}"))?,
+        None => EvalParams::default(),
+    };
+    let client = Client::new(args.token.clone());
+    let account = client.account().map_err(|e| e.to_string())?;
+    eprintln!("playing as {}", account.username);
+
+    let playing = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for event in client.stream_events().map_err(|e| e.to_string())? {
+            match event.map_err(|e| e.to_string())? {
+                // challenges the bot sends come through here too
+                Event::Challenge { challenge } if challenge.challenger.id != account.id => {
+                    let reason = match decline_reason(&challenge) {
+                        None if playing.load(Ordering::Relaxed) >= args.concurrency => {
+                            Some("later")
+                        }
+                        reason => reason,
+                    };
+                    let answer = match reason {
+                        Some(reason) => client.decline_challenge(&challenge.id, reason),
+                        None => client.accept_challenge(&challenge.id),
+                    };
+                    if let Err(e) = answer {
+                        eprintln!("challenge {}: {e}", challenge.id);
+                    }
+                }
+                Event::GameStart { game } => {
+                    playing.fetch_add(1, Ordering::Relaxed);
+                    let (client, account, params, args, playing) =
+                        (&client, &account, &params, &args, &playing);
+                    scope.spawn(move || {
+                        eprintln!("game {} started", game.game_id);
+                        if let Err(e) = play(client, &account.id, &game.game_id, params, args) {
+                            eprintln!("game {}: {e}", game.game_id);
+                        }
+                        playing.fetch_sub(1, Ordering::Relaxed);
+                    });
+                }
+                Event::GameFinish { game } => eprintln!("game {} finished", game.game_id),
+                _ => {}
+            }
+        }
+        Ok(())
+    })
+}
+
+fn main() -> ExitCode {
+    let Some(args) = parse_args() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    process::ExitCode,
+};
+
+use board::pgn;
+use tuner::export::{samples, Filter, Format};
+
+const USAGE: &str = "usage: export <games.pgn>... [--format text|marlin] [--out FILE]
+              [--max-score CP] [--skip-plies N] [--keep-check] [--keep-tactical]";
+
+struct Args {
+    games: Vec<String>,
+    format: Format,
+    out: Option<String>,
+    filter: Filter,
+}
+
+fn parse_args() -> Option<Args> {
+    let mut args = std::env::args().skip(1);
+    let mut parsed = Args {
+        games: Vec::new(),
+        format: Format::Text,
+        out: None,
+        filter: Filter::default(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => parsed.format = args.next()?.parse().ok()?,
+            "--out" => parsed.out = Some(args.next()?),
+            "--max-score" => parsed.filter.max_score = args.next()?.parse().ok()?,
+            "--skip-plies" => parsed.filter.skip_plies = args.next()?.parse().ok()?,
+            "--keep-check" => parsed.filter.skip_check = false,
+            "--keep-tactical" => parsed.filter.skip_tactical = false,
+            _ if arg.starts_with("--") => return None,
+            _ => parsed.games.push(arg),
+        }
+    }
+    (!parsed.games.is_empty()).then_some(parsed)
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let mut out: BufWriter<Box<dyn Write>> = BufWriter::new(match &args.out {
+        Some(path) => Box::new(File::create(path).map_err(|e| format!("{path}: {e}"))?),
+        None => Box::new(io::stdout()),
+    });
+
+    let (mut games, mut positions) = (0, 0);
+    for path in &args.games {
+        let text = fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+        for (index, game) in pgn::games(&text).enumerate() {
+            let error = |e: &dyn std::fmt::Display| format!("{path}: game {}: {e}", index + 1);
+            let game = game.map_err(|e| error(&e))?;
+            for sample in samples(&game, &args.filter).map_err(|e| error(&e))? {
+                args.format
+                    .write(&mut out, &sample)
+                    .map_err(|e| e.to_string())?;
+                positions += 1;
+            }
+            games += 1;
+        }
+    }
+    out.flush().map_err(|e| e.to_string())?;
+
+    eprintln!("wrote {positions} positions from {games} games");
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let Some(args) = parse_args() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
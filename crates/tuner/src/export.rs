@@ -0,0 +1,236 @@
+//! Turning self-play games into training data for NNUE trainers.
+//!
+//! Only positions the engine searched are used, labelled with the `[%eval]` it gave
+//! and the result of the game, both from White's point of view.
+//! Two formats can be written:
+//!
+//! - [`Format::Text`]: `<fen> | <score> | <result>` lines, as read by
+//!   [bullet](https://github.com/jw1912/bullet) and by [`parse_line`](crate::parse_line).
+//! - [`Format::Marlin`]: 32 byte records in the
+//!   [marlinformat](https://github.com/jnlt3/marlinflow) layout, which bullet can also read.
+
+use std::{
+    fmt,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use board::{
+    pgn::{self, Eval, Outcome, PgnError},
+    CastleSide, Color, PieceKind, Position, Square,
+};
+use engine::is_tactical;
+
+/// How the samples are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Marlin,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unknown format '{0}', expected 'text' or 'marlin'")]
+pub struct ParseFormatError(String);
+
+impl FromStr for Format {
+    type Err = ParseFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "marlin" => Ok(Self::Marlin),
+            _ => Err(ParseFormatError(s.to_owned())),
+        }
+    }
+}
+
+impl Format {
+    /// Writes a single sample.
+    pub fn write(self, out: &mut impl Write, sample: &Sample) -> io::Result<()> {
+        match self {
+            Self::Text => writeln!(out, "{sample}"),
+            Self::Marlin => out.write_all(&sample.to_marlin()),
+        }
+    }
+}
+
+/// Which positions are left out of the training data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Filter {
+    /// Leave out positions where the side to move is in check.
+    pub skip_check: bool,
+    /// Leave out positions where the engine played a capture or promotion,
+    /// whose scores depend on the exchange being resolved.
+    pub skip_tactical: bool,
+    /// Leave out positions scored further than this from zero.
+    pub max_score: i32,
+    /// Leave out this many plies at the start of each game.
+    pub skip_plies: usize,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            skip_check: true,
+            skip_tactical: true,
+            max_score: 3000,
+            skip_plies: 0,
+        }
+    }
+}
+
+/// A labelled training position.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub position: Position,
+    /// The engine's score in centipawns, from White's point of view.
+    pub score: i32,
+    /// 1 for a white win, 0.5 for a draw and 0 for a black win.
+    pub result: f64,
+}
+
+impl Sample {
+    /// The sample as a marlinformat record.
+    ///
+    /// The occupied squares as a bitboard are followed by a nibble for each piece,
+    /// in square order, holding its kind with bit 3 set for black.
+    /// Rooks that can still castle get kind 6.
+    pub fn to_marlin(&self) -> [u8; 32] {
+        let position = &self.position;
+        let board = position.board();
+
+        let mut castling_rooks = 0u64;
+        for color in [Color::White, Color::Black] {
+            for side in [CastleSide::King, CastleSide::Queen] {
+                if position.castling().has(color, side) {
+                    castling_rooks |= position.castling_rook(color, side).bit();
+                }
+            }
+        }
+
+        let mut occupancy = 0u64;
+        let mut pieces = 0u128;
+        let mut count = 0;
+        for square in (0..64).map(Square::from_raw) {
+            let Some(piece) = board.piece_on(square) else {
+                continue;
+            };
+            let kind = match piece.kind() {
+                PieceKind::Rook if castling_rooks & square.bit() != 0 => 6,
+                kind => kind as u128,
+            };
+            let color = (piece.color() == Color::Black) as u128;
+            occupancy |= square.bit();
+            pieces |= (kind | color << 3) << (4 * count);
+            count += 1;
+        }
+
+        let en_passant = position.en_passant().map_or(64, |square| square as u8);
+        let stm = ((position.side_to_move() == Color::Black) as u8) << 7;
+        let wdl = (self.result * 2.0).round() as u8;
+
+        let mut record = [0; 32];
+        record[..8].copy_from_slice(&occupancy.to_le_bytes());
+        record[8..24].copy_from_slice(&pieces.to_le_bytes());
+        record[24] = stm | en_passant;
+        record[25] = position.halfmove_clock().min(u8::MAX as u32) as u8;
+        record[26..28].copy_from_slice(&(position.fullmove_number() as u16).to_le_bytes());
+        record[28..30].copy_from_slice(&(self.score.clamp(-32_000, 32_000) as i16).to_le_bytes());
+        record[30] = wdl;
+        record
+    }
+}
+
+/// Writes the sample as a `<fen> | <score> | <result>` line.
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} | {} | {:.1}",
+            self.position.to_fen(),
+            self.score,
+            self.result
+        )
+    }
+}
+
+/// The samples from the main line of `game` that pass `filter`.
+///
+/// Games without a result give no samples, nor do moves scored as mates.
+pub fn samples(game: &pgn::Game, filter: &Filter) -> Result<Vec<Sample>, PgnError> {
+    let result = match game.outcome {
+        Outcome::WhiteWins => 1.0,
+        Outcome::BlackWins => 0.0,
+        Outcome::Draw => 0.5,
+        Outcome::Unknown => return Ok(Vec::new()),
+    };
+
+    let mut position = game.start_position()?;
+    let mut samples = Vec::new();
+    for (ply, node) in game.moves.iter().enumerate() {
+        let keep = match node.eval {
+            Some(Eval::Centipawns(score)) => (ply >= filter.skip_plies
+                && score.abs() <= filter.max_score
+                && !(filter.skip_check && position.in_check(position.side_to_move()))
+                && !(filter.skip_tactical && is_tactical(&position, node.mv)))
+            .then_some(score),
+            _ => None,
+        };
+        if let Some(score) = keep {
+            samples.push(Sample {
+                position: position.clone(),
+                score,
+                result,
+            });
+        }
+        position.make_move(node.mv);
+    }
+    Ok(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filtering() {
+        let game = pgn::Game::parse(
+            "1. e4 {[%eval 0.3]} e5 {[%eval 0.2]} 2. Qh5 {[%eval 0.1]} Nc6 {[%eval 0.5]} \
+             3. Bc4 {[%eval 0.6]} Nf6 {[%eval 3.1]} 4. Qxf7# {[%eval #1]} 1-0",
+        )
+        .unwrap();
+
+        let samples = samples(&game, &Filter::default()).unwrap();
+        assert_eq!(samples.len(), 6);
+        assert_eq!(samples[1].score, 20);
+        assert_eq!(samples[1].result, 1.0);
+        assert_eq!(
+            samples[1].to_string(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1 | 20 | 1.0"
+        );
+
+        let strict = Filter {
+            max_score: 100,
+            skip_plies: 2,
+            ..Filter::default()
+        };
+        assert_eq!(super::samples(&game, &strict).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn marlin() {
+        let sample = Sample {
+            position: Position::start(),
+            score: -25,
+            result: 0.5,
+        };
+        let record = sample.to_marlin();
+        assert_eq!(&record[..8], &0xFFFF_0000_0000_FFFFu64.to_le_bytes());
+        // a1 is a rook that can castle, b1 a knight, ..., h8 a black castling rook
+        assert_eq!(record[8], 0x16);
+        assert_eq!(record[23] >> 4, 0xE);
+        assert_eq!(record[24], 64);
+        assert_eq!(i16::from_le_bytes([record[28], record[29]]), -25);
+        assert_eq!(record[30], 1);
+    }
+}
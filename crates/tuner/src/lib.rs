@@ -0,0 +1,385 @@
+//! [Texel tuning](https://www.chessprogramming.org/Texel%27s_Tuning_Method) of [`EvalParams`].
+//!
+//! Each position is labelled with the result of the game it came from. The evaluation is
+//! turned into an expected result with a sigmoid, and the weights are moved by gradient
+//! descent until the expected results best match the real ones.
+//!
+//! The evaluation is linear in its weights, see [`EvalParams::coefficients`],
+//! so each position only has to be looked at once when it is loaded. The coefficients are
+//! scaled by the game phase and the endgame scale, so their sum is divided by [`MAX_PHASE`]
+//! times [`FULL_SCALE`] to get the evaluation.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+    thread,
+};
+
+use board::{Position, FEN};
+use engine::{EvalParams, FULL_SCALE, MAX_PHASE};
+
+pub mod export;
+
+/// Positions along with the results of their games, ready for tuning.
+#[derive(Debug, Clone, Default)]
+pub struct Dataset {
+    samples: Vec<Sample>,
+    /// The non-zero coefficients of every sample, one after another.
+    terms: Vec<(u16, i32)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    /// Where the sample's terms start.
+    start: u32,
+    len: u16,
+    /// 1 for a white win, 0.5 for a draw and 0 for a black win.
+    result: f32,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DataError {
+    #[error("line {line}: {reason}")]
+    Invalid { line: usize, reason: String },
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl Dataset {
+    /// Creates an empty dataset.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a position from a game that ended with `result` for white,
+    /// 1 for a win, 0.5 for a draw and 0 for a loss.
+    pub fn push(&mut self, position: &Position, result: f64) {
+        let start = self.terms.len() as u32;
+        self.terms.extend(
+            EvalParams::coefficients(position)
+                .into_iter()
+                .enumerate()
+                .filter(|&(_, coefficient)| coefficient != 0)
+                .map(|(index, coefficient)| (index as u16, coefficient)),
+        );
+        self.samples.push(Sample {
+            start,
+            len: (self.terms.len() - start as usize) as u16,
+            result: result as f32,
+        });
+    }
+
+    /// Reads one labelled position per line, see [`parse_line`].
+    ///
+    /// Empty lines and lines starting with `#` are skipped.
+    pub fn read(reader: impl BufRead) -> Result<Self, DataError> {
+        let mut dataset = Self::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (position, result) = parse_line(line).map_err(|reason| DataError::Invalid {
+                line: number + 1,
+                reason,
+            })?;
+            dataset.push(&position, result);
+        }
+        Ok(dataset)
+    }
+
+    /// Reads a file of labelled positions, see [`Dataset::read`].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DataError> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// How many positions there are.
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Returns `true` if there are no positions.
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    fn terms(&self, sample: &Sample) -> &[(u16, i32)] {
+        let start = sample.start as usize;
+        &self.terms[start..start + sample.len as usize]
+    }
+}
+
+/// Parses a position followed by the result of its game, for white.
+///
+/// The result can be written as `1-0`, `1/2-1/2` or `0-1`, or as a number such as `0.5`,
+/// and may be wrapped in brackets or quotes, as in `[1.0]` or EPD's `c9 "1-0";`.
+/// The FEN can leave out the move counters.
+///
+/// Lines written by [`export`] as `<fen> | <score> | <result>` are read too,
+/// with the score ignored.
+pub fn parse_line(line: &str) -> Result<(Position, f64), String> {
+    let (fen, result) = match line.split_once('|') {
+        Some((fen, rest)) => rest
+            .rsplit_once('|')
+            .map(|(_, result)| (fen.trim_end(), result.trim()))
+            .ok_or("expected a FEN, score and result")?,
+        None => line
+            .trim_end()
+            .rsplit_once(char::is_whitespace)
+            .ok_or("expected a FEN followed by a result")?,
+    };
+
+    let token = result.trim_matches(|c| matches!(c, '[' | ']' | '"' | ';'));
+    let result = match token {
+        "1-0" => 1.0,
+        "1/2-1/2" => 0.5,
+        "0-1" => 0.0,
+        _ => token
+            .parse()
+            .ok()
+            .filter(|result| (0.0..=1.0).contains(result))
+            .ok_or_else(|| format!("invalid result '{result}'"))?,
+    };
+
+    let mut fields = fen.split_whitespace().collect::<Vec<_>>();
+    if fields.last() == Some(&"c9") {
+        fields.pop();
+    }
+    if fields.len() == 4 {
+        fields.extend(["0", "1"]);
+    }
+    let position = FEN::try_from(fields.join(" "))
+        .and_then(|fen| fen.parse_position())
+        .map_err(|e| e.to_string())?;
+
+    Ok((position, result))
+}
+
+/// Finds weights that best predict the results in a [`Dataset`].
+///
+/// Weights are held as real numbers while tuning, and stepped with Adam,
+/// which copes with the weights having very different scales.
+#[derive(Debug, Clone)]
+pub struct Tuner {
+    weights: Vec<f64>,
+    /// Scales centipawns before the sigmoid, see [`Tuner::fit_scale`].
+    scale: f64,
+    learning_rate: f64,
+    step: i32,
+    /// Adam's running means of the gradient and its square.
+    momentum: Vec<f64>,
+    velocity: Vec<f64>,
+}
+
+impl Tuner {
+    const BETA1: f64 = 0.9;
+    const BETA2: f64 = 0.999;
+    const EPSILON: f64 = 1e-8;
+
+    /// Starts tuning from `params`.
+    pub fn new(params: &EvalParams) -> Self {
+        let weights = params
+            .weights()
+            .into_iter()
+            .map(f64::from)
+            .collect::<Vec<_>>();
+        let len = weights.len();
+        Self {
+            weights,
+            scale: 1.0,
+            learning_rate: 1.0,
+            step: 0,
+            momentum: vec![0.0; len],
+            velocity: vec![0.0; len],
+        }
+    }
+
+    /// Sets how far the weights move each epoch, in centipawns. The default is 1.
+    pub fn learning_rate(self, learning_rate: f64) -> Self {
+        Self {
+            learning_rate,
+            ..self
+        }
+    }
+
+    /// The tuned weights, rounded to whole centipawns.
+    pub fn params(&self) -> EvalParams {
+        let mut params = EvalParams::default();
+        let weights = self
+            .weights
+            .iter()
+            .map(|weight| weight.round() as i32)
+            .collect::<Vec<_>>();
+        params.set_weights(&weights);
+        params
+    }
+
+    /// Finds the scale for the sigmoid that best fits the current weights to `data`,
+    /// which should be done once before tuning.
+    ///
+    /// This stops the tuner moving every weight just to match a different scale.
+    pub fn fit_scale(&mut self, data: &Dataset) -> f64 {
+        // the error is unimodal in the scale, so a ternary search finds the best one
+        let (mut low, mut high) = (0.0, 10.0);
+        for _ in 0..60 {
+            let third = (high - low) / 3.0;
+            let (a, b) = (low + third, high - third);
+            self.scale = a;
+            let error_a = self.error(data);
+            self.scale = b;
+            let error_b = self.error(data);
+            if error_a < error_b {
+                high = b;
+            } else {
+                low = a;
+            }
+        }
+        self.scale = (low + high) / 2.0;
+        self.scale
+    }
+
+    /// The mean squared difference between the expected and real results.
+    pub fn error(&self, data: &Dataset) -> f64 {
+        let total = parallel(data, |samples| {
+            samples
+                .iter()
+                .map(|sample| {
+                    let expected = self.expected(data, sample);
+                    (sample.result as f64 - expected).powi(2)
+                })
+                .sum::<f64>()
+        })
+        .into_iter()
+        .sum::<f64>();
+        total / data.len().max(1) as f64
+    }
+
+    /// Takes one step of gradient descent over the whole of `data`.
+    pub fn epoch(&mut self, data: &Dataset) {
+        let len = self.weights.len();
+        let gradients = parallel(data, |samples| {
+            let mut gradient = vec![0.0; len];
+            for sample in samples {
+                let expected = self.expected(data, sample);
+                // the derivative of the squared error through the sigmoid
+                let slope = (expected - sample.result as f64) * expected * (1.0 - expected);
+                for &(index, coefficient) in data.terms(sample) {
+                    gradient[index as usize] += slope * coefficient as f64;
+                }
+            }
+            gradient
+        });
+
+        let mut gradient = vec![0.0; len];
+        for part in gradients {
+            for (total, value) in gradient.iter_mut().zip(part) {
+                *total += value;
+            }
+        }
+
+        self.step += 1;
+        let scale = 2.0 * self.scale * 10_f64.ln() / 400.0 / data.len().max(1) as f64;
+        for (i, gradient) in gradient.into_iter().enumerate() {
+            let gradient = gradient * scale;
+            self.momentum[i] = Self::BETA1 * self.momentum[i] + (1.0 - Self::BETA1) * gradient;
+            self.velocity[i] =
+                Self::BETA2 * self.velocity[i] + (1.0 - Self::BETA2) * gradient * gradient;
+
+            let momentum = self.momentum[i] / (1.0 - Self::BETA1.powi(self.step));
+            let velocity = self.velocity[i] / (1.0 - Self::BETA2.powi(self.step));
+            self.weights[i] -= self.learning_rate * momentum / (velocity.sqrt() + Self::EPSILON);
+        }
+    }
+
+    /// The expected result for white of a sample with the current weights.
+    fn expected(&self, data: &Dataset, sample: &Sample) -> f64 {
+        let eval = data
+            .terms(sample)
+            .iter()
+            .map(|&(index, coefficient)| self.weights[index as usize] * coefficient as f64)
+            .sum::<f64>()
+            / (MAX_PHASE * FULL_SCALE) as f64;
+        1.0 / (1.0 + 10_f64.powf(-self.scale * eval / 400.0))
+    }
+}
+
+/// Splits the samples between threads, returning what each thread worked out.
+fn parallel<T: Send>(data: &Dataset, work: impl Fn(&[Sample]) -> T + Sync) -> Vec<T> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = data.samples.len().div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        let work = &work;
+        let handles = data
+            .samples
+            .chunks(chunk)
+            .map(|samples| scope.spawn(move || work(samples)))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("a tuning thread panicked"))
+            .collect()
+    })
+}
+
+/// Writes `params` as Rust source, to be pasted into the engine.
+pub fn rust_source(params: &EvalParams) -> String {
+    format!("// written by the chesster tuner\nconst TUNED: EvalParams = {params:#?};\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine::Tapered;
+
+    #[test]
+    fn labelled_lines() {
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -";
+        for (line, expected) in [
+            (format!("{start} 0 1 [1.0]"), 1.0),
+            (format!("{start} 0 1 1/2-1/2"), 0.5),
+            (format!("{start} c9 \"0-1\";"), 0.0),
+            (format!("{start} 0.25"), 0.25),
+            (format!("{start} 0 1 | -31 | 0.5"), 0.5),
+        ] {
+            let (position, result) = parse_line(&line).unwrap();
+            assert_eq!(position.zobrist_key(), Position::start().zobrist_key());
+            assert_eq!(result, expected);
+        }
+
+        assert!(parse_line(&format!("{start} 0 1 2-0")).is_err());
+        assert!(Dataset::read("# a comment\n\nnot a position 1-0\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn tuning_reduces_error() {
+        // white wins when it has an extra knight, and black when it has an extra knight,
+        // with pawns left so the knight isn't a dead draw on its own
+        let data = Dataset::read(
+            "4k3/pp6/8/8/8/8/PP6/1N2K3 w - - 0 1 1-0\n\
+             4k3/pp6/8/8/8/8/PP6/1N2K3 b - - 0 1 1-0\n\
+             1n2k3/pp6/8/8/8/8/PP6/4K3 w - - 0 1 0-1\n\
+             4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1 1/2-1/2\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(data.len(), 4);
+
+        // start with knights worth nothing
+        let mut params = EvalParams::default();
+        params.piece_values[1] = Tapered::default();
+        let mut tuner = Tuner::new(&params).learning_rate(5.0);
+        tuner.scale = 1.0;
+
+        let before = tuner.error(&data);
+        for _ in 0..50 {
+            tuner.epoch(&data);
+        }
+        assert!(tuner.error(&data) < before);
+        assert!(tuner.params().piece_values[1].eg > 0);
+
+        assert!(rust_source(&tuner.params()).contains("piece_values: ["));
+    }
+}
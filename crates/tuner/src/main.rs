@@ -0,0 +1,95 @@
+use std::{fs, process::ExitCode};
+
+use engine::EvalParams;
+use tuner::{rust_source, Dataset, Tuner};
+
+const USAGE: &str =
+    "usage: tuner <positions> [--epochs N] [--rate R] [--params FILE] [--out FILE] [--rust]";
+
+struct Args {
+    data: String,
+    epochs: u32,
+    rate: f64,
+    params: Option<String>,
+    out: Option<String>,
+    rust: bool,
+}
+
+fn parse_args() -> Option<Args> {
+    let mut args = std::env::args().skip(1);
+    let mut parsed = Args {
+        data: String::new(),
+        epochs: 1000,
+        rate: 1.0,
+        params: None,
+        out: None,
+        rust: false,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--epochs" => parsed.epochs = args.next()?.parse().ok()?,
+            "--rate" => parsed.rate = args.next()?.parse().ok()?,
+            "--params" => parsed.params = Some(args.next()?),
+            "--out" => parsed.out = Some(args.next()?),
+            "--rust" => parsed.rust = true,
+            _ if arg.starts_with("--") || !parsed.data.is_empty() => return None,
+            _ => parsed.data = arg,
+        }
+    }
+    (!parsed.data.is_empty()).then_some(parsed)
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let params = match &args.params {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|e| format!("{path}: {e}"))?
+            .parse::<EvalParams>()
+            .map_err(|e| format!("{path}: {e}"))?,
+        None => EvalParams::default(),
+    };
+
+    let data = Dataset::load(&args.data).map_err(|e| format!("{}: {e}", args.data))?;
+    if data.is_empty() {
+        return Err(format!("{}: no positions", args.data));
+    }
+    eprintln!("loaded {} positions", data.len());
+
+    let mut tuner = Tuner::new(&params).learning_rate(args.rate);
+    let scale = tuner.fit_scale(&data);
+    eprintln!("scale {scale:.4}, error {:.6}", tuner.error(&data));
+
+    for epoch in 1..=args.epochs {
+        tuner.epoch(&data);
+        if epoch % 50 == 0 || epoch == args.epochs {
+            eprintln!("epoch {epoch}, error {:.6}", tuner.error(&data));
+        }
+    }
+
+    let params = tuner.params();
+    let output = if args.rust {
+        rust_source(&params)
+    } else {
+        params.to_string()
+    };
+    match &args.out {
+        Some(path) => fs::write(path, output).map_err(|e| format!("{path}: {e}")),
+        None => {
+            print!("{output}");
+            Ok(())
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let Some(args) = parse_args() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
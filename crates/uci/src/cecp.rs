@@ -0,0 +1,475 @@
+//! A frontend speaking xboard's [Chess Engine Communication Protocol](https://www.gnu.org/software/xboard/engine-intf.html),
+//! for GUIs and tournament managers that don't support UCI.
+//!
+//! The same [`Engine`] is driven as with UCI. Unlike UCI, the engine keeps track of the game
+//! itself, choosing when to move and reporting the result when the game ends.
+
+use std::{
+    io::{self, BufRead, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use board::{Color, Draw, Game, GameResult, Move, FEN};
+
+use crate::{send, Engine, GoParams, Info, InfoScore, Output, Protocol, Searcher};
+
+/// Scores at least this far from zero are mates, as xboard writes them.
+const MATE_SCORE: i32 = 100_000;
+
+/// How long to think for when the GUI hasn't set a time control.
+const DEFAULT_MOVE_TIME: Duration = Duration::from_secs(5);
+
+/// The time control set by `level`, `st` and `sd`, and the clocks sent with `time` and `otim`.
+#[derive(Debug, Clone, Default)]
+struct Clock {
+    /// Moves per time control, 0 for the whole game.
+    moves_per_control: u32,
+    base: Duration,
+    increment: Duration,
+    /// Exactly how long to think for each move.
+    move_time: Option<Duration>,
+    depth: Option<u32>,
+    ours: Option<Duration>,
+    theirs: Option<Duration>,
+}
+
+/// A search running on another thread.
+struct Search {
+    stop: Arc<AtomicBool>,
+    /// Set to throw away the move instead of playing it.
+    discard: Arc<AtomicBool>,
+    /// The move played, if it was sent to the GUI.
+    handle: JoinHandle<Option<Move>>,
+}
+
+struct Cecp<E> {
+    engine: Arc<Mutex<E>>,
+    game: Game,
+    output: Output,
+    /// The side the engine plays, None in force mode.
+    engine_side: Option<Color>,
+    clock: Clock,
+    post: bool,
+    search: Option<Search>,
+}
+
+impl<E: Engine> Cecp<E> {
+    fn send(&self, line: std::fmt::Arguments) {
+        send(&self.output, line);
+    }
+
+    /// Waits for a running search, stopping it first unless it's left to finish on its own.
+    ///
+    /// When `discard` is set, the move is thrown away if it hasn't already been sent.
+    fn finish_search(&mut self, stop: bool, discard: bool) {
+        let Some(search) = self.search.take() else {
+            return;
+        };
+        if discard {
+            // the search sends its move while holding the output,
+            // so it either sees the flag or has already sent the move
+            let _output = self.output.lock().unwrap();
+            search.discard.store(true, Ordering::Relaxed);
+        }
+        if stop || discard {
+            search.stop.store(true, Ordering::Relaxed);
+        }
+        if let Ok(Some(mv)) = search.handle.join() {
+            self.game.play(mv);
+        }
+    }
+
+    /// Stops thinking and forgets the move, as when the game changes under the engine.
+    fn abort_search(&mut self) {
+        self.finish_search(true, true);
+    }
+
+    /// The limits for a search by `side`.
+    fn params(&self, side: Color) -> GoParams {
+        let clock = &self.clock;
+        let mut params = GoParams {
+            depth: clock.depth,
+            move_time: clock.move_time,
+            ..GoParams::default()
+        };
+        if clock.move_time.is_some() {
+            return params;
+        }
+        if clock.ours.is_none() && clock.base.is_zero() {
+            // no time control has been given, so think for a while rather than not at all
+            if clock.depth.is_none() {
+                params.move_time = Some(DEFAULT_MOVE_TIME);
+            }
+            return params;
+        }
+
+        let ours = clock.ours.or(Some(clock.base));
+        let theirs = clock.theirs.or(Some(clock.base));
+        (params.wtime, params.btime) = match side {
+            Color::White => (ours, theirs),
+            Color::Black => (theirs, ours),
+        };
+        (params.winc, params.binc) = (Some(clock.increment), Some(clock.increment));
+
+        if clock.moves_per_control > 0 {
+            let played = self.game.position().fullmove_number().saturating_sub(1);
+            params.moves_to_go = Some(clock.moves_per_control - played % clock.moves_per_control);
+        }
+        params
+    }
+
+    /// Starts thinking if it's the engine's turn and the game isn't over.
+    fn maybe_search(&mut self) {
+        let side = self.game.position().side_to_move();
+        if self.engine_side != Some(side) || self.game.result().is_some() {
+            return;
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let discard = Arc::new(AtomicBool::new(false));
+        let engine = Arc::clone(&self.engine);
+        let mut game = self.game.clone();
+        let params = self.params(side);
+        let searcher = Searcher {
+            stop: Arc::clone(&stop),
+            output: Arc::clone(&self.output),
+            protocol: Protocol::Cecp { post: self.post },
+        };
+        let flag = Arc::clone(&discard);
+
+        let handle = std::thread::spawn(move || {
+            let best = engine
+                .lock()
+                .unwrap()
+                .search(game.position(), &params, &searcher)?;
+
+            let mut output = searcher.output.lock().unwrap();
+            if flag.load(Ordering::Relaxed) {
+                return None;
+            }
+            let _ = writeln!(output, "move {}", game.position().uci(best));
+            game.play(best);
+            if let Some(result) = game.result() {
+                let _ = writeln!(output, "{}", result_line(result));
+            }
+            let _ = output.flush();
+            Some(best)
+        });
+
+        self.search = Some(Search {
+            stop,
+            discard,
+            handle,
+        });
+    }
+
+    /// Plays a move from the GUI, in coordinate notation or SAN.
+    fn user_move(&mut self, text: &str) {
+        self.finish_search(false, false);
+        let position = self.game.position();
+        let mv = position
+            .parse_uci(text)
+            .ok()
+            .or_else(|| position.parse_san(text).ok());
+        let Some(mv) = mv else {
+            self.send(format_args!("Illegal move: {text}"));
+            return;
+        };
+
+        self.game.play(mv);
+        match self.game.result() {
+            Some(result) => self.send(format_args!("{}", result_line(result))),
+            None => self.maybe_search(),
+        }
+    }
+
+    /// Handles a single command, returning `false` when the engine should quit.
+    fn handle(&mut self, line: &str) -> bool {
+        let (command, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let args = args.trim();
+        let number = |index: usize| {
+            args.split_whitespace()
+                .nth(index)
+                .and_then(|n| n.parse().ok())
+        };
+
+        match command {
+            "" | "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer"
+            | "name" | "rating" | "ics" | "draw" | "otherboard" => {}
+            "protover" => {
+                let engine = self.engine.lock().unwrap();
+                self.send(format_args!(
+                    "feature myname=\"{}\" usermove=1 setboard=1 ping=1 playother=1 san=0 \
+                     colors=0 sigint=0 sigterm=0 analyze=0 done=1",
+                    engine.name()
+                ));
+            }
+            "new" => {
+                self.abort_search();
+                self.engine.lock().unwrap().new_game();
+                self.game = Game::start();
+                self.engine_side = Some(Color::Black);
+                self.clock.depth = None;
+            }
+            "force" => {
+                self.abort_search();
+                self.engine_side = None;
+            }
+            "go" => {
+                self.finish_search(false, false);
+                self.engine_side = Some(self.game.position().side_to_move());
+                self.maybe_search();
+            }
+            "playother" => {
+                self.finish_search(false, false);
+                self.engine_side = Some(self.game.position().side_to_move().opposite());
+            }
+            "usermove" => self.user_move(args),
+            "?" => self.finish_search(true, false),
+            "ping" => {
+                // the reply promises everything sent before it has been dealt with
+                self.finish_search(false, false);
+                self.send(format_args!("pong {args}"));
+            }
+            "setboard" => {
+                self.abort_search();
+                match FEN::try_from(args).and_then(|fen| fen.parse_position()) {
+                    Ok(position) => self.game = Game::new(position),
+                    Err(e) => self.send(format_args!("tellusererror Illegal position: {e}")),
+                }
+            }
+            "undo" => {
+                self.abort_search();
+                self.game.undo();
+            }
+            "remove" => {
+                self.abort_search();
+                self.game.undo();
+                self.game.undo();
+            }
+            "result" => {
+                self.abort_search();
+                self.engine_side = None;
+            }
+            "level" => match parse_level(args) {
+                Some((moves, base, increment)) => {
+                    self.clock.moves_per_control = moves;
+                    self.clock.base = base;
+                    self.clock.increment = increment;
+                    self.clock.move_time = None;
+                }
+                None => self.send(format_args!("Error (invalid level): {args}")),
+            },
+            "st" => self.clock.move_time = number(0).map(Duration::from_secs),
+            "sd" => self.clock.depth = number(0).map(|depth: u64| depth as u32),
+            "time" => self.clock.ours = number(0).map(centiseconds),
+            "otim" => self.clock.theirs = number(0).map(centiseconds),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "quit" => {
+                self.abort_search();
+                return false;
+            }
+            _ => self.send(format_args!("Error (unknown command): {command}")),
+        }
+        true
+    }
+}
+
+fn centiseconds(time: u64) -> Duration {
+    Duration::from_millis(time * 10)
+}
+
+/// Parses `level` arguments, the moves per control, base time and increment.
+///
+/// The base time is in minutes, or minutes and seconds as in `0:30`,
+/// and the increment is in seconds.
+fn parse_level(args: &str) -> Option<(u32, Duration, Duration)> {
+    let mut args = args.split_whitespace();
+    let moves = args.next()?.parse().ok()?;
+    let base = args.next()?;
+    let (minutes, seconds) = base.split_once(':').unwrap_or((base, "0"));
+    let base = minutes
+        .parse::<u64>()
+        .ok()?
+        .checked_mul(60)?
+        .checked_add(seconds.parse().ok()?)?;
+    // `inf` and `NaN` parse as floats too, and neither is a length of time
+    let increment = args.next()?.parse::<f64>().ok()?;
+    let increment = Duration::try_from_secs_f64(increment.max(0.0))
+        .ok()
+        .filter(|_| increment.is_finite())?;
+    Some((moves, Duration::from_secs(base), increment))
+}
+
+/// The line announcing the end of the game.
+fn result_line(result: GameResult) -> String {
+    let reason = match result {
+        GameResult::Checkmate {
+            winner: Color::White,
+        } => "White mates",
+        GameResult::Checkmate {
+            winner: Color::Black,
+        } => "Black mates",
+        GameResult::VariantWin {
+            winner: Color::White,
+        } => "White wins",
+        GameResult::VariantWin {
+            winner: Color::Black,
+        } => "Black wins",
+        GameResult::Draw(Draw::Stalemate) => "Stalemate",
+        GameResult::Draw(Draw::InsufficientMaterial) => "Insufficient material",
+        GameResult::Draw(Draw::ThreefoldRepetition | Draw::FivefoldRepetition) => {
+            "Draw by repetition"
+        }
+        GameResult::Draw(Draw::FiftyMoveRule | Draw::SeventyFiveMoveRule) => {
+            "Draw by fifty move rule"
+        }
+    };
+    format!("{} {{{reason}}}", result.outcome().as_str())
+}
+
+/// Writes a search's progress as thinking output, `ply score time nodes pv`.
+///
+/// Only lines with a depth and score are sent, and the time is in centiseconds.
+pub(crate) fn thinking(info: &Info) -> Option<String> {
+    let score = match info.score? {
+        InfoScore::Centipawns(cp) => cp,
+        InfoScore::Mate(moves) if moves > 0 => MATE_SCORE + moves,
+        InfoScore::Mate(moves) => -MATE_SCORE + moves,
+    };
+    let time = info.time.unwrap_or_default().as_millis() / 10;
+    let pv = info
+        .pv
+        .iter()
+        .map(|mv| mv.to_uci())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some(format!(
+        "{} {score} {time} {} {pv}",
+        info.depth?,
+        info.nodes.unwrap_or_default()
+    ))
+}
+
+/// Runs the xboard protocol until `quit` is received or `input` ends.
+///
+/// The engine starts out playing Black, as if `new` had been sent.
+pub fn run<E: Engine>(
+    engine: E,
+    input: impl BufRead,
+    output: impl Write + Send + 'static,
+) -> io::Result<()> {
+    let mut cecp = Cecp {
+        engine: Arc::new(Mutex::new(engine)),
+        game: Game::start(),
+        output: Arc::new(Mutex::new(output)),
+        engine_side: Some(Color::Black),
+        clock: Clock::default(),
+        post: false,
+        search: None,
+    };
+
+    for line in input.lines() {
+        if !cecp.handle(&line?) {
+            return Ok(());
+        }
+    }
+
+    // let the last search finish and send its move
+    cecp.finish_search(false, false);
+    Ok(())
+}
+
+/// Runs the xboard protocol over stdin and stdout.
+pub fn run_stdio<E: Engine>(engine: E) -> io::Result<()> {
+    run(engine, io::stdin().lock(), io::stdout())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{session_with, FirstMove};
+
+    fn session(input: &str) -> String {
+        session_with(input, |input, output| run(FirstMove, input, output))
+    }
+
+    /// The move the test engine plays after `moves` from the start.
+    fn first_move(moves: &[&str]) -> String {
+        let mut position = board::Position::start();
+        for mv in moves {
+            position.make_move(position.parse_uci(mv).unwrap());
+        }
+        position.uci(position.legal_moves()[0])
+    }
+
+    #[test]
+    fn plays_a_game() {
+        let output = session(
+            "xboard\nprotover 2\nnew\nusermove e2e4\nusermove e2e5\nusermove d2d4\nping 7\n",
+        );
+        let lines = output.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("feature myname=\"first move\""));
+
+        // the engine plays Black, and moves after each of White's legal moves
+        let reply = first_move(&["e2e4"]);
+        let second = first_move(&["e2e4", &reply, "d2d4"]);
+        assert_eq!(
+            &lines[1..],
+            [
+                format!("move {reply}"),
+                "Illegal move: e2e5".to_owned(),
+                format!("move {second}"),
+                "pong 7".to_owned()
+            ]
+        );
+    }
+
+    #[test]
+    fn force_and_go() {
+        let output = session("new\nforce\nusermove e2e4\nusermove e7e5\ngo\n");
+        assert_eq!(output, format!("move {}\n", first_move(&["e2e4", "e7e5"])));
+
+        let output = session("force\nusermove f3\nusermove e5\nusermove g4\nusermove Qh4\n");
+        assert_eq!(output, "0-1 {Black mates}\n");
+        let output = session("setboard 7k/5Q2/6K1/8/8/8/8/8 w - - 0 1\nforce\nusermove f7g7\n");
+        assert_eq!(output, "1-0 {White mates}\n");
+    }
+
+    #[test]
+    fn time_controls() {
+        assert_eq!(
+            parse_level("40 5 0"),
+            Some((40, Duration::from_secs(300), Duration::ZERO))
+        );
+        assert_eq!(
+            parse_level("0 0:30 1.5"),
+            Some((0, Duration::from_secs(30), Duration::from_millis(1500)))
+        );
+        assert_eq!(parse_level("40 five 0"), None);
+        assert_eq!(parse_level("40 5 inf"), None);
+        assert_eq!(parse_level("40 5 NaN"), None);
+        assert_eq!(parse_level("40 5 1e30"), None);
+        assert_eq!(parse_level("40 18446744073709551615 0"), None);
+    }
+
+    #[test]
+    fn thinking_output() {
+        let info = Info {
+            depth: Some(4),
+            score: Some(InfoScore::Mate(2)),
+            nodes: Some(1000),
+            time: Some(Duration::from_millis(250)),
+            pv: vec![board::Position::start().parse_uci("e2e4").unwrap()],
+            ..Default::default()
+        };
+        assert_eq!(thinking(&info).unwrap(), "4 100002 25 1000 e2e4");
+        assert_eq!(thinking(&Info::default()), None);
+    }
+}
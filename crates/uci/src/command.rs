@@ -0,0 +1,247 @@
+use std::time::Duration;
+
+use board::{Move, Position, FEN};
+
+/// A command sent from the GUI to the engine.
+#[derive(Debug, Clone)]
+pub enum Command {
+    Uci,
+    Debug(bool),
+    IsReady,
+    SetOption { name: String, value: Option<String> },
+    NewGame,
+    Position(Position),
+    Go(GoParams),
+    Stop,
+    PonderHit,
+    Quit,
+}
+
+/// Limits and time controls sent with the `go` command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GoParams {
+    /// Only search these moves, if not empty.
+    pub search_moves: Vec<Move>,
+    pub ponder: bool,
+    /// White's remaining time.
+    pub wtime: Option<Duration>,
+    /// Black's remaining time.
+    pub btime: Option<Duration>,
+    /// White's increment per move.
+    pub winc: Option<Duration>,
+    /// Black's increment per move.
+    pub binc: Option<Duration>,
+    /// Moves until the next time control.
+    pub moves_to_go: Option<u32>,
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    /// Search for a mate in this many moves.
+    pub mate: Option<u32>,
+    /// Search for exactly this long.
+    pub move_time: Option<Duration>,
+    /// Search until told to stop.
+    pub infinite: bool,
+}
+
+/// Why a line of input couldn't be understood.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum CommandError {
+    #[error("unknown command '{0}'")]
+    Unknown(String),
+    #[error("missing argument for '{0}'")]
+    MissingArgument(&'static str),
+    #[error("invalid value for '{0}'")]
+    InvalidValue(&'static str),
+    #[error("invalid FEN: {0}")]
+    InvalidFen(#[from] board::fen::ParseError),
+    #[error(transparent)]
+    Move(#[from] board::UciMoveError),
+}
+
+impl Command {
+    /// Parses a single line of input.
+    ///
+    /// Moves given to `go searchmoves` are checked against the current `position`.
+    /// Returns `Ok(None)` for blank lines.
+    pub fn parse(line: &str, position: &Position) -> Result<Option<Self>, CommandError> {
+        let mut tokens = line.split_whitespace();
+        let Some(name) = tokens.next() else {
+            return Ok(None);
+        };
+
+        let command = match name {
+            "uci" => Self::Uci,
+            "debug" => Self::Debug(tokens.next() == Some("on")),
+            "isready" => Self::IsReady,
+            "setoption" => parse_set_option(tokens)?,
+            "ucinewgame" => Self::NewGame,
+            "position" => Self::Position(parse_position(tokens)?),
+            "go" => Self::Go(parse_go(tokens, position)?),
+            "stop" => Self::Stop,
+            "ponderhit" => Self::PonderHit,
+            "quit" => Self::Quit,
+            other => return Err(CommandError::Unknown(other.to_owned())),
+        };
+
+        Ok(Some(command))
+    }
+}
+
+fn parse_set_option<'a>(
+    mut tokens: impl Iterator<Item = &'a str>,
+) -> Result<Command, CommandError> {
+    if tokens.next() != Some("name") {
+        return Err(CommandError::MissingArgument("name"));
+    }
+
+    // names and values may contain spaces
+    let mut name = Vec::new();
+    let mut value = None::<Vec<&str>>;
+    for token in tokens {
+        match (&mut value, token) {
+            (None, "value") => value = Some(Vec::new()),
+            (None, token) => name.push(token),
+            (Some(value), token) => value.push(token),
+        }
+    }
+
+    if name.is_empty() {
+        return Err(CommandError::MissingArgument("name"));
+    }
+
+    Ok(Command::SetOption {
+        name: name.join(" "),
+        value: value.map(|value| value.join(" ")),
+    })
+}
+
+fn parse_position<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Result<Position, CommandError> {
+    let mut position = match tokens.next() {
+        Some("startpos") => {
+            // skip to the moves, if there are any
+            if let Some(token) = tokens.next() {
+                if token != "moves" {
+                    return Err(CommandError::InvalidValue("position"));
+                }
+            }
+            Position::start()
+        }
+        Some("fen") => {
+            let fen = tokens
+                .by_ref()
+                .take_while(|&token| token != "moves")
+                .collect::<Vec<_>>()
+                .join(" ");
+            FEN::try_from(fen)?.parse_position()?
+        }
+        _ => return Err(CommandError::MissingArgument("position")),
+    };
+
+    for token in tokens {
+        let mv = position.parse_uci(token)?;
+        position.make_move(mv);
+    }
+
+    Ok(position)
+}
+
+fn parse_go<'a>(
+    tokens: impl Iterator<Item = &'a str>,
+    position: &Position,
+) -> Result<GoParams, CommandError> {
+    fn value<'a, T: std::str::FromStr>(
+        tokens: &mut std::iter::Peekable<impl Iterator<Item = &'a str>>,
+        name: &'static str,
+    ) -> Result<T, CommandError> {
+        tokens
+            .next()
+            .ok_or(CommandError::MissingArgument(name))?
+            .parse()
+            .map_err(|_| CommandError::InvalidValue(name))
+    }
+
+    fn millis<'a>(
+        tokens: &mut std::iter::Peekable<impl Iterator<Item = &'a str>>,
+        name: &'static str,
+    ) -> Result<Option<Duration>, CommandError> {
+        // GUIs occasionally send negative times when flagging
+        let ms = value::<i64>(tokens, name)?;
+        Ok(Some(Duration::from_millis(ms.max(0) as u64)))
+    }
+
+    let mut params = GoParams::default();
+    let mut tokens = tokens.peekable();
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "searchmoves" => {
+                while let Some(&mv) = tokens.peek() {
+                    if !is_move_like(mv) {
+                        break;
+                    }
+                    params.search_moves.push(position.parse_uci(mv)?);
+                    tokens.next();
+                }
+            }
+            "ponder" => params.ponder = true,
+            "wtime" => params.wtime = millis(&mut tokens, "wtime")?,
+            "btime" => params.btime = millis(&mut tokens, "btime")?,
+            "winc" => params.winc = millis(&mut tokens, "winc")?,
+            "binc" => params.binc = millis(&mut tokens, "binc")?,
+            "movestogo" => params.moves_to_go = Some(value(&mut tokens, "movestogo")?),
+            "depth" => params.depth = Some(value(&mut tokens, "depth")?),
+            "nodes" => params.nodes = Some(value(&mut tokens, "nodes")?),
+            "mate" => params.mate = Some(value(&mut tokens, "mate")?),
+            "movetime" => params.move_time = millis(&mut tokens, "movetime")?,
+            "infinite" => params.infinite = true,
+            _ => return Err(CommandError::InvalidValue("go")),
+        }
+    }
+
+    Ok(params)
+}
+
+fn is_move_like(token: &str) -> bool {
+    let bytes = token.as_bytes();
+    (4..=5).contains(&bytes.len())
+        && matches!(bytes[0], b'a'..=b'h')
+        && matches!(bytes[1], b'1'..=b'8')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_position_with_moves() {
+        let Some(Command::Position(position)) =
+            Command::parse("position startpos moves e2e4 e7e5 g1f3", &Position::start()).unwrap()
+        else {
+            panic!("expected a position command");
+        };
+
+        assert_eq!(position.side_to_move(), board::Color::Black);
+        assert_eq!(position.fullmove_number(), 2);
+    }
+
+    #[test]
+    fn parse_go_params() {
+        let line = "wtime 1000 btime -5 movestogo 20 searchmoves e2e4 d2d4 infinite";
+        let params = parse_go(line.split(' '), &Position::start()).unwrap();
+
+        assert_eq!(params.wtime, Some(Duration::from_secs(1)));
+        assert_eq!(params.btime, Some(Duration::ZERO));
+        assert_eq!(params.moves_to_go, Some(20));
+        assert_eq!(params.search_moves.len(), 2);
+        assert!(params.infinite);
+    }
+
+    #[test]
+    fn parse_set_option_with_spaces() {
+        let command = Command::parse("setoption name Clear Hash", &Position::start()).unwrap();
+        assert!(matches!(
+            command,
+            Some(Command::SetOption { name, value: None }) if name == "Clear Hash"
+        ));
+    }
+}
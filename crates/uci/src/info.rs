@@ -0,0 +1,103 @@
+use std::{fmt, time::Duration};
+
+use board::Move;
+
+/// How good the position is for the engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfoScore {
+    /// The score in centipawns.
+    Centipawns(i32),
+    /// Mate in this many moves, negative if the engine is getting mated.
+    Mate(i32),
+}
+
+/// Progress reported by the engine while searching.
+///
+/// Only the fields that are set are sent to the GUI.
+#[derive(Debug, Clone, Default)]
+pub struct Info {
+    pub depth: Option<u32>,
+    pub seldepth: Option<u32>,
+    /// Which line this is, when searching more than one.
+    pub multipv: Option<u32>,
+    pub score: Option<InfoScore>,
+    pub nodes: Option<u64>,
+    pub nps: Option<u64>,
+    /// Permille of the hash table in use.
+    pub hashfull: Option<u32>,
+    pub time: Option<Duration>,
+    pub pv: Vec<Move>,
+    pub current_move: Option<Move>,
+    /// Any free text, must be sent last.
+    pub string: Option<String>,
+}
+
+impl fmt::Display for InfoScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Centipawns(cp) => write!(f, "score cp {cp}"),
+            Self::Mate(moves) => write!(f, "score mate {moves}"),
+        }
+    }
+}
+
+impl fmt::Display for Info {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "info")?;
+
+        if let Some(depth) = self.depth {
+            write!(f, " depth {depth}")?;
+        }
+        if let Some(seldepth) = self.seldepth {
+            write!(f, " seldepth {seldepth}")?;
+        }
+        if let Some(multipv) = self.multipv {
+            write!(f, " multipv {multipv}")?;
+        }
+        if let Some(score) = self.score {
+            write!(f, " {score}")?;
+        }
+        if let Some(nodes) = self.nodes {
+            write!(f, " nodes {nodes}")?;
+        }
+        if let Some(nps) = self.nps {
+            write!(f, " nps {nps}")?;
+        }
+        if let Some(hashfull) = self.hashfull {
+            write!(f, " hashfull {hashfull}")?;
+        }
+        if let Some(time) = self.time {
+            write!(f, " time {}", time.as_millis())?;
+        }
+        if let Some(mv) = self.current_move {
+            write!(f, " currmove {}", mv.to_uci())?;
+        }
+        if !self.pv.is_empty() {
+            write!(f, " pv")?;
+            for &mv in &self.pv {
+                write!(f, " {}", mv.to_uci())?;
+            }
+        }
+        if let Some(string) = &self.string {
+            write!(f, " string {string}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn formatting() {
+    let info = Info {
+        depth: Some(3),
+        score: Some(InfoScore::Mate(-2)),
+        nodes: Some(1024),
+        time: Some(Duration::from_millis(15)),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        info.to_string(),
+        "info depth 3 score mate -2 nodes 1024 time 15"
+    );
+}
@@ -0,0 +1,375 @@
+//! A frontend speaking the [Universal Chess Interface](https://www.wbec-ridderkerk.nl/html/UCIProtocol.html).
+//!
+//! Any search built on the `board` crate can be plugged in by implementing [`Engine`],
+//! then handing it to [`run`] along with the input and output streams.
+//! The same engine can also speak xboard's protocol, see [`cecp`].
+
+pub mod cecp;
+mod command;
+mod info;
+mod option;
+
+use std::{
+    io::{self, BufRead, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+};
+
+use board::{Move, Position};
+
+pub use command::{Command, CommandError, GoParams};
+pub use info::{Info, InfoScore};
+pub use option::{OptionError, OptionKind, UciOption};
+
+/// A chess engine that can be driven over UCI.
+pub trait Engine: Send + 'static {
+    /// The name sent in `id name`.
+    fn name(&self) -> &str;
+
+    /// The author sent in `id author`.
+    fn author(&self) -> &str;
+
+    /// The options the GUI may change with `setoption`.
+    fn options(&self) -> Vec<UciOption> {
+        Vec::new()
+    }
+
+    /// Changes an option, `value` is None for buttons.
+    fn set_option(&mut self, name: &str, value: Option<&str>) -> Result<(), OptionError> {
+        let _ = value;
+        Err(OptionError::Unknown(name.to_owned()))
+    }
+
+    /// Called when the next search will be from a different game.
+    fn new_game(&mut self) {}
+
+    /// Searches `position` for the best move.
+    ///
+    /// The search should poll [`Searcher::should_stop`] and return soon after it becomes `true`.
+    /// Returning None means there are no legal moves.
+    fn search(
+        &mut self,
+        position: &Position,
+        params: &GoParams,
+        searcher: &Searcher,
+    ) -> Option<Move>;
+}
+
+type Output = Arc<Mutex<dyn Write + Send>>;
+
+/// Lets a running search report progress and check if it has been stopped.
+pub struct Searcher {
+    stop: Arc<AtomicBool>,
+    output: Output,
+    protocol: Protocol,
+}
+
+/// How progress is reported to the GUI.
+#[derive(Clone, Copy)]
+enum Protocol {
+    Uci,
+    /// Thinking output is only sent when the GUI has asked for it with `post`.
+    Cecp {
+        post: bool,
+    },
+}
+
+impl Searcher {
+    /// Returns `true` once the GUI has asked the search to stop.
+    #[inline]
+    pub fn should_stop(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// A handle that can be shared with other search threads to stop them.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    /// Sends an `info` line to the GUI.
+    pub fn info(&self, info: &Info) {
+        match self.protocol {
+            Protocol::Uci => send(&self.output, format_args!("{info}")),
+            Protocol::Cecp { post: true } => {
+                if let Some(line) = cecp::thinking(info) {
+                    send(&self.output, format_args!("{line}"));
+                }
+            }
+            Protocol::Cecp { post: false } => {}
+        }
+    }
+}
+
+fn send(output: &Output, line: std::fmt::Arguments) {
+    let mut output = output.lock().unwrap();
+    // the GUI going away isn't something we can recover from, or report
+    let _ = writeln!(output, "{line}");
+    let _ = output.flush();
+}
+
+struct Uci<E> {
+    engine: Arc<Mutex<E>>,
+    position: Position,
+    output: Output,
+    stop: Arc<AtomicBool>,
+    /// Set by `ponderhit`, or when the input ends, letting an infinite or pondering search
+    /// that has finished send its best move without being stopped.
+    released: Arc<AtomicBool>,
+    search: Option<JoinHandle<()>>,
+    debug: bool,
+}
+
+impl<E: Engine> Uci<E> {
+    fn send(&self, line: std::fmt::Arguments) {
+        send(&self.output, line);
+    }
+
+    /// Stops any running search, waiting for it to send its best move.
+    fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.stop.store(true, Ordering::Relaxed);
+            search.thread().unpark();
+            let _ = search.join();
+        }
+    }
+
+    /// Lets an infinite or pondering search send its best move once it is done.
+    fn release(&mut self) {
+        self.released.store(true, Ordering::Relaxed);
+        if let Some(search) = &self.search {
+            search.thread().unpark();
+        }
+    }
+
+    fn start_search(&mut self, params: GoParams) {
+        self.stop_search();
+        self.stop = Arc::new(AtomicBool::new(false));
+        self.released = Arc::new(AtomicBool::new(false));
+
+        let engine = Arc::clone(&self.engine);
+        let released = Arc::clone(&self.released);
+        let position = self.position.clone();
+        let searcher = Searcher {
+            stop: Arc::clone(&self.stop),
+            output: Arc::clone(&self.output),
+            protocol: Protocol::Uci,
+        };
+
+        self.search = Some(std::thread::spawn(move || {
+            let best = engine.lock().unwrap().search(&position, &params, &searcher);
+
+            // an infinite or pondering search can end early, such as on finding a mate,
+            // but the best move mustn't be sent until the GUI asks for it
+            if params.infinite || params.ponder {
+                while !searcher.should_stop() && !released.load(Ordering::Relaxed) {
+                    std::thread::park();
+                }
+            }
+
+            match best {
+                Some(mv) => send(
+                    &searcher.output,
+                    format_args!("bestmove {}", position.uci(mv)),
+                ),
+                None => send(&searcher.output, format_args!("bestmove 0000")),
+            }
+        }));
+    }
+
+    /// Handles a single command, returning `false` when the engine should quit.
+    fn handle(&mut self, command: Command) -> bool {
+        match command {
+            Command::Uci => {
+                let engine = self.engine.lock().unwrap();
+                self.send(format_args!("id name {}", engine.name()));
+                self.send(format_args!("id author {}", engine.author()));
+                for option in engine.options() {
+                    self.send(format_args!("{option}"));
+                }
+                self.send(format_args!("uciok"));
+            }
+            Command::Debug(on) => self.debug = on,
+            Command::IsReady => self.send(format_args!("readyok")),
+            Command::SetOption { name, value } => {
+                self.stop_search();
+                let result = self
+                    .engine
+                    .lock()
+                    .unwrap()
+                    .set_option(&name, value.as_deref());
+                if let Err(e) = result {
+                    self.send(format_args!("info string {e}"));
+                }
+            }
+            Command::NewGame => {
+                self.stop_search();
+                self.engine.lock().unwrap().new_game();
+                self.position = Position::start();
+            }
+            Command::Position(position) => {
+                self.stop_search();
+                self.position = position;
+            }
+            Command::Go(params) => self.start_search(params),
+            Command::Stop => self.stop_search(),
+            Command::PonderHit => self.release(),
+            Command::Quit => {
+                self.stop_search();
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Runs the UCI protocol until `quit` is received or `input` ends.
+///
+/// Searches run on a separate thread, so `stop` can be handled while searching.
+pub fn run<E: Engine>(
+    engine: E,
+    input: impl BufRead,
+    output: impl Write + Send + 'static,
+) -> io::Result<()> {
+    let mut uci = Uci {
+        engine: Arc::new(Mutex::new(engine)),
+        position: Position::start(),
+        output: Arc::new(Mutex::new(output)),
+        stop: Arc::new(AtomicBool::new(false)),
+        released: Arc::new(AtomicBool::new(false)),
+        search: None,
+        debug: false,
+    };
+
+    for line in input.lines() {
+        let line = line?;
+        match Command::parse(&line, &uci.position) {
+            Ok(Some(command)) => {
+                if !uci.handle(command) {
+                    return Ok(());
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                if uci.debug {
+                    uci.send(format_args!("info string {e}"));
+                }
+            }
+        }
+    }
+
+    // wait for the last search to finish on its own
+    uci.release();
+    if let Some(search) = uci.search.take() {
+        let _ = search.join();
+    }
+
+    Ok(())
+}
+
+/// Runs the UCI protocol over stdin and stdout.
+pub fn run_stdio<E: Engine>(engine: E) -> io::Result<()> {
+    run(engine, io::stdin().lock(), io::stdout())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) struct FirstMove;
+
+    impl Engine for FirstMove {
+        fn name(&self) -> &str {
+            "first move"
+        }
+
+        fn author(&self) -> &str {
+            "tests"
+        }
+
+        fn search(
+            &mut self,
+            position: &Position,
+            params: &GoParams,
+            searcher: &Searcher,
+        ) -> Option<Move> {
+            searcher.info(&Info {
+                depth: Some(1),
+                ..Default::default()
+            });
+            params
+                .search_moves
+                .first()
+                .or(position.legal_moves().first())
+                .copied()
+        }
+    }
+
+    #[derive(Clone, Default)]
+    pub(crate) struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Runs a protocol over `input`, returning everything it sent.
+    pub(crate) fn session_with(
+        input: &str,
+        run: impl FnOnce(&[u8], Shared) -> io::Result<()>,
+    ) -> String {
+        let output = Shared::default();
+        run(input.as_bytes(), output.clone()).unwrap();
+        let bytes = output.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    fn session(input: &str) -> String {
+        session_with(input, |input, output| run(FirstMove, input, output))
+    }
+
+    #[test]
+    fn handshake() {
+        let output = session("uci\nisready\nquit\n");
+        assert_eq!(
+            output,
+            "id name first move\nid author tests\nuciok\nreadyok\n"
+        );
+    }
+
+    #[test]
+    fn search_reports_best_move() {
+        let output = session("position startpos moves e2e4\ngo depth 1 searchmoves e7e5\n");
+        assert_eq!(output, "info depth 1\nbestmove e7e5\n");
+    }
+
+    #[test]
+    fn infinite_search_waits_to_be_stopped() {
+        // the engine returns at once, but the best move waits for `stop` or `ponderhit`
+        for input in [
+            "go infinite\nisready\nstop\n",
+            "go ponder\nisready\nponderhit\n",
+        ] {
+            let output = session(input);
+            let ready = output.find("readyok").unwrap();
+            assert!(output[ready..].ends_with("bestmove a2a3\n"), "{output}");
+        }
+        // or for the input to end
+        let output = session("go infinite\n");
+        assert_eq!(output, "info depth 1\nbestmove a2a3\n");
+    }
+
+    #[test]
+    fn no_legal_moves() {
+        let output = session("position fen 7k/5QQ1/8/8/8/8/8/K7 b - - 0 1\ngo\n");
+        assert!(output.ends_with("bestmove 0000\n"));
+    }
+}
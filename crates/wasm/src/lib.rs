@@ -0,0 +1,211 @@
+//! JavaScript bindings for the `board` crate, built with `wasm-bindgen`.
+//!
+//! ```js
+//! import { Chess } from "chesster-wasm";
+//!
+//! const chess = new Chess();
+//! chess.play("e4");
+//! chess.legalMoves(); // ["a7a6", "a7a5", ...]
+//! ```
+//!
+//! Moves are given and returned as UCI text such as `e7e8q`, but [`Chess::play`]
+//! also accepts SAN.
+//!
+//! Build the package for JavaScript with `wasm-pack build crates/wasm`.
+
+use board::{Color, Draw, Game, GameResult, Move, Position, Square, FEN};
+use wasm_bindgen::prelude::*;
+
+/// A game of chess, tracking the moves played so repetitions can be detected.
+#[wasm_bindgen]
+pub struct Chess {
+    game: Game,
+}
+
+#[wasm_bindgen]
+impl Chess {
+    /// A new game from the standard starting position.
+    #[wasm_bindgen(constructor)]
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self {
+            game: Game::start(),
+        }
+    }
+
+    /// A new game from a position in FEN.
+    #[wasm_bindgen(js_name = fromFen)]
+    pub fn from_fen(fen: &str) -> Result<Chess, JsError> {
+        let position = FEN::try_from(fen)?.parse_position()?;
+        Ok(Self {
+            game: Game::new(position),
+        })
+    }
+
+    /// The current position in FEN.
+    pub fn fen(&self) -> String {
+        self.position().to_fen().to_string()
+    }
+
+    /// The player to move, `"w"` or `"b"`.
+    pub fn turn(&self) -> String {
+        match self.position().side_to_move() {
+            Color::White => "w".to_owned(),
+            Color::Black => "b".to_owned(),
+        }
+    }
+
+    /// Every legal move in UCI notation.
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self) -> Vec<String> {
+        let position = self.position();
+        position
+            .legal_moves()
+            .iter()
+            .map(|&mv| position.uci(mv))
+            .collect()
+    }
+
+    /// The legal moves of the piece on `square`, such as `"e2"`, in UCI notation.
+    #[wasm_bindgen(js_name = movesFrom)]
+    pub fn moves_from(&self, square: &str) -> Result<Vec<String>, JsError> {
+        let square: Square = square.parse()?;
+        let position = self.position();
+        Ok(position
+            .legal_moves()
+            .iter()
+            .filter(|mv| mv.from() == square)
+            .map(|&mv| position.uci(mv))
+            .collect())
+    }
+
+    /// Converts a legal move from UCI to SAN notation.
+    #[wasm_bindgen(js_name = toSan)]
+    pub fn to_san(&self, uci: &str) -> Result<String, JsError> {
+        let position = self.position();
+        let mv = position.parse_uci(uci)?;
+        Ok(position.san(mv))
+    }
+
+    /// Plays a legal move given in SAN or UCI notation, returning its SAN.
+    pub fn play(&mut self, text: &str) -> Result<String, JsError> {
+        let mv = self.parse(text)?;
+        let san = self.position().san(mv);
+        self.game.play(mv);
+        Ok(san)
+    }
+
+    /// Takes back the last move, returning it in UCI notation.
+    pub fn undo(&mut self) -> Option<String> {
+        let mv = self.game.undo()?;
+        Some(self.position().uci(mv))
+    }
+
+    /// The moves played so far in UCI notation.
+    pub fn history(&self) -> Vec<String> {
+        let mut position = self.game.position().clone();
+        let moves = self.game.moves();
+        for _ in moves {
+            position.unmake_move();
+        }
+        moves
+            .iter()
+            .map(|&mv| {
+                let uci = position.uci(mv);
+                position.make_move(mv);
+                uci
+            })
+            .collect()
+    }
+
+    /// Whether the player to move is in check.
+    #[wasm_bindgen(js_name = inCheck)]
+    pub fn in_check(&self) -> bool {
+        let position = self.position();
+        position.in_check(position.side_to_move())
+    }
+
+    /// Whether the game has ended by checkmate or an automatic draw.
+    ///
+    /// Draws that must be claimed, such as threefold repetition, don't end the game.
+    #[wasm_bindgen(js_name = isGameOver)]
+    pub fn is_game_over(&self) -> bool {
+        self.game.result().is_some_and(GameResult::is_automatic)
+    }
+
+    /// The result as `"1-0"`, `"0-1"` or `"1/2-1/2"`, or `undefined` while the game goes on.
+    ///
+    /// Includes draws that can be claimed.
+    pub fn result(&self) -> Option<String> {
+        self.game
+            .result()
+            .map(|result| result.outcome().as_str().to_owned())
+    }
+
+    /// Why the game ended or can be drawn, such as `"checkmate"` or `"threefold repetition"`.
+    pub fn reason(&self) -> Option<String> {
+        let reason = match self.game.result()? {
+            GameResult::Checkmate { .. } => "checkmate",
+            GameResult::VariantWin { .. } => "variant win",
+            GameResult::Draw(Draw::Stalemate) => "stalemate",
+            GameResult::Draw(Draw::InsufficientMaterial) => "insufficient material",
+            GameResult::Draw(Draw::ThreefoldRepetition) => "threefold repetition",
+            GameResult::Draw(Draw::FivefoldRepetition) => "fivefold repetition",
+            GameResult::Draw(Draw::FiftyMoveRule) => "fifty move rule",
+            GameResult::Draw(Draw::SeventyFiveMoveRule) => "seventy-five move rule",
+        };
+        Some(reason.to_owned())
+    }
+}
+
+impl Chess {
+    fn position(&self) -> &Position {
+        self.game.position()
+    }
+
+    /// Finds the legal move written in UCI, falling back to SAN.
+    fn parse(&self, text: &str) -> Result<Move, JsError> {
+        let position = self.position();
+        match position.parse_uci(text) {
+            Ok(mv) => Ok(mv),
+            Err(_) => Ok(position.parse_san(text)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // errors can only be created when running in JavaScript,
+    // so only the successful paths are tested natively
+
+    #[test]
+    fn play_a_game() {
+        let mut chess = Chess::new();
+        assert_eq!(chess.legal_moves().len(), 20);
+        assert_eq!(chess.moves_from("g1").unwrap(), ["g1f3", "g1h3"]);
+
+        // fool's mate, mixing SAN and UCI
+        assert_eq!(chess.play("f3").unwrap(), "f3");
+        assert_eq!(chess.play("e7e5").unwrap(), "e5");
+        assert_eq!(chess.to_san("g2g4").unwrap(), "g4");
+        chess.play("g4").unwrap();
+        assert!(!chess.is_game_over());
+        assert_eq!(chess.play("Qh4").unwrap(), "Qh4#");
+
+        assert!(chess.in_check());
+        assert!(chess.is_game_over());
+        assert_eq!(chess.result().as_deref(), Some("0-1"));
+        assert_eq!(chess.reason().as_deref(), Some("checkmate"));
+        assert_eq!(chess.history(), ["f2f3", "e7e5", "g2g4", "d8h4"]);
+
+        assert_eq!(chess.undo().as_deref(), Some("d8h4"));
+        assert_eq!(chess.turn(), "b");
+        assert_eq!(chess.result(), None);
+        assert_eq!(
+            chess.fen(),
+            "rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq g3 0 2"
+        );
+    }
+}